@@ -0,0 +1,57 @@
+/* C bindings for embedding the Mast build system.
+ *
+ * All functions returning `int` return 0 on success and -1 on failure.
+ * Strings are NUL-terminated and must be valid UTF-8.
+ */
+#ifndef MAST_H
+#define MAST_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opaque handle to a set of files whose changes are tracked across runs. */
+typedef struct MastBuilder MastBuilder;
+
+/* Create a new builder with no files registered.
+ * The returned handle must be freed with `mast_builder_free`. */
+MastBuilder *mast_builder_new(void);
+
+/* Free a builder. Does nothing if `builder` is NULL. */
+void mast_builder_free(MastBuilder *builder);
+
+/* Register the file at `path` under the given `name`,
+ * replacing any file previously registered under that name. */
+int mast_builder_add_file(MastBuilder *builder, const char *name, const char *path);
+
+/* Check every registered file for changes since the previous run. */
+int mast_builder_run(MastBuilder *builder);
+
+/* Get the number of files that changed in the most recent run. */
+size_t mast_builder_changed_len(const MastBuilder *builder);
+
+/* Get the name of the `index`th file that changed in the most recent run,
+ * or NULL if `index` is out of bounds.
+ * The string is owned by the builder and remains valid
+ * until the builder is next modified or freed. */
+const char *mast_builder_changed(const MastBuilder *builder, size_t index);
+
+/* Salt the saved state with the `len` bytes at `salt`.
+ * State saved under a different salt is discarded when loaded.
+ * Must be called before `mast_builder_load_state`. */
+int mast_builder_set_salt(MastBuilder *builder, const unsigned char *salt, size_t len);
+
+/* Load the state of a previous run.
+ * A missing file or one saved under a different salt is not an error. */
+int mast_builder_load_state(MastBuilder *builder, const char *path);
+
+/* Save the state of the most recent run. */
+int mast_builder_save_state(const MastBuilder *builder, const char *path);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,342 @@
+//! C bindings for embedding Mast in non-Rust tools, such as editors and other build systems.
+//!
+//! The API revolves around an opaque [`MastBuilder`] handle:
+//! register the files the host tool cares about with [`mast_builder_add_file`],
+//! call [`mast_builder_run`] to check them all,
+//! and then query which of them changed since the previous run
+//! with [`mast_builder_changed_len`] and [`mast_builder_changed`].
+//! The etags from previous runs can be persisted across processes
+//! with [`mast_builder_save_state`] and [`mast_builder_load_state`],
+//! and invalidated wholesale with [`mast_builder_set_salt`].
+//!
+//! The corresponding C header is `include/mast.h`.
+//!
+//! All functions returning `int` return `0` on success and `-1` on failure.
+//! Strings are NUL-terminated and must be valid UTF-8.
+#![warn(
+    noop_method_call,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    missing_docs,
+    missing_debug_implementations,
+    clippy::pedantic
+)]
+// We put `use` declarations at the bottom of modules, after any tests.
+#![allow(clippy::items_after_test_module)]
+
+/// An opaque handle to a set of files whose changes are tracked across runs.
+#[derive(Debug, Default)]
+pub struct MastBuilder {
+    files: Vec<File>,
+    state: BTreeMap<String, Stamp>,
+    salt: Digest,
+    changed: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct File {
+    name: CString,
+    path: PathBuf,
+}
+
+impl File {
+    fn key(&self) -> &str {
+        // Names are only ever constructed from valid UTF-8.
+        self.name.to_str().unwrap_or_default()
+    }
+}
+
+/// Create a new builder with no files registered.
+///
+/// The returned handle must be freed with [`mast_builder_free`].
+#[no_mangle]
+pub extern "C" fn mast_builder_new() -> *mut MastBuilder {
+    Box::into_raw(Box::default())
+}
+
+/// Free a builder created by [`mast_builder_new`].
+/// Does nothing if `builder` is null.
+///
+/// # Safety
+///
+/// `builder` must be null or a handle returned by [`mast_builder_new`] that has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn mast_builder_free(builder: *mut MastBuilder) {
+    if !builder.is_null() {
+        drop(unsafe { Box::from_raw(builder) });
+    }
+}
+
+/// Register the file at `path` under the given `name`,
+/// replacing any file previously registered under that name.
+///
+/// # Safety
+///
+/// `builder` must be a valid handle, and `name` and `path` must be valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn mast_builder_add_file(
+    builder: *mut MastBuilder,
+    name: *const c_char,
+    path: *const c_char,
+) -> c_int {
+    let (Some(name), Some(path)) = (unsafe { str(name) }, unsafe { str(path) }) else {
+        return -1;
+    };
+    let Some(builder) = (unsafe { builder.as_mut() }) else {
+        return -1;
+    };
+    let Ok(c_name) = CString::new(name) else {
+        return -1;
+    };
+    let file = File {
+        name: c_name,
+        path: path.into(),
+    };
+    match builder.files.iter_mut().find(|f| f.key() == name) {
+        Some(existing) => *existing = file,
+        None => builder.files.push(file),
+    }
+    0
+}
+
+/// Check every registered file for changes since the previous run,
+/// recording which ones changed.
+///
+/// Files that cannot be read are always considered changed.
+/// Any state belonging to files that are no longer registered is discarded.
+///
+/// # Safety
+///
+/// `builder` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn mast_builder_run(builder: *mut MastBuilder) -> c_int {
+    let Some(builder) = (unsafe { builder.as_mut() }) else {
+        return -1;
+    };
+    let mut old_state = mem::take(&mut builder.state);
+    builder.changed.clear();
+    for (i, file) in builder.files.iter().enumerate() {
+        let mut stamp = old_state.remove(file.key()).unwrap_or_default();
+        let asset = fs::bytes(&file.path).update(Context::default(), &mut stamp);
+        if asset.is_modified() {
+            builder.changed.push(i);
+        }
+        builder.state.insert(file.key().to_owned(), stamp);
+    }
+    0
+}
+
+/// Get the number of files that changed in the most recent run.
+///
+/// # Safety
+///
+/// `builder` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn mast_builder_changed_len(builder: *const MastBuilder) -> usize {
+    unsafe { builder.as_ref() }.map_or(0, |builder| builder.changed.len())
+}
+
+/// Get the name of the `index`th file that changed in the most recent run,
+/// or null if `index` is out of bounds.
+///
+/// The returned string is owned by the builder,
+/// and remains valid until the builder is next modified or freed.
+///
+/// # Safety
+///
+/// `builder` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn mast_builder_changed(
+    builder: *const MastBuilder,
+    index: usize,
+) -> *const c_char {
+    unsafe { builder.as_ref() }
+        .and_then(|builder| builder.files.get(*builder.changed.get(index)?))
+        .map_or(ptr::null(), |file| file.name.as_ptr())
+}
+
+/// Salt the saved state with the `len` bytes at `salt`.
+///
+/// State saved under a different salt is discarded when it is loaded,
+/// so changing the salt (say, when the host tool is upgraded)
+/// causes every file to be considered changed.
+/// This must be called before [`mast_builder_load_state`] to have any effect on it.
+///
+/// # Safety
+///
+/// `builder` must be a valid handle,
+/// and `salt` must point to `len` readable bytes (it may be null if `len` is zero).
+#[no_mangle]
+pub unsafe extern "C" fn mast_builder_set_salt(
+    builder: *mut MastBuilder,
+    salt: *const u8,
+    len: usize,
+) -> c_int {
+    let Some(builder) = (unsafe { builder.as_mut() }) else {
+        return -1;
+    };
+    let salt = match len {
+        0 => &[],
+        _ if salt.is_null() => return -1,
+        _ => unsafe { slice::from_raw_parts(salt, len) },
+    };
+    builder.salt = Sha256::digest(salt);
+    0
+}
+
+/// Load the state of a previous run from the file at `path`.
+///
+/// It is not an error for the file not to exist
+/// or to have been saved under a different salt;
+/// in that case, every file will be considered changed on the next run.
+///
+/// # Safety
+///
+/// `builder` must be a valid handle, and `path` must be a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn mast_builder_load_state(
+    builder: *mut MastBuilder,
+    path: *const c_char,
+) -> c_int {
+    let (Some(builder), Some(path)) = (unsafe { builder.as_mut() }, unsafe { str(path) }) else {
+        return -1;
+    };
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return 0,
+        Err(_) => return -1,
+    };
+    match Salted::from_bytes(&bytes) {
+        Ok(state) => {
+            builder.state = state.unsalt(&builder.salt);
+            0
+        }
+        Err(_) => -1,
+    }
+}
+
+/// Save the state of the most recent run to the file at `path`.
+///
+/// # Safety
+///
+/// `builder` must be a valid handle, and `path` must be a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn mast_builder_save_state(
+    builder: *const MastBuilder,
+    path: *const c_char,
+) -> c_int {
+    let (Some(builder), Some(path)) = (unsafe { builder.as_ref() }, unsafe { str(path) }) else {
+        return -1;
+    };
+    let state = Salted::new(builder.salt, builder.state.clone());
+    match std::fs::write(path, state.to_vec()) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Convert a possibly-null C string to a `&str`.
+unsafe fn str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn changes() {
+        let dir = std::env::temp_dir().join(format!("mast-capi-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let a = CString::new(dir.join("a").to_str().unwrap()).unwrap();
+        let b = CString::new(dir.join("b").to_str().unwrap()).unwrap();
+        let state = CString::new(dir.join("state").to_str().unwrap()).unwrap();
+        std::fs::write(dir.join("a"), "a").unwrap();
+        std::fs::write(dir.join("b"), "b").unwrap();
+
+        let changed = |builder| unsafe {
+            (0..mast_builder_changed_len(builder))
+                .map(|i| CStr::from_ptr(mast_builder_changed(builder, i)))
+                .map(|name| name.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        unsafe {
+            let builder = mast_builder_new();
+            assert_eq!(
+                mast_builder_add_file(builder, c("a").as_ptr(), a.as_ptr()),
+                0
+            );
+            assert_eq!(
+                mast_builder_add_file(builder, c("b").as_ptr(), b.as_ptr()),
+                0
+            );
+            assert_eq!(mast_builder_load_state(builder, state.as_ptr()), 0);
+            assert_eq!(mast_builder_run(builder), 0);
+            assert_eq!(changed(builder), ["a", "b"]);
+            assert!(mast_builder_changed(builder, 2).is_null());
+            assert_eq!(mast_builder_save_state(builder, state.as_ptr()), 0);
+            mast_builder_free(builder);
+
+            let builder = mast_builder_new();
+            assert_eq!(
+                mast_builder_add_file(builder, c("a").as_ptr(), a.as_ptr()),
+                0
+            );
+            assert_eq!(
+                mast_builder_add_file(builder, c("b").as_ptr(), b.as_ptr()),
+                0
+            );
+            assert_eq!(mast_builder_load_state(builder, state.as_ptr()), 0);
+            std::fs::write(dir.join("b"), "bb").unwrap();
+            assert_eq!(mast_builder_run(builder), 0);
+            assert_eq!(changed(builder), ["b"]);
+            assert_eq!(mast_builder_run(builder), 0);
+            assert_eq!(changed(builder), <[&str; 0]>::default());
+            assert_eq!(mast_builder_save_state(builder, state.as_ptr()), 0);
+            mast_builder_free(builder);
+
+            let builder = mast_builder_new();
+            assert_eq!(
+                mast_builder_add_file(builder, c("a").as_ptr(), a.as_ptr()),
+                0
+            );
+            assert_eq!(mast_builder_set_salt(builder, b"v2".as_ptr(), 2), 0);
+            assert_eq!(mast_builder_load_state(builder, state.as_ptr()), 0);
+            assert_eq!(mast_builder_run(builder), 0);
+            assert_eq!(changed(builder), ["a"]);
+            mast_builder_free(builder);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    use super::*;
+}
+
+use mast::asset::Context;
+use mast::etag::Salted;
+use mast::fs;
+use mast::fs::Stamp;
+use mast::hash::Digest;
+use mast::hash::Sha256;
+use mast::Asset as _;
+use mast::Etag;
+use std::collections::BTreeMap;
+use std::ffi::c_char;
+use std::ffi::c_int;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::path::PathBuf;
+use std::ptr;
+use std::slice;
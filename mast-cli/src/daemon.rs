@@ -0,0 +1,120 @@
+//! The protocol between the `daemon` command and the commands that talk to it.
+//!
+//! The daemon listens on a Unix socket.
+//! Each connection carries a single request line from the client
+//! and a single reply line from the daemon.
+//! On other platforms there is never a daemon to talk to.
+//!
+//! The requests are:
+//!
+//! - `build <handshake>`: build, if the handshake identifying the client’s pipeline
+//!   matches the daemon’s own.
+//!   The handshake includes the path, size and modification time of the executable,
+//!   so a daemon started by another build of the build program never builds
+//!   with its stale pipeline: it replies `stale` and stops,
+//!   and the client builds in-process instead.
+//! - `reset`: forget the etags kept in memory, after `clean` removed the state.
+//! - `status` and `stop`.
+
+/// Send a request to the daemon listening on `socket` and return its reply,
+/// or [`None`] if no daemon is listening.
+#[cfg(unix)]
+pub(crate) fn request(socket: &Path, request: &str) -> io::Result<Option<String>> {
+    let mut stream = match UnixStream::connect(socket) {
+        Ok(stream) => stream,
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+            ) =>
+        {
+            return Ok(None)
+        }
+        Err(e) => return Err(e),
+    };
+    writeln!(stream, "{request}")?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(Some(reply.trim_end().to_owned()))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn request(_socket: &Path, _request: &str) -> io::Result<Option<String>> {
+    Ok(None)
+}
+
+/// Listen on `socket`, answering each request with `handle`
+/// until it breaks with its final reply.
+///
+/// A socket left behind by a daemon that exited uncleanly is replaced,
+/// but it is an error for another daemon to be listening already.
+#[cfg(unix)]
+pub(crate) fn serve<H>(socket: &Path, mut handle: H) -> io::Result<()>
+where
+    H: FnMut(&str) -> ControlFlow<String, String>,
+{
+    if request(socket, "status")?.is_some() {
+        let msg = format!("a daemon is already listening on {}", socket.display());
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, msg));
+    }
+    match fs::remove_file(socket) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let listener = UnixListener::bind(socket)?;
+    let res = (|| {
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+            // A client that stops responding must not hold up every other client forever.
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            let mut line = String::new();
+            if BufReader::new(&stream).read_line(&mut line).is_err() {
+                continue;
+            }
+            let (reply, stop) = match handle(line.trim_end()) {
+                ControlFlow::Continue(reply) => (reply, false),
+                ControlFlow::Break(reply) => (reply, true),
+            };
+            // The client may have gone away; that is its problem, not the daemon’s.
+            drop(writeln!(stream, "{reply}"));
+            if stop {
+                break;
+            }
+        }
+        Ok(())
+    })();
+    drop(fs::remove_file(socket));
+    res
+}
+
+/// How long the daemon waits for a client to send its request or receive the reply.
+#[cfg(unix)]
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[cfg(not(unix))]
+pub(crate) fn serve<H>(_socket: &Path, _handle: H) -> io::Result<()>
+where
+    H: FnMut(&str) -> ControlFlow<String, String>,
+{
+    let msg = "the daemon is only supported on Unix";
+    Err(io::Error::new(io::ErrorKind::Unsupported, msg))
+}
+
+#[cfg(unix)]
+use std::fs;
+use std::io;
+#[cfg(unix)]
+use std::io::BufRead as _;
+#[cfg(unix)]
+use std::io::BufReader;
+#[cfg(unix)]
+use std::io::Write as _;
+use std::ops::ControlFlow;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+#[cfg(unix)]
+use std::time::Duration;
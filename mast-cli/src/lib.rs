@@ -0,0 +1,1008 @@
+//! A standard command-line interface for Mast build programs.
+//!
+//! Rather than every project reimplementing argument parsing and etag persistence,
+//! a build program can hand its pipeline to [`Cli`] and get the following subcommands:
+//!
+//! - `build [--json]`: bring the pipeline up to date once.
+//! - `watch [--interval <ms>]`: keep the pipeline up to date, polling for changes.
+//!   With the `journal` feature and a [`Journal`](mast::fs::Journal) in the context,
+//!   this instead waits for the journal to report changes,
+//!   rebuilding once per burst of changes (see [`Cli::debounce`]).
+//!   With the `tui` feature and a `tui::Monitor`,
+//!   this displays a live tree of the observed assets.
+//! - `graph --dot`: print the pipeline’s graph in the Graphviz DOT language.
+//! - `clean [--dry-run]`: remove the pipeline’s outputs and its saved state,
+//!   and make a running daemon forget the etags it keeps in memory.
+//! - `daemon [--stop]`: on Unix, keep the etags in memory in a long-lived process
+//!   listening on a socket (see [`Cli::socket_path`]), or tell a running daemon to stop.
+//!   While a daemon is running, `build` asks it to build instead of building in-process,
+//!   so repeated invocations neither start cold nor reload the saved state.
+//!   A daemon started by a different build of the build program,
+//!   or with a different [salt](Cli::salt), is stopped instead, and `build` builds in-process;
+//!   start the daemon again to keep the new pipeline warm.
+//! - `status`: report whether a daemon is running and what it last did.
+//!
+//! With a [`Diagnostics`] in the context,
+//! the diagnostics emitted during each build are printed to standard error once it finishes,
+//! and any errors among them fail the build.
+//! A [`FailurePolicy`] in the context can make warnings fail it too,
+//! and lets pipelines keep going past failures to report all of them at once.
+//! `build --json` instead prints them to standard output as JSON Lines,
+//! one [object](mast::diagnostic::Diagnostic::to_json) per line.
+//!
+//! # Examples
+//!
+//! A typical `src/bin/mast.rs`:
+//!
+//! ```no_run
+//! use mast::pipeline::Pipeline;
+//! use std::process::ExitCode;
+//!
+//! fn pipeline() -> Pipeline {
+//!     Pipeline::new()
+//!         .source("post", "content/post.md")
+//!         .transform("html", "markdown", "post")
+//!         .sink("public/post.html", "html")
+//! }
+//!
+//! fn main() -> ExitCode {
+//!     let mut registry = mast::pipeline::Registry::new();
+//!     registry.register("markdown", |input| Ok(input.to_owned()));
+//!     let cx = (registry,);
+//!
+//!     mast_cli::Cli::new(pipeline)
+//!         .context(mast::asset::Context::from_tuple(&cx))
+//!         .outputs(pipeline().outputs())
+//!         .dot(pipeline().to_dot())
+//!         .main()
+//! }
+//! ```
+#![warn(
+    noop_method_call,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    missing_docs,
+    missing_debug_implementations,
+    clippy::pedantic
+)]
+// We put `use` declarations at the bottom of modules, after any tests.
+#![allow(clippy::items_after_test_module)]
+
+#[cfg(feature = "tui")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "tui")))]
+pub mod tui;
+
+mod daemon;
+
+/// A command-line interface for a pipeline.
+///
+/// `pipeline` is a function that constructs the root asset of the build;
+/// it is called once per build.
+/// Etags are persisted between runs in a state file,
+/// `.mast-state` in the working directory by default.
+/// While building or cleaning, a [`Lock`] is held on the state file’s path with `.lock` appended,
+/// so that concurrent invocations wait for each other
+/// rather than corrupting the state or interleaving writes to outputs.
+#[derive(Debug)]
+pub struct Cli<'cx, F> {
+    pipeline: F,
+    cx: Context<'cx>,
+    state_path: PathBuf,
+    socket_path: Option<PathBuf>,
+    salt: Digest,
+    lock_policy: LockPolicy,
+    outputs: Vec<PathBuf>,
+    dot: Option<String>,
+    json: bool,
+    #[cfg(feature = "journal")]
+    debounce: Duration,
+    #[cfg(feature = "tui")]
+    monitor: Option<tui::Monitor>,
+}
+
+impl<F> Cli<'_, F> {
+    /// Construct a command-line interface for the given pipeline.
+    #[must_use]
+    pub fn new(pipeline: F) -> Self {
+        Self {
+            pipeline,
+            cx: Context::default(),
+            state_path: PathBuf::from(".mast-state"),
+            socket_path: None,
+            salt: Digest::default(),
+            lock_policy: LockPolicy::new().timeout(Duration::from_secs(60)),
+            outputs: Vec::new(),
+            dot: None,
+            json: false,
+            #[cfg(feature = "journal")]
+            debounce: Duration::from_millis(100),
+            #[cfg(feature = "tui")]
+            monitor: None,
+        }
+    }
+}
+
+impl<'cx, F> Cli<'cx, F> {
+    /// Set the context the pipeline is run in.
+    #[must_use]
+    pub fn context(mut self, cx: Context<'cx>) -> Self {
+        self.cx = cx;
+        self
+    }
+
+    /// Set the path of the file etags are saved to between runs.
+    #[must_use]
+    pub fn state_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.state_path = path.into();
+        self
+    }
+
+    /// Set the path of the socket the `daemon` listens on.
+    ///
+    /// By default, this is the state file’s path with `.sock` appended.
+    #[must_use]
+    pub fn socket_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.socket_path = Some(path.into());
+        self
+    }
+
+    /// Salt the saved state with the given bytes.
+    ///
+    /// State saved under a different salt is discarded,
+    /// so changing the salt forces a full rebuild.
+    /// This is useful for invalidating everything when the build logic itself changes,
+    /// for example by salting with the modification time of the executable:
+    ///
+    /// ```
+    /// use mast::Etag as _;
+    /// # let pipeline = || mast::pipeline::Pipeline::new();
+    ///
+    /// let cli = mast_cli::Cli::new(pipeline).salt(mast::time::exe_modified().to_vec());
+    /// ```
+    #[must_use]
+    pub fn salt<B: AsRef<[u8]>>(mut self, bytes: B) -> Self {
+        self.salt = Sha256::digest(bytes.as_ref());
+        self
+    }
+
+    /// Set how to wait for other invocations holding the lock.
+    ///
+    /// By default, the lock is waited for for up to a minute and never stolen.
+    #[must_use]
+    pub fn lock_policy(mut self, policy: LockPolicy) -> Self {
+        self.lock_policy = policy;
+        self
+    }
+
+    /// Set the files and directories the pipeline writes to, which `clean` will remove.
+    #[must_use]
+    pub fn outputs<I>(mut self, outputs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        self.outputs = outputs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the DOT graph printed by `graph --dot`.
+    ///
+    /// Without this, `graph` fails.
+    #[must_use]
+    pub fn dot<S: Into<String>>(mut self, dot: S) -> Self {
+        self.dot = Some(dot.into());
+        self
+    }
+
+    /// Set how long `watch` mode waits for a burst of changes to settle before rebuilding,
+    /// when there is a [`Journal`](mast::fs::Journal) in the context.
+    ///
+    /// Defaults to 100 milliseconds.
+    /// Changes the journal is set to [`ignore`](mast::fs::Journal::ignore)
+    /// never trigger a rebuild.
+    #[cfg(feature = "journal")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "journal")))]
+    #[must_use]
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Display the progress of `watch` mode with the given monitor.
+    ///
+    /// The monitor’s [`observer`](tui::Monitor::observer) must also be in the context.
+    #[cfg(feature = "tui")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "tui")))]
+    #[must_use]
+    pub fn monitor(mut self, monitor: tui::Monitor) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    /// Run the command given by the process’s arguments,
+    /// printing any error to standard error.
+    #[must_use]
+    pub fn main<A, E, O>(self) -> ExitCode
+    where
+        F: FnMut() -> A,
+        A: for<'c> Asset<'c, Etag = E, Output = O>,
+        E: Etag,
+        O: Outcome,
+    {
+        match self.run(env::args_os().skip(1)) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {e}");
+                if let Error::Usage(_) = e {
+                    eprintln!("\n{USAGE}");
+                }
+                ExitCode::FAILURE
+            }
+        }
+    }
+
+    /// Run the command given by `args`, not including the program name.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the arguments are invalid or the command fails.
+    pub fn run<A, E, O, I>(mut self, args: I) -> Result<(), Error>
+    where
+        F: FnMut() -> A,
+        A: for<'c> Asset<'c, Etag = E, Output = O>,
+        E: Etag,
+        O: Outcome,
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+        let args = args
+            .iter()
+            .map(|arg| {
+                arg.to_str()
+                    .ok_or_else(|| usage(format!("invalid argument {arg:?}")))
+            })
+            .collect::<Result<Vec<&str>, _>>()?;
+
+        match *args {
+            ["build"] => {
+                let request = format!("build {}", self.handshake());
+                match daemon::request(&self.socket(), &request).map_err(Error::Io)? {
+                    Some(reply) if reply == "stale" => {
+                        eprintln!("stopped a daemon running a different build of the pipeline");
+                    }
+                    Some(reply) => {
+                        if let Some(msg) = reply.strip_prefix("error: ") {
+                            return Err(Error::Daemon(msg.to_owned()));
+                        }
+                        println!("{reply}");
+                        return Ok(());
+                    }
+                    None => {}
+                }
+                let built = self.build()?;
+                println!("{}", if built { "built" } else { "up to date" });
+                Ok(())
+            }
+            ["build", "--json"] => {
+                self.json = true;
+                self.build().map(drop)
+            }
+            ["watch"] => self.watch(Duration::from_millis(500)),
+            ["watch", "--interval", ms] => {
+                let ms = ms.parse().map_err(|_| usage("invalid interval"))?;
+                self.watch(Duration::from_millis(ms))
+            }
+            ["graph", "--dot"] => {
+                print!("{}", self.dot.as_ref().ok_or(Error::NoGraph)?);
+                Ok(())
+            }
+            ["daemon"] => self.daemon(),
+            ["daemon", "--stop"] => {
+                let reply = daemon::request(&self.socket(), "stop").map_err(Error::Io)?;
+                println!("{}", reply.as_deref().unwrap_or("no daemon running"));
+                Ok(())
+            }
+            ["status"] => {
+                let reply = daemon::request(&self.socket(), "status").map_err(Error::Io)?;
+                println!("{}", reply.as_deref().unwrap_or("no daemon running"));
+                Ok(())
+            }
+            ["clean"] => self.clean(false),
+            ["clean", "--dry-run"] => self.clean(true),
+            ["help"] => {
+                println!("{USAGE}");
+                Ok(())
+            }
+            [command @ ("build" | "watch" | "graph" | "clean" | "daemon" | "status" | "help"), ..] => {
+                Err(usage(format!("invalid arguments to `{command}`")))
+            }
+            [command, ..] => Err(usage(format!("unknown command `{command}`"))),
+            [] => Err(usage("no command given")),
+        }
+    }
+
+    fn watch<A, E, O>(&mut self, interval: Duration) -> Result<(), Error>
+    where
+        F: FnMut() -> A,
+        A: for<'c> Asset<'c, Etag = E, Output = O>,
+        E: Etag,
+        O: Outcome,
+    {
+        loop {
+            #[cfg(feature = "tui")]
+            if let Some(monitor) = self.monitor.clone() {
+                monitor.begin();
+                match self.build() {
+                    Ok(true) => monitor.draw(),
+                    Ok(false) => {}
+                    Err(e) => {
+                        monitor.fail(&e);
+                        monitor.draw();
+                    }
+                }
+                self.wait(interval);
+                continue;
+            }
+            match self.build() {
+                Ok(true) => println!("built"),
+                Ok(false) => {}
+                Err(e) => eprintln!("error: {e}"),
+            }
+            self.wait(interval);
+        }
+    }
+
+    /// Serve build requests until told to stop, keeping the etags in memory between builds.
+    fn daemon<A, E, O>(&mut self) -> Result<(), Error>
+    where
+        F: FnMut() -> A,
+        A: for<'c> Asset<'c, Etag = E, Output = O>,
+        E: Etag,
+        O: Outcome,
+    {
+        let socket = self.socket();
+        let handshake = self.handshake();
+        let mut warm = None;
+        let mut builds = 0_u64;
+        let mut last = String::from("none");
+        println!("listening on {}", socket.display());
+        daemon::serve(&socket, |request| match request.split_once(' ') {
+            Some(("build", theirs)) if theirs == handshake => {
+                builds += 1;
+                let reply = match self.build_warm(&mut warm) {
+                    Ok(true) => String::from("built"),
+                    Ok(false) => String::from("up to date"),
+                    Err(e) => format!("error: {e}"),
+                };
+                last.clone_from(&reply);
+                ControlFlow::Continue(reply)
+            }
+            // Building with this daemon's pipeline would be wrong, so make way for the client's.
+            Some(("build", _)) => ControlFlow::Break(String::from("stale")),
+            _ => match request {
+                "reset" => {
+                    warm = None;
+                    ControlFlow::Continue(String::from("reset"))
+                }
+                "status" => {
+                    ControlFlow::Continue(format!("running; {builds} builds; last: {last}"))
+                }
+                "stop" => ControlFlow::Break(String::from("stopped")),
+                _ => ControlFlow::Continue(format!("error: unknown request {request:?}")),
+            },
+        })
+        .map_err(Error::Io)
+    }
+
+    /// What identifies the pipeline to the daemon:
+    /// builds are only handed to a daemon running the same build of the build program,
+    /// with the same version of this crate and the same [salt](Self::salt).
+    fn handshake(&self) -> String {
+        format!(
+            "{} {} {}",
+            env!("CARGO_PKG_VERSION"),
+            exe_identity(),
+            self.salt
+        )
+    }
+
+    fn socket(&self) -> PathBuf {
+        self.socket_path.clone().unwrap_or_else(|| {
+            let mut path = self.state_path.clone().into_os_string();
+            path.push(".sock");
+            PathBuf::from(path)
+        })
+    }
+
+    /// Wait until it is time for the next build in `watch` mode.
+    #[cfg_attr(not(feature = "journal"), allow(clippy::unused_self))]
+    fn wait(&self, interval: Duration) {
+        #[cfg(feature = "journal")]
+        if let Some(journal) = self.cx.try_get::<mast::fs::Journal>() {
+            return journal.wait(self.debounce);
+        }
+        thread::sleep(interval);
+    }
+
+    /// Bring the pipeline up to date, returning whether anything was regenerated.
+    fn build<A, E, O>(&mut self) -> Result<bool, Error>
+    where
+        F: FnMut() -> A,
+        A: for<'c> Asset<'c, Etag = E, Output = O>,
+        E: Etag,
+        O: Outcome,
+    {
+        self.build_warm(&mut None)
+    }
+
+    /// Like [`Self::build`], but starting from the etag in `warm` if there is one
+    /// rather than loading it from the state file,
+    /// and leaving the etag after the build in `warm`.
+    fn build_warm<A, E, O>(&mut self, warm: &mut Option<E>) -> Result<bool, Error>
+    where
+        F: FnMut() -> A,
+        A: for<'c> Asset<'c, Etag = E, Output = O>,
+        E: Etag,
+        O: Outcome,
+    {
+        let _lock = self.lock()?;
+        let mut etag = match warm.take() {
+            Some(etag) => {
+                if let Some(keys) = self.cx.try_get::<Keys>() {
+                    keys.begin(&etag.to_vec());
+                }
+                etag
+            }
+            None => self.load()?,
+        };
+        let Tracked { value, delta } = (self.pipeline)().update(self.cx, &mut etag);
+        if delta == Delta::Same {
+            drop(value);
+            *warm = Some(etag);
+            self.report()?;
+            return Ok(false);
+        }
+        let outcome = value.generate().into_result();
+        let reported = self.report();
+        // The state is only saved once the build has succeeded,
+        // so that a failed build is retried next time rather than considered up to date,
+        // and any diagnostics that failed it are emitted again.
+        outcome.map_err(Error::Build)?;
+        reported?;
+        let state = Salted::new(self.salt, etag).to_vec();
+        self.save(&state).map_err(Error::Io)?;
+        *warm = Salted::<E>::from_bytes(&state)
+            .ok()
+            .map(|salted| salted.etag);
+        Ok(true)
+    }
+
+    /// Load the etag from the state file, preparing any [`Keys`] in the context.
+    fn load<E: Etag>(&self) -> Result<E, Error> {
+        let bytes = match fs::read(&self.state_path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::Io(e)),
+        };
+        // Unreadable state is discarded; everything will simply be rebuilt.
+        let etag = Salted::<E>::from_bytes(&bytes)
+            .map_or_else(|_| E::default(), |salted| salted.unsalt(&self.salt));
+        if let Some(keys) = self.cx.try_get::<Keys>() {
+            // Keyed etags can still be recovered from state of a different shape,
+            // but not from state with a different salt.
+            let salt = Digest::deserialize(&mut Reader::new(&bytes));
+            keys.begin(if salt.ok() == Some(self.salt) {
+                &bytes
+            } else {
+                &[]
+            });
+        }
+        Ok(etag)
+    }
+
+    /// Replace the state file atomically,
+    /// so that a crash while saving leaves the previous state intact.
+    fn save(&self, state: &[u8]) -> io::Result<()> {
+        let mut tmp = self.state_path.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", process::id()));
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(state)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.state_path)
+    }
+
+    /// Print the diagnostics emitted since the last report,
+    /// failing if any of them are errors.
+    fn report(&self) -> Result<(), Error> {
+        let Some(diagnostics) = self.cx.try_get::<Diagnostics>() else {
+            return Ok(());
+        };
+        let policy = self
+            .cx
+            .try_get::<FailurePolicy>()
+            .copied()
+            .unwrap_or_default();
+        let mut errors = 0;
+        for diagnostic in diagnostics.take() {
+            if policy.fails(diagnostic.severity) {
+                errors += 1;
+            }
+            if self.json {
+                println!("{}", diagnostic.to_json());
+            } else {
+                eprintln!("{diagnostic}");
+            }
+        }
+        match errors {
+            0 => Ok(()),
+            errors => Err(Error::Diagnostics(errors)),
+        }
+    }
+
+    fn clean(&self, dry_run: bool) -> Result<(), Error> {
+        let _lock = self.lock()?;
+        for path in self.outputs.iter().chain([&self.state_path]) {
+            if !path.exists() {
+                continue;
+            }
+            if dry_run {
+                println!("would remove {}", path.display());
+            } else {
+                let metadata = fs::symlink_metadata(path).map_err(Error::Io)?;
+                if metadata.is_dir() {
+                    fs::remove_dir_all(path).map_err(Error::Io)?;
+                } else {
+                    fs::remove_file(path).map_err(Error::Io)?;
+                }
+                println!("removed {}", path.display());
+            }
+        }
+        // A running daemon would otherwise keep building from the etags it has in memory,
+        // considering the removed outputs up to date.
+        if !dry_run
+            && daemon::request(&self.socket(), "reset")
+                .map_err(Error::Io)?
+                .is_some()
+        {
+            println!("reset the daemon");
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<Lock, Error> {
+        let mut path = self.state_path.clone().into_os_string();
+        path.push(".lock");
+        Lock::acquire(path, &self.lock_policy).map_err(Error::Io)
+    }
+}
+
+const USAGE: &str = "\
+usage: mast <command>
+
+commands:
+    build [--json]              bring the pipeline up to date
+    watch [--interval <ms>]     keep the pipeline up to date
+    graph --dot                 print the pipeline graph
+    clean [--dry-run]           remove generated files
+    daemon [--stop]             keep the pipeline warm in a background process
+    status                      report on the running daemon
+    help                        print this message";
+
+/// The output of a pipeline run by the [`Cli`],
+/// which can be reported as either success or failure.
+///
+/// Results are reported with their error’s [`Display`] implementation;
+/// to also report the chain of [sources](std::error::Error::source) of an error
+/// that does not include them itself, wrap it in [`Sources`].
+pub trait Outcome {
+    /// Convert the outcome to a result, with the error message in the error case.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the outcome represents a failure.
+    fn into_result(self) -> Result<(), String>;
+}
+
+impl Outcome for () {
+    fn into_result(self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl<T, E: Display> Outcome for Result<T, E> {
+    fn into_result(self) -> Result<(), String> {
+        self.map(drop).map_err(|e| e.to_string())
+    }
+}
+
+impl<T, E: std::error::Error> Outcome for Result<T, Sources<E>> {
+    fn into_result(self) -> Result<(), String> {
+        self.map(drop).map_err(|Sources(e)| {
+            let mut msg = e.to_string();
+            let mut source = e.source();
+            while let Some(e) = source {
+                write!(msg, ": {e}").unwrap();
+                source = e.source();
+            }
+            msg
+        })
+    }
+}
+
+/// An error whose chain of [sources](std::error::Error::source) is reported
+/// when it is the error of an [`Outcome`].
+///
+/// # Examples
+///
+/// ```
+/// use mast_cli::Outcome as _;
+/// use mast_cli::Sources;
+/// use std::error::Error;
+/// use std::fmt;
+/// use std::io;
+///
+/// #[derive(Debug)]
+/// struct Render(io::Error);
+/// impl fmt::Display for Render {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         f.write_str("could not render the page")
+///     }
+/// }
+/// impl Error for Render {
+///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+///         Some(&self.0)
+///     }
+/// }
+///
+/// let e = Render(io::Error::new(io::ErrorKind::NotFound, "no such template"));
+/// assert_eq!(Err::<(), _>(&e).into_result().unwrap_err(), "could not render the page");
+/// assert_eq!(
+///     Err::<(), _>(Sources(e)).into_result().unwrap_err(),
+///     "could not render the page: no such template",
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Sources<E>(pub E);
+
+/// An error running a [`Cli`] command.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The command-line arguments were invalid.
+    Usage(String),
+    /// An I/O error occurred.
+    Io(io::Error),
+    /// The pipeline failed.
+    Build(String),
+    /// `graph` was requested, but no graph was provided.
+    NoGraph,
+    /// The daemon reported that the build failed with this message.
+    Daemon(String),
+    /// The pipeline emitted this many diagnostics that fail the build
+    /// under the [`FailurePolicy`] in the context.
+    Diagnostics(usize),
+}
+
+fn usage<S: Into<String>>(msg: S) -> Error {
+    Error::Usage(msg.into())
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(msg) => f.write_str(msg),
+            Self::Io(e) => Display::fmt(e, f),
+            Self::Build(msg) => write!(f, "build failed: {msg}"),
+            Self::Daemon(msg) => write!(f, "daemon: {msg}"),
+            Self::NoGraph => f.write_str("this pipeline does not describe its graph"),
+            Self::Diagnostics(1) => f.write_str("build failed: 1 error reported"),
+            Self::Diagnostics(n) => write!(f, "build failed: {n} errors reported"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A digest of the path, size and modification time of the running executable,
+/// which changes whenever the build program is rebuilt.
+fn exe_identity() -> Digest {
+    let identity = env::current_exe().and_then(|exe| {
+        let metadata = fs::metadata(&exe)?;
+        Ok(format!(
+            "{} {} {:?}",
+            exe.display(),
+            metadata.len(),
+            metadata.modified()?
+        ))
+    });
+    Sha256::digest(identity.unwrap_or_default().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn build_and_clean() {
+        let dir = env::temp_dir().join(format!("mast-cli-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("in"), "Hello").unwrap();
+        let pipeline = || {
+            Pipeline::new()
+                .source("in", dir.join("in"))
+                .transform("out", "shout", "in")
+                .sink(dir.join("out"), "out")
+        };
+        let mut registry = Registry::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        registry.register("shout", move |input| {
+            counter.fetch_add(1, atomic::Ordering::Relaxed);
+            Ok(input.to_ascii_uppercase())
+        });
+        let cx = (registry,);
+
+        let cli = || {
+            Cli::new(pipeline)
+                .context(Context::from_tuple(&cx))
+                .state_path(dir.join("state"))
+                .outputs(pipeline().outputs())
+        };
+
+        cli().run(["build"]).unwrap();
+        assert_eq!(fs::read(dir.join("out")).unwrap(), b"HELLO");
+        assert!(dir.join("state").exists());
+
+        // Nothing is regenerated unless the salt changes.
+        cli().run(["build"]).unwrap();
+        assert_eq!(runs.load(atomic::Ordering::Relaxed), 1);
+        cli().salt("v2").run(["build"]).unwrap();
+        assert_eq!(runs.load(atomic::Ordering::Relaxed), 2);
+
+        let lock = Lock::acquire(dir.join("state.lock"), &LockPolicy::new()).unwrap();
+        let policy = LockPolicy::new().timeout(Duration::ZERO);
+        let res = cli().lock_policy(policy).run(["build"]);
+        assert!(matches!(res, Err(Error::Io(e)) if e.kind() == io::ErrorKind::TimedOut));
+        drop(lock);
+
+        cli().run(["clean", "--dry-run"]).unwrap();
+        assert!(dir.join("out").exists());
+        cli().run(["clean"]).unwrap();
+        assert!(!dir.join("out").exists());
+        assert!(!dir.join("state").exists());
+
+        // Directory outputs are removed with their contents.
+        fs::create_dir_all(dir.join("site/posts")).unwrap();
+        fs::write(dir.join("site/posts/index.html"), "").unwrap();
+        cli().outputs([dir.join("site")]).run(["clean"]).unwrap();
+        assert!(!dir.join("site").exists());
+
+        assert!(matches!(cli().run(["graph", "--dot"]), Err(Error::NoGraph)));
+        assert!(matches!(cli().run(["bild"]), Err(Error::Usage(_))));
+        assert!(matches!(cli().run(["clean", "-n"]), Err(Error::Usage(_))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn retry_failed() {
+        static FAIL: AtomicBool = AtomicBool::new(true);
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = env::temp_dir().join(format!("mast-cli-test-retry-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cli = || {
+            Cli::new(|| {
+                asset::constant(()).version(1).map(|()| {
+                    RUNS.fetch_add(1, atomic::Ordering::Relaxed);
+                    if FAIL.load(atomic::Ordering::Relaxed) {
+                        Err("flaky")
+                    } else {
+                        Ok(())
+                    }
+                })
+            })
+            .state_path(dir.join("state"))
+        };
+
+        assert!(matches!(cli().run(["build"]), Err(Error::Build(_))));
+        assert!(!dir.join("state").exists());
+
+        FAIL.store(false, atomic::Ordering::Relaxed);
+        cli().run(["build"]).unwrap();
+        assert_eq!(RUNS.load(atomic::Ordering::Relaxed), 2);
+        cli().run(["build"]).unwrap();
+        assert_eq!(RUNS.load(atomic::Ordering::Relaxed), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn diagnostics() {
+        struct Lint(Severity);
+        impl<'c> Asset<'c> for Lint {
+            type Etag = ();
+            type Output = ();
+            type Generator = fn();
+            fn update(self, cx: Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+                let location = Location::new("post.md").line(1);
+                diagnostic::emit(cx, Diagnostic::new(self.0, "bad link").at(location));
+                Delta::Modified.track(|| {})
+            }
+        }
+
+        let dir = env::temp_dir().join(format!("mast-cli-test-diagnostics-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cx = (Diagnostics::new(),);
+        let cli = |severity| {
+            Cli::new(move || Lint(severity))
+                .context(Context::from_tuple(&cx))
+                .state_path(dir.join("state"))
+        };
+
+        cli(Severity::Warning).run(["build"]).unwrap();
+        cli(Severity::Warning).run(["build", "--json"]).unwrap();
+        fs::remove_file(dir.join("state")).unwrap();
+        let res = cli(Severity::Error).run(["build"]);
+        assert!(matches!(res, Err(Error::Diagnostics(1))));
+        assert!(cx.0.take().is_empty());
+        assert!(!dir.join("state").exists());
+
+        let strict = (
+            Diagnostics::new(),
+            FailurePolicy::new().warnings_as_errors(true),
+        );
+        let res = Cli::new(|| Lint(Severity::Warning))
+            .context(Context::from_tuple(&strict))
+            .state_path(dir.join("state"))
+            .run(["build"]);
+        assert!(matches!(res, Err(Error::Diagnostics(1))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn daemon() {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        struct Count;
+        impl<'c> Asset<'c> for Count {
+            type Etag = bool;
+            type Output = ();
+            type Generator = Box<dyn FnOnce() + 'c>;
+            fn update(self, _: Context<'c>, built: &'c mut bool) -> Tracked<Self::Generator> {
+                Delta::cmp(built, &true).track(Box::new(move || {
+                    RUNS.fetch_add(1, atomic::Ordering::Relaxed);
+                    *built = true;
+                }))
+            }
+        }
+
+        let dir = env::temp_dir().join(format!("mast-cli-test-daemon-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cli = || Cli::new(|| Count).state_path(dir.join("state"));
+
+        cli().run(["status"]).unwrap();
+        thread::scope(|s| {
+            let daemon = s.spawn(|| cli().run(["daemon"]));
+            while !dir.join("state.sock").exists() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            assert!(matches!(cli().run(["daemon"]), Err(Error::Io(_))));
+
+            cli().run(["build"]).unwrap();
+            cli().run(["build"]).unwrap();
+            cli().run(["status"]).unwrap();
+            assert_eq!(RUNS.load(atomic::Ordering::Relaxed), 1);
+            // The daemon kept the etag in memory, but also saved it.
+            assert!(dir.join("state").exists());
+
+            // Cleaning makes the daemon forget its etags too.
+            cli().run(["clean"]).unwrap();
+            cli().run(["build"]).unwrap();
+            assert_eq!(RUNS.load(atomic::Ordering::Relaxed), 2);
+
+            cli().run(["daemon", "--stop"]).unwrap();
+            daemon.join().unwrap().unwrap();
+        });
+        assert!(!dir.join("state.sock").exists());
+
+        // Without the daemon, builds resume from the saved state.
+        cli().run(["build"]).unwrap();
+        assert_eq!(RUNS.load(atomic::Ordering::Relaxed), 2);
+
+        // A build program with a different pipeline never uses the daemon’s:
+        // the daemon is stopped, and the build happens in-process.
+        thread::scope(|s| {
+            let daemon = s.spawn(|| cli().run(["daemon"]));
+            while !dir.join("state.sock").exists() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            cli().salt("v2").run(["build"]).unwrap();
+            daemon.join().unwrap().unwrap();
+            assert_eq!(RUNS.load(atomic::Ordering::Relaxed), 3);
+        });
+        assert!(!dir.join("state.sock").exists());
+
+        // Its replacement builds with the new pipeline.
+        thread::scope(|s| {
+            let daemon = s.spawn(|| cli().salt("v2").run(["daemon"]));
+            while !dir.join("state.sock").exists() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            cli().salt("v2").run(["build"]).unwrap();
+            assert_eq!(RUNS.load(atomic::Ordering::Relaxed), 3);
+            cli().run(["daemon", "--stop"]).unwrap();
+            daemon.join().unwrap().unwrap();
+        });
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use super::Cli;
+    use super::Error;
+    use mast::asset;
+    use mast::asset::Context;
+    use mast::diagnostic;
+    use mast::diagnostic::Diagnostic;
+    use mast::diagnostic::Diagnostics;
+    use mast::diagnostic::FailurePolicy;
+    use mast::diagnostic::Location;
+    use mast::diagnostic::Severity;
+    use mast::pipeline::Pipeline;
+    use mast::pipeline::Registry;
+    use mast::store::Lock;
+    use mast::store::LockPolicy;
+    use mast::Asset;
+    use mast::Delta;
+    use mast::Tracked;
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::sync::atomic;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+}
+
+use mast::asset::Context;
+use mast::asset::Generator as _;
+use mast::asset::Keys;
+use mast::diagnostic::Diagnostics;
+use mast::diagnostic::FailurePolicy;
+use mast::etag::Reader;
+use mast::etag::Salted;
+use mast::hash::Digest;
+use mast::hash::Sha256;
+use mast::store::Lock;
+use mast::store::LockPolicy;
+use mast::Asset;
+use mast::Delta;
+use mast::Etag;
+use mast::Tracked;
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::process;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
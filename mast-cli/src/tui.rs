@@ -0,0 +1,189 @@
+//! A live terminal display of the asset tree, used by `watch` mode.
+
+/// A build monitor, which records the progress of observed assets
+/// and renders it as a tree.
+///
+/// Place the [`Observer`] returned by [`Self::observer`] in the pipeline’s [`Context`],
+/// and pass the monitor to [`Cli::monitor`](crate::Cli::monitor).
+/// Only assets wrapped with [`Asset::observe`] appear in the tree.
+///
+/// [`Context`]: mast::asset::Context
+/// [`Asset::observe`]: mast::Asset::observe
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// let monitor = mast_cli::tui::Monitor::new();
+/// let cx = (monitor.observer(),);
+/// let cx = asset::Context::from_tuple(&cx);
+///
+/// let mut etag = Default::default();
+/// let args = asset::cli_args().observe("args");
+/// args.update(cx, &mut etag).value.generate();
+/// assert!(monitor.render().contains("args"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Monitor {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    rows: Vec<Row>,
+    /// The indices of the rows currently being updated, innermost last.
+    stack: Vec<usize>,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Row {
+    depth: usize,
+    name: String,
+    status: Status,
+    update: Option<Duration>,
+    generate: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Updating,
+    Cached,
+    Stale,
+    Generating,
+    Generated,
+}
+
+impl Monitor {
+    /// Construct a new monitor with no recorded assets.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Obtain an observer that records events into this monitor.
+    #[must_use]
+    pub fn observer(&self) -> Observer {
+        let state = self.state.clone();
+        Observer::new(move |event| lock(&state).record(event))
+    }
+
+    /// Clear the recorded assets in preparation for a new build.
+    pub fn begin(&self) {
+        let mut state = lock(&self.state);
+        state.rows.clear();
+        state.stack.clear();
+        state.error = None;
+    }
+
+    /// Record that the build failed with the given error.
+    pub fn fail(&self, error: &dyn Display) {
+        lock(&self.state).error = Some(error.to_string());
+    }
+
+    /// Render the current state of the build as text.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let state = lock(&self.state);
+        let mut out = String::new();
+        for row in &state.rows {
+            let indent = row.depth * 2;
+            let width = 32_usize.saturating_sub(indent);
+            let status = match row.status {
+                Status::Updating => "checking",
+                Status::Cached => "cached",
+                Status::Stale => "stale",
+                Status::Generating => "building",
+                Status::Generated => "built",
+            };
+            write!(out, "{:indent$}{:width$} {status:8}", "", row.name).unwrap();
+            if let Some(update) = row.update {
+                write!(out, " check {}", Ms(update)).unwrap();
+            }
+            if let Some(generate) = row.generate {
+                write!(out, " build {}", Ms(generate)).unwrap();
+            }
+            out.push('\n');
+        }
+        if let Some(error) = &state.error {
+            writeln!(out, "\nerror: {error}").unwrap();
+        }
+        out
+    }
+
+    /// Clear the terminal and draw the current state of the build.
+    pub(crate) fn draw(&self) {
+        // Clear the screen and move the cursor to the top left.
+        print!("\x1b[2J\x1b[H{}", self.render());
+    }
+}
+
+impl State {
+    fn record(&mut self, event: &Event<'_>) {
+        match *event {
+            Event::UpdateStart { name } => {
+                self.rows.push(Row {
+                    depth: self.stack.len(),
+                    name: name.to_owned(),
+                    status: Status::Updating,
+                    update: None,
+                    generate: None,
+                });
+                self.stack.push(self.rows.len() - 1);
+            }
+            Event::UpdateEnd { delta, elapsed, .. } => {
+                if let Some(row) = self.stack.pop().map(|i| &mut self.rows[i]) {
+                    row.status = match delta {
+                        Delta::Same => Status::Cached,
+                        Delta::Modified => Status::Stale,
+                    };
+                    row.update = Some(elapsed);
+                }
+            }
+            Event::GenerateStart { name } => {
+                if let Some(row) = self.find(name) {
+                    row.status = Status::Generating;
+                }
+            }
+            Event::GenerateEnd { name, elapsed } => {
+                if let Some(row) = self.find(name) {
+                    row.status = Status::Generated;
+                    row.generate = Some(elapsed);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn find(&mut self, name: &str) -> Option<&mut Row> {
+        self.rows.iter_mut().rev().find(|row| row.name == name)
+    }
+}
+
+struct Ms(Duration);
+
+impl Display for Ms {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1}ms", self.0.as_secs_f64() * 1000.0)
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+use mast::observe::Event;
+use mast::observe::Observer;
+use mast::Delta;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::time::Duration;
@@ -0,0 +1,157 @@
+//! Derive macros for Mast.
+//!
+//! These are re-exported from `mast` when its `derive` feature is enabled,
+//! and should be used through there rather than by depending on this crate directly.
+#![warn(
+    noop_method_call,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    missing_docs,
+    missing_debug_implementations,
+    clippy::pedantic
+)]
+// We put `use` declarations at the bottom of modules, after any tests.
+#![allow(clippy::items_after_test_module)]
+
+/// Implement `Asset` for a struct by delegating to one of its fields.
+///
+/// The struct’s etag, output and generator are those of the field,
+/// and updating the struct updates the field.
+/// This gives a distinct name to a stage of a pipeline,
+/// without writing out the trait implementation by hand.
+///
+/// A struct with a single field delegates to that field.
+/// A struct with more than one field must mark the one to delegate to with `#[asset]`;
+/// the other fields are dropped when the struct is updated.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset;
+///
+/// #[derive(Asset)]
+/// struct Greeting(asset::Constant<&'static str>);
+///
+/// #[derive(Asset)]
+/// struct Named<A> {
+///     name: &'static str,
+///     #[asset]
+///     inner: A,
+/// }
+///
+/// let cx = asset::Context::default();
+/// let mut etag = Default::default();
+/// let greeting = Named { name: "greeting", inner: Greeting(asset::constant("hello")) };
+/// assert_eq!(greeting.name, "greeting");
+/// assert_eq!(greeting.update(cx, &mut etag).value.generate(), "hello");
+/// ```
+#[proc_macro_derive(Asset, attributes(asset))]
+pub fn derive_asset(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    asset(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn asset(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`Asset` can only be derived for structs",
+        ));
+    };
+
+    let (index, field) = delegate(&data.fields).ok_or_else(|| {
+        syn::Error::new_spanned(&input.ident, "expected a field to delegate to")
+    })??;
+    let member = match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(index)),
+    };
+    let field_ty = &field.ty;
+
+    let lifetime = Lifetime::new("'__mast_c", Span::call_site());
+    let mut generics = input.generics.clone();
+    generics.params.insert(
+        0,
+        GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())),
+    );
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#field_ty: ::mast::Asset<#lifetime>));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let ident = &input.ident;
+
+    Ok(quote! {
+        impl #impl_generics ::mast::Asset<#lifetime> for #ident #ty_generics #where_clause {
+            type Etag = <#field_ty as ::mast::Asset<#lifetime>>::Etag;
+            type Output = <#field_ty as ::mast::Asset<#lifetime>>::Output;
+            type Generator = <#field_ty as ::mast::Asset<#lifetime>>::Generator;
+
+            fn update(
+                self,
+                cx: ::mast::asset::Context<#lifetime>,
+                etag: &#lifetime mut Self::Etag,
+            ) -> ::mast::Tracked<Self::Generator> {
+                ::mast::Asset::update(self.#member, cx, etag)
+            }
+        }
+    })
+}
+
+/// Find the field to delegate to: the one marked `#[asset]`, or else the only field.
+fn delegate(fields: &Fields) -> Option<syn::Result<(usize, &Field)>> {
+    let mut marked = fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| field.attrs.iter().any(|attr| attr.path().is_ident("asset")));
+
+    if let Some((index, field)) = marked.next() {
+        for attr in &field.attrs {
+            if attr.path().is_ident("asset") {
+                if let Err(e) = attr.meta.require_path_only() {
+                    return Some(Err(e));
+                }
+            }
+        }
+        if let Some((_, other)) = marked.next() {
+            return Some(Err(syn::Error::new_spanned(
+                other,
+                "only one field can be marked `#[asset]`",
+            )));
+        }
+        return Some(Ok((index, field)));
+    }
+
+    let mut fields = fields.iter();
+    match (fields.next(), fields.next()) {
+        (Some(field), None) => Some(Ok((0, field))),
+        (Some(_), Some(other)) => Some(Err(syn::Error::new_spanned(
+            other,
+            "mark the field to delegate to with `#[asset]`",
+        ))),
+        (None, _) => None,
+    }
+}
+
+use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_macro_input;
+use syn::parse_quote;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Field;
+use syn::Fields;
+use syn::GenericParam;
+use syn::Index;
+use syn::Lifetime;
+use syn::LifetimeParam;
+use syn::Member;
@@ -0,0 +1,722 @@
+//! Python bindings for authoring Mast pipelines.
+//!
+//! This crate builds a Python extension module named `mast`
+//! (use [`maturin`](https://www.maturin.rs) to build and install it).
+//! The module exposes a single class, `Builder`,
+//! onto which file and command assets are registered from Python;
+//! all change detection and etag persistence happens on the Rust side.
+//!
+//! ```python
+//! import mast
+//!
+//! b = mast.Builder("target/mast-state")
+//! b.file("main.c", "src/main.c")
+//! b.command(
+//!     "main",
+//!     ["cc", "-o", "target/main", "src/main.c"],
+//!     inputs=["main.c"],
+//!     outputs=["target/main"],
+//! )
+//! print(b.run())  # the names of the assets that changed, e.g. ["main.c", "main"]
+//! print(b.snapshot())  # a summary of that run, for diffing against other builds
+//! print(b.explain("main"))  # e.g. "main rebuilt because main.c changed; ..."
+//! b.run_targets(["target/main"])  # only run what is needed to produce `target/main`
+//! b.freeze("mast.lock")  # record the contents of every file, e.g. for a reviewed release
+//! print(b.verify("mast.lock"))  # the files that differ from it since, e.g. []
+//! ```
+#![warn(
+    noop_method_call,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    missing_docs,
+    missing_debug_implementations,
+    clippy::pedantic
+)]
+// We put `use` declarations at the bottom of modules, after any tests.
+#![allow(clippy::items_after_test_module)]
+
+/// A pipeline of file and command assets.
+///
+/// Assets are registered in order, and may only depend on assets registered before them,
+/// so the pipeline is always acyclic.
+#[pyclass(module = "mast")]
+#[derive(Debug)]
+pub struct Builder {
+    state_path: Option<PathBuf>,
+    salt: Digest,
+    state: State,
+    assets: Vec<Node>,
+    last_run: Vec<Record>,
+}
+
+#[derive(Debug)]
+enum Node {
+    File {
+        name: String,
+        path: PathBuf,
+    },
+    Command {
+        name: String,
+        argv: Vec<OsString>,
+        inputs: Vec<String>,
+        outputs: Vec<PathBuf>,
+    },
+}
+
+impl Node {
+    fn name(&self) -> &str {
+        match self {
+            Self::File { name, .. } | Self::Command { name, .. } => name,
+        }
+    }
+}
+
+/// What happened to a single asset in the most recent run.
+#[derive(Debug)]
+struct Record {
+    name: String,
+    etag: Digest,
+    /// Why the asset changed, or `None` if it did not.
+    reason: Option<Reason>,
+    elapsed: Duration,
+    outputs: Vec<PathBuf>,
+}
+
+/// Why an asset changed in a run.
+#[derive(Debug)]
+enum Reason {
+    /// The asset has no state from a previous run.
+    New,
+    /// The file’s size changed.
+    Size,
+    /// The file’s modification time changed.
+    Modified,
+    /// The file’s metadata could not be read, with this error message.
+    Unreadable(String),
+    /// The command’s arguments changed.
+    Arguments,
+    /// The named input of the command changed.
+    Input(String),
+}
+
+/// The etags of every asset, keyed by name.
+#[derive(Debug, Default)]
+struct State {
+    files: BTreeMap<String, Stamp>,
+    commands: BTreeMap<String, Digest>,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.files.serialize(writer);
+        self.commands.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            files: Etag::deserialize(reader)?,
+            commands: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[pymethods]
+impl Builder {
+    /// Create a new builder.
+    ///
+    /// If `state_path` is given, etags are loaded from it
+    /// and saved back to it at the end of every run.
+    /// State saved with a different `salt` (a `bytes` object) is discarded,
+    /// so changing the salt forces everything to be rebuilt.
+    #[new]
+    #[pyo3(signature = (state_path = None, salt = None))]
+    fn new(state_path: Option<PathBuf>, salt: Option<&[u8]>) -> PyResult<Self> {
+        let salt = Sha256::digest(salt.unwrap_or_default());
+        let state = match &state_path {
+            Some(path) => match std::fs::read(path) {
+                // Corrupt state is discarded; everything will simply be rebuilt.
+                Ok(bytes) => Salted::<State>::from_bytes(&bytes)
+                    .map_or_else(|_| State::default(), |salted| salted.unsalt(&salt)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => State::default(),
+                Err(e) => return Err(e.into()),
+            },
+            None => State::default(),
+        };
+        Ok(Self {
+            state_path,
+            salt,
+            state,
+            assets: Vec::new(),
+            last_run: Vec::new(),
+        })
+    }
+
+    /// Register the file at `path` as an asset called `name`.
+    fn file(&mut self, name: String, path: PathBuf) -> PyResult<()> {
+        self.check_name(&name)?;
+        self.assets.push(Node::File { name, path });
+        Ok(())
+    }
+
+    /// Register a command as an asset called `name`.
+    ///
+    /// The command is run whenever its arguments change
+    /// or any of the assets named in `inputs` change.
+    /// The files it writes may be listed in `outputs`,
+    /// which are reported by `snapshot`.
+    #[pyo3(signature = (name, argv, inputs = Vec::new(), outputs = Vec::new()))]
+    fn command(
+        &mut self,
+        name: String,
+        argv: Vec<OsString>,
+        inputs: Vec<String>,
+        outputs: Vec<PathBuf>,
+    ) -> PyResult<()> {
+        self.check_name(&name)?;
+        if argv.is_empty() {
+            return Err(PyValueError::new_err(
+                "command must have at least one argument",
+            ));
+        }
+        for input in &inputs {
+            if !self.assets.iter().any(|asset| asset.name() == input) {
+                return Err(PyValueError::new_err(format!("unknown input {input:?}")));
+            }
+        }
+        self.assets.push(Node::Command {
+            name,
+            argv,
+            inputs,
+            outputs,
+        });
+        Ok(())
+    }
+
+    /// Bring every asset up to date,
+    /// returning the names of the assets that changed.
+    ///
+    /// Raises `RuntimeError` if a command fails;
+    /// the failed command and everything that changed in this run
+    /// will be considered changed again in the next run.
+    fn run(&mut self, py: Python<'_>) -> PyResult<Vec<String>> {
+        self.run_only(py, None)
+    }
+
+    /// Like `run`, but only bring the given targets and the assets they depend on up to date.
+    ///
+    /// Each target is either the name of an asset
+    /// or one of the `outputs` declared by a command.
+    /// Every other asset is skipped entirely, and its state is kept for future runs,
+    /// except that a skipped command whose inputs changed will run the next time it is needed.
+    /// Raises `ValueError` if a target is unknown.
+    fn run_targets(&mut self, py: Python<'_>, targets: Vec<PathBuf>) -> PyResult<Vec<String>> {
+        let mut needed = BTreeSet::new();
+        for target in targets {
+            let asset = self.assets.iter().find(|asset| match asset {
+                Node::File { name, .. } => Path::new(name) == target,
+                Node::Command { name, outputs, .. } => {
+                    Path::new(name) == target || outputs.contains(&target)
+                }
+            });
+            let Some(asset) = asset else {
+                let target = target.display();
+                return Err(PyValueError::new_err(format!(
+                    "unknown target \"{target}\""
+                )));
+            };
+            needed.insert(asset.name().to_owned());
+        }
+        // Inputs always come before the assets that use them.
+        for asset in self.assets.iter().rev() {
+            if let Node::Command { name, inputs, .. } = asset {
+                if needed.contains(name) {
+                    needed.extend(inputs.iter().cloned());
+                }
+            }
+        }
+        self.run_only(py, Some(&needed))
+    }
+
+    /// Summarize the most recent run, for example to diff two builds in CI.
+    ///
+    /// Returns a list with a `dict` for every asset that was looked at, in order,
+    /// containing its `name`, a hex digest of its `etag`,
+    /// whether it `changed`, how many seconds it took (`elapsed`)
+    /// and the `outputs` it declared.
+    /// Only strings, booleans, floats and lists are used,
+    /// so the result can be passed straight to `json.dumps`.
+    fn snapshot<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.last_run
+            .iter()
+            .map(|record| {
+                let dict = PyDict::new(py);
+                dict.set_item("name", &record.name)?;
+                dict.set_item("etag", record.etag.to_string())?;
+                dict.set_item("changed", record.reason.is_some())?;
+                dict.set_item("elapsed", record.elapsed.as_secs_f64())?;
+                let outputs: Vec<String> = record
+                    .outputs
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect();
+                dict.set_item("outputs", outputs)?;
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// Explain why the asset called `name` changed in the most recent run,
+    /// following the chain of inputs back to the file that caused it,
+    /// e.g. `"main rebuilt because main.c changed; main.c changed because its size changed"`.
+    ///
+    /// Raises `ValueError` if the asset was not looked at in the most recent run.
+    fn explain(&self, name: &str) -> PyResult<String> {
+        let mut steps = Vec::new();
+        let mut name = name;
+        loop {
+            let Some(record) = self.last_run.iter().find(|record| record.name == name) else {
+                return Err(PyValueError::new_err(format!(
+                    "{name:?} was not looked at in the last run"
+                )));
+            };
+            let is_file = matches!(
+                self.assets.iter().find(|asset| asset.name() == name),
+                Some(Node::File { .. })
+            );
+            let verb = if is_file { "changed" } else { "rebuilt" };
+            let because = match &record.reason {
+                None => {
+                    steps.push(format!("{name} is up to date"));
+                    break;
+                }
+                Some(Reason::New) if is_file => "it is new",
+                Some(Reason::New) => "it has not run before",
+                Some(Reason::Size) => "its size changed",
+                Some(Reason::Modified) => "its modification time changed",
+                Some(Reason::Unreadable(error)) => {
+                    steps.push(format!(
+                        "{name} {verb} because it could not be read: {error}"
+                    ));
+                    break;
+                }
+                Some(Reason::Arguments) => "its arguments changed",
+                Some(Reason::Input(input)) => {
+                    steps.push(format!("{name} {verb} because {input} changed"));
+                    name = input;
+                    continue;
+                }
+            };
+            steps.push(format!("{name} {verb} because {because}"));
+            break;
+        }
+        Ok(steps.join("; "))
+    }
+
+    /// Record the digest of the contents of every file asset, as it is now,
+    /// in a lock file at `lock_path`.
+    ///
+    /// Commands are not run and the builder’s state is not touched;
+    /// the lock can later be checked against with `verify`,
+    /// for example to ensure a release is built from exactly the inputs that were reviewed.
+    /// Since the contents are recorded rather than modification times,
+    /// the lock still matches after a fresh checkout of the same files.
+    /// Raises `OSError` if a file could not be read.
+    fn freeze(&self, lock_path: PathBuf) -> PyResult<()> {
+        let lock = self
+            .files()
+            .map(|(name, path)| Ok((name.to_owned(), ContentHash.etag(path)?)))
+            .collect::<io::Result<BTreeMap<String, Digest>>>()?;
+        std::fs::write(lock_path, lock.to_vec())?;
+        Ok(())
+    }
+
+    /// Compare every file asset against the lock file at `lock_path` written by `freeze`,
+    /// returning the names of those that differ from it, in order.
+    ///
+    /// A file differs if its contents changed,
+    /// if it was not registered when the lock was written,
+    /// or if it is recorded in the lock but is no longer registered.
+    /// If `strict` is true, `RuntimeError` is raised instead of returning a non-empty list.
+    /// Raises `ValueError` if the lock file is corrupt
+    /// and `OSError` if it or a file could not be read.
+    #[pyo3(signature = (lock_path, strict = false))]
+    fn verify(&self, lock_path: PathBuf, strict: bool) -> PyResult<Vec<String>> {
+        let bytes = std::fs::read(lock_path)?;
+        let mut lock = BTreeMap::<String, Digest>::from_bytes(&bytes)
+            .map_err(|e| PyValueError::new_err(format!("corrupt lock file: {e}")))?;
+        let mut differ = Vec::new();
+        for (name, path) in self.files() {
+            if lock.remove(name) != Some(ContentHash.etag(path)?) {
+                differ.push(name.to_owned());
+            }
+        }
+        differ.extend(lock.into_keys());
+        if strict && !differ.is_empty() {
+            return Err(PyRuntimeError::new_err(format!(
+                "inputs differ from the lock file: {}",
+                differ.join(", ")
+            )));
+        }
+        Ok(differ)
+    }
+}
+
+impl Builder {
+    /// Run every asset, or only those in `needed` if given.
+    fn run_only(
+        &mut self,
+        py: Python<'_>,
+        needed: Option<&BTreeSet<String>>,
+    ) -> PyResult<Vec<String>> {
+        let mut old = mem::take(&mut self.state);
+        let mut changed = Vec::<String>::new();
+        // Skipped commands that depend on something that changed in this run.
+        let mut stale = BTreeSet::<&str>::new();
+        let mut res = Ok(());
+        self.last_run.clear();
+
+        for asset in &self.assets {
+            if matches!(needed, Some(needed) if !needed.contains(asset.name())) {
+                match asset {
+                    Node::File { name, .. } => {
+                        if let Some(stamp) = old.files.remove(name) {
+                            self.state.files.insert(name.clone(), stamp);
+                        }
+                    }
+                    Node::Command { name, inputs, .. } => {
+                        let digest = old.commands.remove(name);
+                        // The new etags of its inputs are saved,
+                        // so its own state is dropped for it to run next time it is needed.
+                        let is_stale = inputs
+                            .iter()
+                            .any(|input| changed.contains(input) || stale.contains(&**input));
+                        if is_stale {
+                            stale.insert(name);
+                        } else if let Some(digest) = digest {
+                            self.state.commands.insert(name.clone(), digest);
+                        }
+                    }
+                }
+                continue;
+            }
+            let start = Instant::now();
+            let reason;
+            let (etag, outputs) = match asset {
+                Node::File { name, path } => {
+                    let prev = old.files.remove(name);
+                    // Like `mast::fs::bytes`, an unreadable file is always considered changed
+                    // and its stamp is reset.
+                    let (stamp, error) = match Mtime.etag(path) {
+                        Ok(stamp) => (stamp, None),
+                        Err(e) => (Stamp::default(), Some(e)),
+                    };
+                    reason = match (prev, error) {
+                        (_, Some(e)) => Some(Reason::Unreadable(e.to_string())),
+                        (None, None) => Some(Reason::New),
+                        (Some(prev), None) if prev.len() != stamp.len() => Some(Reason::Size),
+                        (Some(prev), None) if prev != stamp => Some(Reason::Modified),
+                        (Some(_), None) => None,
+                    };
+                    if reason.is_some() {
+                        changed.push(name.clone());
+                    }
+                    let mut hasher = Sha256::new();
+                    stamp.serialize(&mut hasher);
+                    self.state.files.insert(name.clone(), stamp);
+                    (hasher.finish(), Vec::new())
+                }
+                Node::Command {
+                    name,
+                    argv,
+                    inputs,
+                    outputs,
+                } => {
+                    let mut hasher = Sha256::new();
+                    argv.serialize(&mut hasher);
+                    let digest = hasher.finish();
+                    let prev = old.commands.remove(name);
+                    let changed_input = inputs.iter().find(|&input| changed.contains(input));
+                    reason = match (prev, changed_input) {
+                        (None, _) => Some(Reason::New),
+                        (Some(prev), _) if prev != digest => Some(Reason::Arguments),
+                        (_, Some(input)) => Some(Reason::Input(input.clone())),
+                        (_, None) => None,
+                    };
+                    if reason.is_some() {
+                        changed.push(name.clone());
+                        res = py.allow_threads(|| run_command(argv));
+                    }
+                    self.state.commands.insert(name.clone(), digest);
+                    (digest, outputs.clone())
+                }
+            };
+            self.last_run.push(Record {
+                name: asset.name().to_owned(),
+                etag,
+                reason,
+                elapsed: start.elapsed(),
+                outputs,
+            });
+            if res.is_err() {
+                break;
+            }
+        }
+
+        if res.is_err() {
+            for name in &changed {
+                self.state.files.remove(name);
+                self.state.commands.remove(name);
+            }
+            // Assets after the failed command were not looked at, so keep their state.
+            self.state.files.append(&mut old.files);
+            self.state.commands.append(&mut old.commands);
+        }
+        self.save()?;
+        res.map(|()| changed)
+    }
+
+    /// The name and path of every file asset, in order.
+    fn files(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.assets.iter().filter_map(|asset| match asset {
+            Node::File { name, path } => Some((&**name, &**path)),
+            Node::Command { .. } => None,
+        })
+    }
+
+    fn check_name(&self, name: &str) -> PyResult<()> {
+        if self.assets.iter().any(|asset| asset.name() == name) {
+            return Err(PyValueError::new_err(format!("duplicate asset {name:?}")));
+        }
+        Ok(())
+    }
+
+    fn save(&mut self) -> PyResult<()> {
+        if let Some(path) = &self.state_path {
+            let state = Salted::new(self.salt, mem::take(&mut self.state));
+            std::fs::write(path, state.to_vec())?;
+            self.state = state.etag;
+        }
+        Ok(())
+    }
+}
+
+fn run_command(argv: &[OsString]) -> PyResult<()> {
+    let status = process::Command::new(&argv[0]).args(&argv[1..]).status()?;
+    if !status.success() {
+        let program = Path::new(&argv[0]).display();
+        return Err(PyRuntimeError::new_err(format!(
+            "`{program}` failed: {status}"
+        )));
+    }
+    Ok(())
+}
+
+/// The `mast` Python module.
+#[pymodule]
+#[pyo3(name = "mast")]
+fn mast_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Builder>()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    #[test]
+    fn pipeline() {
+        let dir = std::env::temp_dir().join(format!("mast-python-test-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input");
+        let output = dir.join("output");
+        let state = dir.join("state");
+        std::fs::write(&input, "a").unwrap();
+
+        let builder = |salt: &[u8]| {
+            let mut b = Builder::new(Some(state.clone()), Some(salt)).unwrap();
+            b.file("input".into(), input.clone()).unwrap();
+            let copy = ["cp".as_ref(), input.as_os_str(), output.as_os_str()];
+            let copy = copy.iter().map(|&s| s.to_owned()).collect();
+            b.command(
+                "copy".into(),
+                copy,
+                vec!["input".into()],
+                vec![output.clone()],
+            )
+            .unwrap();
+            b
+        };
+        let build = |py: Python<'_>, salt: &[u8]| builder(salt).run(py).unwrap();
+
+        Python::with_gil(|py| {
+            assert_eq!(build(py, b""), ["input", "copy"]);
+            assert_eq!(std::fs::read(&output).unwrap(), b"a");
+            assert_eq!(build(py, b""), <[&str; 0]>::default());
+            std::fs::write(&input, "bb").unwrap();
+            assert_eq!(build(py, b""), ["input", "copy"]);
+            assert_eq!(std::fs::read(&output).unwrap(), b"bb");
+            assert_eq!(build(py, b"v2"), ["input", "copy"]);
+            assert_eq!(build(py, b"v2"), <[&str; 0]>::default());
+
+            std::fs::write(&input, "c").unwrap();
+            let mut b = builder(b"v2");
+            b.run(py).unwrap();
+            let snapshot = b.snapshot(py).unwrap();
+            let item = |i: usize, key| snapshot[i].get_item(key).unwrap().unwrap();
+            assert_eq!(item(0, "name").extract::<String>().unwrap(), "input");
+            assert_eq!(item(1, "name").extract::<String>().unwrap(), "copy");
+            assert!(item(1, "changed").extract::<bool>().unwrap());
+            assert_eq!(item(1, "etag").extract::<String>().unwrap().len(), 64);
+            let outputs = item(1, "outputs").extract::<Vec<PathBuf>>().unwrap();
+            assert_eq!(outputs, std::slice::from_ref(&output));
+            assert_eq!(
+                b.explain("copy").unwrap(),
+                "copy rebuilt because input changed; input changed because its size changed",
+            );
+            b.run(py).unwrap();
+            let snapshot = b.snapshot(py).unwrap();
+            assert!(!snapshot[1]
+                .get_item("changed")
+                .unwrap()
+                .unwrap()
+                .extract::<bool>()
+                .unwrap());
+
+            assert_eq!(b.explain("copy").unwrap(), "copy is up to date");
+
+            let other = dir.join("other");
+            let mut b = builder(b"v2");
+            let touch = vec!["touch".into(), other.clone().into_os_string()];
+            b.command("touch".into(), touch, Vec::new(), Vec::new())
+                .unwrap();
+            std::fs::write(&input, "dd").unwrap();
+            assert_eq!(
+                b.run_targets(py, vec![output.clone()]).unwrap(),
+                ["input", "copy"]
+            );
+            assert!(!other.exists());
+            assert_eq!(b.run_targets(py, vec!["touch".into()]).unwrap(), ["touch"]);
+            assert!(other.exists());
+            assert_eq!(b.run(py).unwrap(), <[&str; 0]>::default());
+            let err = b.run_targets(py, vec!["nonexistent".into()]).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+
+            let lock = dir.join("lock");
+            b.freeze(lock.clone()).unwrap();
+            assert!(b.verify(lock.clone(), true).unwrap().is_empty());
+            // Rewriting the same contents changes only the modification time.
+            std::fs::write(&input, "dd").unwrap();
+            assert!(b.verify(lock.clone(), true).unwrap().is_empty());
+            std::fs::write(&input, "eee").unwrap();
+            assert_eq!(b.verify(lock.clone(), false).unwrap(), ["input"]);
+            let err = b.verify(lock.clone(), true).unwrap_err();
+            assert!(err.is_instance_of::<PyRuntimeError>(py));
+            assert_eq!(builder(b"").verify(lock.clone(), false).unwrap(), ["input"]);
+            let mut b = Builder::new(None, None).unwrap();
+            b.file("other".into(), other.clone()).unwrap();
+            assert_eq!(b.verify(lock.clone(), false).unwrap(), ["other", "input"]);
+            std::fs::write(&lock, "corrupt").unwrap();
+            let err = b.verify(lock, false).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+
+            let mut b = Builder::new(None, None).unwrap();
+            b.file("missing".into(), dir.join("missing")).unwrap();
+            let err = b.freeze(dir.join("missing.lock")).unwrap_err();
+            assert!(err.is_instance_of::<PyOSError>(py));
+
+            let mut b = Builder::new(None, None).unwrap();
+            let err = b.command(
+                "x".into(),
+                vec!["true".into()],
+                vec!["y".into()],
+                Vec::new(),
+            );
+            assert!(err.unwrap_err().is_instance_of::<PyValueError>(py));
+        });
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn skipped_dependants() {
+        let dir = std::env::temp_dir().join(format!("mast-python-test-skipped-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input");
+        std::fs::write(&input, "a").unwrap();
+
+        let mut b = Builder::new(Some(dir.join("state")), None).unwrap();
+        b.file("input".into(), input.clone()).unwrap();
+        for name in ["a", "b"] {
+            let output = dir.join(name);
+            let copy = ["cp".as_ref(), input.as_os_str(), output.as_os_str()];
+            let copy = copy.iter().map(|&s| s.to_owned()).collect();
+            b.command(name.into(), copy, vec!["input".into()], Vec::new())
+                .unwrap();
+        }
+        Python::with_gil(|py| {
+            b.run(py).unwrap();
+            std::fs::write(&input, "bb").unwrap();
+            assert_eq!(b.run_targets(py, vec!["a".into()]).unwrap(), ["input", "a"]);
+            assert_eq!(
+                b.run_targets(py, vec!["a".into()]).unwrap(),
+                <[&str; 0]>::default()
+            );
+            assert_eq!(b.run_targets(py, vec!["b".into()]).unwrap(), ["b"]);
+        });
+        assert_eq!(std::fs::read(dir.join("b")).unwrap(), b"bb");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable() {
+        let dir =
+            std::env::temp_dir().join(format!("mast-python-test-unreadable-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input");
+        std::fs::write(&input, "a").unwrap();
+
+        let mut b = Builder::new(Some(dir.join("state")), None).unwrap();
+        b.file("input".into(), input.clone()).unwrap();
+        Python::with_gil(|py| {
+            assert_eq!(b.run(py).unwrap(), ["input"]);
+            std::fs::remove_file(&input).unwrap();
+            assert_eq!(b.run(py).unwrap(), ["input"]);
+        });
+        let explanation = b.explain("input").unwrap();
+        assert!(
+            explanation.starts_with("input changed because it could not be read: "),
+            "{explanation}",
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use super::*;
+    use pyo3::exceptions::PyOSError;
+}
+
+use mast::etag::DeserializeError;
+use mast::etag::Reader;
+use mast::etag::Salted;
+use mast::etag::Writer;
+use mast::fs::ContentHash;
+use mast::fs::Mtime;
+use mast::fs::Stamp;
+use mast::fs::Strategy as _;
+use mast::hash::Digest;
+use mast::hash::Sha256;
+use mast::Etag;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+use std::time::Instant;
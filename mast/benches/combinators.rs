@@ -0,0 +1,93 @@
+//! Benchmarks of the overhead of combinators, on the synthetic graphs of `mast::bench`.
+//!
+//! Run with `cargo bench -p mast --features bench`.
+
+/// Chain one `.then(bench::step)` onto `$asset` for each token after the semicolon.
+macro_rules! chain {
+    ($asset:expr;) => { $asset };
+    ($asset:expr; $_level:tt $($rest:tt)*) => { chain!($asset.then(bench::step); $($rest)*) };
+}
+
+/// Benchmark building a fresh graph and generating it, and updating an up-to-date graph.
+fn bench_graph<P, A, E, O, F>(group: &mut BenchmarkGroup<'_, WallTime>, size: P, graph: F)
+where
+    P: Display,
+    F: Fn() -> A,
+    A: for<'c> Asset<'c, Etag = E, Output = O>,
+    E: Etag,
+{
+    group.bench_function(BenchmarkId::new("modified", &size), |b| {
+        b.iter_batched_ref(
+            E::default,
+            |etag| {
+                black_box(graph())
+                    .update(asset::Context::default(), etag)
+                    .value
+                    .generate()
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    let mut etag = E::default();
+    drop(
+        graph()
+            .update(asset::Context::default(), &mut etag)
+            .value
+            .generate(),
+    );
+    group.bench_function(BenchmarkId::new("same", size), |b| {
+        b.iter(|| {
+            black_box(graph())
+                .update(asset::Context::default(), &mut etag)
+                .is_same()
+        });
+    });
+}
+
+fn then_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("then_chain");
+    bench_graph(&mut group, 1, || chain!(bench::leaf(0); x));
+    bench_graph(&mut group, 4, || chain!(bench::leaf(0); x x x x));
+    bench_graph(&mut group, 8, || chain!(bench::leaf(0); x x x x x x x x));
+    bench_graph(
+        &mut group,
+        16,
+        || chain!(bench::leaf(0); x x x x x x x x x x x x x x x x),
+    );
+    group.finish();
+}
+
+fn dyn_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dyn_chain");
+    for depth in [1, 4, 8, 16] {
+        bench_graph(&mut group, depth, || bench::dyn_chain(depth, 0));
+    }
+    group.finish();
+}
+
+fn wide(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wide");
+    for width in [1, 16, 256] {
+        bench_graph(&mut group, width, || bench::wide(width));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, then_chain, dyn_chain, wide);
+criterion_main!(benches);
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::measurement::WallTime;
+use criterion::BatchSize;
+use criterion::BenchmarkGroup;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use mast::asset;
+use mast::asset::Generator as _;
+use mast::bench;
+use mast::Asset;
+use mast::Etag;
+use std::fmt::Display;
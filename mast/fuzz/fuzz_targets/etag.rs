@@ -0,0 +1,6 @@
+//! Fuzz the varint decoder and the integer `Etag` implementations.
+//!
+//! Run with `cargo +nightly fuzz run etag` from the `mast` directory.
+#![no_main]
+
+libfuzzer_sys::fuzz_target!(|bytes: &[u8]| mast::etag::fuzz_check(bytes));
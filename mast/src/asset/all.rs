@@ -0,0 +1,630 @@
+/// Combine many assets of the same type into one whose output is a vector of their outputs.
+///
+/// The assets are generated in parallel,
+/// but the outputs are always in the same order as the assets,
+/// regardless of which finishes first.
+/// Use [`All::unordered`] to instead receive outputs as soon as they are generated,
+/// or [`All::stream`] to consume them in order without waiting for the whole vector.
+///
+/// The etag is the vector of the assets’ etags,
+/// and the combined asset is modified if any of the assets is or if their number changes.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// let assets = (0..4).map(|i| asset::cli_args().map(move |_| i));
+/// let mut etag = Vec::new();
+/// let all = asset::all(assets).update(asset::Context::default(), &mut etag);
+/// assert!(all.is_modified());
+/// assert_eq!(all.value.generate(), [0, 1, 2, 3]);
+/// ```
+pub fn all<'c, I>(assets: I) -> All<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Asset<'c>,
+{
+    All {
+        assets: assets.into_iter().collect(),
+        threads: None,
+    }
+}
+
+/// Asset for [`all`].
+#[derive(Debug)]
+pub struct All<A> {
+    assets: Vec<A>,
+    threads: Option<NonZeroUsize>,
+}
+
+impl<A> All<A> {
+    /// Set the maximum number of threads to generate the assets on.
+    ///
+    /// By default, this is the [available parallelism](thread::available_parallelism).
+    /// With a single thread, the assets are generated one after another
+    /// on the thread generating this asset.
+    #[must_use]
+    pub fn threads(self, threads: NonZeroUsize) -> Self {
+        Self {
+            threads: Some(threads),
+            ..self
+        }
+    }
+
+    /// Yield outputs in the order they are generated rather than the order of the assets.
+    ///
+    /// This lets consumers that do not care about order
+    /// start processing outputs before the slowest asset has finished;
+    /// see [`UnorderedGenerator::for_each`].
+    /// With a [`Deterministic`](super::Deterministic) in the context,
+    /// outputs are instead yielded in the order of the assets,
+    /// once all the outputs before them are generated.
+    #[must_use]
+    pub fn unordered(self) -> Unordered<A> {
+        Unordered { all: self }
+    }
+
+    /// Pass each output to `f` as soon as it and all the outputs before it are generated,
+    /// instead of waiting for every asset to finish.
+    ///
+    /// The output is the vector of `f`’s return values, in the order of the assets.
+    /// Since each output is handed off as soon as possible,
+    /// only outputs that finished ahead of a slower earlier asset are ever held at once,
+    /// reducing both peak memory and the latency of pipelines like
+    /// “render each page then write it”.
+    ///
+    /// `f` is always called on the thread that generates this asset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// let pages = (0..4).map(|i| asset::cli_args().map(move |_| format!("page {i}")));
+    /// let mut written = Vec::new();
+    /// let mut etag = Vec::new();
+    /// let all = asset::all(pages).stream(|page| written.push(page));
+    /// all.update(asset::Context::default(), &mut etag).value.generate();
+    /// assert_eq!(written, ["page 0", "page 1", "page 2", "page 3"]);
+    /// ```
+    #[must_use]
+    pub fn stream<'c, O, F>(self, f: F) -> Streamed<A, F>
+    where
+        A: Asset<'c>,
+        F: FnMut(A::Output) -> O,
+    {
+        Streamed { all: self, f }
+    }
+
+    /// Apply `f` to the output of each asset that is modified,
+    /// reusing the results of `f` from the previous build for the assets that are not.
+    ///
+    /// Where [`stream`](Self::stream) regenerates every asset whenever any one of them changes,
+    /// this only generates the assets that are modified themselves,
+    /// so the cost of a build is proportional to the number of changed items.
+    /// Assets are identified by their position,
+    /// and the result of `f` for each one is kept in the etag alongside that asset’s own,
+    /// so it is persisted along with it.
+    ///
+    /// `f` is always called on the thread that generates this asset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// let pages = |versions: [u32; 3]| {
+    ///     let pages = versions.into_iter().enumerate();
+    ///     asset::all(pages.map(|(i, version)| asset::constant(i).version(version)))
+    /// };
+    /// let mut rendered = Vec::new();
+    /// let mut etag = Vec::new();
+    /// let cx = asset::Context::default();
+    ///
+    /// let all = pages([1, 1, 1]).map_changed(|i| {
+    ///     rendered.push(i);
+    ///     format!("page {i}")
+    /// });
+    /// all.update(cx, &mut etag).value.generate();
+    /// assert_eq!(rendered, [0, 1, 2]);
+    ///
+    /// rendered.clear();
+    /// let all = pages([1, 2, 1]).map_changed(|i| {
+    ///     rendered.push(i);
+    ///     format!("page {i} v2")
+    /// });
+    /// let outputs = all.update(cx, &mut etag).value.generate();
+    /// // Only the changed page was rendered again.
+    /// assert_eq!(rendered, [1]);
+    /// assert_eq!(outputs, ["page 0", "page 1 v2", "page 2"]);
+    /// ```
+    #[must_use]
+    pub fn map_changed<'c, O, F>(self, f: F) -> MapChanged<A, F>
+    where
+        A: Asset<'c>,
+        F: FnMut(A::Output) -> O,
+        O: Etag + Clone,
+    {
+        MapChanged { all: self, f }
+    }
+
+    fn update_all<'c>(
+        self,
+        cx: Context<'c>,
+        etag: &'c mut Vec<A::Etag>,
+    ) -> Tracked<Workers<A::Generator>>
+    where
+        A: Asset<'c>,
+    {
+        let mut delta = Delta::cmp(&etag.len(), &self.assets.len());
+        etag.resize_with(self.assets.len(), Default::default);
+        let generators = self
+            .assets
+            .into_iter()
+            .zip(etag)
+            .map(|(asset, etag)| {
+                let tracked = asset.update(cx, etag);
+                delta = delta.or(tracked.delta);
+                tracked.value
+            })
+            .collect();
+        delta.track(Workers {
+            generators,
+            threads: self.threads,
+        })
+    }
+}
+
+impl<'c, A> Asset<'c> for All<A>
+where
+    A: Asset<'c>,
+    A::Generator: Send,
+    A::Output: Send,
+{
+    type Etag = Vec<A::Etag>;
+    type Output = Vec<A::Output>;
+    type Generator = Generator<A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        self.update_all(cx, etag)
+            .map(|workers| Generator { workers })
+    }
+}
+
+/// Generator for [`All`].
+#[derive(Debug)]
+pub struct Generator<G> {
+    workers: Workers<G>,
+}
+
+impl<G> super::Generator for Generator<G>
+where
+    G: super::Generator + Send,
+    G::Output: Send,
+{
+    type Output = Vec<G::Output>;
+
+    fn generate(self) -> Self::Output {
+        let mut outputs: Vec<Option<G::Output>> = Vec::new();
+        outputs.resize_with(self.workers.generators.len(), || None);
+        self.workers.run(|i, output| outputs[i] = Some(output));
+        // `run` only returns once every generator has produced its output.
+        outputs.into_iter().map(Option::unwrap).collect()
+    }
+}
+
+/// Asset for [`All::unordered`].
+///
+/// The output is a vector of each asset’s index alongside its output,
+/// in the order the outputs were generated.
+#[derive(Debug)]
+pub struct Unordered<A> {
+    all: All<A>,
+}
+
+impl<'c, A> Asset<'c> for Unordered<A>
+where
+    A: Asset<'c>,
+    A::Generator: Send,
+    A::Output: Send,
+{
+    type Etag = Vec<A::Etag>;
+    type Output = Vec<(usize, A::Output)>;
+    type Generator = UnorderedGenerator<A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let deterministic = Deterministic::enabled(cx);
+        self.all
+            .update_all(cx, etag)
+            .map(|workers| UnorderedGenerator {
+                workers,
+                deterministic,
+            })
+    }
+}
+
+/// Generator for [`Unordered`].
+#[derive(Debug)]
+pub struct UnorderedGenerator<G> {
+    workers: Workers<G>,
+    deterministic: bool,
+}
+
+impl<G> UnorderedGenerator<G>
+where
+    G: super::Generator + Send,
+    G::Output: Send,
+{
+    /// Generate the assets, calling `f` with each asset’s index and output as soon as it is ready.
+    ///
+    /// `f` is always called on the current thread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::Asset as _;
+    ///
+    /// let assets = (0..4).map(|i| asset::cli_args().map(move |_| i * 10));
+    /// let mut etag = Vec::new();
+    /// let all = asset::all(assets).unordered();
+    /// let mut seen = Vec::new();
+    /// all.update(asset::Context::default(), &mut etag)
+    ///     .value
+    ///     .for_each(|i, output| seen.push((i, output)));
+    /// seen.sort_unstable();
+    /// assert_eq!(seen, [(0, 0), (1, 10), (2, 20), (3, 30)]);
+    /// ```
+    pub fn for_each<F: FnMut(usize, G::Output)>(self, mut f: F) {
+        if !self.deterministic {
+            return self.workers.run(f);
+        }
+        let mut next = 0;
+        // Outputs that finished before an earlier one, waiting for their turn.
+        let mut early = BTreeMap::new();
+        self.workers.run(|i, output| {
+            early.insert(i, output);
+            while let Some(output) = early.remove(&next) {
+                f(next, output);
+                next += 1;
+            }
+        });
+    }
+}
+
+impl<G> super::Generator for UnorderedGenerator<G>
+where
+    G: super::Generator + Send,
+    G::Output: Send,
+{
+    type Output = Vec<(usize, G::Output)>;
+
+    fn generate(self) -> Self::Output {
+        let mut outputs = Vec::with_capacity(self.workers.generators.len());
+        self.for_each(|i, output| outputs.push((i, output)));
+        outputs
+    }
+}
+
+/// Asset for [`All::stream`].
+#[derive(Debug)]
+pub struct Streamed<A, F> {
+    all: All<A>,
+    f: F,
+}
+
+impl<'c, A, F, O> Asset<'c> for Streamed<A, F>
+where
+    A: Asset<'c>,
+    A::Generator: Send,
+    A::Output: Send,
+    F: FnMut(A::Output) -> O,
+{
+    type Etag = Vec<A::Etag>;
+    type Output = Vec<O>;
+    type Generator = StreamedGenerator<A::Generator, F>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let f = self.f;
+        self.all
+            .update_all(cx, etag)
+            .map(|workers| StreamedGenerator { workers, f })
+    }
+}
+
+/// Generator for [`Streamed`].
+#[derive(Debug)]
+pub struct StreamedGenerator<G, F> {
+    workers: Workers<G>,
+    f: F,
+}
+
+impl<G, F, O> super::Generator for StreamedGenerator<G, F>
+where
+    G: super::Generator + Send,
+    G::Output: Send,
+    F: FnMut(G::Output) -> O,
+{
+    type Output = Vec<O>;
+
+    fn generate(self) -> Self::Output {
+        let Self { workers, mut f } = self;
+        let mut outputs = Vec::with_capacity(workers.generators.len());
+        // Outputs that finished before an earlier one, waiting for their turn.
+        let mut early = BTreeMap::new();
+        workers.run(|i, output| {
+            early.insert(i, output);
+            while let Some(output) = early.remove(&outputs.len()) {
+                outputs.push(f(output));
+            }
+        });
+        outputs
+    }
+}
+
+/// Asset for [`All::map_changed`].
+#[derive(Debug)]
+pub struct MapChanged<A, F> {
+    all: All<A>,
+    f: F,
+}
+
+impl<'c, A, F, O> Asset<'c> for MapChanged<A, F>
+where
+    A: Asset<'c>,
+    A::Generator: Send,
+    A::Output: Send,
+    F: FnMut(A::Output) -> O,
+    O: Etag + Clone + 'c,
+{
+    type Etag = Vec<(A::Etag, Option<O>)>;
+    type Output = Vec<O>;
+    type Generator = MapChangedGenerator<'c, A::Generator, O, F>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let All { assets, threads } = self.all;
+        let mut delta = Delta::cmp(&etag.len(), &assets.len());
+        etag.resize_with(assets.len(), Default::default);
+        let mut generators = Vec::new();
+        let mut changed = Vec::new();
+        let mut outputs = Vec::with_capacity(assets.len());
+        for (i, (asset, (etag, output))) in assets.into_iter().zip(etag).enumerate() {
+            let tracked = asset.update(cx, etag);
+            if tracked.is_modified() || output.is_none() {
+                delta = Delta::Modified;
+                // Forget the old output until the new one is generated,
+                // in case this asset’s etag is saved without generating it.
+                *output = None;
+                generators.push(tracked.value);
+                changed.push(i);
+            }
+            outputs.push(output);
+        }
+        delta.track(MapChangedGenerator {
+            workers: Workers {
+                generators,
+                threads,
+            },
+            changed,
+            outputs,
+            f: self.f,
+        })
+    }
+}
+
+/// Generator for [`MapChanged`].
+#[derive(Debug)]
+pub struct MapChangedGenerator<'c, G, O, F> {
+    workers: Workers<G>,
+    changed: Vec<usize>,
+    outputs: Vec<&'c mut Option<O>>,
+    f: F,
+}
+
+impl<G, O, F> super::Generator for MapChangedGenerator<'_, G, O, F>
+where
+    G: super::Generator + Send,
+    G::Output: Send,
+    F: FnMut(G::Output) -> O,
+    O: Clone,
+{
+    type Output = Vec<O>;
+
+    fn generate(self) -> Self::Output {
+        let Self {
+            workers,
+            changed,
+            mut outputs,
+            mut f,
+        } = self;
+        workers.run(|i, output| *outputs[changed[i]] = Some(f(output)));
+        // `run` only returns once every modified asset has been generated,
+        // and every other asset kept its previous output.
+        outputs
+            .into_iter()
+            .map(|output| output.clone().unwrap())
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct Workers<G> {
+    generators: Vec<G>,
+    threads: Option<NonZeroUsize>,
+}
+
+impl<G> Workers<G>
+where
+    G: super::Generator + Send,
+    G::Output: Send,
+{
+    /// Run all the generators on a pool of threads,
+    /// passing their outputs to `f` on the current thread in the order they finish.
+    fn run<F: FnMut(usize, G::Output)>(self, mut f: F) {
+        let threads = self
+            .threads
+            .map_or_else(
+                || thread::available_parallelism().map_or(1, NonZeroUsize::get),
+                NonZeroUsize::get,
+            )
+            .min(self.generators.len());
+        // A single worker would only hand its outputs back to this thread one by one.
+        if threads <= 1 {
+            for (i, generator) in self.generators.into_iter().enumerate() {
+                f(i, generator.generate());
+            }
+            return;
+        }
+        let queue = Mutex::new(self.generators.into_iter().enumerate());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|s| {
+            for _ in 0..threads {
+                let queue = &queue;
+                let sender = sender.clone();
+                s.spawn(move || loop {
+                    let Some((i, generator)) = lock(queue).next() else {
+                        break;
+                    };
+                    if sender.send((i, generator.generate())).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+            for (i, output) in receiver {
+                f(i, output);
+            }
+        });
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn order() {
+        struct Sleep(u64);
+        impl<'c> Asset<'c> for Sleep {
+            type Etag = u64;
+            type Output = u64;
+            type Generator = Box<dyn FnOnce() -> u64 + Send>;
+            fn update(self, _: Context<'c>, etag: &'c mut u64) -> Tracked<Self::Generator> {
+                let delta = Delta::cmp(etag, &self.0);
+                *etag = self.0;
+                let millis = self.0;
+                delta.track(Box::new(move || {
+                    thread::sleep(Duration::from_millis(millis));
+                    millis
+                }))
+            }
+        }
+        let sleeps = || [150, 0, 100, 50].map(Sleep);
+        let four = NonZeroUsize::new(4).unwrap();
+
+        let mut etag = Vec::new();
+        let all = asset::all(sleeps()).threads(four);
+        let tracked = all.update(Context::default(), &mut etag);
+        assert!(tracked.is_modified());
+        assert_eq!(tracked.value.generate(), [150, 0, 100, 50]);
+
+        // Unordered outputs arrive in whatever order the threads finish.
+        let all = asset::all(sleeps()).threads(four).unordered();
+        let tracked = all.update(Context::default(), &mut etag);
+        assert!(tracked.is_same());
+        let mut outputs = tracked.value.generate();
+        outputs.sort_unstable();
+        assert_eq!(outputs, [(0, 150), (1, 0), (2, 100), (3, 50)]);
+
+        let values = (Deterministic::new(Time::EARLIEST),);
+        let cx = Context::from_tuple(&values);
+        let all = asset::all(sleeps()).threads(four).unordered();
+        let tracked = all.update(cx, &mut etag);
+        assert_eq!(
+            tracked.value.generate(),
+            [(0, 150), (1, 0), (2, 100), (3, 50)]
+        );
+
+        let all = asset::all([150, 0, 100].map(Sleep));
+        assert!(all.update(Context::default(), &mut etag).is_modified());
+        assert_eq!(etag, [150, 0, 100]);
+    }
+
+    #[test]
+    fn stream() {
+        static FAST_CONSUMED: AtomicUsize = AtomicUsize::new(0);
+        struct Item(bool);
+        impl<'c> Asset<'c> for Item {
+            type Etag = ();
+            type Output = bool;
+            type Generator = Box<dyn FnOnce() -> bool + Send>;
+            fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+                let slow = self.0;
+                Delta::Modified.track(Box::new(move || {
+                    // The slow item only finishes once the fast ones have been consumed,
+                    // which would never happen if outputs were not streamed.
+                    let deadline = Instant::now() + Duration::from_secs(10);
+                    while slow && FAST_CONSUMED.load(Ordering::SeqCst) < 2 {
+                        assert!(Instant::now() < deadline, "outputs were not streamed");
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                    slow
+                }))
+            }
+        }
+
+        let mut etag = Vec::new();
+        let all = asset::all([false, false, true].map(Item))
+            .threads(NonZeroUsize::new(3).unwrap())
+            .stream(|slow| {
+                if !slow {
+                    FAST_CONSUMED.fetch_add(1, Ordering::SeqCst);
+                }
+                slow
+            });
+        let outputs = all.update(Context::default(), &mut etag).value.generate();
+        assert_eq!(outputs, [false, false, true]);
+    }
+
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::asset::Deterministic;
+    use crate::asset::Generator as _;
+    use crate::time::Time;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Tracked;
+    use core::num::NonZeroUsize;
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering;
+    use core::time::Duration;
+    use std::boxed::Box;
+    use std::thread;
+    use std::time::Instant;
+    use std::vec::Vec;
+}
+
+use super::Asset;
+use super::Context;
+use super::Deterministic;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::num::NonZeroUsize;
+use std::collections::BTreeMap;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::thread;
+use std::vec::Vec;
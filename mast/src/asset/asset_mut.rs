@@ -0,0 +1,86 @@
+/// An [`Asset`] that can be updated through a mutable reference,
+/// so that it can be stored in a struct and reused across builds.
+///
+/// Unlike [`Asset`], this trait is object safe,
+/// so differing assets can be stored as `Box<dyn AssetMut<'c, ...>>`.
+/// A mutable reference to an `AssetMut` is itself an [`Asset`],
+/// so it can be passed anywhere an asset is expected.
+///
+/// This is implemented for every asset that is [`Clone`],
+/// by cloning the asset on each update.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::AssetMut;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// struct Site<A> {
+///     title: A,
+/// }
+///
+/// let mut site = Site { title: asset::constant("Home") };
+/// let cx = asset::Context::default();
+/// assert_eq!((&mut site.title).update(cx, &mut ()).value.generate(), "Home");
+/// assert_eq!(site.title.update_mut(cx, &mut ()).value.generate(), "Home");
+/// ```
+pub trait AssetMut<'c> {
+    /// The asset’s etag; see [`Asset::Etag`].
+    type Etag: Etag;
+
+    /// The result of the asset; see [`Asset::Output`].
+    type Output;
+
+    /// The asset’s generator; see [`Asset::Generator`].
+    type Generator: Generator<Output = Self::Output>;
+
+    /// Check whether the etag is still accurate and generate the asset’s result,
+    /// leaving the asset in place to be updated again by a later build.
+    fn update_mut(&mut self, cx: Context<'c>, etag: &'c mut Self::Etag)
+        -> Tracked<Self::Generator>;
+}
+
+impl<'c, A: Asset<'c> + Clone> AssetMut<'c> for A {
+    type Etag = A::Etag;
+    type Output = A::Output;
+    type Generator = A::Generator;
+
+    fn update_mut(
+        &mut self,
+        cx: Context<'c>,
+        etag: &'c mut Self::Etag,
+    ) -> Tracked<Self::Generator> {
+        self.clone().update(cx, etag)
+    }
+}
+
+impl<'c, A: ?Sized + AssetMut<'c>> Asset<'c> for &mut A {
+    type Etag = A::Etag;
+    type Output = A::Output;
+    type Generator = A::Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        self.update_mut(cx, etag)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'c, T: Asset<'c>> Asset<'c> for Box<T> {
+    type Etag = T::Etag;
+    type Output = T::Output;
+    type Generator = T::Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        (*self).update(cx, etag)
+    }
+}
+
+use super::Asset;
+use super::Context;
+use super::Generator;
+use crate::Etag;
+use crate::Tracked;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
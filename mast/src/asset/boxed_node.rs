@@ -0,0 +1,264 @@
+/// Asset for [`Asset::boxed_node`].
+pub struct BoxedNode<'c, O> {
+    label: &'static str,
+    update: Box<UpdateFn<'c, O>>,
+}
+
+type UpdateFn<'c, O> =
+    dyn FnOnce(Context<'c>, &'c mut BoxedEtag) -> Tracked<BoxedGenerator<'c, O>> + 'c;
+
+/// Generator for [`BoxedNode`].
+pub type BoxedGenerator<'c, O> = Box<dyn FnOnce() -> O + 'c>;
+
+impl<'c, O> BoxedNode<'c, O> {
+    pub(crate) fn new<A>(asset: A, label: &'static str) -> Self
+    where
+        A: Asset<'c, Output = O> + 'c,
+        A::Etag: Send + Sync,
+        A::Generator: 'c,
+    {
+        Self {
+            label,
+            update: Box::new(move |cx, etag: &'c mut BoxedEtag| {
+                asset.update(cx, etag.get_mut::<A::Etag>()).map(
+                    |generator| -> BoxedGenerator<'c, O> { Box::new(move || generator.generate()) },
+                )
+            }),
+        }
+    }
+
+    /// The label given to [`Asset::boxed_node`].
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+}
+
+impl<O> Debug for BoxedNode<'_, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxedNode")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, O> Asset<'c> for BoxedNode<'c, O> {
+    type Etag = BoxedEtag;
+    type Output = O;
+    type Generator = BoxedGenerator<'c, O>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        (self.update)(cx, etag)
+    }
+}
+
+/// Asset for [`Asset::boxed_send_node`].
+pub struct BoxedSendNode<'c, O> {
+    label: &'static str,
+    update: Box<SendUpdateFn<'c, O>>,
+}
+
+type SendUpdateFn<'c, O> =
+    dyn FnOnce(Context<'c>, &'c mut BoxedEtag) -> Tracked<BoxedSendGenerator<'c, O>> + Send + 'c;
+
+/// Generator for [`BoxedSendNode`].
+pub type BoxedSendGenerator<'c, O> = Box<dyn FnOnce() -> O + Send + 'c>;
+
+impl<'c, O> BoxedSendNode<'c, O> {
+    pub(crate) fn new<A>(asset: A, label: &'static str) -> Self
+    where
+        A: Asset<'c, Output = O> + Send + 'c,
+        A::Etag: Send + Sync,
+        A::Generator: Send + 'c,
+    {
+        Self {
+            label,
+            update: Box::new(move |cx, etag: &'c mut BoxedEtag| {
+                asset.update(cx, etag.get_mut::<A::Etag>()).map(
+                    |generator| -> BoxedSendGenerator<'c, O> {
+                        Box::new(move || generator.generate())
+                    },
+                )
+            }),
+        }
+    }
+
+    /// The label given to [`Asset::boxed_send_node`].
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+}
+
+impl<O> Debug for BoxedSendNode<'_, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxedSendNode")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, O> Asset<'c> for BoxedSendNode<'c, O> {
+    type Etag = BoxedEtag;
+    type Output = O;
+    type Generator = BoxedSendGenerator<'c, O>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        (self.update)(cx, etag)
+    }
+}
+
+/// Etag for [`BoxedNode`] and [`BoxedSendNode`], holding the etag of the boxed asset with its type erased.
+///
+/// It is serialized as the serialized form of the boxed asset’s etag.
+/// Deserializing it only keeps the bytes;
+/// they are deserialized as the boxed asset’s etag on its first update.
+#[derive(Default)]
+pub struct BoxedEtag {
+    value: Option<Box<dyn ErasedEtag>>,
+    bytes: Vec<u8>,
+}
+
+impl BoxedEtag {
+    /// Get the etag as an `E`,
+    /// deserializing it or falling back to the default if it is not one yet.
+    fn get_mut<E: Etag + Send + Sync>(&mut self) -> &mut E {
+        if !self
+            .value
+            .as_ref()
+            .is_some_and(|value| value.as_any().is::<E>())
+        {
+            let value = E::from_bytes(&self.bytes).unwrap_or_default();
+            self.value = Some(Box::new(value));
+            self.bytes = Vec::new();
+        }
+        let value = self.value.as_mut().unwrap().as_any_mut();
+        value.downcast_mut().unwrap()
+    }
+}
+
+impl Debug for BoxedEtag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => f.debug_tuple("BoxedEtag").field(value).finish(),
+            None => f.debug_tuple("BoxedEtag").field(&self.bytes).finish(),
+        }
+    }
+}
+
+impl Etag for BoxedEtag {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        match &self.value {
+            Some(value) => value.to_vec().serialize(writer),
+            None => self.bytes.serialize(writer),
+        }
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            value: None,
+            bytes: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+trait ErasedEtag: Debug + Send + Sync {
+    fn to_vec(&self) -> Vec<u8>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<E: Etag + Send + Sync> ErasedEtag for E {
+    fn to_vec(&self) -> Vec<u8> {
+        Etag::to_vec(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn persisted() {
+        struct Value(u32);
+        impl<'c> Asset<'c> for Value {
+            type Etag = u32;
+            type Output = u32;
+            type Generator = Constant<u32>;
+            fn update(self, _: Context<'c>, etag: &'c mut u32) -> Tracked<Self::Generator> {
+                let delta = Delta::cmp(etag, &self.0);
+                *etag = self.0;
+                delta.track(constant(self.0))
+            }
+        }
+        let cx = Context::default();
+
+        let mut etag = BoxedEtag::default();
+        assert!(Value(5).boxed_node("a").update(cx, &mut etag).is_modified());
+        let bytes = etag.to_vec();
+
+        let mut etag = BoxedEtag::from_bytes(&bytes).unwrap();
+        assert_eq!(etag.to_vec(), bytes);
+        assert!(Value(5).boxed_node("a").update(cx, &mut etag).is_same());
+        assert!(Value(6).boxed_node("a").update(cx, &mut etag).is_modified());
+
+        // Boxing a different asset in the same place rebuilds it.
+        let mut etag = BoxedEtag::from_bytes(&bytes).unwrap();
+        let res = Value(5).map(u64::from).version(1).boxed_node("b");
+        assert!(res.update(cx, &mut etag).is_modified());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn send() {
+        fn assert_send<T: Send>(_: &T) {}
+        fn nodes<'c>() -> impl Iterator<Item = BoxedSendNode<'c, u32>> {
+            (0..4).map(|i| constant(i).boxed_send_node("n"))
+        }
+
+        let cx = Context::default();
+        nodes().for_each(|node| assert_send(&node));
+        assert_send(&constant(1).map(|x| x + 1).version(1).boxed_send_node("n"));
+
+        // Boxed send nodes can be generated in parallel.
+        let mut etag = Vec::new();
+        let res = all(nodes()).update(cx, &mut etag).value.generate();
+        assert_eq!(res, [0, 1, 2, 3]);
+        assert!(all(nodes()).update(cx, &mut etag).is_same());
+    }
+
+    use super::BoxedEtag;
+    #[cfg(feature = "std")]
+    use super::BoxedSendNode;
+    #[cfg(feature = "std")]
+    use crate::asset::all;
+    use crate::asset::constant;
+    use crate::asset::Constant;
+    use crate::asset::Context;
+    #[cfg(feature = "std")]
+    use crate::asset::Generator as _;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Etag as _;
+    use crate::Tracked;
+    #[cfg(feature = "std")]
+    use std::vec::Vec;
+}
+
+use super::Asset;
+use super::Context;
+use super::Generator as _;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::Etag;
+use crate::Tracked;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
@@ -0,0 +1,521 @@
+/// Storage for the output of an asset between builds, used by [`Asset::cache`].
+///
+/// A cache should only ever be used with one asset and one etag;
+/// otherwise, the output of one asset may be mistaken for that of another.
+///
+/// Cloning a cache is cheap, and the clones share their contents.
+pub struct Cache<O> {
+    inner: Arc<Inner<O>>,
+}
+
+struct Inner<O> {
+    output: Mutex<Option<Arc<O>>>,
+    soft: bool,
+    failures: Mutex<Option<Failures<O>>>,
+}
+
+/// How a cache set up with [`Cache::remember_failures`] remembers failures.
+struct Failures<O> {
+    ttl: Duration,
+    is_failure: fn(&O) -> bool,
+    /// If the cached output is a failure, when the update that produced it happened.
+    last: Option<Time>,
+}
+
+impl<O> Cache<O> {
+    /// Construct an empty cache that keeps its output until it is [cleared](Self::clear).
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_softness(false)
+    }
+
+    /// Construct an empty cache whose output is dropped by [`trim_memory`].
+    ///
+    /// This is intended for large intermediate outputs that are cheaper to regenerate
+    /// than to keep in memory indefinitely:
+    /// if the output has been trimmed when it is next needed,
+    /// the asset is simply generated again.
+    #[must_use]
+    pub fn soft() -> Self
+    where
+        O: Send + Sync + 'static,
+    {
+        let cache = Self::with_softness(true);
+        let mut soft = lock(&SOFT);
+        soft.retain(|cache| cache.strong_count() != 0);
+        let inner: Arc<dyn Trim + Send + Sync> = cache.inner.clone();
+        soft.push(Arc::downgrade(&inner));
+        cache
+    }
+
+    fn with_softness(soft: bool) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                output: Mutex::new(None),
+                soft,
+                failures: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Whether this cache was constructed with [`soft`](Self::soft).
+    #[must_use]
+    pub fn is_soft(&self) -> bool {
+        self.inner.soft
+    }
+
+    /// Whether this cache currently holds an output.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        lock(&self.inner.output).is_none()
+    }
+
+    /// Drop the cached output, if there is one.
+    pub fn clear(&self) {
+        self.inner.trim();
+    }
+
+    /// Serialize the cached output, if there is one,
+    /// so that it can be [restored](Self::restore) by a later process.
+    ///
+    /// Without this, restarting a long-running process
+    /// regenerates every output that was only cached in memory,
+    /// even though the etags saved on disk report those assets as the same.
+    /// Save the snapshot at the same time as the etag of the cached asset,
+    /// since the cached output is only valid for that etag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// let page = || asset::constant(String::from("<h1>Hi</h1>"));
+    /// let cx = asset::Context::default();
+    ///
+    /// let cache = asset::Cache::new();
+    /// page().cache(&cache).update(cx, &mut ()).value.generate();
+    /// let snapshot = cache.snapshot();
+    ///
+    /// // In the next process, the output is available without generating it again.
+    /// let cache = asset::Cache::<String>::new();
+    /// cache.restore(&snapshot)?;
+    /// assert!(!cache.is_empty());
+    /// # Ok::<_, mast::etag::FromBytesError>(())
+    /// ```
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<u8>
+    where
+        O: Etag,
+    {
+        let mut bytes = Vec::new();
+        match self.get() {
+            None => bytes.write_discriminant(0),
+            Some(output) => {
+                bytes.write_discriminant(1);
+                output.serialize(&mut bytes);
+            }
+        }
+        bytes
+    }
+
+    /// Replace the contents of the cache with a [snapshot](Self::snapshot).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the snapshot is invalid, in which case the cache is left unchanged.
+    pub fn restore(&self, snapshot: &[u8]) -> Result<(), FromBytesError>
+    where
+        O: Etag,
+    {
+        let output = Option::<O>::from_bytes(snapshot)?;
+        let old = mem::replace(&mut *lock(&self.inner.output), output.map(Arc::new));
+        drop(old);
+        Ok(())
+    }
+
+    fn get(&self) -> Option<Arc<O>> {
+        lock(&self.inner.output).clone()
+    }
+
+    fn set(&self, output: Arc<O>, attempt: Option<Time>) {
+        if let Some(failures) = &mut *lock(&self.inner.failures) {
+            failures.last = attempt.filter(|_| (failures.is_failure)(&output));
+        }
+        *lock(&self.inner.output) = Some(output);
+    }
+
+    /// Record the time of an update, if this cache remembers failures.
+    fn attempt(&self, cx: Context<'_>) -> Option<Time> {
+        lock(&self.inner.failures).as_ref()?;
+        time::now(cx)
+    }
+
+    /// The cached output to reuse for an update of the asset, if there is one.
+    fn hit(&self, delta: Delta, attempt: Option<Time>) -> Option<Arc<O>> {
+        let output = self.get();
+        let failures = lock(&self.inner.failures);
+        let failed = match (&*failures, &output) {
+            (Some(failures), Some(output)) if (failures.is_failure)(output) => Some(failures),
+            _ => None,
+        };
+        // Whether the failure is recent enough to be reused.
+        let remembered = || {
+            let (Some(failures), Some(now)) = (failed, attempt) else {
+                return false;
+            };
+            failures.last.is_some_and(|last| {
+                let elapsed = now.unix_nanos().saturating_sub(last.unix_nanos());
+                let ttl = i128::try_from(failures.ttl.as_nanos()).unwrap_or(i128::MAX);
+                elapsed < ttl
+            })
+        };
+        match delta {
+            Delta::Same if failed.is_none() || remembered() => output,
+            Delta::Same | Delta::Modified => {
+                drop(failures);
+                self.clear();
+                None
+            }
+        }
+    }
+}
+
+impl<T, E> Cache<Result<T, E>> {
+    /// Remember a failed output for `ttl` while the asset is the same.
+    ///
+    /// Without this, a cache reuses a failed output like any other
+    /// for as long as the asset is the same.
+    /// With this, the error is only reused while it is younger than `ttl`,
+    /// so that an expensive step that failed is neither run again
+    /// on every change to an unrelated file in a `watch` loop,
+    /// nor stuck on a failure caused by something its etag does not track.
+    /// Once `ttl` has passed, the asset is generated again even if it is the same.
+    /// A modified asset is always generated again,
+    /// so a fix to one of its inputs is picked up immediately.
+    ///
+    /// The age of a failure is measured with [`time::now`],
+    /// so it follows any [`Clock`](time::Clock) in the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// let cache = asset::Cache::new().remember_failures(Duration::from_secs(60));
+    /// let cx = asset::Context::default();
+    ///
+    /// let broken = |v| asset::constant("a").map(str::parse::<u32>).version(v);
+    /// let mut etag = Default::default();
+    /// let first = broken(1).cache(&cache).update(cx, &mut etag).value.generate();
+    /// assert!(first.is_err());
+    ///
+    /// // The asset is the same, so the failure is remembered.
+    /// let second = broken(1).cache(&cache).update(cx, &mut etag);
+    /// assert!(second.is_same());
+    /// assert!(Arc::ptr_eq(&first, &second.value.generate()));
+    ///
+    /// // The asset is modified, so it is generated again.
+    /// let third = broken(2).cache(&cache).update(cx, &mut etag).value.generate();
+    /// assert!(!Arc::ptr_eq(&first, &third));
+    /// ```
+    #[must_use]
+    pub fn remember_failures(self, ttl: Duration) -> Self {
+        *lock(&self.inner.failures) = Some(Failures {
+            ttl,
+            is_failure: Result::is_err,
+            last: None,
+        });
+        self
+    }
+}
+
+impl<O> Default for Cache<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O> Clone for Cache<O> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<O> Debug for Cache<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("soft", &self.inner.soft)
+            .field("empty", &self.is_empty())
+            .finish()
+    }
+}
+
+trait Trim {
+    fn trim(&self);
+}
+
+impl<O> Trim for Inner<O> {
+    fn trim(&self) {
+        // Take the output out before dropping it, so as not to drop it under the lock.
+        let output = lock(&self.output).take();
+        drop(output);
+    }
+}
+
+static SOFT: Mutex<Vec<Weak<dyn Trim + Send + Sync>>> = Mutex::new(Vec::new());
+
+/// Drop the outputs held by every [soft](Cache::soft) cache in the process.
+///
+/// Call this when memory is scarce,
+/// for example between builds in a long-running `watch` process
+/// or in response to a memory pressure notification from the operating system.
+/// Outputs still in use elsewhere are only freed once those uses end.
+pub fn trim_memory() {
+    let soft = mem::take(&mut *lock(&SOFT));
+    let soft: Vec<_> = soft
+        .into_iter()
+        .filter_map(|cache| cache.upgrade())
+        .collect();
+    for cache in &soft {
+        cache.trim();
+    }
+    lock(&SOFT).extend(soft.iter().map(Arc::downgrade));
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Asset for [`Asset::cache`].
+#[derive(Debug)]
+pub struct Cached<'c, A: Asset<'c>> {
+    asset: A,
+    cache: &'c Cache<A::Output>,
+}
+
+impl<'c, A: Asset<'c>> Cached<'c, A> {
+    pub(crate) fn new(asset: A, cache: &'c Cache<A::Output>) -> Self {
+        Self { asset, cache }
+    }
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for Cached<'c, A> {
+    type Etag = A::Etag;
+    type Output = Arc<A::Output>;
+    type Generator = Generator<'c, A::Generator, A::Output>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let attempt = self.cache.attempt(cx);
+        let tracked = self.asset.update(cx, etag);
+        let hit = self.cache.hit(tracked.delta, attempt);
+        let cache = self.cache;
+        tracked.map(|inner| Generator {
+            inner,
+            cache,
+            hit,
+            attempt,
+        })
+    }
+}
+
+/// Generator for [`Cached`].
+#[derive(Debug)]
+pub struct Generator<'c, G, O> {
+    inner: G,
+    cache: &'c Cache<O>,
+    hit: Option<Arc<O>>,
+    attempt: Option<Time>,
+}
+
+impl<G: super::Generator> super::Generator for Generator<'_, G, G::Output> {
+    type Output = Arc<G::Output>;
+
+    fn generate(self) -> Self::Output {
+        if let Some(output) = self.hit {
+            return output;
+        }
+        let output = Arc::new(self.inner.generate());
+        self.cache.set(output.clone(), self.attempt);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn soft() {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        struct Counted(Delta);
+        impl<'c> Asset<'c> for Counted {
+            type Etag = ();
+            type Output = usize;
+            type Generator = fn() -> usize;
+            fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+                self.0.track(|| RUNS.fetch_add(1, Ordering::Relaxed) + 1)
+            }
+        }
+        let build = |delta, cache| {
+            *Counted(delta)
+                .cache(cache)
+                .update(Context::default(), &mut ())
+                .value
+                .generate()
+        };
+
+        let soft = Cache::soft();
+        assert_eq!(build(Delta::Modified, &soft), 1);
+        assert_eq!(build(Delta::Same, &soft), 1);
+        assert_eq!(build(Delta::Modified, &soft), 2);
+        assert_eq!(build(Delta::Same, &soft), 2);
+
+        let hard = Cache::new();
+        assert_eq!(build(Delta::Modified, &hard), 3);
+
+        trim_memory();
+        assert!(soft.is_empty());
+        assert!(!hard.is_empty());
+        assert_eq!(build(Delta::Same, &soft), 4);
+        assert_eq!(build(Delta::Same, &soft), 4);
+        assert_eq!(build(Delta::Same, &hard), 3);
+    }
+
+    #[test]
+    fn snapshot() {
+        struct Once<'a>(&'a AtomicUsize);
+        impl<'c> Asset<'c> for Once<'c> {
+            type Etag = ();
+            type Output = u32;
+            type Generator = Box<dyn FnOnce() -> u32 + 'c>;
+            fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+                let runs = self.0;
+                Delta::Same.track(Box::new(move || {
+                    runs.fetch_add(1, Ordering::Relaxed);
+                    37
+                }))
+            }
+        }
+        let runs = AtomicUsize::new(0);
+        let build = |cache| {
+            *Once(&runs)
+                .cache(cache)
+                .update(Context::default(), &mut ())
+                .value
+                .generate()
+        };
+
+        let cache = Cache::new();
+        let empty = cache.snapshot();
+        assert_eq!(build(&cache), 37);
+        let snapshot = cache.snapshot();
+
+        let restored = Cache::new();
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(build(&restored), 37);
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+
+        assert!(restored.restore(&[]).is_err());
+        assert!(!restored.is_empty());
+        restored.restore(&empty).unwrap();
+        assert!(restored.is_empty());
+    }
+
+    #[test]
+    fn remember_failures() {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        static SECS: AtomicI64 = AtomicI64::new(0);
+        struct Step(u32, bool);
+        impl<'c> Asset<'c> for Step {
+            type Etag = u32;
+            type Output = Result<u32, u32>;
+            type Generator = Box<dyn FnOnce() -> Result<u32, u32> + 'c>;
+            fn update(self, _: Context<'c>, etag: &'c mut u32) -> Tracked<Self::Generator> {
+                let delta = Delta::cmp(etag, &self.0);
+                *etag = self.0;
+                delta.track(Box::new(move || {
+                    RUNS.fetch_add(1, Ordering::Relaxed);
+                    if self.1 {
+                        Ok(self.0)
+                    } else {
+                        Err(self.0)
+                    }
+                }))
+            }
+        }
+        let clock = (Clock::new(|| {
+            Time::from_unix_nanos(i128::from(SECS.load(Ordering::Relaxed)) * 1_000_000_000)
+        }),);
+        let cx = Context::from_tuple(&clock);
+        let cache = Cache::new().remember_failures(Duration::from_secs(10));
+        let build =
+            |step: Step, etag: &mut u32| *step.cache(&cache).update(cx, etag).value.generate();
+        let runs = || RUNS.load(Ordering::Relaxed);
+        let mut etag = 0;
+
+        assert_eq!(build(Step(1, false), &mut etag), Err(1));
+        assert_eq!(runs(), 1);
+        SECS.store(5, Ordering::Relaxed);
+        assert_eq!(build(Step(1, false), &mut etag), Err(1));
+        assert_eq!(runs(), 1);
+
+        // Failures are retried once they expire…
+        SECS.store(11, Ordering::Relaxed);
+        assert_eq!(build(Step(1, false), &mut etag), Err(1));
+        assert_eq!(runs(), 2);
+
+        // …or as soon as the asset is modified.
+        SECS.store(12, Ordering::Relaxed);
+        assert_eq!(build(Step(2, false), &mut etag), Err(2));
+        assert_eq!(runs(), 3);
+        assert_eq!(build(Step(3, true), &mut etag), Ok(3));
+        assert_eq!(runs(), 4);
+
+        // Successes are kept as usual.
+        SECS.store(100, Ordering::Relaxed);
+        assert_eq!(build(Step(3, true), &mut etag), Ok(3));
+        assert_eq!(runs(), 4);
+    }
+
+    use super::trim_memory;
+    use super::Cache;
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::time::Clock;
+    use crate::time::Time;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Tracked;
+    use core::sync::atomic::AtomicI64;
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering;
+    use core::time::Duration;
+    use std::boxed::Box;
+}
+
+use super::Asset;
+use super::Context;
+use crate::etag::FromBytesError;
+use crate::etag::Writer as _;
+use crate::time;
+use crate::time::Time;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::mem;
+use core::time::Duration;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::sync::Weak;
+use std::vec::Vec;
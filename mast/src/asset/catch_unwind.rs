@@ -0,0 +1,102 @@
+/// Asset for [`Asset::catch_unwind`].
+#[derive(Debug)]
+pub struct CatchUnwind<A> {
+    asset: A,
+    name: Cow<'static, str>,
+}
+
+impl<A> CatchUnwind<A> {
+    pub(crate) fn new(asset: A, name: Cow<'static, str>) -> Self {
+        Self { asset, name }
+    }
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for CatchUnwind<A> {
+    type Etag = A::Etag;
+    type Output = Result<A::Output, Panic>;
+    type Generator = Generator<A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let name = self.name;
+        self.asset
+            .update(cx, etag)
+            .map(|inner| Generator { inner, name })
+    }
+}
+
+/// Generator for [`CatchUnwind`].
+#[derive(Debug)]
+pub struct Generator<G> {
+    inner: G,
+    name: Cow<'static, str>,
+}
+
+impl<G: super::Generator> super::Generator for Generator<G> {
+    type Output = Result<G::Output, Panic>;
+
+    fn generate(self) -> Self::Output {
+        let inner = self.inner;
+        panic::catch_unwind(AssertUnwindSafe(|| inner.generate()))
+            .map_err(|payload| Panic::new(self.name, &*payload))
+    }
+}
+
+/// The error returned by an asset that panicked while generating,
+/// when wrapped with [`Asset::catch_unwind`].
+#[derive(Debug, Clone)]
+pub struct Panic {
+    asset: Cow<'static, str>,
+    message: Option<String>,
+}
+
+impl Panic {
+    /// Construct a panic error for the named asset from a panic payload,
+    /// such as that returned by [`std::panic::catch_unwind`].
+    #[must_use]
+    pub fn new<N: Into<Cow<'static, str>>>(asset: N, payload: &(dyn Any + Send)) -> Self {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => Some(String::from(*message)),
+            None => payload.downcast_ref::<String>().cloned(),
+        };
+        Self {
+            asset: asset.into(),
+            message,
+        }
+    }
+
+    /// The name of the asset that panicked.
+    #[must_use]
+    pub fn asset(&self) -> &str {
+        &self.asset
+    }
+
+    /// The panic message, if the payload was a string.
+    #[must_use]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+impl Display for Panic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "asset `{}` panicked", self.asset)?;
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Panic {}
+
+use super::Asset;
+use super::Context;
+use crate::Tracked;
+use core::any::Any;
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use std::borrow::Cow;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::string::String;
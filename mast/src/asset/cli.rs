@@ -0,0 +1,111 @@
+/// An asset for the arguments the current process was invoked with,
+/// excluding the program name.
+///
+/// The etag is a digest of the arguments,
+/// so dependent assets are considered modified exactly when the arguments change.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// let mut etag = Default::default();
+/// let args = asset::cli_args().update(asset::Context::default(), &mut etag);
+/// assert!(args.is_modified());
+/// let args: Vec<std::ffi::OsString> = args.value.generate();
+///
+/// assert!(asset::cli_args().update(asset::Context::default(), &mut etag).is_same());
+/// ```
+#[must_use]
+pub fn cli_args() -> CliArgs {
+    CliArgs { _private: () }
+}
+
+/// Asset for [`cli_args`].
+#[derive(Debug, Clone)]
+pub struct CliArgs {
+    _private: (),
+}
+
+impl<'c> Asset<'c> for CliArgs {
+    type Etag = Digest;
+    type Output = Vec<OsString>;
+    type Generator = Generator<Vec<OsString>>;
+
+    fn update(self, _: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let args: Vec<OsString> = env::args_os().skip(1).collect();
+        let mut hasher = Sha256::new();
+        args.serialize(&mut hasher);
+        let digest = hasher.finish();
+        let delta = Delta::cmp(etag, &digest);
+        *etag = digest;
+        delta.track(Generator(args))
+    }
+}
+
+/// An asset for the entire contents of the current process’s standard input.
+///
+/// Standard input is read to the end when the asset is updated,
+/// so that the etag can be computed as a digest of its contents.
+/// Because standard input can only be read once,
+/// this asset should be updated at most once per process.
+/// If reading fails, the asset is considered modified and its output is the error.
+#[must_use]
+pub fn stdin() -> Stdin {
+    Stdin { _private: () }
+}
+
+/// Asset for [`stdin`].
+#[derive(Debug, Clone)]
+pub struct Stdin {
+    _private: (),
+}
+
+impl<'c> Asset<'c> for Stdin {
+    type Etag = Digest;
+    type Output = io::Result<Vec<u8>>;
+    type Generator = Generator<io::Result<Vec<u8>>>;
+
+    fn update(self, _: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let mut input = Vec::new();
+        match io::stdin().lock().read_to_end(&mut input) {
+            Ok(_) => {
+                let digest = Sha256::digest(&input);
+                let delta = Delta::cmp(etag, &digest);
+                *etag = digest;
+                delta.track(Generator(Ok(input)))
+            }
+            Err(e) => {
+                *etag = Digest::default();
+                Delta::Modified.track(Generator(Err(e)))
+            }
+        }
+    }
+}
+
+/// Generator for [`CliArgs`] and [`Stdin`].
+#[derive(Debug)]
+pub struct Generator<T>(T);
+
+impl<T> asset::Generator for Generator<T> {
+    type Output = T;
+    fn generate(self) -> Self::Output {
+        self.0
+    }
+}
+
+use crate::asset;
+use crate::asset::Asset;
+use crate::asset::Context;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Delta;
+use crate::Etag as _;
+use crate::Tracked;
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::io::Read as _;
+use std::vec::Vec;
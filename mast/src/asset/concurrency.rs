@@ -0,0 +1,152 @@
+/// Asset for [`Asset::concurrency`].
+#[derive(Debug)]
+pub struct Concurrency<A> {
+    asset: A,
+    key: Cow<'static, str>,
+    limit: usize,
+}
+
+impl<A> Concurrency<A> {
+    pub(crate) fn new(asset: A, key: Cow<'static, str>, limit: usize) -> Self {
+        assert!(limit != 0, "concurrency limit must be non-zero");
+        Self { asset, key, limit }
+    }
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for Concurrency<A> {
+    type Etag = A::Etag;
+    type Output = A::Output;
+    type Generator = Generator<A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let Self { asset, key, limit } = self;
+        asset
+            .update(cx, etag)
+            .map(|inner| Generator { inner, key, limit })
+    }
+}
+
+/// Generator for [`Concurrency`].
+#[derive(Debug)]
+pub struct Generator<G> {
+    inner: G,
+    key: Cow<'static, str>,
+    limit: usize,
+}
+
+impl<G: super::Generator> super::Generator for Generator<G> {
+    type Output = G::Output;
+
+    fn generate(self) -> Self::Output {
+        let semaphore = {
+            let mut semaphores = lock(&SEMAPHORES);
+            // Forget the semaphores that nothing is waiting on or holding.
+            semaphores.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+            semaphores.entry(self.key.into_owned()).or_default().clone()
+        };
+        let _permit = semaphore.acquire(self.limit);
+        self.inner.generate()
+    }
+}
+
+/// The semaphores of every key that is currently in use.
+static SEMAPHORES: Mutex<BTreeMap<String, Arc<Semaphore>>> = Mutex::new(BTreeMap::new());
+
+#[derive(Default)]
+struct Semaphore {
+    running: Mutex<usize>,
+    condvar: Condvar,
+}
+
+impl Semaphore {
+    /// Wait until fewer than `limit` holders of this semaphore are running.
+    ///
+    /// Each waiter brings its own limit,
+    /// so assets that share a key but disagree on the limit still behave sensibly:
+    /// each waits only until there are fewer than its own limit running.
+    fn acquire(&self, limit: usize) -> Permit<'_> {
+        let mut running = lock(&self.running);
+        while *running >= limit {
+            running = self
+                .condvar
+                .wait(running)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *running += 1;
+        Permit { semaphore: self }
+    }
+}
+
+struct Permit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *lock(&self.semaphore.running) -= 1;
+        self.semaphore.condvar.notify_all();
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn limit() {
+        static RUNNING: AtomicUsize = AtomicUsize::new(0);
+        static MAX: AtomicUsize = AtomicUsize::new(0);
+        fn work() {
+            let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+            MAX.fetch_max(running, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            RUNNING.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        struct Work;
+        impl<'c> Asset<'c> for Work {
+            type Etag = ();
+            type Output = ();
+            type Generator = fn();
+            fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+                Delta::Modified.track(work)
+            }
+        }
+
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let asset = Work.concurrency("test", 2);
+                    asset.update(Context::default(), &mut ()).value.generate();
+                });
+            }
+        });
+        // The threads may happen not to overlap at all, so only the limit can be asserted.
+        let max = MAX.load(Ordering::SeqCst);
+        assert!((1..=2).contains(&max), "{max} ran at once");
+    }
+
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Tracked;
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering;
+    use core::time::Duration;
+    use std::thread;
+}
+
+use super::Asset;
+use super::Context;
+use crate::Tracked;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::string::String;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
@@ -0,0 +1,103 @@
+/// An asset whose output is a value fixed for the lifetime of the program,
+/// such as a resource compiled into the binary.
+///
+/// The etag of this asset is zero-sized and it is never modified,
+/// so it costs nothing at runtime.
+/// The value must therefore be the same in every build;
+/// a value that can change between runs of the program,
+/// such as one read from the environment,
+/// should be mixed into the etag with [`Asset::map_etag`] instead.
+///
+/// Since this is a `const fn`, constant assets can be defined in `const` items.
+/// For embedded bytes and strings, see also [`static_bytes`] and [`static_str`].
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// const PAGE_SIZE: asset::Constant<usize> = asset::constant(20);
+///
+/// let tracked = PAGE_SIZE.update(asset::Context::default(), &mut ());
+/// assert!(tracked.is_same());
+/// assert_eq!(tracked.value.generate(), 20);
+/// ```
+pub const fn constant<T>(value: T) -> Constant<T> {
+    Constant { value }
+}
+
+/// An asset whose output is bytes compiled into the binary,
+/// typically with [`include_bytes!`].
+///
+/// This is a [`constant`] asset.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// const FAVICON: asset::Constant<&[u8]> = asset::static_bytes(b"\x00\x00\x01\x00");
+///
+/// let tracked = FAVICON.update(asset::Context::default(), &mut ());
+/// assert_eq!(tracked.value.generate(), b"\x00\x00\x01\x00");
+/// ```
+#[must_use]
+pub const fn static_bytes(bytes: &'static [u8]) -> Constant<&'static [u8]> {
+    constant(bytes)
+}
+
+/// An asset whose output is a string compiled into the binary,
+/// typically with [`include_str!`].
+///
+/// This is a [`constant`] asset.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// const STYLE: asset::Constant<&str> = asset::static_str("body { margin: 0 }");
+///
+/// let tracked = STYLE.update(asset::Context::default(), &mut ());
+/// assert_eq!(tracked.value.generate(), "body { margin: 0 }");
+/// ```
+#[must_use]
+pub const fn static_str(s: &'static str) -> Constant<&'static str> {
+    constant(s)
+}
+
+/// Asset for [`constant`], [`static_bytes`] and [`static_str`].
+///
+/// This is also its own generator.
+#[derive(Debug, Clone, Copy)]
+pub struct Constant<T> {
+    value: T,
+}
+
+impl<'c, T> Asset<'c> for Constant<T> {
+    type Etag = ();
+    type Output = T;
+    type Generator = Self;
+
+    fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+        Tracked::constant(self)
+    }
+}
+
+impl<T> super::Generator for Constant<T> {
+    type Output = T;
+
+    fn generate(self) -> Self::Output {
+        self.value
+    }
+}
+
+use super::Asset;
+use super::Context;
+use crate::Tracked;
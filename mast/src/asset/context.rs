@@ -3,13 +3,16 @@
 //! to all [`Asset`](super::Asset)s.
 
 /// A type used to easily thread miscellaneous context types to all [`Asset`](super::Asset)s.
+///
+/// A context borrows its values,
+/// which come from an array, a tuple or an [`OwnedContext`].
 #[derive(Clone, Copy)]
 pub struct Context<'cx> {
     inner: &'cx dyn Inner,
 }
 
 impl<'cx> Context<'cx> {
-    fn with_values<O>(self, f: impl FnOnce(&[&'cx dyn Value]) -> O) -> O {
+    fn with_values<O>(self, f: impl FnOnce(Values<'_, 'cx>) -> O) -> O {
         let mut output = None;
         let mut f = Some(f);
         self.inner
@@ -21,8 +24,8 @@ impl<'cx> Context<'cx> {
     fn new<I: Inner>(inner: &'cx I) -> Self {
         let this = Self { inner };
         let res = this.with_values(|values| {
-            for (i, &lhs) in values.iter().enumerate() {
-                for &rhs in &values[i + 1..] {
+            for (i, lhs) in values.iter().enumerate() {
+                for rhs in values.iter().skip(i + 1) {
                     if lhs.__as_dyn_any(Token).type_id() == rhs.__as_dyn_any(Token).type_id() {
                         return Err(lhs.__type_name(Token));
                     }
@@ -132,20 +135,66 @@ impl<'cx> Context<'cx> {
         self.with_values(|values| {
             values
                 .iter()
-                .find_map(|&value| value.__as_dyn_any(Token).downcast_ref::<T>())
+                .find_map(|value| value.__as_dyn_any(Token).downcast_ref::<T>())
         })
     }
+
+    /// Retrieve a reference to the value of type `T` stored under `key`
+    /// in the [`Namespaced<T>`] in the `Context`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no value of type `T` under `key` in the context.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+    #[must_use]
+    #[track_caller]
+    pub fn get_in<T: 'static>(self, key: &str) -> &'cx T {
+        match self.try_get_in(key) {
+            Some(val) => val,
+            None => panic!(
+                "no value of type {} under {key:?} found in `Context`",
+                type_name::<T>()
+            ),
+        }
+    }
+
+    /// Attempt to retrieve a reference to the value of type `T` stored under `key`
+    /// in the [`Namespaced<T>`] in the `Context`.
+    /// Returns [`None`] if there is no [`Namespaced<T>`] in the context,
+    /// or it has no value under `key`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use mast::asset;
+    /// use mast::asset::context::Namespaced;
+    ///
+    /// #[derive(Debug, PartialEq, Eq)]
+    /// struct OutputDir(&'static str);
+    ///
+    /// let dirs = Namespaced::new()
+    ///     .with("blog", OutputDir("public/blog"))
+    ///     .with("docs", OutputDir("public/docs"));
+    /// let cx = (dirs,);
+    /// let cx = asset::Context::from_tuple(&cx);
+    /// assert_eq!(cx.try_get_in::<OutputDir>("blog"), Some(&OutputDir("public/blog")));
+    /// assert_eq!(cx.try_get_in::<OutputDir>("docs"), Some(&OutputDir("public/docs")));
+    /// assert_eq!(cx.try_get_in::<OutputDir>("shop"), None);
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+    #[must_use]
+    pub fn try_get_in<T: 'static>(self, key: &str) -> Option<&'cx T> {
+        self.try_get::<Namespaced<T>>()?.get(key)
+    }
 }
 
 impl Debug for Context<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         self.with_values(|values| {
             f.debug_map()
-                .entries(
-                    values
-                        .iter()
-                        .map(|&value| (value.__type_name(Token), value)),
-                )
+                .entries(values.iter().map(|value| (value.__type_name(Token), value)))
                 .finish()
         })
     }
@@ -157,6 +206,361 @@ impl Default for Context<'_> {
     }
 }
 
+/// A [`Value`] that assets can add items to while they generate,
+/// such as the metadata of every page for a later index step to read.
+///
+/// Values in a [`Context`] are only accessible by shared reference,
+/// so a collector is internally synchronized.
+/// The driver of the build [drains](Self::drain) it between phases.
+///
+/// Since a context can only contain one value of each type,
+/// put the collectors in a [`Namespaced`]
+/// if there are multiple collectors of the same item type.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::context::Collector;
+///
+/// #[derive(Debug)]
+/// struct Page {
+///     title: &'static str,
+/// }
+///
+/// let values = (Collector::<Page>::new(),);
+/// let cx = asset::Context::from_tuple(&values);
+///
+/// // During the first phase, each page’s generator adds its metadata…
+/// for title in ["Home", "About"] {
+///     cx.get::<Collector<Page>>().push(Page { title });
+/// }
+///
+/// // …and before the second phase, the driver hands it to the index.
+/// let pages = cx.get::<Collector<Page>>().drain();
+/// let titles: Vec<_> = pages.iter().map(|page| page.title).collect();
+/// assert_eq!(titles, ["Home", "About"]);
+/// assert!(cx.get::<Collector<Page>>().is_empty());
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+#[derive(Debug)]
+pub struct Collector<T> {
+    items: Mutex<Vec<T>>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Collector<T> {
+    /// Construct an empty collector.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            items: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add an item to the collector.
+    pub fn push(&self, item: T) {
+        self.lock().push(item);
+    }
+
+    /// Add several items to the collector.
+    pub fn extend<I: IntoIterator<Item = T>>(&self, items: I) {
+        self.lock().extend(items);
+    }
+
+    /// The number of items collected.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no items have been collected.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove and return all the items collected so far, in the order they were added.
+    #[must_use]
+    pub fn drain(&self) -> Vec<T> {
+        mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<T>> {
+        self.items.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for Collector<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`Value`] holding several values of the same type under distinct keys,
+/// such as the output directories of two sub-sites.
+///
+/// A context can only contain one value of each type,
+/// so this is used instead of a newtype for each instance.
+/// Values are retrieved with [`Context::get_in`] and [`Context::try_get_in`].
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::context::Namespaced;
+///
+/// #[derive(Debug)]
+/// struct OutputDir(&'static str);
+///
+/// let dirs: Namespaced<_> = [
+///     ("blog", OutputDir("public/blog")),
+///     ("docs", OutputDir("public/docs")),
+/// ]
+/// .into_iter()
+/// .collect();
+/// assert_eq!(dirs.keys().collect::<Vec<_>>(), ["blog", "docs"]);
+///
+/// let values = (dirs,);
+/// let cx = asset::Context::from_tuple(&values);
+/// assert_eq!(cx.get_in::<OutputDir>("docs").0, "public/docs");
+/// ```
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+#[derive(Debug)]
+pub struct Namespaced<T> {
+    values: BTreeMap<String, T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Namespaced<T> {
+    /// Construct an empty set of values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Add a value under `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is already a value under `key`.
+    #[must_use]
+    #[track_caller]
+    pub fn with<K: Into<String>>(mut self, key: K, value: T) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Add a value under `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is already a value under `key`.
+    #[track_caller]
+    pub fn insert<K: Into<String>>(&mut self, key: K, value: T) {
+        match self.values.entry(key.into()) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+            btree_map::Entry::Occupied(entry) => {
+                panic!(
+                    "attempted to add more than one value under {:?}",
+                    entry.key()
+                );
+            }
+        }
+    }
+
+    /// Retrieve the value under `key`, if there is one.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&T> {
+        self.values.get(key)
+    }
+
+    /// Iterate over the keys, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(|key| &**key)
+    }
+
+    /// Iterate over the keys and their values, in order of key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.values.iter().map(|(key, value)| (&**key, value))
+    }
+
+    /// The number of values.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether there are no values.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Default for Namespaced<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: Into<String>, T> FromIterator<(K, T)> for Namespaced<T> {
+    /// # Panics
+    ///
+    /// Panics if a key appears more than once.
+    #[track_caller]
+    fn from_iter<I: IntoIterator<Item = (K, T)>>(iter: I) -> Self {
+        let mut this = Self::new();
+        for (key, value) in iter {
+            this.insert(key, value);
+        }
+        this
+    }
+}
+
+/// A builder for an [`OwnedContext`],
+/// for applications that assemble their context dynamically.
+///
+/// Unlike a tuple passed to [`Context::from_tuple`],
+/// the set of values need not be known at compile time,
+/// and the result owns its values, so it need not be kept alive on the stack.
+/// Adding a value [overrides](Self::with) any existing value of the same type,
+/// while a [default](Self::with_default) is only added if there is none.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset::context::ContextBuilder;
+///
+/// #[derive(Debug)]
+/// struct OutputDir(&'static str);
+///
+/// #[derive(Debug)]
+/// struct Minify(bool);
+///
+/// let verbose = true;
+/// let mut builder = ContextBuilder::new().with(OutputDir("public"));
+/// if verbose {
+///     builder.insert(Minify(false));
+/// }
+/// let owned = builder
+///     .with_default(Minify(true))
+///     .with_default(OutputDir("dist"))
+///     .build();
+///
+/// let cx = owned.context();
+/// assert_eq!(cx.get::<OutputDir>().0, "public");
+/// assert!(!cx.get::<Minify>().0);
+/// ```
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+#[derive(Debug, Default)]
+pub struct ContextBuilder {
+    values: Vec<Box<dyn Value>>,
+}
+
+#[cfg(feature = "alloc")]
+impl ContextBuilder {
+    /// Construct a builder with no values.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { values: Vec::new() }
+    }
+
+    /// Add a value, replacing any existing value of the same type.
+    #[must_use]
+    pub fn with<T: Value>(mut self, value: T) -> Self {
+        self.insert(value);
+        self
+    }
+
+    /// Add a value if there is no value of the same type yet.
+    #[must_use]
+    pub fn with_default<T: Value>(mut self, value: T) -> Self {
+        self.insert_default(value);
+        self
+    }
+
+    /// Add a value, replacing any existing value of the same type.
+    pub fn insert<T: Value>(&mut self, value: T) {
+        match self.position::<T>() {
+            Some(i) => self.values[i] = Box::new(value),
+            None => self.values.push(Box::new(value)),
+        }
+    }
+
+    /// Add a value if there is no value of the same type yet.
+    pub fn insert_default<T: Value>(&mut self, value: T) {
+        if self.position::<T>().is_none() {
+            self.values.push(Box::new(value));
+        }
+    }
+
+    /// Whether there is a value of type `T`.
+    #[must_use]
+    pub fn contains<T: Value>(&self) -> bool {
+        self.position::<T>().is_some()
+    }
+
+    /// Finish building, producing an [`OwnedContext`] holding the values.
+    #[must_use]
+    pub fn build(self) -> OwnedContext {
+        OwnedContext {
+            values: self.values,
+        }
+    }
+
+    fn position<T: Value>(&self) -> Option<usize> {
+        self.values
+            .iter()
+            .position(|value| (**value).__as_dyn_any(Token).is::<T>())
+    }
+}
+
+/// A set of [`Value`]s owned by the context, built with a [`ContextBuilder`].
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+pub struct OwnedContext {
+    values: Vec<Box<dyn Value>>,
+}
+
+#[cfg(feature = "alloc")]
+impl OwnedContext {
+    /// Borrow the values as a [`Context`].
+    #[must_use]
+    pub fn context(&self) -> Context<'_> {
+        // `ContextBuilder` never holds two values of the same type.
+        Context { inner: self }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Debug for OwnedContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.context().fmt(f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Inner for OwnedContext {
+    fn __with_values_erased<'this>(
+        &'this self,
+        Token: Token,
+        f: &mut dyn FnMut(Values<'_, 'this>),
+    ) {
+        f(Values::Boxed(&self.values));
+    }
+}
+
 /// A value that can be stored in a [`Context`].
 ///
 /// This is automatically implemented any type that is:
@@ -177,9 +581,37 @@ mod sealed {
         fn __with_values_erased<'this>(
             &'this self,
             token: Token,
-            f: &mut dyn FnMut(&[&'this dyn super::Value]),
+            f: &mut dyn FnMut(Values<'_, 'this>),
         );
     }
+
+    /// The values of a context, however they are stored.
+    #[allow(missing_debug_implementations)]
+    #[derive(Clone, Copy)]
+    pub enum Values<'s, 'a> {
+        Borrowed(&'s [&'a dyn super::Value]),
+        #[cfg(feature = "alloc")]
+        Boxed(&'a [Box<dyn super::Value>]),
+    }
+
+    impl<'s, 'a: 's> Values<'s, 'a> {
+        #[cfg(not(feature = "alloc"))]
+        pub fn iter(self) -> impl Iterator<Item = &'a dyn super::Value> + 's {
+            let Self::Borrowed(borrowed) = self;
+            borrowed.iter().copied()
+        }
+
+        #[cfg(feature = "alloc")]
+        pub fn iter(self) -> impl Iterator<Item = &'a dyn super::Value> + 's {
+            let (borrowed, boxed): (&[&dyn super::Value], &[Box<dyn super::Value>]) = match self {
+                Self::Borrowed(borrowed) => (borrowed, &[]),
+                Self::Boxed(boxed) => (&[], boxed),
+            };
+            // Deref the boxes explicitly, since `Box<dyn Value>` is itself a `Value`.
+            let boxed = boxed.iter().map(|value| &**value);
+            borrowed.iter().copied().chain(boxed)
+        }
+    }
     pub trait Value: 'static + Sync + Debug {
         fn __type_name(&self, token: Token) -> &'static str;
         fn __as_dyn_any(&self, token: Token) -> &dyn Any;
@@ -198,20 +630,23 @@ mod sealed {
     // We prevent this by requiring an unconstructable `Token` type.
     #[allow(missing_debug_implementations)]
     pub struct Token;
+    #[cfg(feature = "alloc")]
+    use alloc::boxed::Box;
     use core::any::type_name;
     use core::any::Any;
     use core::fmt::Debug;
 }
 use sealed::Inner;
 use sealed::Token;
+use sealed::Values;
 
 impl<const N: usize> Inner for [&'_ dyn Value; N] {
     fn __with_values_erased<'this>(
         &'this self,
         Token: Token,
-        f: &mut dyn FnMut(&[&'this dyn Value]),
+        f: &mut dyn FnMut(Values<'_, 'this>),
     ) {
-        f(self);
+        f(Values::Borrowed(self));
     }
 }
 
@@ -222,11 +657,11 @@ macro_rules! impl_for_tuple {
             fn __with_values_erased<'this>(
                 &'this self,
                 Token: Token,
-                f: &mut dyn FnMut(&[&'this dyn Value]),
+                f: &mut dyn FnMut(Values<'_, 'this>),
             ) {
                 let ($($t,)*) = self;
                 $(let $t: &'this dyn Value = $t;)*
-                f(&[$($t,)*]);
+                f(Values::Borrowed(&[$($t,)*]));
             }
         }
         impl<$($t: Value,)*> Tuple for ($($t,)*) {}
@@ -234,7 +669,25 @@ macro_rules! impl_for_tuple {
 }
 crate::for_tuples!(impl_for_tuple);
 
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::btree_map;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use core::any::type_name;
 use core::fmt;
 use core::fmt::Debug;
 use core::fmt::Formatter;
+#[cfg(feature = "std")]
+use core::mem;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use std::sync::MutexGuard;
+#[cfg(feature = "std")]
+use std::sync::PoisonError;
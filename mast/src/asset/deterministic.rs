@@ -0,0 +1,73 @@
+/// A request for deterministic builds, which can be placed in the [`Context`].
+///
+/// With a `Deterministic` in the context,
+/// two builds from identical inputs produce byte-identical outputs,
+/// as needed for remote caching and reproducible deployments:
+///
+/// - [`time::now`](crate::time::now) returns the pinned [`timestamp`](Self::timestamp)
+///   instead of the system clock,
+///   unless a [`Clock`](crate::time::Clock) in the context says otherwise.
+/// - [`All::unordered`](super::All::unordered) yields outputs in the order of its assets
+///   rather than the order they finish in.
+///
+/// The other built-in assets are deterministic regardless:
+/// directory listings and scans are sorted by path,
+/// and no output depends on the iteration order of a hash map.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Deterministic;
+/// use mast::time;
+/// use mast::time::Time;
+///
+/// let values = (Deterministic::new(Time::from_unix_nanos(37)),);
+/// let cx = asset::Context::from_tuple(&values);
+/// assert!(Deterministic::enabled(cx));
+/// assert_eq!(time::now(cx), Some(Time::from_unix_nanos(37)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deterministic {
+    timestamp: Time,
+}
+
+impl Deterministic {
+    /// Request deterministic builds, pinning the current time to `timestamp`.
+    #[must_use]
+    pub const fn new(timestamp: Time) -> Self {
+        Self { timestamp }
+    }
+
+    /// Request deterministic builds,
+    /// pinning the current time to the `SOURCE_DATE_EPOCH` environment variable,
+    /// a number of seconds since the Unix epoch,
+    /// or to the Unix epoch itself if it is unset or invalid.
+    ///
+    /// See <https://reproducible-builds.org/specs/source-date-epoch/>.
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    #[must_use]
+    pub fn from_env() -> Self {
+        let secs = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|secs| secs.trim().parse::<i64>().ok())
+            .unwrap_or(0);
+        Self::new(Time::from_unix_nanos(i128::from(secs) * 1_000_000_000))
+    }
+
+    /// The time that the current time is pinned to.
+    #[must_use]
+    pub const fn timestamp(&self) -> Time {
+        self.timestamp
+    }
+
+    /// Whether there is a `Deterministic` in the context.
+    #[must_use]
+    pub fn enabled(cx: Context<'_>) -> bool {
+        cx.try_get::<Self>().is_some()
+    }
+}
+
+use super::Context;
+use crate::time::Time;
@@ -0,0 +1,48 @@
+/// An [`Asset`] whose output does not borrow from the build,
+/// so it can outlive the lifetime `'c` of the context and etag it was generated with.
+///
+/// [`Asset::Output`] may depend on `'c`,
+/// which stops generic code from keeping outputs past the end of a build,
+/// for example to store them, share them with other threads,
+/// or return them from a function that creates the etag itself.
+/// Bounding an asset by `FixedOutput` instead of [`Asset`]
+/// gives its output a single type, [`Self::Fixed`], for every `'c`.
+///
+/// This is implemented for every asset whose output type is the same for every `'c`,
+/// which includes every asset whose type does not itself depend on `'c`.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::FixedOutput;
+/// use mast::asset::Generator as _;
+///
+/// fn build_all<A: FixedOutput>(assets: Vec<A>) -> Vec<A::Fixed> {
+///     let cx = asset::Context::default();
+///     let build = |asset: A| {
+///         // The etag only lives until the end of this closure,
+///         // but the output can still be returned from it.
+///         let mut etag = Default::default();
+///         let output = asset.update(cx, &mut etag).value.generate();
+///         output
+///     };
+///     assets.into_iter().map(build).collect()
+/// }
+///
+/// let outputs = build_all(vec![asset::constant(1), asset::constant(2)]);
+/// assert_eq!(outputs, [1, 2]);
+/// ```
+pub trait FixedOutput: for<'c> Asset<'c, Output = <Self as FixedOutput>::Fixed> {
+    /// The output of the asset, whatever the lifetime of the build.
+    type Fixed;
+}
+
+impl<A, O> FixedOutput for A
+where
+    A: for<'c> Asset<'c, Output = O>,
+{
+    type Fixed = O;
+}
+
+use super::Asset;
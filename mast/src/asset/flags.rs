@@ -0,0 +1,161 @@
+/// The set of flags enabled for a build, which can be placed in the [`Context`].
+///
+/// Flags toggle variants of a build from one run to the next,
+/// such as including drafts in development or skipping minification.
+/// Assets can check them directly with [`Flags::enabled`],
+/// or be built only when a flag is enabled with [`when_flag`].
+/// Without a `Flags` in the context, every flag is disabled.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Flags;
+///
+/// let values = (Flags::new().enable("drafts"),);
+/// let cx = asset::Context::from_tuple(&values);
+/// assert!(Flags::enabled(cx, "drafts"));
+/// assert!(!Flags::enabled(cx, "minify"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Flags {
+    enabled: BTreeSet<Cow<'static, str>>,
+}
+
+impl Flags {
+    /// Construct a set of flags with every flag disabled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable a flag.
+    #[must_use]
+    pub fn enable<N: Into<Cow<'static, str>>>(mut self, name: N) -> Self {
+        self.enabled.insert(name.into());
+        self
+    }
+
+    /// Whether the flag is enabled in this set.
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.enabled.contains(name)
+    }
+
+    /// Whether the flag is enabled in the `Flags` in the context.
+    #[must_use]
+    pub fn enabled(cx: Context<'_>, name: &str) -> bool {
+        cx.try_get::<Self>()
+            .is_some_and(|flags| flags.contains(name))
+    }
+
+    /// A string identifying this combination of enabled flags:
+    /// the names of the flags in sorted order, separated by commas.
+    #[must_use]
+    pub fn key(&self) -> String {
+        let mut key = String::new();
+        for (i, name) in self.enabled.iter().enumerate() {
+            if i != 0 {
+                key.push(',');
+            }
+            key.push_str(name);
+        }
+        key
+    }
+}
+
+/// Build an asset only when a flag is enabled in the [`Flags`] in the context.
+///
+/// The output is [`None`] when the flag is disabled.
+/// The asset keeps a separate etag for each combination of enabled flags,
+/// so that an asset whose behaviour depends on other flags too
+/// never mistakes the state of one variant of the build for that of another,
+/// and switching back to a previous combination does not rebuild it.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Flags;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// let mut etag = Default::default();
+///
+/// let drafts = asset::when_flag("drafts", asset::cli_args());
+/// let output = drafts.update(asset::Context::default(), &mut etag).value.generate();
+/// assert_eq!(output, None);
+///
+/// let values = (Flags::new().enable("drafts"),);
+/// let cx = asset::Context::from_tuple(&values);
+/// let drafts = asset::when_flag("drafts", asset::cli_args());
+/// assert!(drafts.update(cx, &mut etag).value.generate().is_some());
+/// ```
+pub fn when_flag<N, A>(name: N, asset: A) -> WhenFlag<A>
+where
+    N: Into<Cow<'static, str>>,
+{
+    WhenFlag {
+        name: name.into(),
+        asset,
+    }
+}
+
+/// Asset for [`when_flag`].
+#[derive(Debug)]
+pub struct WhenFlag<A> {
+    name: Cow<'static, str>,
+    asset: A,
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for WhenFlag<A> {
+    type Etag = (Option<bool>, BTreeMap<String, A::Etag>);
+    type Output = Option<A::Output>;
+    type Generator = Generator<'c, A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (was_enabled, etags) = etag;
+        let enabled = Flags::enabled(cx, &self.name);
+        let delta = Delta::cmp(&*was_enabled, &Some(enabled));
+        let inner = if enabled {
+            let key = cx.try_get::<Flags>().map(Flags::key).unwrap_or_default();
+            let tracked = self.asset.update(cx, etags.entry(key).or_default());
+            Some(tracked)
+        } else {
+            None
+        };
+        let delta = inner.as_ref().map_or(delta, |inner| delta.or(inner.delta));
+        delta.track(Generator {
+            inner: inner.map(|inner| inner.value),
+            was_enabled,
+            enabled,
+        })
+    }
+}
+
+/// Generator for [`WhenFlag`].
+#[derive(Debug)]
+pub struct Generator<'c, G> {
+    inner: Option<G>,
+    was_enabled: &'c mut Option<bool>,
+    enabled: bool,
+}
+
+impl<G: super::Generator> super::Generator for Generator<'_, G> {
+    type Output = Option<G::Output>;
+
+    fn generate(self) -> Self::Output {
+        let output = self.inner.map(super::Generator::generate);
+        *self.was_enabled = Some(self.enabled);
+        output
+    }
+}
+
+use super::Asset;
+use super::Context;
+use crate::Delta;
+use crate::Tracked;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::string::String;
@@ -0,0 +1,132 @@
+/// Construct a sequence asset from an arbitrary producer of items,
+/// such as an API, a database or in-memory state.
+///
+/// `f` is called on every update to produce the items,
+/// and `etag` is called with each item to compute its etag,
+/// for example a revision number or a hash of its contents.
+/// The asset is modified when the sequence of item etags changes.
+/// Its output is the items in order, each tracked as modified
+/// only if its etag was not among the item etags of the previous update,
+/// so that work for the unchanged items can be skipped
+/// even when items are added, removed or reordered.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+///
+/// struct Post {
+///     slug: &'static str,
+///     revision: u32,
+/// }
+///
+/// fn posts(db: &[(&'static str, u32)]) -> Vec<Post> {
+///     db.iter().map(|&(slug, revision)| Post { slug, revision }).collect()
+/// }
+///
+/// fn revision(post: &Post) -> (String, u32) {
+///     (post.slug.to_owned(), post.revision)
+/// }
+///
+/// let mut etag = Default::default();
+/// let cx = asset::Context::default();
+///
+/// let db = [("hello", 1), ("world", 1)];
+/// let source = asset::from_iter(|| posts(&db), revision);
+/// let res = source.update(cx, &mut etag);
+/// assert!(res.is_modified());
+/// assert!(res.value.generate().iter().all(|post| post.is_modified()));
+///
+/// let db = [("hello", 2), ("world", 1)];
+/// let source = asset::from_iter(|| posts(&db), revision);
+/// let res = source.update(cx, &mut etag);
+/// assert!(res.is_modified());
+/// let modified: Vec<_> = res.value.generate().into_iter()
+///     .filter(|post| post.is_modified())
+///     .map(|post| post.value.slug)
+///     .collect();
+/// assert_eq!(modified, ["hello"]);
+/// ```
+pub fn from_iter<F, I, G, E>(f: F, etag: G) -> FromIter<F, G>
+where
+    F: FnOnce() -> I,
+    I: IntoIterator,
+    G: FnMut(&I::Item) -> E,
+    E: Etag + Ord,
+{
+    FromIter { f, etag }
+}
+
+/// Asset for [`from_iter`].
+pub struct FromIter<F, G> {
+    f: F,
+    etag: G,
+}
+
+impl<F, G> Debug for FromIter<F, G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromIter").finish_non_exhaustive()
+    }
+}
+
+impl<'c, F, I, G, E> Asset<'c> for FromIter<F, G>
+where
+    F: FnOnce() -> I,
+    I: IntoIterator,
+    G: FnMut(&I::Item) -> E,
+    E: Etag + Ord,
+{
+    type Etag = Vec<E>;
+    type Output = Vec<Tracked<I::Item>>;
+    type Generator = Generator<I::Item>;
+
+    fn update(self, _: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let mut etag_of = self.etag;
+        let (items, etags): (Vec<_>, Vec<_>) = (self.f)()
+            .into_iter()
+            .map(|item| {
+                let etag = etag_of(&item);
+                (item, etag)
+            })
+            .unzip();
+
+        let old: BTreeSet<&E> = etag.iter().collect();
+        let items = items
+            .into_iter()
+            .zip(&etags)
+            .map(|(item, new)| Delta::cmp(&old.contains(new), &true).track(item))
+            .collect();
+        drop(old);
+
+        let delta = Delta::cmp(etag, &etags);
+        *etag = etags;
+        delta.track(Generator { items })
+    }
+}
+
+/// Generator for [`FromIter`].
+#[derive(Debug)]
+pub struct Generator<T> {
+    items: Vec<Tracked<T>>,
+}
+
+impl<T> super::Generator for Generator<T> {
+    type Output = Vec<Tracked<T>>;
+
+    fn generate(self) -> Self::Output {
+        self.items
+    }
+}
+
+use super::Asset;
+use super::Context;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::collections::BTreeSet;
+use std::vec::Vec;
@@ -0,0 +1,150 @@
+/// An asset’s output built at most once per process, on first use,
+/// for generated data that is used as a global resource, such as compiled templates.
+///
+/// This is usually declared as a `static` with [`lazy_asset!`](crate::lazy_asset).
+/// The first call to [`get`](Self::get), from any thread, runs `init`;
+/// concurrent callers wait for it to finish, and later callers reuse its output
+/// without taking any lock.
+/// If `init` panics, the next call runs it again.
+///
+/// [`try_get`](Self::try_get) never waits, even while `init` is running.
+/// Calling `get` on the same global asset from within its own `init` deadlocks.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset::GlobalAsset;
+///
+/// static ANSWER: GlobalAsset<u32> = GlobalAsset::new(|| 6 * 7);
+/// assert_eq!(ANSWER.try_get(), None);
+/// assert_eq!(*ANSWER.get(), 42);
+/// assert_eq!(ANSWER.try_get(), Some(&42));
+/// ```
+pub struct GlobalAsset<O> {
+    init: fn() -> O,
+    output: OnceLock<O>,
+}
+
+impl<O> GlobalAsset<O> {
+    /// Construct a global asset whose output is produced by `init`.
+    #[must_use]
+    pub const fn new(init: fn() -> O) -> Self {
+        Self {
+            init,
+            output: OnceLock::new(),
+        }
+    }
+
+    /// Get the output, producing it if this is the first call.
+    #[must_use]
+    pub fn get(&self) -> &O {
+        self.output.get_or_init(self.init)
+    }
+
+    /// Get the output if it has already been produced.
+    #[must_use]
+    pub fn try_get(&self) -> Option<&O> {
+        self.output.get()
+    }
+}
+
+impl<O: Debug> Debug for GlobalAsset<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlobalAsset")
+            .field("output", &self.try_get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Declare `static` [`GlobalAsset`]s built from assets on first use.
+///
+/// Each asset expression is evaluated, updated with an empty [`Context`] and a default etag,
+/// and generated, the first time the static is accessed.
+/// Since there is no previous etag, this always produces the output in full.
+///
+/// [`GlobalAsset`]: crate::asset::GlobalAsset
+/// [`Context`]: crate::asset::Context
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::Asset as _;
+///
+/// mast::lazy_asset! {
+///     /// The greeting, shouted.
+///     static GREETING: String = asset::constant("hello").map(str::to_uppercase);
+///     pub(crate) static LENGTH: usize = asset::constant(GREETING.get()).map(|s| s.len());
+/// }
+///
+/// assert_eq!(GREETING.get(), "HELLO");
+/// assert_eq!(*LENGTH.get(), 5);
+/// ```
+#[macro_export]
+macro_rules! lazy_asset {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $asset:expr;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::asset::GlobalAsset<$ty> =
+                $crate::asset::GlobalAsset::new(|| $crate::asset::__generate_global($asset));
+        )*
+    };
+}
+
+#[doc(hidden)]
+pub fn __generate_global<A, E, O>(asset: A) -> O
+where
+    A: for<'c> Asset<'c, Etag = E, Output = O>,
+    E: Etag,
+{
+    let mut etag = E::default();
+    // Bound to a variable so that the update, which borrows `etag`, ends before it is dropped.
+    let output = asset.update(Context::default(), &mut etag).value.generate();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn once() {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        static COUNTED: GlobalAsset<usize> = GlobalAsset::new(|| RUNS.fetch_add(1, SeqCst) + 1);
+
+        let threads: Vec<_> = (0..4).map(|_| thread::spawn(|| COUNTED.get())).collect();
+        for thread in threads {
+            assert_eq!(*thread.join().unwrap(), 1);
+        }
+        assert_eq!(*COUNTED.get(), 1);
+        assert_eq!(RUNS.load(SeqCst), 1);
+    }
+
+    #[test]
+    fn retry_after_panic() {
+        static FAIL: AtomicBool = AtomicBool::new(true);
+        static FLAKY: GlobalAsset<&str> = GlobalAsset::new(|| {
+            assert!(!FAIL.swap(false, SeqCst), "first attempt fails");
+            "ok"
+        });
+
+        assert!(panic::catch_unwind(|| FLAKY.get()).is_err());
+        assert_eq!(FLAKY.try_get(), None);
+        assert_eq!(*FLAKY.get(), "ok");
+    }
+
+    use super::GlobalAsset;
+    use std::panic;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::SeqCst;
+    use std::thread;
+    use std::vec::Vec;
+}
+
+use super::Asset;
+use super::Context;
+use super::Generator as _;
+use crate::Etag;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::sync::OnceLock;
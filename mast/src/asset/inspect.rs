@@ -0,0 +1,108 @@
+/// Asset for [`Asset::inspect`].
+pub struct Inspect<A, F> {
+    asset: A,
+    f: F,
+}
+
+impl<A, F> Inspect<A, F> {
+    pub(crate) fn new(asset: A, f: F) -> Self {
+        Self { asset, f }
+    }
+}
+
+impl<A: Debug, F> Debug for Inspect<A, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inspect")
+            .field("asset", &self.asset)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, A, F> Asset<'c> for Inspect<A, F>
+where
+    A: Asset<'c>,
+    F: FnOnce(&A::Output),
+{
+    type Etag = A::Etag;
+    type Output = A::Output;
+    type Generator = Generator<A::Generator, F>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        self.asset.update(cx, etag).map(|generator| Generator {
+            generator,
+            f: self.f,
+        })
+    }
+}
+
+/// Generator for [`Inspect`].
+pub struct Generator<G, F> {
+    generator: G,
+    f: F,
+}
+
+impl<G: Debug, F> Debug for Generator<G, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Generator")
+            .field("generator", &self.generator)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<G, F> super::Generator for Generator<G, F>
+where
+    G: super::Generator,
+    F: FnOnce(&G::Output),
+{
+    type Output = G::Output;
+
+    fn generate(self) -> Self::Output {
+        let output = self.generator.generate();
+        (self.f)(&output);
+        output
+    }
+}
+
+/// Asset for [`Asset::inspect_delta`].
+pub struct InspectDelta<A, F> {
+    asset: A,
+    f: F,
+}
+
+impl<A, F> InspectDelta<A, F> {
+    pub(crate) fn new(asset: A, f: F) -> Self {
+        Self { asset, f }
+    }
+}
+
+impl<A: Debug, F> Debug for InspectDelta<A, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InspectDelta")
+            .field("asset", &self.asset)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, A, F> Asset<'c> for InspectDelta<A, F>
+where
+    A: Asset<'c>,
+    F: FnOnce(Delta),
+{
+    type Etag = A::Etag;
+    type Output = A::Output;
+    type Generator = A::Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let tracked = self.asset.update(cx, etag);
+        (self.f)(tracked.delta);
+        tracked
+    }
+}
+
+use super::Asset;
+use super::Context;
+use crate::Delta;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
@@ -0,0 +1,430 @@
+/// Asset for [`Asset::keyed`] and [`Asset::keyed_by_position`].
+#[derive(Debug)]
+pub struct Keyed<A> {
+    asset: A,
+    label: Option<Cow<'static, str>>,
+}
+
+impl<A> Keyed<A> {
+    pub(crate) fn new(asset: A, label: Option<Cow<'static, str>>) -> Self {
+        Self { asset, label }
+    }
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for Keyed<A> {
+    type Etag = KeyedEtag<A::Etag>;
+    type Output = A::Output;
+    type Generator = A::Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let keys = cx.try_get::<Keys>();
+        // Exiting on drop keeps the stack balanced even if the inner asset panics.
+        let entered = keys.map(|keys| keys.enter(self.label.as_deref()));
+        let key = match &entered {
+            Some(entered) => entered.key.clone(),
+            None => self
+                .label
+                .map_or_else(|| String::from("#0"), Cow::into_owned),
+        };
+        if etag.key != key {
+            // The tree has changed shape since this etag was saved;
+            // look for the etag this node had under its key instead.
+            let recovered = keys
+                .and_then(|keys| keys.recovered(&key))
+                .and_then(|bytes| A::Etag::from_bytes(&bytes).ok());
+            *etag = KeyedEtag {
+                key,
+                etag: recovered.unwrap_or_default(),
+            };
+        }
+        let tracked = self.asset.update(cx, &mut etag.etag);
+        drop(entered);
+        tracked
+    }
+}
+
+/// The etag of a [`Keyed`] asset:
+/// the inner asset’s etag, tagged with the key it was saved under.
+///
+/// In its serialized form, the key is preceded by a marker
+/// and the inner etag is length-delimited,
+/// so that [`Keys::begin`] can find it in old state
+/// even once the rest of the state no longer deserializes.
+/// An inner etag that fails to deserialize is replaced by its default,
+/// rather than failing the whole state.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct KeyedEtag<E> {
+    key: String,
+    etag: E,
+}
+
+impl<E> KeyedEtag<E> {
+    /// The key this etag was saved under.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The inner etag.
+    #[must_use]
+    pub fn etag(&self) -> &E {
+        &self.etag
+    }
+}
+
+impl<E: Etag> Etag for KeyedEtag<E> {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_bytes(&MARKER);
+        self.key.serialize(writer);
+        let etag = self.etag.to_vec();
+        writer.write_usize_var(etag.len());
+        writer.write_bytes(&etag);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        let (key, etag) = read_entry(reader)?;
+        Ok(Self {
+            key,
+            etag: E::from_bytes(etag).unwrap_or_default(),
+        })
+    }
+}
+
+/// The bytes that begin every serialized [`KeyedEtag`].
+const MARKER: [u8; 8] = [0xFF, b'm', b'a', b's', b't', b'k', b'e', b'y'];
+
+fn read_entry<'buf>(reader: &mut Reader<'buf>) -> Result<(String, &'buf [u8]), DeserializeError> {
+    if reader.read_array()? != MARKER {
+        return Err(DeserializeError::Invalid);
+    }
+    let key = String::deserialize(reader)?;
+    let len = reader.read_usize_var()?;
+    Ok((key, reader.read_bytes(len)?))
+}
+
+/// Derives the keys of [`Keyed`] assets and recovers their etags from old state,
+/// placed in the [`Context`].
+///
+/// A keyed asset’s key is the path of labels from the outermost keyed asset enclosing it,
+/// joined with `/`,
+/// where assets keyed [by position](Asset::keyed_by_position) are labelled `#0`, `#1` and so on
+/// in the order they are updated within their enclosing keyed asset.
+/// Without a `Keys` in the context, the key is just the asset’s own label.
+///
+/// Before each build, the driver passes the previously saved state to [`Self::begin`].
+/// This also restarts the numbering of assets keyed by position,
+/// so it must be called before every update of the root asset,
+/// even when there is no state to recover;
+/// [`Build`](crate::build::Build) and [`OnDemand`](crate::build::OnDemand) do so automatically.
+/// When a refactor changes the shape of the etag tree,
+/// the state no longer deserializes and every asset would otherwise be rebuilt;
+/// instead, each keyed asset recovers the etag saved under its key, if there is one.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset as _;
+/// use mast::Etag as _;
+///
+/// let values = (asset::Keys::new(),);
+/// let cx = asset::Context::from_tuple(&values);
+///
+/// let mut etag = Default::default();
+/// let args = asset::cli_args().keyed("args");
+/// args.update(cx, &mut etag).value.generate();
+/// let state = etag.to_vec();
+///
+/// // After a refactor the old state no longer deserializes as a whole…
+/// values.0.begin(&state);
+/// let mut etag = Default::default();
+/// let refactored = asset::cli_args().keyed("args").then(|args| {
+///     // …but the keyed asset’s etag is recovered.
+///     assert!(args.is_same());
+///     asset::cli_args()
+/// });
+/// refactored.update(cx, &mut etag);
+/// ```
+#[derive(Debug, Default)]
+pub struct Keys {
+    state: Mutex<State>,
+    normalization: PathNormalization,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    recovered: BTreeMap<String, Vec<u8>>,
+    /// The keyed assets currently being updated, outermost first.
+    stack: Vec<Frame>,
+    /// The number of assets keyed by position at the top level.
+    positions: usize,
+}
+
+#[derive(Debug)]
+struct Frame {
+    key: String,
+    positions: usize,
+}
+
+impl Keys {
+    /// Construct a `Keys` with no old state to recover from.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Normalize the labels of keyed assets before deriving keys from them,
+    /// for pipelines that label assets with the paths of their files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::fs::PathNormalization;
+    /// use mast::Asset as _;
+    /// use mast::Etag as _;
+    ///
+    /// let keys = asset::Keys::new().with_normalization(PathNormalization::new().case_insensitive());
+    /// let values = (keys,);
+    /// let cx = asset::Context::from_tuple(&values);
+    ///
+    /// let mut etag = Default::default();
+    /// asset::cli_args().keyed("Posts/Hello.md").update(cx, &mut etag).value.generate();
+    /// assert_eq!(etag.key(), "posts/hello.md");
+    /// ```
+    #[must_use]
+    pub fn with_normalization(self, normalization: PathNormalization) -> Self {
+        Self {
+            normalization,
+            ..self
+        }
+    }
+
+    /// Prepare for a new build,
+    /// given the state saved after the previous one.
+    ///
+    /// `state` may be anything containing serialized [`KeyedEtag`]s,
+    /// such as the serialized root etag, possibly salted.
+    /// Pass an empty slice to recover nothing,
+    /// for example when the state has been invalidated.
+    ///
+    /// Assets keyed by position are numbered from `#0` again after this is called.
+    pub fn begin(&self, state: &[u8]) {
+        let mut recovered = BTreeMap::new();
+        for start in 0..state.len() {
+            if !state[start..].starts_with(&MARKER) {
+                continue;
+            }
+            if let Ok((key, etag)) = read_entry(&mut Reader::new(&state[start..])) {
+                recovered.entry(key).or_insert_with(|| etag.to_vec());
+            }
+        }
+        *lock(&self.state) = State {
+            recovered,
+            ..State::default()
+        };
+    }
+
+    /// Recover the etags saved under the key `from`, and every key nested within it,
+    /// under the key `to` instead.
+    ///
+    /// Call this after [`Self::begin`] when the label of a keyed asset changes
+    /// but its inputs do not,
+    /// for example when the file it reads has been renamed
+    /// (see [`fs::renames`](crate::fs::renames)),
+    /// so that the asset carries over its state rather than being rebuilt.
+    ///
+    /// An etag already recovered under a key within `to` belongs to another asset,
+    /// so it is kept, and the etag that would have replaced it stays under `from`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    /// use mast::Etag as _;
+    ///
+    /// let values = (asset::Keys::new(),);
+    /// let cx = asset::Context::from_tuple(&values);
+    ///
+    /// let mut etag = Default::default();
+    /// let args = asset::cli_args().keyed("draft.md");
+    /// args.update(cx, &mut etag).value.generate();
+    /// let state = etag.to_vec();
+    ///
+    /// values.0.begin(&state);
+    /// values.0.rename("draft.md", "hello.md");
+    /// let args = asset::cli_args().keyed("hello.md");
+    /// assert!(args.update(cx, &mut etag).is_same());
+    /// ```
+    pub fn rename(&self, from: &str, to: &str) {
+        let mut state = lock(&self.state);
+        let moves: Vec<(String, String)> = state
+            .recovered
+            .keys()
+            .filter_map(|key| match key.strip_prefix(from) {
+                Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                    Some((key.clone(), format!("{to}{rest}")))
+                }
+                _ => None,
+            })
+            .collect();
+        for (old, new) in moves {
+            if state.recovered.contains_key(&new) {
+                continue;
+            }
+            if let Some(etag) = state.recovered.remove(&old) {
+                state.recovered.insert(new, etag);
+            }
+        }
+    }
+
+    /// Enter a keyed asset, until the returned guard is dropped.
+    fn enter(&self, label: Option<&str>) -> Entered<'_> {
+        let mut state = lock(&self.state);
+        let state = &mut *state;
+        let (parent, positions) = match state.stack.last_mut() {
+            Some(frame) => (Some(&*frame.key), &mut frame.positions),
+            None => (None, &mut state.positions),
+        };
+        let label = label.map_or_else(
+            || {
+                *positions += 1;
+                format!("#{}", *positions - 1)
+            },
+            |label| self.normalization.normalize_str(label).into_owned(),
+        );
+        let key = match parent {
+            Some(parent) => format!("{parent}/{label}"),
+            None => label,
+        };
+        state.stack.push(Frame {
+            key: key.clone(),
+            positions: 0,
+        });
+        Entered { keys: self, key }
+    }
+
+    fn recovered(&self, key: &str) -> Option<Vec<u8>> {
+        lock(&self.state).recovered.get(key).cloned()
+    }
+}
+
+/// A keyed asset being updated, which is exited when this is dropped.
+struct Entered<'a> {
+    keys: &'a Keys,
+    key: String,
+}
+
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        lock(&self.keys.state).stack.pop();
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn keys() {
+        let keys = Keys::new();
+        let site = keys.enter(Some("site"));
+        assert_eq!(site.key, "site");
+        assert_eq!(keys.enter(None).key, "site/#0");
+        let index = keys.enter(Some("index"));
+        assert_eq!(index.key, "site/index");
+        assert_eq!(keys.enter(None).key, "site/index/#0");
+        drop(index);
+        assert_eq!(keys.enter(None).key, "site/#1");
+        drop(site);
+        assert_eq!(keys.enter(None).key, "#0");
+
+        let etag = KeyedEtag {
+            key: String::from("site/#1"),
+            etag: (
+                37_u32,
+                KeyedEtag {
+                    key: String::from("inner"),
+                    etag: 5_u8,
+                },
+            ),
+        };
+        keys.begin(&etag.to_vec());
+        assert_eq!(keys.recovered("site/#1"), Some(etag.etag.to_vec()));
+        assert_eq!(keys.recovered("inner"), Some(std::vec![5]));
+        assert_eq!(keys.enter(None).key, "#0");
+    }
+
+    #[test]
+    fn rename() {
+        let keyed = |key: &str, etag| KeyedEtag {
+            key: String::from(key),
+            etag,
+        };
+        let state = (
+            (keyed("a", 1_u8), keyed("a/x", 2_u8)),
+            (keyed("b", 3_u8), keyed("c", 4_u8)),
+        );
+        let keys = Keys::new();
+        keys.begin(&state.to_vec());
+
+        // `b` is taken, but `b/x` is not.
+        keys.rename("a", "b");
+        assert_eq!(keys.recovered("a"), Some(std::vec![1]));
+        assert_eq!(keys.recovered("b"), Some(std::vec![3]));
+        assert_eq!(keys.recovered("b/x"), Some(std::vec![2]));
+        assert_eq!(keys.recovered("a/x"), None);
+
+        keys.rename("c", "d");
+        assert_eq!(keys.recovered("c"), None);
+        assert_eq!(keys.recovered("d"), Some(std::vec![4]));
+    }
+
+    #[test]
+    fn panic_safe() {
+        let values = (Keys::new(),);
+        let cx = Context::from_tuple(&values);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            let panics = asset::constant(()).then(|_| -> asset::Constant<()> {
+                panic!("updating the inner asset panicked");
+            });
+            let nested = asset::constant(()).then(|_| panics.keyed("inner"));
+            let mut etag = KeyedEtag::default();
+            let _ = nested.keyed("outer").update(cx, &mut etag);
+        }));
+        assert!(res.is_err());
+        assert_eq!(values.0.enter(Some("next")).key, "next");
+    }
+
+    use super::KeyedEtag;
+    use super::Keys;
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::Asset as _;
+    use crate::Etag as _;
+    use std::panic;
+    use std::panic::AssertUnwindSafe;
+    use std::string::String;
+}
+
+use super::Asset;
+use super::Context;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::fs::PathNormalization;
+use crate::Etag;
+use crate::Tracked;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::format;
+use std::string::String;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::vec::Vec;
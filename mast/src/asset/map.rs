@@ -12,7 +12,9 @@ impl<A, F> Map<A, F> {
 
 impl<A: Debug, F> Debug for Map<A, F> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Map").field("asset", &self.asset).finish()
+        f.debug_struct("Map")
+            .field("asset", &self.asset)
+            .finish_non_exhaustive()
     }
 }
 
@@ -33,11 +35,20 @@ where
     }
 }
 
+/// Generator for [`Map`].
 pub struct Generator<G, F> {
     generator: G,
     f: F,
 }
 
+impl<G: Debug, F> Debug for Generator<G, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Generator")
+            .field("generator", &self.generator)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<G, F, O> super::Generator for Generator<G, F>
 where
     G: super::Generator,
@@ -0,0 +1,73 @@
+/// Asset for [`Asset::map_etag`].
+pub struct MapEtag<A, F> {
+    asset: A,
+    f: F,
+}
+
+impl<A, F> MapEtag<A, F> {
+    pub(crate) fn new(asset: A, f: F) -> Self {
+        Self { asset, f }
+    }
+}
+
+impl<A: Debug, F> Debug for MapEtag<A, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapEtag")
+            .field("asset", &self.asset)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, A, F, E> Asset<'c> for MapEtag<A, F>
+where
+    A: Asset<'c>,
+    F: FnOnce(Context<'c>) -> E,
+    E: Etag + PartialEq,
+{
+    type Etag = (Option<E>, A::Etag);
+    type Output = A::Output;
+    type Generator = Generator<'c, A::Generator, E>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (extra, inner) = etag;
+        let new_extra = (self.f)(cx);
+        let delta = Delta::cmp(&extra.as_ref(), &Some(&new_extra));
+        if delta == Delta::Modified {
+            // The inner etag was saved under a different extra etag, so it cannot be trusted.
+            *inner = A::Etag::default();
+        }
+        let tracked = self.asset.update(cx, inner);
+        delta.or(tracked.delta).track(Generator {
+            inner: tracked.value,
+            extra,
+            new_extra,
+        })
+    }
+}
+
+/// Generator for [`MapEtag`].
+#[derive(Debug)]
+pub struct Generator<'c, G, E> {
+    inner: G,
+    extra: &'c mut Option<E>,
+    new_extra: E,
+}
+
+impl<G: super::Generator, E> super::Generator for Generator<'_, G, E> {
+    type Output = G::Output;
+
+    fn generate(self) -> Self::Output {
+        let output = self.inner.generate();
+        *self.extra = Some(self.new_extra);
+        output
+    }
+}
+
+use super::Asset;
+use super::Context;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
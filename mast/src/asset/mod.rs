@@ -1,6 +1,17 @@
 //! The [`Asset`] trait.
 
 /// A step in a build process.
+///
+/// # Thread safety
+///
+/// Combinators, their etags and their generators are [`Send`] and [`Sync`]
+/// whenever the assets, functions and values they hold are,
+/// so whether a pipeline can be moved to another thread follows from its parts.
+/// [`all`] generates its assets on several threads,
+/// so it requires their generators and outputs to be [`Send`].
+/// [`boxed_node`](Self::boxed_node) erases the asset and its generator
+/// to types that are neither;
+/// [`boxed_send_node`](Self::boxed_send_node) keeps them [`Send`].
 pub trait Asset<'c>: Sized {
     /// An asset’s etag, analagous to the `ETag` header found in HTTP,
     /// is a fingerprint of the `Output` and sideeffects —
@@ -37,14 +48,682 @@ pub trait Asset<'c>: Sized {
     {
         ensure_asset(Map::new(self, f))
     }
+
+    /// Call a function with a reference to the output of this asset when it is generated,
+    /// passing the output on unchanged.
+    ///
+    /// This is useful for debugging a chain of assets without restructuring it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// let answer = asset::constant(6 * 7).inspect(|answer| assert_eq!(*answer, 42));
+    /// let answer = answer.update(asset::Context::default(), &mut ()).value.generate();
+    /// assert_eq!(answer, 42);
+    /// ```
+    fn inspect<F>(self, f: F) -> Inspect<Self, F>
+    where
+        F: FnOnce(&Self::Output),
+    {
+        ensure_asset(Inspect::new(self, f))
+    }
+
+    /// Call a function with whether this asset was modified when it is updated,
+    /// passing the result of the update on unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::Asset as _;
+    /// use mast::Delta;
+    ///
+    /// let mut etag = Default::default();
+    /// let mut seen = None;
+    /// let answer = asset::constant(42).version(1).inspect_delta(|delta| seen = Some(delta));
+    /// answer.update(asset::Context::default(), &mut etag);
+    /// assert_eq!(seen, Some(Delta::Modified));
+    /// ```
+    fn inspect_delta<F>(self, f: F) -> InspectDelta<Self, F>
+    where
+        F: FnOnce(Delta),
+    {
+        ensure_asset(InspectDelta::new(self, f))
+    }
+
+    /// Run this asset as a phase of the build of its own,
+    /// before passing its output to the next phase.
+    ///
+    /// This is for aggregates that later assets depend on,
+    /// such as the tags, backlinks or search index of every page,
+    /// which can only be known once the whole of the first phase has been generated.
+    /// If this asset is modified, it is generated immediately, during the update;
+    /// its output is then kept in the etag,
+    /// so that the next phase can read it even when this asset is not modified.
+    /// The callback receives a clone of the output,
+    /// tracked as modified only when it has changed,
+    /// so that assets of the next phase which depend on it can stay incremental.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// use mast::asset;
+    /// use mast::build::Build;
+    /// use mast::Asset;
+    /// use mast::Tracked;
+    ///
+    /// /// Renders the index page from the titles of every page.
+    /// struct Index(Tracked<Vec<String>>);
+    ///
+    /// impl<'c> Asset<'c> for Index {
+    ///     type Etag = ();
+    ///     type Output = String;
+    ///     type Generator = Box<dyn FnOnce() -> String + 'c>;
+    ///
+    ///     fn update(self, _: asset::Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+    ///         self.0.map(|titles| -> Self::Generator { Box::new(move || titles.join(", ")) })
+    ///     }
+    /// }
+    ///
+    /// let mut site = Build::new(|_| {
+    ///     asset::cli_args()
+    ///         .map(|args| args.iter().map(|arg| arg.to_string_lossy().into_owned()).collect())
+    ///         .phase(Index)
+    /// });
+    /// let cx = asset::Context::default();
+    /// assert!(site.build(cx).is_some());
+    /// // The first phase’s output is remembered, so the index is up to date.
+    /// assert_eq!(site.build(cx), None);
+    /// # }
+    /// ```
+    fn phase<A, F>(self, f: F) -> Phase<Self, F>
+    where
+        Self::Output: Etag + PartialEq + Clone,
+        F: FnOnce(Tracked<Self::Output>) -> A,
+        A: Asset<'c>,
+    {
+        ensure_asset(Phase::new(self, f))
+    }
+
+    /// Fold the outputs of this asset into a state that persists from build to build.
+    ///
+    /// When this asset is modified,
+    /// `f` is called with the output of the previous build and the new output of this asset,
+    /// so that it can update the previous output incrementally
+    /// instead of computing it from scratch,
+    /// for example to append to a changelog
+    /// or to update only the shards of a search index that are affected.
+    /// On the first build, or if the previous output has been lost,
+    /// `f` is called with `initial` instead.
+    /// When this asset is not modified, the previous output is reused as is.
+    ///
+    /// The output is kept in the etag, so it is persisted along with it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// let mut etag = Default::default();
+    /// let cx = asset::Context::default();
+    ///
+    /// let add = |total: u64, entry| total + entry;
+    /// let total = asset::constant(1).version(1).scan(0, add);
+    /// assert_eq!(total.update(cx, &mut etag).value.generate(), 1);
+    ///
+    /// let total = asset::constant(1).version(1).scan(0, add);
+    /// let tracked = total.update(cx, &mut etag);
+    /// assert!(tracked.is_same());
+    /// assert_eq!(tracked.value.generate(), 1);
+    ///
+    /// let total = asset::constant(2).version(2).scan(0, add);
+    /// assert_eq!(total.update(cx, &mut etag).value.generate(), 3);
+    /// ```
+    fn scan<S, F>(self, initial: S, f: F) -> Scan<Self, S, F>
+    where
+        S: Etag + Clone,
+        F: FnOnce(S, Self::Output) -> S,
+    {
+        ensure_asset(Scan::new(self, initial, f))
+    }
+
+    /// Mix a version number for the logic of this asset into its etag.
+    ///
+    /// Bump the version whenever the implementation of the asset changes
+    /// in a way that affects its output, without any of its inputs changing.
+    /// The asset is then rebuilt from scratch on the next build,
+    /// while every other asset stays up to date.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// let mut etag = Default::default();
+    /// let cx = asset::Context::default();
+    ///
+    /// let double = asset::constant(21).map(|n| n * 2).version(1);
+    /// assert_eq!(double.update(cx, &mut etag).value.generate(), 42);
+    /// let double = asset::constant(21).map(|n| n * 2).version(1);
+    /// assert!(double.update(cx, &mut etag).is_same());
+    ///
+    /// // The logic changed, so the asset is rebuilt even though its inputs did not.
+    /// let double = asset::constant(21).map(|n| n + n).version(2);
+    /// assert!(double.update(cx, &mut etag).is_modified());
+    /// ```
+    fn version(self, version: u32) -> Versioned<Self> {
+        ensure_asset(Versioned::new(self, version))
+    }
+
+    /// Augment this asset’s etag with an extra etag computed from the context.
+    ///
+    /// The extra etag is stored alongside the asset’s own.
+    /// When it changes, for example because a salt in the context has changed,
+    /// the asset’s own etag is discarded and the asset is rebuilt from scratch.
+    /// [`version`](Self::version) is a special case of this
+    /// for a number that does not depend on the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// #[derive(Debug)]
+    /// struct OptLevel(u32);
+    ///
+    /// let mut etag = Default::default();
+    /// let values = (OptLevel(1),);
+    /// let cx = asset::Context::from_tuple(&values);
+    ///
+    /// let opt_level = |cx: asset::Context<'_>| cx.get::<OptLevel>().0;
+    /// let build = asset::constant("main.rs").map_etag(opt_level);
+    /// build.update(cx, &mut etag).value.generate();
+    /// assert!(asset::constant("main.rs").map_etag(opt_level).update(cx, &mut etag).is_same());
+    ///
+    /// let values = (OptLevel(3),);
+    /// let cx = asset::Context::from_tuple(&values);
+    /// let build = asset::constant("main.rs").map_etag(opt_level);
+    /// assert!(build.update(cx, &mut etag).is_modified());
+    /// ```
+    fn map_etag<E, F>(self, f: F) -> MapEtag<Self, F>
+    where
+        F: FnOnce(Context<'c>) -> E,
+        E: Etag + PartialEq,
+    {
+        ensure_asset(MapEtag::new(self, f))
+    }
+
+    /// Augment this asset’s etag with an explicitly provided `value`,
+    /// for inputs that the asset cannot see itself, such as a configuration struct.
+    ///
+    /// This is [`map_etag`](Self::map_etag) with a value that does not depend on the context:
+    /// when `value` differs from the one given on the previous build,
+    /// the asset’s own etag is discarded and the asset is rebuilt from scratch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// let mut etag = Default::default();
+    /// let cx = asset::Context::default();
+    ///
+    /// let minify = true;
+    /// let page = asset::constant("<p> Hi </p>").with_etag(minify);
+    /// page.update(cx, &mut etag).value.generate();
+    /// let page = asset::constant("<p> Hi </p>").with_etag(minify);
+    /// assert!(page.update(cx, &mut etag).is_same());
+    ///
+    /// let minify = false;
+    /// let page = asset::constant("<p> Hi </p>").with_etag(minify);
+    /// assert!(page.update(cx, &mut etag).is_modified());
+    /// ```
+    fn with_etag<E>(self, value: E) -> WithEtag<Self, E>
+    where
+        E: Etag + PartialEq,
+    {
+        ensure_asset(WithEtag::new(self, value))
+    }
+
+    /// Erase the type of this asset behind a box, labelled with `label` for debugging.
+    ///
+    /// Long chains of combinators produce enormous types,
+    /// which slow down compilation of large pipelines,
+    /// since every combinator is monomorphized for every type nested inside it.
+    /// Boxing at strategic points, such as the boundaries between sections of a site,
+    /// keeps the types of the assets that consume this one small.
+    ///
+    /// This has a cost at run time:
+    /// the asset and its generator are each boxed and called through dynamic dispatch,
+    /// and the etag is held in a [`BoxedEtag`],
+    /// which adds a length prefix to its serialized form
+    /// and is boxed and downcast on every update.
+    /// Changing where assets are boxed changes the format of the etags around them,
+    /// so those assets are rebuilt once.
+    ///
+    /// Neither the boxed asset nor its generator is [`Send`],
+    /// so it cannot be generated in parallel by [`all`];
+    /// use [`boxed_send_node`](Self::boxed_send_node) for that.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    /// use mast::Delta;
+    /// use mast::Tracked;
+    ///
+    /// let mut etag = Default::default();
+    /// let cx = asset::Context::default();
+    ///
+    /// let count = asset::cli_args().map(|args| args.len()).boxed_node("count");
+    /// assert_eq!(count.label(), "count");
+    /// let Tracked { value, delta } = count.update(cx, &mut etag);
+    /// assert_eq!(delta, Delta::Modified);
+    /// value.generate();
+    ///
+    /// let count = asset::cli_args().map(|args| args.len()).boxed_node("count");
+    /// assert!(count.update(cx, &mut etag).is_same());
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+    fn boxed_node(self, label: &'static str) -> BoxedNode<'c, Self::Output>
+    where
+        Self: 'c,
+        Self::Etag: Send + Sync,
+        Self::Generator: 'c,
+    {
+        ensure_asset(BoxedNode::new(self, label))
+    }
+
+    /// Like [`boxed_node`](Self::boxed_node),
+    /// but the boxed asset and its generator are [`Send`],
+    /// so they can be moved to other threads, for example by [`all`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")] {
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// let pages = (0..3).map(|i| asset::constant(i).boxed_send_node("page"));
+    /// let mut etag = Vec::new();
+    /// let pages = asset::all(pages).update(asset::Context::default(), &mut etag);
+    /// assert_eq!(pages.value.generate(), [0, 1, 2]);
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+    fn boxed_send_node(self, label: &'static str) -> BoxedSendNode<'c, Self::Output>
+    where
+        Self: Send + 'c,
+        Self::Etag: Send + Sync,
+        Self::Generator: Send + 'c,
+    {
+        ensure_asset(BoxedSendNode::new(self, label))
+    }
+
+    /// Share the output of this asset between multiple consumers.
+    ///
+    /// The output is wrapped in an [`Arc`](std::sync::Arc),
+    /// and the resulting generator can be cheaply cloned.
+    /// The underlying generator runs at most once,
+    /// no matter how many clones are generated or which threads they are generated on.
+    /// Use [`SharedOutput::read_write`] when many threads generate clones at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    /// use std::sync::Arc;
+    ///
+    /// let mut etag = Default::default();
+    /// let args = asset::cli_args().shared_output();
+    /// let args = args.update(asset::Context::default(), &mut etag).value;
+    ///
+    /// let other_args = args.clone();
+    /// let thread = std::thread::spawn(move || other_args.generate());
+    /// let args = args.generate();
+    /// assert!(Arc::ptr_eq(&args, &thread.join().unwrap()));
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    fn shared_output(self) -> SharedOutput<Self> {
+        ensure_asset(SharedOutput::new(self))
+    }
+
+    /// Keep the output of this asset in memory between builds.
+    ///
+    /// When the asset is not modified and the cache holds its previous output,
+    /// that output is reused instead of generating the asset again.
+    /// Outputs are wrapped in an [`Arc`](std::sync::Arc) so that they can be shared with the cache.
+    ///
+    /// The cache must outlive the build, so it is typically kept alongside the etag.
+    /// A [soft](Cache::soft) cache lets the output be dropped by [`trim_memory`]
+    /// to save memory, at the cost of generating it again the next time it is needed.
+    /// A cache can also [remember failures](Cache::remember_failures) for a short time,
+    /// so that a failing asset is not generated again on every build.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    /// use std::sync::Arc;
+    ///
+    /// let cache = asset::Cache::soft();
+    /// let mut etag = Default::default();
+    /// let cx = asset::Context::default();
+    ///
+    /// let args = asset::cli_args().cache(&cache);
+    /// let first = args.update(cx, &mut etag).value.generate();
+    /// let args = asset::cli_args().cache(&cache);
+    /// assert!(Arc::ptr_eq(&first, &args.update(cx, &mut etag).value.generate()));
+    ///
+    /// asset::trim_memory();
+    /// let args = asset::cli_args().cache(&cache);
+    /// assert!(!Arc::ptr_eq(&first, &args.update(cx, &mut etag).value.generate()));
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    fn cache(self, cache: &'c Cache<Self::Output>) -> Cached<'c, Self> {
+        ensure_asset(Cached::new(self, cache))
+    }
+
+    /// Store the output of this asset on disk when it is large,
+    /// so that large outputs do not stay in memory.
+    ///
+    /// Outputs no larger than the [threshold](Spill::threshold) are kept in memory;
+    /// larger ones are written to a file in `dir` named by the digest of their contents,
+    /// and the output is a [`Spilled`] handle to the file instead.
+    /// Identical outputs share a file, and files are never removed by this asset,
+    /// so `dir` should be a cache directory that can be cleared between builds.
+    /// A relative `dir` is resolved against the [cache root](crate::fs::Roots::cache)
+    /// in the context.
+    ///
+    /// This keeps the memory of long-running watch processes bounded
+    /// when pipelines produce hundreds of megabytes of output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    /// # let dir = mast::fs::TempDirs::default().create()?;
+    ///
+    /// let mut etag = Default::default();
+    /// let cx = asset::Context::default();
+    ///
+    /// let large = asset::constant(vec![0_u8; 64]).spill(dir.path()).threshold(16);
+    /// let spilled = large.update(cx, &mut etag).value.generate()?;
+    /// assert_eq!(spilled.len(), 64);
+    /// assert!(spilled.path().is_some());
+    /// assert_eq!(*spilled.read()?, [0; 64]);
+    ///
+    /// let small = asset::constant(vec![0_u8; 8]).spill(dir.path()).threshold(16);
+    /// let spilled = small.update(cx, &mut ()).value.generate()?;
+    /// assert_eq!(spilled.path(), None);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    fn spill<P: Into<PathBuf>>(self, dir: P) -> Spill<Self>
+    where
+        Self::Output: Into<Vec<u8>>,
+    {
+        ensure_asset(Spill::new(self, dir.into()))
+    }
+
+    /// Limit how many assets sharing the key `key` may generate at once to `limit`.
+    ///
+    /// When many assets become ready at the same time
+    /// and are generated in parallel on separate threads,
+    /// generators beyond the limit block until a running one finishes.
+    /// This keeps resource-heavy external tools from oversubscribing the machine.
+    /// Keys are shared across the whole process;
+    /// if assets disagree on the limit of a key, each respects its own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::Asset as _;
+    ///
+    /// let transcode = asset::cli_args().concurrency("ffmpeg", 2);
+    /// # let _ = transcode;
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    fn concurrency<K: Into<Cow<'static, str>>>(self, key: K, limit: usize) -> Concurrency<Self> {
+        ensure_asset(Concurrency::new(self, key.into(), limit))
+    }
+
+    /// Save this asset’s etag under a key derived from the given label,
+    /// so that it survives refactors that change the shape of the etag tree.
+    ///
+    /// The key also includes the labels of the keyed assets enclosing this one;
+    /// see [`Keys`] for how keys are derived and etags recovered.
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    fn keyed<L: Into<Cow<'static, str>>>(self, label: L) -> Keyed<Self> {
+        ensure_asset(Keyed::new(self, Some(label.into())))
+    }
+
+    /// Like [`keyed`](Self::keyed),
+    /// but labelled by this asset’s position among the others keyed by position
+    /// within the same enclosing keyed asset.
+    ///
+    /// This avoids naming every asset,
+    /// at the cost of keys that change when assets keyed by position are reordered.
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    fn keyed_by_position(self) -> Keyed<Self> {
+        ensure_asset(Keyed::new(self, None))
+    }
+
+    /// Catch panics while generating this asset,
+    /// converting them into a [`Panic`] error carrying the given asset name.
+    ///
+    /// This keeps one misbehaving asset from taking down a long-running process
+    /// such as a `watch` session.
+    /// The panic is still reported by the panic hook as usual.
+    /// An asset that panicked may have left its etag or outputs half-updated;
+    /// assets that only commit their etag once generation succeeds are rebuilt next time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    ///
+    /// # std::panic::set_hook(Box::new(|_| {}));
+    /// let mut etag = Default::default();
+    /// let asset = asset::cli_args().map(|_| panic!("oops")).catch_unwind("transform");
+    /// let err = asset.update(asset::Context::default(), &mut etag).value.generate().unwrap_err();
+    /// assert_eq!(err.to_string(), "asset `transform` panicked: oops");
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    fn catch_unwind<N: Into<Cow<'static, str>>>(self, name: N) -> CatchUnwind<Self> {
+        ensure_asset(CatchUnwind::new(self, name.into()))
+    }
+
+    /// Report the progress of this asset to the [`Observer`](crate::observe::Observer)
+    /// in the context, under the given name.
+    ///
+    /// If there is no observer in the context, this does nothing.
+    ///
+    /// With the `tracing` feature,
+    /// the asset’s update and generation are also wrapped in `update` and `generate` spans
+    /// whose `asset` field is the name,
+    /// and the `update` span records whether the asset was modified in its `delta` field.
+    ///
+    /// With the `metrics` feature, the following metrics are recorded,
+    /// all labelled with the name as `asset`:
+    ///
+    /// - `mast_updates_total`: a counter of updates,
+    ///   additionally labelled with `delta` as either `same` (a cache hit) or `modified`.
+    /// - `mast_update_duration_seconds`: a histogram of update durations.
+    /// - `mast_generates_total`: a counter of generations, i.e. rebuilds.
+    /// - `mast_generate_duration_seconds`: a histogram of generation durations.
+    ///
+    /// These are sent to the global recorder of the `metrics` crate;
+    /// install one such as `metrics-exporter-prometheus` to export them.
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    fn observe<N: Into<Cow<'static, str>>>(self, name: N) -> Observe<Self> {
+        ensure_asset(Observe::new(self, name.into()))
+    }
 }
 
+mod asset_mut;
+pub use asset_mut::AssetMut;
+
+mod fixed_output;
+pub use fixed_output::FixedOutput;
+
 mod then;
 pub use then::Then;
 
 mod map;
 pub use map::Map;
 
+mod inspect;
+pub use inspect::Inspect;
+pub use inspect::InspectDelta;
+
+mod phase;
+pub use phase::Phase;
+
+mod scan;
+pub use scan::Scan;
+
+mod versioned;
+pub use versioned::Versioned;
+
+mod map_etag;
+pub use map_etag::MapEtag;
+
+mod with_etag;
+pub use with_etag::WithEtag;
+
+#[cfg(feature = "alloc")]
+mod boxed_node;
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+pub use boxed_node::BoxedEtag;
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+pub use boxed_node::BoxedGenerator;
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+pub use boxed_node::BoxedNode;
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+pub use boxed_node::BoxedSendGenerator;
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+pub use boxed_node::BoxedSendNode;
+
+#[cfg(feature = "std")]
+mod shared_output;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use shared_output::SharedOutput;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use shared_output::SharedOutputRw;
+
+#[cfg(feature = "std")]
+mod global;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use global::__generate_global;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use global::GlobalAsset;
+
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use cache::trim_memory;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use cache::Cache;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use cache::Cached;
+
+#[cfg(feature = "std")]
+mod spill;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use spill::Spill;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use spill::Spilled;
+
+#[cfg(feature = "std")]
+mod concurrency;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use concurrency::Concurrency;
+
+#[cfg(feature = "std")]
+mod keyed;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use keyed::Keyed;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use keyed::KeyedEtag;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use keyed::Keys;
+
+#[cfg(feature = "std")]
+mod catch_unwind;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use catch_unwind::CatchUnwind;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use catch_unwind::Panic;
+
+#[cfg(feature = "std")]
+mod observe;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use observe::Observe;
+
 /// Helper trait for generating the final result of an [`Asset`].
 /// Returned by [`Asset::update`].
 ///
@@ -67,9 +746,97 @@ impl<O, F: FnOnce() -> O> Generator for F {
 pub mod context;
 pub use context::Context;
 
+mod deterministic;
+pub use deterministic::Deterministic;
+
+mod constant;
+pub use constant::constant;
+pub use constant::static_bytes;
+pub use constant::static_str;
+pub use constant::Constant;
+
+#[cfg(feature = "std")]
+mod all;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use all::all;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use all::All;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use all::MapChanged;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use all::MapChangedGenerator;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use all::Streamed;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use all::StreamedGenerator;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use all::Unordered;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use all::UnorderedGenerator;
+
+#[cfg(feature = "std")]
+mod flags;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use flags::when_flag;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use flags::Flags;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use flags::WhenFlag;
+
+#[cfg(feature = "std")]
+mod per_locale;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use per_locale::per_locale;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use per_locale::PerLocale;
+
+#[cfg(feature = "std")]
+mod from_iter;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use from_iter::from_iter;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use from_iter::FromIter;
+
+#[cfg(feature = "std")]
+mod cli;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use cli::cli_args;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use cli::stdin;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use cli::CliArgs;
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub use cli::Stdin;
+
 fn ensure_asset<'c, T: Asset<'c>>(value: T) -> T {
     value
 }
 
+use crate::Delta;
 use crate::Etag;
 use crate::Tracked;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::vec::Vec;
@@ -0,0 +1,114 @@
+/// Asset for [`Asset::observe`].
+#[derive(Debug)]
+pub struct Observe<A> {
+    asset: A,
+    name: Cow<'static, str>,
+}
+
+impl<A> Observe<A> {
+    pub(crate) fn new(asset: A, name: Cow<'static, str>) -> Self {
+        Self { asset, name }
+    }
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for Observe<A> {
+    type Etag = A::Etag;
+    type Output = A::Output;
+    type Generator = Generator<'c, A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("update", asset = &*self.name, delta = field::Empty);
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        let observer = cx.try_get::<Observer>();
+        if let Some(observer) = observer {
+            observer.notify(&Event::UpdateStart { name: &self.name });
+        }
+        let start = Instant::now();
+
+        let tracked = self.asset.update(cx, etag);
+
+        let elapsed = start.elapsed();
+        #[cfg(feature = "tracing")]
+        span.record("delta", field::debug(tracked.delta));
+        #[cfg(feature = "metrics")]
+        {
+            let delta = match tracked.delta {
+                Delta::Same => "same",
+                Delta::Modified => "modified",
+            };
+            let asset = self.name.clone();
+            metrics::counter!("mast_updates_total", "asset" => asset.clone(), "delta" => delta)
+                .increment(1);
+            metrics::histogram!("mast_update_duration_seconds", "asset" => asset)
+                .record(elapsed.as_secs_f64());
+        }
+        if let Some(observer) = observer {
+            observer.notify(&Event::UpdateEnd {
+                name: &self.name,
+                delta: tracked.delta,
+                elapsed,
+            });
+        }
+
+        tracked.map(|generator| Generator {
+            inner: generator,
+            name: self.name,
+            observer,
+        })
+    }
+}
+
+/// Generator for [`Observe`].
+#[derive(Debug)]
+pub struct Generator<'c, G> {
+    inner: G,
+    name: Cow<'static, str>,
+    observer: Option<&'c Observer>,
+}
+
+impl<G: super::Generator> super::Generator for Generator<'_, G> {
+    type Output = G::Output;
+
+    fn generate(self) -> Self::Output {
+        #[cfg(feature = "tracing")]
+        let _entered = tracing::info_span!("generate", asset = &*self.name).entered();
+
+        if let Some(observer) = self.observer {
+            observer.notify(&Event::GenerateStart { name: &self.name });
+        }
+        let start = Instant::now();
+
+        let output = self.inner.generate();
+
+        let elapsed = start.elapsed();
+        #[cfg(feature = "metrics")]
+        {
+            let asset = self.name.clone();
+            metrics::counter!("mast_generates_total", "asset" => asset.clone()).increment(1);
+            metrics::histogram!("mast_generate_duration_seconds", "asset" => asset)
+                .record(elapsed.as_secs_f64());
+        }
+        if let Some(observer) = self.observer {
+            observer.notify(&Event::GenerateEnd {
+                name: &self.name,
+                elapsed,
+            });
+        }
+        output
+    }
+}
+
+use super::Asset;
+use super::Context;
+use crate::observe::Event;
+use crate::observe::Observer;
+#[cfg(feature = "metrics")]
+use crate::Delta;
+use crate::Tracked;
+use std::borrow::Cow;
+use std::time::Instant;
+#[cfg(feature = "tracing")]
+use tracing::field;
@@ -0,0 +1,135 @@
+/// Instantiate an asset once per locale,
+/// merging their outputs into a map from locale to output.
+///
+/// `f` is called with each locale to construct the asset for that locale.
+/// Each locale’s asset has its own etag, stored under the locale,
+/// so adding a locale only builds the new locale
+/// and removing one discards its state without disturbing the others.
+/// Duplicate locales are built once.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::Asset;
+/// use mast::Delta;
+/// use mast::Tracked;
+///
+/// struct Greeting(&'static str);
+///
+/// impl<'c> Asset<'c> for Greeting {
+///     type Etag = bool;
+///     type Output = &'static str;
+///     type Generator = Box<dyn FnOnce() -> &'static str + 'c>;
+///
+///     fn update(self, _: asset::Context<'c>, built: &'c mut bool) -> Tracked<Self::Generator> {
+///         Delta::cmp(built, &true).track(Box::new(move || {
+///             *built = true;
+///             self.0
+///         }))
+///     }
+/// }
+///
+/// fn greeting(locale: &str) -> Greeting {
+///     Greeting(if locale == "fr" { "Bonjour" } else { "Hello" })
+/// }
+///
+/// let mut etag = Default::default();
+/// let cx = asset::Context::default();
+///
+/// let site = asset::per_locale(["en", "fr"], greeting);
+/// let outputs = site.update(cx, &mut etag).value.generate();
+/// assert_eq!(outputs["fr"], "Bonjour");
+///
+/// // Removing a locale is a modification, but the remaining locales are not rebuilt.
+/// let site = asset::per_locale(["en"], greeting);
+/// assert!(site.update(cx, &mut etag).is_modified());
+/// assert!(asset::per_locale(["en"], greeting).update(cx, &mut etag).is_same());
+/// ```
+pub fn per_locale<I, F, A>(locales: I, f: F) -> PerLocale<I, F>
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+    F: FnMut(&str) -> A,
+{
+    PerLocale { locales, f }
+}
+
+/// Asset for [`per_locale`].
+pub struct PerLocale<I, F> {
+    locales: I,
+    f: F,
+}
+
+impl<I: Debug, F> Debug for PerLocale<I, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PerLocale")
+            .field("locales", &self.locales)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, I, F, A> Asset<'c> for PerLocale<I, F>
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+    F: FnMut(&str) -> A,
+    A: Asset<'c>,
+{
+    type Etag = BTreeMap<String, A::Etag>;
+    type Output = BTreeMap<String, A::Output>;
+    type Generator = Generator<A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let mut f = self.f;
+        let locales: BTreeSet<String> = self.locales.into_iter().map(Into::into).collect();
+
+        let len = etag.len();
+        etag.retain(|locale, _| locales.contains(locale));
+        let mut delta = Delta::cmp(&etag.len(), &len);
+        for locale in locales {
+            etag.entry(locale).or_insert_with(|| {
+                delta = Delta::Modified;
+                A::Etag::default()
+            });
+        }
+
+        let mut generators = Vec::with_capacity(etag.len());
+        for (locale, etag) in etag {
+            let tracked = f(locale).update(cx, etag);
+            delta = delta.or(tracked.delta);
+            generators.push((locale.clone(), tracked.value));
+        }
+        delta.track(Generator { generators })
+    }
+}
+
+/// Generator for [`PerLocale`].
+#[derive(Debug)]
+pub struct Generator<G> {
+    generators: Vec<(String, G)>,
+}
+
+impl<G: super::Generator> super::Generator for Generator<G> {
+    type Output = BTreeMap<String, G::Output>;
+
+    fn generate(self) -> Self::Output {
+        self.generators
+            .into_iter()
+            .map(|(locale, generator)| (locale, generator.generate()))
+            .collect()
+    }
+}
+
+use super::Asset;
+use super::Context;
+use crate::Delta;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::string::String;
+use std::vec::Vec;
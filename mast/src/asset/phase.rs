@@ -0,0 +1,57 @@
+/// Asset for [`Asset::phase`].
+pub struct Phase<A, F> {
+    asset: A,
+    f: F,
+}
+
+impl<A, F> Phase<A, F> {
+    pub(crate) fn new(asset: A, f: F) -> Self {
+        Self { asset, f }
+    }
+}
+
+impl<A: Debug, F> Debug for Phase<A, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Phase")
+            .field("asset", &self.asset)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, A1, A2, F> Asset<'c> for Phase<A1, F>
+where
+    A1: Asset<'c>,
+    A1::Output: Etag + PartialEq + Clone,
+    F: FnOnce(Tracked<A1::Output>) -> A2,
+    A2: Asset<'c>,
+{
+    type Etag = (A1::Etag, Option<A1::Output>, A2::Etag);
+    type Output = A2::Output;
+    type Generator = A2::Generator;
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (etag1, aggregate, etag2) = etag;
+        let tracked = self.asset.update(cx, etag1);
+        let (aggregate, delta) = match aggregate {
+            Some(aggregate) if tracked.is_same() => (aggregate, Delta::Same),
+            _ => {
+                let output = tracked.value.generate();
+                let delta = match &*aggregate {
+                    Some(prev) => Delta::cmp(prev, &output),
+                    None => Delta::Modified,
+                };
+                (aggregate.insert(output), delta)
+            }
+        };
+        (self.f)(delta.track(aggregate.clone())).update(cx, etag2)
+    }
+}
+
+use super::Asset;
+use super::Context;
+use super::Generator as _;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
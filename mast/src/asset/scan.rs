@@ -0,0 +1,96 @@
+/// Asset for [`Asset::scan`].
+pub struct Scan<A, S, F> {
+    asset: A,
+    initial: S,
+    f: F,
+}
+
+impl<A, S, F> Scan<A, S, F> {
+    pub(crate) fn new(asset: A, initial: S, f: F) -> Self {
+        Self { asset, initial, f }
+    }
+}
+
+impl<A: Debug, S: Debug, F> Debug for Scan<A, S, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scan")
+            .field("asset", &self.asset)
+            .field("initial", &self.initial)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, A, S, F> Asset<'c> for Scan<A, S, F>
+where
+    A: Asset<'c>,
+    S: Etag + Clone,
+    F: FnOnce(S, A::Output) -> S,
+{
+    type Etag = (A::Etag, Option<S>);
+    type Output = S;
+    type Generator = Generator<'c, A::Generator, S, F>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (inner, state) = etag;
+        let tracked = self.asset.update(cx, inner);
+        // Without a previous output there is nothing to reuse, so the asset must be rebuilt.
+        let delta = match state {
+            Some(_) => tracked.delta,
+            None => Delta::Modified,
+        };
+        delta.track(Generator {
+            inner: tracked.value,
+            delta,
+            state,
+            initial: self.initial,
+            f: self.f,
+        })
+    }
+}
+
+/// Generator for [`Scan`].
+pub struct Generator<'c, G, S, F> {
+    inner: G,
+    delta: Delta,
+    state: &'c mut Option<S>,
+    initial: S,
+    f: F,
+}
+
+impl<G: Debug, S: Debug, F> Debug for Generator<'_, G, S, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Generator")
+            .field("inner", &self.inner)
+            .field("delta", &self.delta)
+            .field("state", &self.state)
+            .field("initial", &self.initial)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<G, S, F> super::Generator for Generator<'_, G, S, F>
+where
+    G: super::Generator,
+    S: Clone,
+    F: FnOnce(S, G::Output) -> S,
+{
+    type Output = S;
+
+    fn generate(self) -> Self::Output {
+        if let (Delta::Same, Some(state)) = (self.delta, &*self.state) {
+            return state.clone();
+        }
+        let previous = self.state.take().unwrap_or(self.initial);
+        let output = (self.f)(previous, self.inner.generate());
+        self.state.insert(output).clone()
+    }
+}
+
+use super::Asset;
+use super::Context;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
@@ -0,0 +1,283 @@
+/// Asset for [`Asset::shared_output`].
+#[derive(Debug)]
+pub struct SharedOutput<A> {
+    asset: A,
+}
+
+impl<A> SharedOutput<A> {
+    pub(crate) fn new(asset: A) -> Self {
+        Self { asset }
+    }
+
+    /// Guard the shared output with an [`RwLock`] instead of a [`Mutex`].
+    ///
+    /// Every clone of the generator locks the shared state when it is generated.
+    /// With a mutex, clones generated in parallel take turns
+    /// even once the output is ready, although only to clone an [`Arc`];
+    /// with an `RwLock`, they then only take a read lock and so never wait on each other.
+    /// This is worthwhile when many consumers on many threads share one output.
+    ///
+    /// The first clone to be generated still takes the lock exclusively
+    /// while the underlying generator runs, so the others wait for its output as before,
+    /// and the generator still runs at most once.
+    /// In exchange, generating is a little more expensive
+    /// when there are few clones or no contention,
+    /// since a clone that finds the output not yet ready takes a second lock.
+    /// An `RwLock` also requires the underlying generator to be [`Sync`]
+    /// for the shared generator to be [`Send`],
+    /// whereas a mutex only requires it to be `Send`.
+    ///
+    /// In either case the output is only ever shared behind an [`Arc`],
+    /// so it cannot be mutated in place by its consumers.
+    /// An asset whose generator has side effects, such as writing a file,
+    /// performs them exactly once, on whichever thread generates first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::Asset as _;
+    /// use std::sync::Arc;
+    ///
+    /// let mut etag = Default::default();
+    /// let args = asset::cli_args().shared_output().read_write();
+    /// let args = args.update(asset::Context::default(), &mut etag).value;
+    ///
+    /// let threads: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let args = args.clone();
+    ///         std::thread::spawn(move || args.generate())
+    ///     })
+    ///     .collect();
+    /// let args = args.generate();
+    /// for thread in threads {
+    ///     assert!(Arc::ptr_eq(&args, &thread.join().unwrap()));
+    /// }
+    /// ```
+    #[must_use]
+    pub fn read_write(self) -> SharedOutputRw<A> {
+        SharedOutputRw { asset: self.asset }
+    }
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for SharedOutput<A> {
+    type Etag = A::Etag;
+    type Output = Arc<A::Output>;
+    type Generator = Generator<A::Generator, A::Output>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        self.asset.update(cx, etag).map(|generator| Generator {
+            state: Arc::new(Mutex::new(State::Pending(generator))),
+        })
+    }
+}
+
+/// Generator for [`SharedOutput`].
+///
+/// Cloning this generator is cheap,
+/// and all the clones share the output of the single underlying generator.
+pub struct Generator<G, O> {
+    state: Arc<Mutex<State<G, O>>>,
+}
+
+impl<G, O> Clone for Generator<G, O> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<G, O> Debug for Generator<G, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = describe(self.state.try_lock().as_deref().ok());
+        f.debug_struct("Generator").field("state", &state).finish()
+    }
+}
+
+impl<G: super::Generator> super::Generator for Generator<G, G::Output> {
+    type Output = Arc<G::Output>;
+
+    fn generate(self) -> Self::Output {
+        // Holding the lock while generating makes concurrent callers wait for the result
+        // instead of running the generator twice.
+        generate(&mut self.state.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// Asset for [`SharedOutput::read_write`].
+#[derive(Debug)]
+pub struct SharedOutputRw<A> {
+    asset: A,
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for SharedOutputRw<A> {
+    type Etag = A::Etag;
+    type Output = Arc<A::Output>;
+    type Generator = RwGenerator<A::Generator, A::Output>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        self.asset.update(cx, etag).map(|generator| RwGenerator {
+            state: Arc::new(RwLock::new(State::Pending(generator))),
+        })
+    }
+}
+
+/// Generator for [`SharedOutputRw`].
+///
+/// Cloning this generator is cheap,
+/// and all the clones share the output of the single underlying generator.
+pub struct RwGenerator<G, O> {
+    state: Arc<RwLock<State<G, O>>>,
+}
+
+impl<G, O> Clone for RwGenerator<G, O> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<G, O> Debug for RwGenerator<G, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = describe(self.state.try_read().as_deref().ok());
+        f.debug_struct("RwGenerator")
+            .field("state", &state)
+            .finish()
+    }
+}
+
+impl<G: super::Generator> super::Generator for RwGenerator<G, G::Output> {
+    type Output = Arc<G::Output>;
+
+    fn generate(self) -> Self::Output {
+        if let State::Done(output) = &*self.state.read().unwrap_or_else(PoisonError::into_inner) {
+            return output.clone();
+        }
+        generate(&mut self.state.write().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+enum State<G, O> {
+    Pending(G),
+    Running,
+    Done(Arc<O>),
+}
+
+fn describe<G, O>(state: Option<&State<G, O>>) -> &'static str {
+    match state {
+        Some(State::Pending(_)) => "pending",
+        Some(State::Running) | None => "running",
+        Some(State::Done(_)) => "done",
+    }
+}
+
+fn generate<G: super::Generator>(state: &mut State<G, G::Output>) -> Arc<G::Output> {
+    match mem::replace(state, State::Running) {
+        State::Pending(generator) => {
+            let output = Arc::new(generator.generate());
+            *state = State::Done(output.clone());
+            output
+        }
+        State::Done(output) => {
+            *state = State::Done(output.clone());
+            output
+        }
+        State::Running => panic!("shared generator panicked"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn runs_once() {
+        fn check<G>(generator: G, runs: &AtomicUsize)
+        where
+            G: Generator<Output = Arc<usize>> + Clone + Send,
+        {
+            let outputs: Vec<_> = thread::scope(|s| {
+                let threads: Vec<_> = (0..4)
+                    .map(|_| {
+                        let generator = generator.clone();
+                        s.spawn(move || generator.generate())
+                    })
+                    .collect();
+                threads.into_iter().map(|t| t.join().unwrap()).collect()
+            });
+            let output = generator.generate();
+            assert_eq!(*output, 1);
+            assert!(outputs.iter().all(|o| Arc::ptr_eq(o, &output)));
+            assert_eq!(runs.load(SeqCst), 1);
+        }
+
+        let runs = AtomicUsize::new(0);
+        let shared = asset::constant(()).map(|()| runs.fetch_add(1, SeqCst) + 1);
+        let generator = shared
+            .shared_output()
+            .update(Context::default(), &mut ())
+            .value;
+        check(generator, &runs);
+
+        let runs = AtomicUsize::new(0);
+        let shared = asset::constant(()).map(|()| runs.fetch_add(1, SeqCst) + 1);
+        let generator = shared
+            .shared_output()
+            .read_write()
+            .update(Context::default(), &mut ())
+            .value;
+        check(generator, &runs);
+    }
+
+    #[test]
+    fn panic() {
+        let shared = asset::constant(()).map(|()| -> u32 { panic!("failed") });
+        let generator = shared
+            .shared_output()
+            .update(Context::default(), &mut ())
+            .value;
+        let other = generator.clone();
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| generator.generate())).is_err());
+        let err = panic::catch_unwind(AssertUnwindSafe(|| other.generate())).unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&"shared generator panicked"));
+    }
+
+    #[test]
+    fn send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let mut etag = BoxedEtag::default();
+        // Boxed send nodes are not `Sync`, which only the read-write variant needs.
+        let generator = asset::constant(1)
+            .boxed_send_node("n")
+            .shared_output()
+            .update(Context::default(), &mut etag)
+            .value;
+        assert_send(&generator);
+    }
+
+    use crate::asset;
+    use crate::asset::BoxedEtag;
+    use crate::asset::Context;
+    use crate::asset::Generator;
+    use crate::Asset;
+    use core::panic::AssertUnwindSafe;
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering::SeqCst;
+    use std::panic;
+    use std::sync::Arc;
+    use std::thread;
+    use std::vec::Vec;
+}
+
+use super::Asset;
+use super::Context;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::mem;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::sync::RwLock;
@@ -0,0 +1,258 @@
+/// Asset for [`Asset::spill`].
+#[derive(Debug)]
+pub struct Spill<A> {
+    asset: A,
+    dir: PathBuf,
+    threshold: usize,
+}
+
+impl<A> Spill<A> {
+    pub(crate) fn new(asset: A, dir: PathBuf) -> Self {
+        Self {
+            asset,
+            dir,
+            threshold: 1024 * 1024,
+        }
+    }
+
+    /// Set the size in bytes above which outputs are stored on disk.
+    ///
+    /// Defaults to one mebibyte.
+    #[must_use]
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+impl<'c, A> Asset<'c> for Spill<A>
+where
+    A: Asset<'c>,
+    A::Output: Into<Vec<u8>>,
+{
+    type Etag = A::Etag;
+    type Output = io::Result<Spilled>;
+    type Generator = Generator<A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let dir = match cx.try_get::<Roots>() {
+            Some(roots) => roots.cache_path(self.dir),
+            None => self.dir,
+        };
+        let threshold = self.threshold;
+        self.asset.update(cx, etag).map(|inner| Generator {
+            inner,
+            dir,
+            threshold,
+        })
+    }
+}
+
+/// Generator for [`Spill`].
+#[derive(Debug)]
+pub struct Generator<G> {
+    inner: G,
+    dir: PathBuf,
+    threshold: usize,
+}
+
+impl<G> super::Generator for Generator<G>
+where
+    G: super::Generator,
+    G::Output: Into<Vec<u8>>,
+{
+    type Output = io::Result<Spilled>;
+
+    fn generate(self) -> Self::Output {
+        let bytes = self.inner.generate().into();
+        if bytes.len() <= self.threshold {
+            return Ok(Spilled {
+                repr: Repr::Memory(bytes),
+            });
+        }
+        let len = bytes.len() as u64;
+        let name = Sha256::digest(&bytes).to_string();
+        let path = self.dir.join(&name);
+        if !is_complete(&path, len) {
+            fs::create_dir_all(&self.dir)?;
+            // Other threads and processes may be spilling the same bytes at the same time,
+            // so each writes to its own temporary file.
+            let n = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+            let tmp = self.dir.join(format!("{name}.{}-{n}.tmp", process::id()));
+            let written = fs::write(&tmp, &bytes).and_then(|()| fs::rename(&tmp, &path));
+            if let Err(e) = written {
+                let _ = fs::remove_file(&tmp);
+                // Renaming onto an existing file fails on some platforms;
+                // then whoever created it has already written the same bytes.
+                if !is_complete(&path, len) {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(Spilled {
+            repr: Repr::Disk { path, len },
+        })
+    }
+}
+
+/// The number of the next temporary file to spill to in this process.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the file at `path` holds spilled bytes of length `len`.
+///
+/// Files are named by their contents, so an existing file of the right length is complete.
+fn is_complete(path: &Path, len: u64) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.len() == len)
+}
+
+/// The output of [`Asset::spill`]:
+/// bytes that are either held in memory or stored in a file on disk.
+#[derive(Debug, Clone)]
+pub struct Spilled {
+    repr: Repr,
+}
+
+#[derive(Debug, Clone)]
+enum Repr {
+    Memory(Vec<u8>),
+    Disk { path: PathBuf, len: u64 },
+}
+
+impl Spilled {
+    /// The number of bytes.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        match &self.repr {
+            Repr::Memory(bytes) => bytes.len() as u64,
+            Repr::Disk { len, .. } => *len,
+        }
+    }
+
+    /// Whether there are no bytes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The path of the file the bytes are stored in, if they were spilled to disk.
+    ///
+    /// The file is named by the digest of its contents and must not be modified;
+    /// copy it to put the bytes elsewhere.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match &self.repr {
+            Repr::Memory(_) => None,
+            Repr::Disk { path, .. } => Some(path),
+        }
+    }
+
+    /// Get the bytes, reading them from disk if they were spilled.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file the bytes were spilled to could not be read.
+    pub fn read(&self) -> io::Result<Cow<'_, [u8]>> {
+        match &self.repr {
+            Repr::Memory(bytes) => Ok(Cow::Borrowed(bytes)),
+            Repr::Disk { path, .. } => fs::read(path).map(Cow::Owned),
+        }
+    }
+
+    /// Take the bytes, reading them from disk if they were spilled.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file the bytes were spilled to could not be read.
+    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
+        match self.repr {
+            Repr::Memory(bytes) => Ok(bytes),
+            Repr::Disk { path, .. } => fs::read(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn threshold() {
+        let dir = TempDirs::default().create().unwrap();
+        let spill = |len| {
+            let asset = asset::constant(vec![1_u8; len])
+                .spill(dir.path())
+                .threshold(4);
+            asset
+                .update(Context::default(), &mut ())
+                .value
+                .generate()
+                .unwrap()
+        };
+
+        let at = spill(4);
+        assert_eq!(at.path(), None);
+        assert_eq!(*at.read().unwrap(), [1; 4]);
+
+        let above = spill(5);
+        let path = above.path().unwrap();
+        assert_eq!(path.parent(), Some(dir.path()));
+        assert_eq!(fs::read(path).unwrap(), [1; 5]);
+        assert_eq!(above.into_bytes().unwrap(), [1; 5]);
+    }
+
+    #[test]
+    fn reuse() {
+        let dir = TempDirs::default().create().unwrap();
+        let spill = |byte| {
+            let asset = asset::constant(vec![byte; 64])
+                .spill(dir.path())
+                .threshold(0);
+            asset.update(Context::default(), &mut ()).value.generate()
+        };
+
+        let first = spill(1).unwrap();
+        let path = first.path().unwrap();
+        let modified = fs::metadata(path).unwrap().modified().unwrap();
+        assert_eq!(spill(1).unwrap().path(), Some(path));
+        assert_eq!(fs::metadata(path).unwrap().modified().unwrap(), modified);
+
+        // Threads racing to spill the same bytes all succeed and share one file.
+        let paths: Vec<_> = thread::scope(|s| {
+            let threads: Vec<_> = (0..8).map(|_| s.spawn(|| spill(2))).collect();
+            let paths = threads.into_iter().map(|thread| thread.join().unwrap());
+            paths
+                .map(|spilled| spilled.unwrap().path().unwrap().to_path_buf())
+                .collect()
+        });
+        assert!(paths.iter().all(|p| *p == paths[0]));
+        assert_eq!(fs::read(&paths[0]).unwrap(), [2; 64]);
+
+        // No temporary files are left behind.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::fs::TempDirs;
+    use crate::Asset as _;
+    use std::fs;
+    use std::thread;
+    use std::vec;
+    use std::vec::Vec;
+}
+
+use super::Asset;
+use super::Context;
+use crate::fs::Roots;
+use crate::hash::Sha256;
+use crate::Tracked;
+use core::sync::atomic;
+use core::sync::atomic::AtomicU64;
+use std::borrow::Cow;
+use std::format;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::string::ToString as _;
+use std::vec::Vec;
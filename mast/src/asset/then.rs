@@ -12,7 +12,9 @@ impl<A, F> Then<A, F> {
 
 impl<A: Debug, F> Debug for Then<A, F> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Then").field("asset", &self.asset).finish()
+        f.debug_struct("Then")
+            .field("asset", &self.asset)
+            .finish_non_exhaustive()
     }
 }
 
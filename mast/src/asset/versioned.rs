@@ -0,0 +1,29 @@
+/// Asset for [`Asset::version`].
+#[derive(Debug)]
+pub struct Versioned<A> {
+    asset: A,
+    version: u32,
+}
+
+impl<A> Versioned<A> {
+    pub(crate) fn new(asset: A, version: u32) -> Self {
+        Self { asset, version }
+    }
+}
+
+impl<'c, A: Asset<'c>> Asset<'c> for Versioned<A> {
+    type Etag = (Option<u32>, A::Etag);
+    type Output = A::Output;
+    type Generator = map_etag::Generator<'c, A::Generator, u32>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let version = self.version;
+        MapEtag::new(self.asset, |_| version).update(cx, etag)
+    }
+}
+
+use super::map_etag;
+use super::Asset;
+use super::Context;
+use super::MapEtag;
+use crate::Tracked;
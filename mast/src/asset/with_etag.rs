@@ -0,0 +1,34 @@
+/// Asset for [`Asset::with_etag`].
+#[derive(Debug)]
+pub struct WithEtag<A, E> {
+    asset: A,
+    value: E,
+}
+
+impl<A, E> WithEtag<A, E> {
+    pub(crate) fn new(asset: A, value: E) -> Self {
+        Self { asset, value }
+    }
+}
+
+impl<'c, A, E> Asset<'c> for WithEtag<A, E>
+where
+    A: Asset<'c>,
+    E: Etag + PartialEq,
+{
+    type Etag = (Option<E>, A::Etag);
+    type Output = A::Output;
+    type Generator = map_etag::Generator<'c, A::Generator, E>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let value = self.value;
+        MapEtag::new(self.asset, |_| value).update(cx, etag)
+    }
+}
+
+use super::map_etag;
+use super::Asset;
+use super::Context;
+use super::MapEtag;
+use crate::Etag;
+use crate::Tracked;
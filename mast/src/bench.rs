@@ -0,0 +1,230 @@
+//! Synthetic asset graphs for benchmarking the overhead of combinators.
+//!
+//! The assets here do no work of their own,
+//! so the time taken to update and generate them is the cost of the trait plumbing alone.
+//! [`leaf`] is the trivial asset everything is built from;
+//! chaining [`step`] onto it with [`Asset::then`] builds deep, statically dispatched graphs,
+//! [`dyn_chain`] builds equally deep graphs through dynamic dispatch,
+//! and [`wide`] combines many leaves side by side.
+//! [`report`] measures the types of an asset,
+//! to find where [`Asset::boxed_node`] would keep them small.
+//! The benchmarks in the repository’s `benches` directory are built from these.
+//!
+//! # Examples
+//!
+//! ```
+//! use mast::asset;
+//! use mast::asset::Generator as _;
+//! use mast::bench;
+//! use mast::Asset as _;
+//!
+//! let cx = asset::Context::default();
+//! let mut etag = Default::default();
+//! let chain = bench::leaf(1).then(bench::step).then(bench::step);
+//! assert_eq!(chain.update(cx, &mut etag).value.generate(), 3);
+//!
+//! let mut etag = Default::default();
+//! assert_eq!(bench::dyn_chain(2, 1).update(cx, &mut etag).value.generate(), 3);
+//!
+//! let mut etag = Default::default();
+//! assert_eq!(bench::wide(3).update(cx, &mut etag).value.generate(), [0, 1, 2]);
+//! ```
+
+/// A trivial asset whose etag and output are both `value`.
+///
+/// It is modified whenever its etag differs from `value`.
+#[must_use]
+pub const fn leaf(value: u64) -> Leaf {
+    Leaf { value }
+}
+
+/// Asset for [`leaf`].
+#[derive(Debug, Clone, Copy)]
+pub struct Leaf {
+    value: u64,
+}
+
+impl<'c> Asset<'c> for Leaf {
+    type Etag = u64;
+    type Output = u64;
+    type Generator = Constant<u64>;
+
+    fn update(self, _: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let delta = Delta::cmp(etag, &self.value);
+        *etag = self.value;
+        delta.track(asset::constant(self.value))
+    }
+}
+
+/// One level of a chain of [`Asset::then`] calls,
+/// passing on the delta of the previous level and adding one to its output.
+///
+/// Pass this function to `then` directly.
+pub fn step<G>(previous: Tracked<G>) -> Step<G>
+where
+    G: asset::Generator<Output = u64>,
+{
+    Step { previous }
+}
+
+/// Asset for [`step`].
+#[derive(Debug)]
+pub struct Step<G> {
+    previous: Tracked<G>,
+}
+
+impl<'c, G> Asset<'c> for Step<G>
+where
+    G: asset::Generator<Output = u64>,
+{
+    type Etag = ();
+    type Output = u64;
+    type Generator = StepGenerator<G>;
+
+    fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+        self.previous.map(|previous| StepGenerator { previous })
+    }
+}
+
+/// Generator for [`Step`].
+#[derive(Debug)]
+pub struct StepGenerator<G> {
+    previous: G,
+}
+
+impl<G: asset::Generator<Output = u64>> asset::Generator for StepGenerator<G> {
+    type Output = u64;
+
+    fn generate(self) -> Self::Output {
+        self.previous.generate() + 1
+    }
+}
+
+/// A chain of `depth` levels on top of a [`leaf`] of `value`,
+/// equivalent to calling `.then(step)` `depth` times
+/// but with every generator boxed and called through dynamic dispatch.
+///
+/// Unlike a chain of [`step`]s, its type does not depend on its depth,
+/// so the depth can be chosen at run time.
+#[must_use]
+pub const fn dyn_chain(depth: usize, value: u64) -> DynChain {
+    DynChain { depth, value }
+}
+
+/// Asset for [`dyn_chain`].
+#[derive(Debug, Clone, Copy)]
+pub struct DynChain {
+    depth: usize,
+    value: u64,
+}
+
+impl<'c> Asset<'c> for DynChain {
+    type Etag = u64;
+    type Output = u64;
+    type Generator = Box<dyn FnOnce() -> u64 + 'c>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let mut tracked = leaf(self.value).update(cx, etag).map(boxed);
+        for _ in 0..self.depth {
+            tracked = step(tracked).update(cx, &mut ()).map(boxed);
+        }
+        tracked
+    }
+}
+
+fn boxed<'c, G>(generator: G) -> Box<dyn FnOnce() -> u64 + 'c>
+where
+    G: asset::Generator<Output = u64> + 'c,
+{
+    Box::new(move || generator.generate())
+}
+
+/// `width` [`leaf`]s, with values counting up from zero, combined with [`asset::all`].
+///
+/// The leaves are generated on a [single thread](All::threads),
+/// so that the cost of spawning threads does not drown out that of the combinator.
+#[must_use]
+pub fn wide(width: u64) -> All<Leaf> {
+    asset::all((0..width).map(leaf)).threads(NonZeroUsize::MIN)
+}
+
+/// Measure the types of an asset.
+///
+/// The lengths of type names are a rough proxy for how much work the compiler does
+/// to monomorphize an asset, and so for its effect on compile times.
+///
+/// # Examples
+///
+/// ```
+/// use mast::bench;
+/// use mast::Asset as _;
+///
+/// let chain = bench::leaf(0).then(bench::step).then(bench::step).then(bench::step);
+/// let deep = bench::report(&chain);
+/// let boxed = bench::report(&chain.boxed_node("chain"));
+/// assert!(boxed.asset_name.len() < deep.asset_name.len());
+/// println!("{deep}");
+/// ```
+#[must_use]
+pub fn report<'c, A: Asset<'c>>(_asset: &A) -> Report {
+    Report {
+        asset_name: type_name::<A>(),
+        etag_name: type_name::<A::Etag>(),
+        generator_name: type_name::<A::Generator>(),
+        asset_size: size_of::<A>(),
+        etag_size: size_of::<A::Etag>(),
+    }
+}
+
+/// The sizes of the types of an asset, as returned by [`report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Report {
+    /// The name of the asset’s type.
+    pub asset_name: &'static str,
+    /// The name of the asset’s etag type.
+    pub etag_name: &'static str,
+    /// The name of the asset’s generator type.
+    pub generator_name: &'static str,
+    /// The size of the asset in bytes.
+    pub asset_size: usize,
+    /// The size of the asset’s etag in bytes.
+    pub etag_size: usize,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "asset: {} bytes, type name {} bytes",
+            self.asset_size,
+            self.asset_name.len()
+        )?;
+        writeln!(
+            f,
+            "etag: {} bytes, type name {} bytes",
+            self.etag_size,
+            self.etag_name.len()
+        )?;
+        write!(
+            f,
+            "generator: type name {} bytes",
+            self.generator_name.len()
+        )
+    }
+}
+
+use crate::asset;
+use crate::asset::All;
+use crate::asset::Constant;
+use crate::asset::Context;
+use crate::Asset;
+use crate::Delta;
+use crate::Tracked;
+use core::any::type_name;
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::mem::size_of;
+use core::num::NonZeroUsize;
+use std::boxed::Box;
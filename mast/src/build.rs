@@ -0,0 +1,680 @@
+//! Running the same build repeatedly, for example in a `watch` loop.
+//!
+//! [`Asset::update`] consumes its asset,
+//! so an asset value describes a single build.
+//! [`Build`] separates the two halves:
+//! the user supplies a reusable description of the build,
+//! a function from the [`Context`] to the root asset,
+//! and the `Build` owns the persistent state (the root etag) between builds.
+//!
+//! For large sites during development,
+//! [`OnDemand`] builds only the outputs that are actually requested, one path at a time.
+//!
+//! A [`Workspace`] manages several independent builds, such as a documentation site,
+//! an API reference and a blog, each with its own etag,
+//! and lets one of them consume the output of another.
+
+/// A reusable build, owning the persistent state of its root asset.
+///
+/// `description` is called once per build to construct the root asset.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::build::Build;
+/// use mast::Asset;
+/// use mast::Delta;
+/// use mast::Tracked;
+///
+/// struct Version(u32);
+///
+/// impl<'c> Asset<'c> for Version {
+///     type Etag = u32;
+///     type Output = u32;
+///     type Generator = Box<dyn FnOnce() -> u32 + 'c>;
+///
+///     fn update(self, _: asset::Context<'c>, etag: &'c mut u32) -> Tracked<Self::Generator> {
+///         let delta = Delta::cmp(etag, &self.0);
+///         delta.track(Box::new(move || {
+///             *etag = self.0;
+///             self.0
+///         }))
+///     }
+/// }
+///
+/// let mut version = 1;
+/// let mut build = Build::new(|_| Version(version));
+/// let cx = asset::Context::default();
+/// assert_eq!(build.build(cx), Some(1));
+/// assert_eq!(build.build(cx), None);
+/// assert_eq!(*build.etag(), 1);
+/// ```
+#[derive(Debug)]
+pub struct Build<F, E> {
+    description: F,
+    etag: E,
+}
+
+impl<F, E> Build<F, E> {
+    /// Construct a build with no previous state,
+    /// so that the first build is a full one.
+    #[must_use]
+    pub fn new<A>(description: F) -> Self
+    where
+        F: FnMut(Context<'_>) -> A,
+        E: Default,
+    {
+        Self::with_etag(description, E::default())
+    }
+
+    /// Construct a build that resumes from previously persisted state.
+    #[must_use]
+    pub fn with_etag<A>(description: F, etag: E) -> Self
+    where
+        F: FnMut(Context<'_>) -> A,
+    {
+        Self { description, etag }
+    }
+
+    /// The state of the root asset after the last build.
+    #[must_use]
+    pub fn etag(&self) -> &E {
+        &self.etag
+    }
+
+    /// Take the state of the root asset, for example to persist it.
+    #[must_use]
+    pub fn into_etag(self) -> E {
+        self.etag
+    }
+
+    /// Construct the root asset and update it,
+    /// returning its generator for the caller to run if it is modified.
+    ///
+    /// The build cannot be run again until the generator has been dropped.
+    /// If there is a [`Keys`](crate::asset::Keys) in the context,
+    /// it is prepared for the build with the current etag.
+    pub fn update<'c, A>(&'c mut self, cx: Context<'c>) -> Tracked<A::Generator>
+    where
+        F: FnMut(Context<'c>) -> A,
+        A: Asset<'c, Etag = E>,
+        E: Etag,
+    {
+        #[cfg(feature = "std")]
+        if let Some(keys) = cx.try_get::<asset::Keys>() {
+            keys.begin(&self.etag.to_vec());
+        }
+        (self.description)(cx).update(cx, &mut self.etag)
+    }
+
+    /// Forget the state of the root asset, so that the next build is a full one.
+    #[cfg(feature = "arc-swap")]
+    pub(crate) fn reset(&mut self)
+    where
+        E: Default,
+    {
+        self.etag = E::default();
+    }
+
+    /// Bring the build up to date,
+    /// returning the root asset’s output if it had to be regenerated.
+    pub fn build<'c, A>(&'c mut self, cx: Context<'c>) -> Option<A::Output>
+    where
+        F: FnMut(Context<'c>) -> A,
+        A: Asset<'c, Etag = E>,
+        E: Etag,
+    {
+        let Tracked { value, delta } = self.update(cx);
+        match delta {
+            Delta::Same => None,
+            Delta::Modified => Some(value.generate()),
+        }
+    }
+}
+
+/// A build whose outputs are brought up to date only when they are requested,
+/// owning the persistent state of the asset for each requested path.
+///
+/// `routes` is called with the path of each request
+/// to construct the asset responsible for it, or [`None`] if there is none.
+/// Unlike with [`Build`], where every change rebuilds the whole site,
+/// only the assets behind the paths a development server is actually asked for
+/// are ever updated or generated.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::build::OnDemand;
+/// use mast::Asset;
+/// use mast::Delta;
+/// use mast::Tracked;
+///
+/// struct Page(String);
+///
+/// impl<'c> Asset<'c> for Page {
+///     type Etag = String;
+///     type Output = String;
+///     type Generator = Box<dyn FnOnce() -> String + 'c>;
+///
+///     fn update(self, _: asset::Context<'c>, etag: &'c mut String) -> Tracked<Self::Generator> {
+///         let delta = Delta::cmp(etag, &self.0);
+///         delta.track(Box::new(move || {
+///             etag.clone_from(&self.0);
+///             format!("<h1>{}</h1>", self.0)
+///         }))
+///     }
+/// }
+///
+/// let mut site = OnDemand::new(|_, path: &str| {
+///     let name = path.strip_prefix('/')?.strip_suffix(".html")?;
+///     Some(Page(name.to_owned()))
+/// });
+/// let cx = asset::Context::default();
+///
+/// assert_eq!(site.get(cx, "/hello.html").as_deref(), Some("<h1>hello</h1>"));
+/// assert!(site.update(cx, "/hello.html").unwrap().is_same());
+/// assert!(site.get(cx, "/style.css").is_none());
+/// // Only the requested page has been built.
+/// assert_eq!(site.etags().len(), 1);
+/// ```
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+#[derive(Debug)]
+pub struct OnDemand<F, E> {
+    routes: F,
+    etags: BTreeMap<String, E>,
+}
+
+#[cfg(feature = "alloc")]
+impl<F, E> OnDemand<F, E> {
+    /// Construct an on-demand build with no previous state.
+    #[must_use]
+    pub fn new<A>(routes: F) -> Self
+    where
+        F: FnMut(Context<'_>, &str) -> Option<A>,
+    {
+        Self::with_etags(routes, BTreeMap::new())
+    }
+
+    /// Construct an on-demand build that resumes from previously persisted state.
+    #[must_use]
+    pub fn with_etags<A>(routes: F, etags: BTreeMap<String, E>) -> Self
+    where
+        F: FnMut(Context<'_>, &str) -> Option<A>,
+    {
+        Self { routes, etags }
+    }
+
+    /// The state of the asset for each path requested so far.
+    #[must_use]
+    pub fn etags(&self) -> &BTreeMap<String, E> {
+        &self.etags
+    }
+
+    /// Take the state of the asset for each path requested so far, for example to persist it.
+    #[must_use]
+    pub fn into_etags(self) -> BTreeMap<String, E> {
+        self.etags
+    }
+
+    /// Construct and update the asset for `path`,
+    /// returning its generator for the caller to run,
+    /// or [`None`] if no asset is responsible for the path.
+    ///
+    /// A server that keeps the last response for each path
+    /// can reuse it when the asset is the same, and only run the generator otherwise.
+    /// If there is a [`Keys`](crate::asset::Keys) in the context,
+    /// it is prepared for the update with the path’s etag.
+    pub fn update<'c, A>(&'c mut self, cx: Context<'c>, path: &str) -> Option<Tracked<A::Generator>>
+    where
+        F: FnMut(Context<'c>, &str) -> Option<A>,
+        A: Asset<'c, Etag = E>,
+        E: Etag,
+    {
+        let asset = (self.routes)(cx, path)?;
+        let etag = self.etags.entry(String::from(path)).or_default();
+        #[cfg(feature = "std")]
+        if let Some(keys) = cx.try_get::<asset::Keys>() {
+            keys.begin(&etag.to_vec());
+        }
+        Some(asset.update(cx, etag))
+    }
+
+    /// Bring the asset for `path` up to date and generate its output,
+    /// or return [`None`] if no asset is responsible for the path.
+    pub fn get<'c, A>(&'c mut self, cx: Context<'c>, path: &str) -> Option<A::Output>
+    where
+        F: FnMut(Context<'c>, &str) -> Option<A>,
+        A: Asset<'c, Etag = E>,
+        E: Etag,
+    {
+        Some(self.update(cx, path)?.value.generate())
+    }
+}
+
+/// Several independent builds sharing one context,
+/// each owning the persistent state of its root asset.
+///
+/// Roots are added with [`root`](Self::root) and built in the order they were added
+/// with the same [`Context`],
+/// so they share whatever executor, watcher or other values it holds.
+/// Each root’s etag is saved in a [`Store`] of its own,
+/// in a subdirectory named after the root.
+///
+/// Adding a root returns a [`Link`] to its output,
+/// through which a root added later can consume it as an input.
+/// The etag of the linked asset is a digest of the other root’s etag,
+/// so the consumer is modified exactly when the root it consumes is,
+/// even across restarts.
+/// The output of every root is kept in memory for its links,
+/// and is generated on the first build of the process even if the root is the same.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::build::Workspace;
+/// use mast::Asset;
+/// use mast::Delta;
+/// use mast::Tracked;
+/// use std::cell::Cell;
+///
+/// struct Version<'a>(&'a Cell<u32>);
+///
+/// impl<'c> Asset<'c> for Version<'_> {
+///     type Etag = u32;
+///     type Output = u32;
+///     type Generator = asset::Constant<u32>;
+///
+///     fn update(self, _: asset::Context<'c>, etag: &'c mut u32) -> Tracked<Self::Generator> {
+///         let delta = Delta::cmp(etag, &self.0.get());
+///         *etag = self.0.get();
+///         delta.track(asset::constant(self.0.get()))
+///     }
+/// }
+///
+/// let version = Cell::new(1);
+/// let mut workspace = Workspace::new();
+/// let api = workspace.root("api", |_| Version(&version));
+/// let docs = workspace.root("docs", move |_| {
+///     api.output().map(|api| format!("docs for version {}", api.unwrap()))
+/// });
+/// let cx = asset::Context::default();
+///
+/// assert_eq!(workspace.build(cx), ["api", "docs"]);
+/// assert_eq!(*docs.get().unwrap(), "docs for version 1");
+/// assert!(workspace.build(cx).is_empty());
+///
+/// // Changing the API reference rebuilds the docs too.
+/// version.set(2);
+/// assert_eq!(workspace.build(cx), ["api", "docs"]);
+/// assert_eq!(*docs.get().unwrap(), "docs for version 2");
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub struct Workspace<'a> {
+    roots: Vec<(String, Box<dyn ErasedRoot + 'a>)>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Workspace<'a> {
+    /// Construct a workspace with no roots.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { roots: Vec::new() }
+    }
+
+    /// Add a root to the workspace, built after every root added before it,
+    /// and return a link to its output.
+    ///
+    /// `description` is called once per build to construct the root asset, as in [`Build`].
+    /// The name is also the name of the directory of the root’s [`Store`],
+    /// so it should be unique within the workspace and a valid file name.
+    pub fn root<N, F, A, E, O>(&mut self, name: N, description: F) -> Link<O>
+    where
+        N: Into<String>,
+        F: 'a + FnMut(Context<'_>) -> A,
+        A: 'a + for<'c> Asset<'c, Etag = E, Output = O>,
+        E: 'a + Etag,
+        O: 'a,
+    {
+        let link = Link {
+            inner: Arc::new(Mutex::new(None)),
+        };
+        let root = Root {
+            build: Build::new(description),
+            link: link.clone(),
+        };
+        self.roots.push((name.into(), Box::new(root)));
+        link
+    }
+
+    /// The names of the roots in the workspace, in the order they are built.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.roots.iter().map(|(name, _)| &**name)
+    }
+
+    /// Bring every root up to date,
+    /// returning the names of those whose outputs had to be regenerated.
+    pub fn build(&mut self, cx: Context<'_>) -> Vec<&str> {
+        let mut rebuilt = Vec::new();
+        for (name, root) in &mut self.roots {
+            if root.build(cx) {
+                rebuilt.push(&**name);
+            }
+        }
+        rebuilt
+    }
+
+    /// Replace the etag of every root with the one saved in its store,
+    /// the subdirectory of `dir` named after the root.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a store could not be opened or an etag could not be read.
+    pub fn load<P: AsRef<Path>>(&mut self, dir: P) -> io::Result<()> {
+        for (name, root) in &mut self.roots {
+            let store = Store::open(dir.as_ref().join(&**name))?;
+            root.load(&store, name)?;
+        }
+        Ok(())
+    }
+
+    /// Save the etag of every root in its store,
+    /// the subdirectory of `dir` named after the root.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a store could not be opened or an etag could not be written.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        for (name, root) in &self.roots {
+            let store = Store::open(dir.as_ref().join(&**name))?;
+            root.save(&store, name)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Workspace<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Debug for Workspace<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Workspace")
+            .field("roots", &self.names().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A link to the output of a root of a [`Workspace`].
+///
+/// Cloning a link is cheap, and the clones share the output.
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub struct Link<O> {
+    inner: Arc<Mutex<Current<O>>>,
+}
+
+#[cfg(feature = "std")]
+impl<O> Link<O> {
+    /// An asset whose output is the output of the root,
+    /// or [`None`] if the root has not been built yet,
+    /// as when it was added after the root consuming it.
+    #[must_use]
+    pub fn output(&self) -> Linked<O> {
+        Linked {
+            current: lock(&self.inner).clone(),
+        }
+    }
+
+    /// The output of the root after the last build,
+    /// or [`None`] if it has not been built yet.
+    #[must_use]
+    pub fn get(&self) -> Option<Arc<O>> {
+        Some(lock(&self.inner).as_ref()?.1.clone())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O> Clone for Link<O> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O> Debug for Link<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let built = lock(&self.inner).is_some();
+        f.debug_struct("Link").field("built", &built).finish()
+    }
+}
+
+/// Asset for [`Link::output`].
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+#[derive(Debug)]
+pub struct Linked<O> {
+    current: Current<O>,
+}
+
+#[cfg(feature = "std")]
+impl<'c, O> Asset<'c> for Linked<O> {
+    type Etag = Option<Digest>;
+    type Output = Option<Arc<O>>;
+    type Generator = Constant<Option<Arc<O>>>;
+
+    fn update(self, _: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (digest, output) = match self.current {
+            Some((digest, output)) => (Some(digest), Some(output)),
+            None => (None, None),
+        };
+        let delta = Delta::cmp(etag, &digest);
+        *etag = digest;
+        delta.track(asset::constant(output))
+    }
+}
+
+/// The output of a root and the digest of its etag, if it has been built.
+#[cfg(feature = "std")]
+type Current<O> = Option<(Digest, Arc<O>)>;
+
+#[cfg(feature = "std")]
+trait ErasedRoot {
+    /// Bring the root up to date, returning whether its output was regenerated.
+    fn build(&mut self, cx: Context<'_>) -> bool;
+    fn load(&mut self, store: &Store, name: &str) -> io::Result<()>;
+    fn save(&self, store: &Store, name: &str) -> io::Result<()>;
+}
+
+#[cfg(feature = "std")]
+struct Root<F, E, O> {
+    build: Build<F, E>,
+    link: Link<O>,
+}
+
+#[cfg(feature = "std")]
+impl<F, A, E, O> ErasedRoot for Root<F, E, O>
+where
+    F: FnMut(Context<'_>) -> A,
+    A: for<'c> Asset<'c, Etag = E, Output = O>,
+    E: Etag,
+{
+    fn build(&mut self, cx: Context<'_>) -> bool {
+        let Tracked { value, delta } = self.build.update(cx);
+        // Links need an output even if the root was already up to date when the process started.
+        if delta == Delta::Same && lock(&self.link.inner).is_some() {
+            return false;
+        }
+        let output = Arc::new(value.generate());
+        let mut hasher = Sha256::new();
+        self.build.etag.serialize(&mut hasher);
+        *lock(&self.link.inner) = Some((hasher.finish(), output));
+        delta == Delta::Modified
+    }
+
+    fn load(&mut self, store: &Store, name: &str) -> io::Result<()> {
+        self.build.etag = store.load(name)?;
+        Ok(())
+    }
+
+    fn save(&self, store: &Store, name: &str) -> io::Result<()> {
+        store.save(name, &self.build.etag)
+    }
+}
+
+#[cfg(feature = "std")]
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    struct Version<'a>(&'a Cell<u32>);
+
+    impl<'c> Asset<'c> for Version<'_> {
+        type Etag = u32;
+        type Output = u32;
+        type Generator = asset::Constant<u32>;
+
+        fn update(self, _: Context<'c>, etag: &'c mut u32) -> Tracked<Self::Generator> {
+            let delta = Delta::cmp(etag, &self.0.get());
+            *etag = self.0.get();
+            delta.track(asset::constant(self.0.get()))
+        }
+    }
+
+    #[test]
+    fn rerun() {
+        let version = Cell::new(1);
+        let runs = Cell::new(0);
+        let mut build = Build::new(|_| {
+            runs.set(runs.get() + 1);
+            Version(&version)
+        });
+        let cx = Context::default();
+
+        assert_eq!(build.build(cx), Some(1));
+        assert_eq!(build.build(cx), None);
+        version.set(2);
+        assert!(build.update(cx).is_modified());
+        // Updating without generating still records the new state.
+        assert_eq!(build.build(cx), None);
+        assert_eq!(runs.get(), 4);
+
+        // The state can be persisted and resumed from.
+        let etag = build.into_etag();
+        assert_eq!(etag, 2);
+        let mut build = Build::with_etag(|_| Version(&version), etag);
+        assert_eq!(build.build(cx), None);
+        let mut build = Build::with_etag(|_| Version(&version), 1);
+        assert_eq!(build.build(cx), Some(2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn workspace_stores() {
+        let dir = env::temp_dir().join(format!("mast-test-workspace-{}", process::id()));
+        let version = Cell::new(1);
+        let workspace = || {
+            let mut workspace = Workspace::new();
+            workspace.root("api", |_| Version(&version));
+            workspace.root("docs", |_| Version(&version));
+            workspace
+        };
+        let cx = Context::default();
+
+        let mut first = workspace();
+        assert_eq!(first.build(cx), ["api", "docs"]);
+        first.save(&dir).unwrap();
+        assert!(dir.join("api").is_dir());
+        assert!(dir.join("docs").is_dir());
+
+        // Each root resumes from its own store.
+        let mut second = workspace();
+        second.load(&dir).unwrap();
+        assert!(second.build(cx).is_empty());
+
+        Store::open(dir.join("docs"))
+            .unwrap()
+            .save("docs", &0_u32)
+            .unwrap();
+        let mut third = workspace();
+        third.load(&dir).unwrap();
+        assert_eq!(third.build(cx), ["docs"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use super::Build;
+    #[cfg(feature = "std")]
+    use super::Workspace;
+    use crate::asset;
+    use crate::asset::Context;
+    #[cfg(feature = "std")]
+    use crate::store::Store;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Tracked;
+    use core::cell::Cell;
+    #[cfg(feature = "std")]
+    use std::env;
+    #[cfg(feature = "std")]
+    use std::format;
+    #[cfg(feature = "std")]
+    use std::fs;
+    #[cfg(feature = "std")]
+    use std::process;
+}
+
+#[cfg(feature = "std")]
+use crate::asset;
+#[cfg(feature = "std")]
+use crate::asset::Constant;
+use crate::asset::Context;
+use crate::asset::Generator as _;
+#[cfg(feature = "std")]
+use crate::hash::Digest;
+#[cfg(feature = "std")]
+use crate::hash::Sha256;
+#[cfg(feature = "std")]
+use crate::store::Store;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use core::fmt;
+#[cfg(feature = "std")]
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use core::fmt::Formatter;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use std::sync::MutexGuard;
+#[cfg(feature = "std")]
+use std::sync::PoisonError;
+#[cfg(feature = "std")]
+use std::vec::Vec;
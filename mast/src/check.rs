@@ -0,0 +1,279 @@
+//! Assets that produce verification data for generated files.
+
+/// Produce a `SHA256SUMS`-style manifest of a set of files,
+/// suitable for verification with `sha256sum --check`.
+///
+/// The `files` asset should output an iterator of `(name, path)` pairs;
+/// each line of the manifest is the hexadecimal digest of the file at `path`,
+/// followed by two spaces and `name`,
+/// in the order the files were given.
+/// As with `sha256sum`, a name containing a backslash or a line break
+/// is escaped and its line is prefixed with a backslash,
+/// so that every file takes up exactly one line.
+///
+/// Digests are remembered in the etag along with each file’s [`Stamp`],
+/// so only files whose size or modification time changed are hashed again.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::check;
+/// use mast::Asset as _;
+/// use std::fs;
+/// use std::path::PathBuf;
+/// # use mast::{Asset, Delta, Tracked};
+/// # struct Files(Vec<(&'static str, PathBuf)>);
+/// # impl<'c> Asset<'c> for Files {
+/// #     type Etag = ();
+/// #     type Output = Vec<(&'static str, PathBuf)>;
+/// #     type Generator = Self;
+/// #     fn update(self, _: asset::Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+/// #         Delta::Modified.track(self)
+/// #     }
+/// # }
+/// # impl asset::Generator for Files {
+/// #     type Output = Vec<(&'static str, PathBuf)>;
+/// #     fn generate(self) -> Self::Output { self.0 }
+/// # }
+/// # fn files(files: Vec<(&'static str, PathBuf)>) -> Files { Files(files) }
+///
+/// let path = std::env::temp_dir().join(format!("mast-doctest-sha256-{}", std::process::id()));
+/// fs::write(&path, "abc")?;
+///
+/// let mut etag = Default::default();
+/// // `files` is some asset outputting `(name, path)` pairs.
+/// let manifest = check::sha256_manifest(files(vec![("abc.txt", path.clone())]));
+/// let manifest = manifest.update(asset::Context::default(), &mut etag).value.generate()?;
+/// assert_eq!(
+///     manifest,
+///     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  abc.txt\n",
+/// );
+/// # fs::remove_file(&path)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn sha256_manifest<A>(files: A) -> Sha256Manifest<A> {
+    Sha256Manifest { files }
+}
+
+/// Asset for [`sha256_manifest`].
+#[derive(Debug)]
+pub struct Sha256Manifest<A> {
+    files: A,
+}
+
+impl<'c, A, K, P> Asset<'c> for Sha256Manifest<A>
+where
+    A: Asset<'c>,
+    A::Output: IntoIterator<Item = (K, P)>,
+    K: Into<String>,
+    P: Into<PathBuf>,
+{
+    type Etag = (A::Etag, State);
+    type Output = io::Result<String>;
+    type Generator = Generator<'c, A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (files_etag, state) = etag;
+        let files = self.files.update(cx, files_etag);
+        let delta = files
+            .delta
+            .or(Delta::cmp(&state.complete, &true))
+            .or_else(|| {
+                let unchanged = state
+                    .files
+                    .iter()
+                    .all(|(_, file)| Stamp::of(&file.path).ok() == Some(file.stamp));
+                Delta::cmp(&unchanged, &true)
+            });
+        delta.track(Generator {
+            files: files.value,
+            state,
+        })
+    }
+}
+
+/// Generator for [`Sha256Manifest`].
+#[derive(Debug)]
+pub struct Generator<'c, G> {
+    files: G,
+    state: &'c mut State,
+}
+
+impl<G, K, P> asset::Generator for Generator<'_, G>
+where
+    G: asset::Generator,
+    G::Output: IntoIterator<Item = (K, P)>,
+    K: Into<String>,
+    P: Into<PathBuf>,
+{
+    type Output = io::Result<String>;
+
+    fn generate(self) -> Self::Output {
+        let state = self.state;
+        state.complete = false;
+
+        let mut manifest = String::new();
+        let mut seen = BTreeSet::new();
+        for (name, path) in self.files.generate() {
+            let name = name.into();
+            let path = path.into();
+            let stamp = Stamp::of(&path)?;
+
+            let digest = match state.files.get(&name) {
+                Some(file) if file.path == path && file.stamp == stamp => file.digest,
+                _ => {
+                    let digest = hash_file(&path)?;
+                    let file = File {
+                        path,
+                        stamp,
+                        digest,
+                    };
+                    state.files.insert(name.clone(), file);
+                    digest
+                }
+            };
+
+            if name.contains(['\\', '\n', '\r']) {
+                manifest.push('\\');
+                writeln!(manifest, "{digest}  {}", escape(&name)).unwrap();
+            } else {
+                writeln!(manifest, "{digest}  {name}").unwrap();
+            }
+            seen.insert(name);
+        }
+        state.files.retain(|name, _| seen.contains(name));
+
+        state.complete = true;
+        Ok(manifest)
+    }
+}
+
+/// Escape a file name in the way `sha256sum` does.
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The persistent state of a [`Sha256Manifest`] asset.
+#[derive(Debug, Default)]
+pub struct State {
+    files: BTreeMap<String, File>,
+    complete: bool,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.files.serialize(writer);
+        self.complete.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            files: Etag::deserialize(reader)?,
+            complete: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct File {
+    path: PathBuf,
+    stamp: Stamp,
+    digest: Digest,
+}
+
+impl Etag for File {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.path.serialize(writer);
+        self.stamp.serialize(writer);
+        self.digest.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            path: Etag::deserialize(reader)?,
+            stamp: Etag::deserialize(reader)?,
+            digest: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn escaped_names() {
+        struct Files(Vec<(&'static str, PathBuf)>);
+        impl<'c> Asset<'c> for Files {
+            type Etag = ();
+            type Output = Vec<(&'static str, PathBuf)>;
+            type Generator = Self;
+            fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+                Delta::Modified.track(self)
+            }
+        }
+        impl asset::Generator for Files {
+            type Output = Vec<(&'static str, PathBuf)>;
+            fn generate(self) -> Self::Output {
+                self.0
+            }
+        }
+
+        let path = env::temp_dir().join(format!("mast-test-sha256-{}", process::id()));
+        fs::write(&path, "abc").unwrap();
+        let files = Files(vec![
+            ("plain", path.clone()),
+            ("two\nlines", path.clone()),
+            ("back\\slash", path.clone()),
+        ]);
+        let mut etag = Default::default();
+        let manifest = sha256_manifest(files).update(Context::default(), &mut etag);
+        let manifest = asset::Generator::generate(manifest.value).unwrap();
+        let digest = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(
+            manifest,
+            format!("{digest}  plain\n\\{digest}  two\\nlines\n\\{digest}  back\\\\slash\n"),
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    use super::sha256_manifest;
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Tracked;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use std::vec;
+    use std::vec::Vec;
+}
+
+use crate::asset;
+use crate::asset::Context;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::fs::hash_file;
+use crate::fs::Stamp;
+use crate::hash::Digest;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt::Write as _;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io;
+use std::path::PathBuf;
+use std::string::String;
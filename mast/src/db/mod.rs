@@ -0,0 +1,162 @@
+//! Assets whose data comes from a database.
+//!
+//! The [`query`] asset is generic over any kind of database connection;
+//! support for specific databases is provided in submodules behind feature flags.
+
+/// Fetch data from a database,
+/// using a separate cheap “version” query to determine whether the data has changed.
+///
+/// `version` is run on every update,
+/// and should return some value that changes whenever the result of `fetch` would —
+/// for example, a row version column,
+/// or the maximum `updated_at` timestamp and row count of the tables `fetch` reads.
+/// The asset’s etag is a digest of that value.
+///
+/// `fetch` is only run when the output is actually generated.
+/// If `version` fails, the asset is considered modified and its output is the error.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::db;
+/// use mast::Asset as _;
+/// use std::cell::RefCell;
+/// use std::convert::Infallible;
+///
+/// struct Table {
+///     version: u64,
+///     rows: Vec<String>,
+/// }
+///
+/// let table = RefCell::new(Table { version: 1, rows: vec!["Hello".to_owned()] });
+/// let posts = || {
+///     db::query(
+///         &table,
+///         |table| Ok::<_, Infallible>(table.borrow().version),
+///         |table| Ok(table.borrow().rows.clone()),
+///     )
+/// };
+///
+/// let mut etag = Default::default();
+/// let res = posts().update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate(), Ok(vec!["Hello".to_owned()]));
+///
+/// assert!(posts().update(asset::Context::default(), &mut etag).is_same());
+///
+/// let mut t = table.borrow_mut();
+/// t.version += 1;
+/// t.rows.push("world".to_owned());
+/// drop(t);
+/// assert!(posts().update(asset::Context::default(), &mut etag).is_modified());
+/// ```
+pub fn query<C, V, F, R, T, E>(connection: C, version: V, fetch: F) -> Query<C, V, F>
+where
+    V: FnOnce(&C) -> Result<R, E>,
+    R: Etag,
+    F: FnOnce(&C) -> Result<T, E>,
+{
+    Query {
+        connection,
+        version,
+        fetch,
+    }
+}
+
+/// Asset for [`query`].
+pub struct Query<C, V, F> {
+    connection: C,
+    version: V,
+    fetch: F,
+}
+
+impl<C: Debug, V, F> Debug for Query<C, V, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Query")
+            .field("connection", &self.connection)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, C, V, F, R, T, E> Asset<'c> for Query<C, V, F>
+where
+    V: FnOnce(&C) -> Result<R, E>,
+    R: Etag,
+    F: FnOnce(&C) -> Result<T, E>,
+{
+    type Etag = Digest;
+    type Output = Result<T, E>;
+    type Generator = Generator<C, F, E>;
+
+    fn update(self, _: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        match (self.version)(&self.connection) {
+            Ok(version) => {
+                let mut hasher = Sha256::new();
+                version.serialize(&mut hasher);
+                let digest = hasher.finish();
+                let delta = Delta::cmp(etag, &digest);
+                *etag = digest;
+                delta.track(Generator(Inner::Fetch {
+                    connection: self.connection,
+                    fetch: self.fetch,
+                }))
+            }
+            Err(e) => {
+                *etag = Digest::default();
+                Delta::Modified.track(Generator(Inner::Failed(e)))
+            }
+        }
+    }
+}
+
+/// Generator for [`Query`].
+pub struct Generator<C, F, E>(Inner<C, F, E>);
+
+enum Inner<C, F, E> {
+    Fetch { connection: C, fetch: F },
+    Failed(E),
+}
+
+impl<C: Debug, F, E: Debug> Debug for Generator<C, F, E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Inner::Fetch { connection, .. } => f
+                .debug_struct("Generator")
+                .field("connection", connection)
+                .finish_non_exhaustive(),
+            Inner::Failed(e) => f.debug_tuple("Generator").field(e).finish(),
+        }
+    }
+}
+
+impl<C, F, T, E> asset::Generator for Generator<C, F, E>
+where
+    F: FnOnce(&C) -> Result<T, E>,
+{
+    type Output = Result<T, E>;
+
+    fn generate(self) -> Self::Output {
+        match self.0 {
+            Inner::Fetch { connection, fetch } => fetch(&connection),
+            Inner::Failed(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "rusqlite")))]
+pub mod sqlite;
+
+use crate::asset;
+use crate::asset::Context;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
@@ -0,0 +1,100 @@
+//! SQLite support, via [`rusqlite`].
+
+/// Fetch data from a SQLite database,
+/// using the rows returned by the SQL statement `version_sql`
+/// to determine whether the data has changed.
+///
+/// This is a specialization of [`db::query`](super::query):
+/// every value of every row returned by `version_sql` is hashed to form the etag.
+/// A typical version query aggregates over the tables read by `fetch`,
+/// for example `SELECT max(updated_at), count(*) FROM posts`.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::db;
+/// use mast::Asset as _;
+///
+/// let conn = rusqlite::Connection::open_in_memory()?;
+/// conn.execute_batch(
+///     "CREATE TABLE posts (title TEXT NOT NULL, updated_at INTEGER NOT NULL);
+///      INSERT INTO posts VALUES ('Hello', 1);",
+/// )?;
+///
+/// let titles = || {
+///     db::sqlite::query(&conn, "SELECT max(updated_at), count(*) FROM posts", |conn| {
+///         let mut stmt = conn.prepare("SELECT title FROM posts ORDER BY title")?;
+///         let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+///         rows.collect::<rusqlite::Result<Vec<_>>>()
+///     })
+/// };
+///
+/// let mut etag = Default::default();
+/// let res = titles().update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate()?, ["Hello"]);
+///
+/// assert!(titles().update(asset::Context::default(), &mut etag).is_same());
+///
+/// conn.execute("INSERT INTO posts VALUES ('World', 2)", [])?;
+/// assert!(titles().update(asset::Context::default(), &mut etag).is_modified());
+/// # Ok::<_, rusqlite::Error>(())
+/// ```
+pub fn query<C, F, T>(
+    connection: C,
+    version_sql: &str,
+    fetch: F,
+) -> db::Query<C, impl FnOnce(&C) -> rusqlite::Result<Digest> + '_, F>
+where
+    C: Borrow<Connection>,
+    F: FnOnce(&C) -> rusqlite::Result<T>,
+{
+    db::query(
+        connection,
+        move |conn: &C| version(conn.borrow(), version_sql),
+        fetch,
+    )
+}
+
+fn version(conn: &Connection, version_sql: &str) -> rusqlite::Result<Digest> {
+    let mut hasher = Sha256::new();
+    let mut stmt = conn.prepare_cached(version_sql)?;
+    let columns = stmt.column_count();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        for i in 0..columns {
+            match row.get_ref(i)? {
+                ValueRef::Null => hasher.write_bytes(&[0]),
+                ValueRef::Integer(n) => {
+                    hasher.write_bytes(&[1]);
+                    hasher.write_i64(n);
+                }
+                ValueRef::Real(n) => {
+                    hasher.write_bytes(&[2]);
+                    hasher.write_u64(n.to_bits());
+                }
+                ValueRef::Text(bytes) => {
+                    hasher.write_bytes(&[3]);
+                    hasher.write_usize_var(bytes.len());
+                    hasher.write_bytes(bytes);
+                }
+                ValueRef::Blob(bytes) => {
+                    hasher.write_bytes(&[4]);
+                    hasher.write_usize_var(bytes.len());
+                    hasher.write_bytes(bytes);
+                }
+            }
+        }
+    }
+    Ok(hasher.finish())
+}
+
+use crate::db;
+use crate::etag::Writer as _;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use core::borrow::Borrow;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
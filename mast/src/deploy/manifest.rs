@@ -0,0 +1,400 @@
+/// Write a machine-readable manifest of a set of built files,
+/// for consumption by web servers and CDN upload tools.
+///
+/// The `files` asset should output an iterator of `(key, path)` pairs,
+/// where each key is the path at which the file is served
+/// and each path is where the built file can be found on disk.
+/// The manifest is written as JSON to `out`, an object whose `files` field is an array
+/// with one object per file, sorted by key, with the fields:
+///
+/// - `path`: the key.
+/// - `sha256`: the SHA-256 digest of the file’s contents, in hexadecimal.
+/// - `size`: the size of the file in bytes.
+/// - `content_type`: the media type of the file,
+///   guessed from the key’s extension with [`mime::from_extension`].
+/// - `encodings`: an array of the precompressed variants of the file found next to it
+///   (`.br`, `.gz` and `.zst` files), each an object with the fields
+///   `encoding` (as in the `Content-Encoding` header), `path`, `sha256` and `size`.
+///
+/// Files are only hashed again when their size or modification time has changed,
+/// and the manifest is only rewritten when its contents change,
+/// or when it was modified or removed since it was last written.
+/// The output of this asset is whether the manifest was rewritten.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::deploy;
+/// use mast::Asset as _;
+/// use std::fs;
+/// use std::path::PathBuf;
+/// # use mast::{Asset, Delta, Tracked};
+/// # struct Files(Vec<(&'static str, PathBuf)>);
+/// # impl<'c> Asset<'c> for Files {
+/// #     type Etag = ();
+/// #     type Output = Vec<(&'static str, PathBuf)>;
+/// #     type Generator = Self;
+/// #     fn update(self, _: asset::Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+/// #         Delta::Same.track(self)
+/// #     }
+/// # }
+/// # impl asset::Generator for Files {
+/// #     type Output = Vec<(&'static str, PathBuf)>;
+/// #     fn generate(self) -> Self::Output { self.0 }
+/// # }
+/// # fn files(files: Vec<(&'static str, PathBuf)>) -> Files { Files(files) }
+/// # let dir = mast::fs::TempDirs::default().create()?;
+/// # let dir = dir.path();
+///
+/// fs::write(dir.join("index.html"), "<h1>Hi</h1>")?;
+/// fs::write(dir.join("index.html.gz"), "(compressed)")?;
+///
+/// let mut etag = Default::default();
+/// let cx = asset::Context::default();
+/// // `files` is some asset outputting `(key, path)` pairs.
+/// let site = || files(vec![("index.html", dir.join("index.html"))]);
+///
+/// let manifest = deploy::manifest(site(), dir.join("manifest.json"));
+/// assert!(manifest.update(cx, &mut etag).value.generate()?);
+/// let json = fs::read_to_string(dir.join("manifest.json"))?;
+/// assert!(json.contains(r#""content_type":"text/html; charset=utf-8""#));
+/// assert!(json.contains(r#""encoding":"gzip","path":"index.html.gz""#));
+///
+/// let manifest = deploy::manifest(site(), dir.join("manifest.json"));
+/// assert!(manifest.update(cx, &mut etag).is_same());
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn manifest<A, P: Into<PathBuf>>(files: A, out: P) -> Manifest<A> {
+    Manifest {
+        files,
+        out: out.into(),
+    }
+}
+
+/// Asset for [`manifest`].
+#[derive(Debug)]
+pub struct Manifest<A> {
+    files: A,
+    out: PathBuf,
+}
+
+impl<'c, A, K, P> Asset<'c> for Manifest<A>
+where
+    A: Asset<'c>,
+    A::Output: IntoIterator<Item = (K, P)>,
+    K: Into<String>,
+    P: Into<PathBuf>,
+{
+    type Etag = (A::Etag, State);
+    type Output = io::Result<bool>;
+    type Generator = Generator<'c, A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (files_etag, state) = etag;
+        let files = self.files.update(cx, files_etag);
+        let delta = files
+            .delta
+            .or(Delta::cmp(&state.complete, &true))
+            .or_else(|| Delta::cmp(&state.out, &Stamp::of(&self.out).ok()))
+            .or_else(|| {
+                let unchanged = state.entries.values().all(Entry::unchanged);
+                Delta::cmp(&unchanged, &true)
+            });
+        delta.track(Generator {
+            files: files.value,
+            out: self.out,
+            state,
+            delta,
+        })
+    }
+}
+
+/// Generator for [`Manifest`].
+#[derive(Debug)]
+pub struct Generator<'c, G> {
+    files: G,
+    out: PathBuf,
+    state: &'c mut State,
+    delta: Delta,
+}
+
+impl<G, K, P> asset::Generator for Generator<'_, G>
+where
+    G: asset::Generator,
+    G::Output: IntoIterator<Item = (K, P)>,
+    K: Into<String>,
+    P: Into<PathBuf>,
+{
+    type Output = io::Result<bool>;
+
+    fn generate(self) -> Self::Output {
+        let state = self.state;
+        if self.delta == Delta::Same {
+            return Ok(false);
+        }
+
+        state.complete = false;
+
+        let mut entries = BTreeMap::new();
+        for (key, source) in self.files.generate() {
+            let key = key.into();
+            let source = source.into();
+            let entry = match state.entries.remove(&key) {
+                Some(entry) if entry.source == source && entry.unchanged() => entry,
+                _ => Entry::hash(source)?,
+            };
+            entries.insert(key, entry);
+        }
+        state.entries = entries;
+
+        let written = write_if_changed(&self.out, to_json(&state.entries))?;
+        state.out = Some(Stamp::of(&self.out)?);
+        state.complete = true;
+        Ok(written)
+    }
+}
+
+/// The precompressed variants looked for next to each file:
+/// their encoding and the extension appended to the file’s path.
+const ENCODINGS: [(&str, &str); 3] = [("br", ".br"), ("gzip", ".gz"), ("zstd", ".zst")];
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+fn to_json(entries: &BTreeMap<String, Entry>) -> String {
+    let mut json = String::from("{\"files\":[");
+    for (i, (key, entry)) in entries.iter().enumerate() {
+        if i != 0 {
+            json.push(',');
+        }
+        json.push_str("{\"path\":");
+        write_json_str(&mut json, key);
+        write!(
+            json,
+            ",\"sha256\":\"{}\",\"size\":{},\"content_type\":\"{}\",\"encodings\":[",
+            entry.digest,
+            entry.stamp.len(),
+            mime::from_extension(key).unwrap_or(mime::OCTET_STREAM),
+        )
+        .unwrap();
+        for (i, variant) in entry.variants.iter().enumerate() {
+            if i != 0 {
+                json.push(',');
+            }
+            json.push_str("{\"encoding\":");
+            write_json_str(&mut json, &variant.encoding);
+            json.push_str(",\"path\":");
+            write_json_str(&mut json, &format!("{key}{}", variant.extension()));
+            write!(
+                json,
+                ",\"sha256\":\"{}\",\"size\":{}}}",
+                variant.digest,
+                variant.stamp.len(),
+            )
+            .unwrap();
+        }
+        json.push_str("]}");
+    }
+    json.push_str("]}\n");
+    json
+}
+
+/// The persistent state of a [`Manifest`] asset.
+#[derive(Debug, Default)]
+pub struct State {
+    entries: BTreeMap<String, Entry>,
+    out: Option<Stamp>,
+    complete: bool,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.entries.serialize(writer);
+        self.out.serialize(writer);
+        self.complete.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            entries: Etag::deserialize(reader)?,
+            out: Etag::deserialize(reader)?,
+            complete: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    source: PathBuf,
+    stamp: Stamp,
+    digest: Digest,
+    variants: Vec<Variant>,
+}
+
+impl Entry {
+    fn hash(source: PathBuf) -> io::Result<Self> {
+        let stamp = Stamp::of(&source)?;
+        let digest = hash_file(&source)?;
+        let mut variants = Vec::new();
+        for (encoding, extension) in ENCODINGS {
+            let path = with_extension(&source, extension);
+            let stamp = match Stamp::of(&path) {
+                Ok(stamp) => stamp,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            variants.push(Variant {
+                encoding: encoding.to_owned(),
+                stamp,
+                digest: hash_file(&path)?,
+            });
+        }
+        Ok(Self {
+            source,
+            stamp,
+            digest,
+            variants,
+        })
+    }
+
+    /// Whether the file and its variants have the same stamps as when they were hashed.
+    fn unchanged(&self) -> bool {
+        Stamp::of(&self.source).ok() == Some(self.stamp)
+            && ENCODINGS.iter().all(|&(encoding, extension)| {
+                let previous = self.variants.iter().find(|v| v.encoding == encoding);
+                Stamp::of(with_extension(&self.source, extension)).ok()
+                    == previous.map(|variant| variant.stamp)
+            })
+    }
+}
+
+impl Etag for Entry {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.source.serialize(writer);
+        self.stamp.serialize(writer);
+        self.digest.serialize(writer);
+        self.variants.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            source: Etag::deserialize(reader)?,
+            stamp: Etag::deserialize(reader)?,
+            digest: Etag::deserialize(reader)?,
+            variants: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Variant {
+    encoding: String,
+    stamp: Stamp,
+    digest: Digest,
+}
+
+impl Variant {
+    fn extension(&self) -> &'static str {
+        ENCODINGS
+            .iter()
+            .find(|(encoding, _)| *encoding == self.encoding)
+            .map_or("", |(_, extension)| extension)
+    }
+}
+
+impl Etag for Variant {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.encoding.serialize(writer);
+        self.stamp.serialize(writer);
+        self.digest.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            encoding: Etag::deserialize(reader)?,
+            stamp: Etag::deserialize(reader)?,
+            digest: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mast-test-{name}-{}", process::id()));
+        drop(fs::remove_dir_all(&dir));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn incremental() {
+        let dir = temp_dir("manifest");
+        let (page, out) = (dir.join("index.html"), dir.join("manifest.json"));
+        fs::write(&page, "<h1>Hi</h1>").unwrap();
+
+        let mut etag = Default::default();
+        let mut update = || {
+            let files = asset::constant(vec![("index.html", page.clone())]);
+            let tracked = super::manifest(files, &out).update(Context::default(), &mut etag);
+            (tracked.delta, tracked.value.generate().unwrap())
+        };
+        assert_eq!(update(), (Delta::Modified, true));
+        assert_eq!(update(), (Delta::Same, false));
+
+        // A removed manifest is written again.
+        fs::remove_file(&out).unwrap();
+        assert_eq!(update(), (Delta::Modified, true));
+        assert!(out.exists());
+        assert_eq!(update(), (Delta::Same, false));
+
+        // Changes to the files are picked up, including new precompressed variants.
+        fs::write(&page, "<h1>Hello</h1>").unwrap();
+        fs::write(dir.join("index.html.br"), "(compressed)").unwrap();
+        assert_eq!(update(), (Delta::Modified, true));
+        let json = fs::read_to_string(&out).unwrap();
+        assert!(json.contains(r#""size":14"#));
+        assert!(json.contains(r#""encoding":"br","path":"index.html.br""#));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::Asset as _;
+    use crate::Delta;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use std::vec;
+}
+
+use crate::asset;
+use crate::asset::Context;
+use crate::diagnostic::write_json_str;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::fs::hash_file;
+use crate::fs::write_if_changed;
+use crate::fs::Stamp;
+use crate::hash::Digest;
+use crate::mime;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt::Write as _;
+use std::borrow::ToOwned;
+use std::collections::BTreeMap;
+use std::format;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::string::String;
+use std::vec::Vec;
@@ -0,0 +1,147 @@
+//! Sink assets that publish generated files to their final destination.
+//!
+//! Remote object stores such as Amazon S3, S3-compatible services and Google Cloud Storage
+//! are supported through the [`Bucket`] trait,
+//! which is a thin abstraction over whichever client library you already use.
+//! The [`upload`] asset remembers the remote etag of every object it uploads,
+//! so subsequent builds upload only the files whose contents actually changed.
+//!
+//! Local directories are supported by [`rsync_like`],
+//! and [`manifest`] describes the built files for web servers and CDN upload tools.
+//!
+//! # Examples
+//!
+//! ```
+//! use mast::asset;
+//! use mast::deploy;
+//! use mast::asset::Generator as _;
+//! use mast::Asset as _;
+//! use std::cell::RefCell;
+//! use std::collections::BTreeMap;
+//! use std::convert::Infallible;
+//! # use mast::{Asset, Delta, Tracked};
+//! # struct Files(Vec<(&'static str, &'static str)>);
+//! # impl<'c> Asset<'c> for Files {
+//! #     type Etag = ();
+//! #     type Output = Vec<(&'static str, &'static str)>;
+//! #     type Generator = Self;
+//! #     fn update(self, _: asset::Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+//! #         Delta::Modified.track(self)
+//! #     }
+//! # }
+//! # impl asset::Generator for Files {
+//! #     type Output = Vec<(&'static str, &'static str)>;
+//! #     fn generate(self) -> Self::Output { self.0 }
+//! # }
+//! # fn files(files: Vec<(&'static str, &'static str)>) -> Files { Files(files) }
+//!
+//! #[derive(Default)]
+//! struct InMemoryBucket(RefCell<BTreeMap<String, Vec<u8>>>);
+//!
+//! impl deploy::Bucket for InMemoryBucket {
+//!     type Error = Infallible;
+//!     fn put(&self, key: &str, body: &[u8]) -> Result<String, Self::Error> {
+//!         self.0.borrow_mut().insert(key.to_owned(), body.to_owned());
+//!         Ok(mast::hash::Sha256::digest(body).to_string())
+//!     }
+//!     fn head(&self, key: &str) -> Result<Option<String>, Self::Error> {
+//!         let objects = self.0.borrow();
+//!         Ok(objects.get(key).map(|body| mast::hash::Sha256::digest(body).to_string()))
+//!     }
+//!     fn delete(&self, key: &str) -> Result<(), Self::Error> {
+//!         self.0.borrow_mut().remove(key);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let bucket = InMemoryBucket::default();
+//! let mut etag = Default::default();
+//!
+//! // `files` is some asset outputting `(key, contents)` pairs.
+//! let site = files(vec![("index.html", "<h1>Hi</h1>"), ("style.css", "h1 {}")]);
+//! let asset = deploy::upload(&bucket, site);
+//! let report = asset.update(asset::Context::default(), &mut etag).value.generate();
+//! assert_eq!(report.unwrap().transferred, ["index.html", "style.css"]);
+//!
+//! let site = files(vec![("index.html", "<h1>Hello</h1>")]);
+//! let asset = deploy::upload(&bucket, site).delete_removed(true);
+//! let report = asset.update(asset::Context::default(), &mut etag).value.generate();
+//! let report = report.unwrap();
+//! assert_eq!(report.transferred, ["index.html"]);
+//! assert_eq!(report.deleted, ["style.css"]);
+//! ```
+
+/// A remote object store, such as an S3 or Google Cloud Storage bucket.
+///
+/// Implement this for whichever client you use to talk to the store.
+/// Keys are the object names within the bucket,
+/// and the returned etags are opaque strings that change whenever the remote object does
+/// (both S3 and GCS return such a value from their upload and metadata endpoints).
+pub trait Bucket {
+    /// The error type of operations on this bucket.
+    type Error;
+
+    /// Create or overwrite the object named `key`,
+    /// returning the etag the store assigned to it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the upload could not be completed.
+    fn put(&self, key: &str, body: &[u8]) -> Result<String, Self::Error>;
+
+    /// Retrieve the current etag of the object named `key`,
+    /// or [`None`] if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the object’s metadata could not be retrieved.
+    fn head(&self, key: &str) -> Result<Option<String>, Self::Error>;
+
+    /// Delete the object named `key`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the object could not be deleted.
+    fn delete(&self, key: &str) -> Result<(), Self::Error>;
+}
+
+impl<B: ?Sized + Bucket> Bucket for &B {
+    type Error = B::Error;
+    fn put(&self, key: &str, body: &[u8]) -> Result<String, Self::Error> {
+        (**self).put(key, body)
+    }
+    fn head(&self, key: &str) -> Result<Option<String>, Self::Error> {
+        (**self).head(key)
+    }
+    fn delete(&self, key: &str) -> Result<(), Self::Error> {
+        (**self).delete(key)
+    }
+}
+
+mod upload;
+pub use upload::upload;
+pub use upload::Upload;
+
+mod rsync_like;
+pub use rsync_like::rsync_like;
+pub use rsync_like::Compare;
+pub use rsync_like::RsyncLike;
+
+mod manifest;
+pub use manifest::manifest;
+pub use manifest::Manifest;
+
+/// A summary of the work performed by a deployment asset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Report {
+    /// The files that were copied to the destination.
+    pub transferred: Vec<String>,
+    /// The files that were already up-to-date at the destination.
+    pub skipped: Vec<String>,
+    /// The files that were removed from the destination.
+    pub deleted: Vec<String>,
+}
+
+use std::string::String;
+use std::vec::Vec;
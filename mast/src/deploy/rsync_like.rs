@@ -0,0 +1,537 @@
+/// Mirror a set of files into a local directory, in the style of `rsync`.
+///
+/// The `src_manifest` asset should output an iterator of `(key, source path)` pairs,
+/// where each key is the relative path at which the file is placed inside `dest_dir`.
+/// Keys must be relative and must not contain `..` components.
+///
+/// A file is copied only if it is determined to differ from its copy in the destination,
+/// using the method chosen with [`RsyncLike::compare`].
+/// The output of this asset is a [`Report`] of the files that were copied, skipped and deleted.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::deploy;
+/// use mast::Asset as _;
+/// use std::fs;
+/// use std::path::PathBuf;
+/// # use mast::{Asset, Delta, Tracked};
+/// # struct Manifest(Vec<(&'static str, PathBuf)>);
+/// # impl<'c> Asset<'c> for Manifest {
+/// #     type Etag = ();
+/// #     type Output = Vec<(&'static str, PathBuf)>;
+/// #     type Generator = Self;
+/// #     fn update(self, _: asset::Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+/// #         Delta::Modified.track(self)
+/// #     }
+/// # }
+/// # impl asset::Generator for Manifest {
+/// #     type Output = Vec<(&'static str, PathBuf)>;
+/// #     fn generate(self) -> Self::Output { self.0 }
+/// # }
+/// # fn manifest(files: Vec<(&'static str, PathBuf)>) -> Manifest { Manifest(files) }
+/// # let dir = std::env::temp_dir().join(format!("mast-doctest-rsync-like-{}", std::process::id()));
+/// # fs::create_dir_all(&dir).unwrap();
+///
+/// let build = dir.join("build");
+/// let public = dir.join("public");
+/// fs::create_dir_all(&build)?;
+/// fs::write(build.join("index.html"), "<h1>Hi</h1>")?;
+///
+/// let mut etag = Default::default();
+/// // `manifest` is some asset outputting `(key, path)` pairs.
+/// let files = manifest(vec![("index.html", build.join("index.html"))]);
+/// let asset = deploy::rsync_like(files, &public);
+/// let report = asset.update(asset::Context::default(), &mut etag).value.generate()?;
+/// assert_eq!(report.transferred, ["index.html"]);
+/// assert_eq!(fs::read_to_string(public.join("index.html"))?, "<h1>Hi</h1>");
+///
+/// let files = manifest(vec![("index.html", build.join("index.html"))]);
+/// let asset = deploy::rsync_like(files, &public);
+/// let report = asset.update(asset::Context::default(), &mut etag).value.generate()?;
+/// assert_eq!(report.skipped, ["index.html"]);
+/// # fs::remove_dir_all(&dir).unwrap();
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn rsync_like<A, P: Into<PathBuf>>(src_manifest: A, dest_dir: P) -> RsyncLike<A> {
+    RsyncLike {
+        src_manifest,
+        dest_dir: dest_dir.into(),
+        compare: Compare::SizeMtime,
+        delete_removed: false,
+        options: WriteOptions::new(),
+    }
+}
+
+/// Asset for [`rsync_like`].
+#[derive(Debug)]
+pub struct RsyncLike<A> {
+    src_manifest: A,
+    dest_dir: PathBuf,
+    compare: Compare,
+    delete_removed: bool,
+    options: WriteOptions,
+}
+
+impl<A> RsyncLike<A> {
+    /// Set the method used to decide whether a file needs to be copied.
+    ///
+    /// Defaults to [`Compare::SizeMtime`].
+    #[must_use]
+    pub fn compare(mut self, compare: Compare) -> Self {
+        self.compare = compare;
+        self
+    }
+
+    /// Set whether files that were previously copied by this asset
+    /// but are no longer in the manifest
+    /// are deleted from the destination directory.
+    ///
+    /// Files in the destination directory that were not put there by this asset
+    /// are never deleted.
+    /// By default, nothing is deleted,
+    /// but removed files are remembered,
+    /// so that they are deleted once this is enabled.
+    #[must_use]
+    pub fn delete_removed(mut self, delete_removed: bool) -> Self {
+        self.delete_removed = delete_removed;
+        self
+    }
+
+    /// Set the options that copies are written with, such as their permissions.
+    ///
+    /// The options are part of the etag,
+    /// so changing them brings the permissions of every copy up to date.
+    /// By default, copies are written with [`WriteOptions::new`].
+    #[must_use]
+    pub fn write_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+/// The method used by [`rsync_like`] to decide whether a file needs to be copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compare {
+    /// A file is copied if the size or modification time of either the source or the destination
+    /// has changed since it was last copied.
+    /// This is fast, but is fooled by tools that rewrite modification times,
+    /// and causes everything to be copied on the first run.
+    SizeMtime,
+    /// A file is copied if the contents of the source and destination differ.
+    /// This requires reading both files in full
+    /// whenever their size or modification time has changed.
+    ContentHash,
+}
+
+impl<'c, A, K, P> Asset<'c> for RsyncLike<A>
+where
+    A: Asset<'c>,
+    A::Output: IntoIterator<Item = (K, P)>,
+    K: Into<String>,
+    P: Into<PathBuf>,
+{
+    type Etag = (A::Etag, State);
+    type Output = io::Result<Report>;
+    type Generator = Generator<'c, A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (manifest_etag, state) = etag;
+        let manifest = self.src_manifest.update(cx, manifest_etag);
+        let delta = manifest
+            .delta
+            .or(Delta::cmp(&state.complete, &true))
+            .or_else(|| Delta::cmp(&state.options, &self.options))
+            .or_else(|| {
+                let to_delete = self.delete_removed && !state.removed.is_empty();
+                Delta::cmp(&to_delete, &false)
+            })
+            .or_else(|| {
+                let unchanged = state.files.iter().all(|(key, file)| {
+                    Stamp::of(&file.source).ok() == Some(file.source_stamp)
+                        && Stamp::of(self.dest_dir.join(key)).ok() == Some(file.dest_stamp)
+                });
+                Delta::cmp(&unchanged, &true)
+            });
+        delta.track(Generator {
+            src_manifest: manifest.value,
+            dest_dir: self.dest_dir,
+            state,
+            delta,
+            compare: self.compare,
+            delete_removed: self.delete_removed,
+            options: self.options,
+        })
+    }
+}
+
+/// Generator for [`RsyncLike`].
+#[derive(Debug)]
+pub struct Generator<'c, G> {
+    src_manifest: G,
+    dest_dir: PathBuf,
+    state: &'c mut State,
+    delta: Delta,
+    compare: Compare,
+    delete_removed: bool,
+    options: WriteOptions,
+}
+
+impl<G, K, P> asset::Generator for Generator<'_, G>
+where
+    G: asset::Generator,
+    G::Output: IntoIterator<Item = (K, P)>,
+    K: Into<String>,
+    P: Into<PathBuf>,
+{
+    type Output = io::Result<Report>;
+
+    fn generate(self) -> Self::Output {
+        let state = self.state;
+        let mut report = Report::default();
+
+        if self.delta == Delta::Same {
+            report.skipped = state.files.keys().cloned().collect();
+            return Ok(report);
+        }
+
+        state.complete = false;
+
+        let mut seen = BTreeSet::new();
+        for (key, source) in self.src_manifest.generate() {
+            let key = key.into();
+            let source = source.into();
+            let dest = self.dest_dir.join(check_key(&key)?);
+
+            // Copy afresh files whose old permissions these options would not replace.
+            self.options.change_from(&state.options, &dest)?;
+            let source_stamp = Stamp::of(&source)?;
+            let dest_stamp = Stamp::of(&dest).ok();
+
+            let previous = state.files.get(&key).filter(|file| file.source == source);
+            let stamps_unchanged = previous.is_some_and(|file| {
+                file.source_stamp == source_stamp && Some(file.dest_stamp) == dest_stamp
+            });
+
+            let up_to_date = match self.compare {
+                Compare::SizeMtime => stamps_unchanged,
+                Compare::ContentHash => {
+                    stamps_unchanged
+                        || dest_stamp
+                            .is_some_and(|dest_stamp| dest_stamp.len() == source_stamp.len())
+                            && hash_file(&source)? == hash_file(&dest)?
+                }
+            };
+
+            let dest_stamp = if let (true, Some(dest_stamp)) = (up_to_date, dest_stamp) {
+                if state.options != self.options {
+                    // Changing permissions does not change the stamp.
+                    self.options.apply(&dest, Some(&source))?;
+                }
+                report.skipped.push(key.clone());
+                dest_stamp
+            } else {
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                make_writable(&dest)?;
+                fs::copy(&source, &dest)?;
+                self.options.apply(&dest, Some(&source))?;
+                report.transferred.push(key.clone());
+                Stamp::of(&dest)?
+            };
+
+            let file = File {
+                source,
+                source_stamp,
+                dest_stamp,
+            };
+            state.removed.remove(&key);
+            state.files.insert(key.clone(), file);
+            seen.insert(key);
+        }
+
+        let removed: Vec<String> = state
+            .files
+            .keys()
+            .filter(|key| !seen.contains(*key))
+            .cloned()
+            .collect();
+        for key in removed {
+            if let Some(file) = state.files.remove(&key) {
+                state.removed.insert(key, file);
+            }
+        }
+        if self.delete_removed {
+            while let Some((key, file)) = state.removed.pop_first() {
+                match fs::remove_file(self.dest_dir.join(&key)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                        state.removed.insert(key, file);
+                        return Err(e);
+                    }
+                    _ => {}
+                }
+                report.deleted.push(key);
+            }
+        }
+
+        state.options = self.options;
+        state.complete = true;
+        Ok(report)
+    }
+}
+
+fn check_key(key: &str) -> io::Result<&Path> {
+    let path = Path::new(key);
+    if path
+        .components()
+        .all(|c| matches!(c, path::Component::Normal(_)))
+    {
+        Ok(path)
+    } else {
+        let msg = format!("invalid destination key {key:?}");
+        Err(io::Error::new(io::ErrorKind::InvalidInput, msg))
+    }
+}
+
+/// The persistent state of an [`RsyncLike`] asset.
+#[derive(Debug, Default)]
+pub struct State {
+    files: BTreeMap<String, File>,
+    /// Files that were copied but have since been removed from the manifest,
+    /// and have not been deleted from the destination yet.
+    removed: BTreeMap<String, File>,
+    options: WriteOptions,
+    complete: bool,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.files.serialize(writer);
+        self.removed.serialize(writer);
+        self.options.serialize(writer);
+        self.complete.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            files: Etag::deserialize(reader)?,
+            removed: Etag::deserialize(reader)?,
+            options: Etag::deserialize(reader)?,
+            complete: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct File {
+    source: PathBuf,
+    source_stamp: Stamp,
+    dest_stamp: Stamp,
+}
+
+impl Etag for File {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.source.serialize(writer);
+        self.source_stamp.serialize(writer);
+        self.dest_stamp.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            source: Etag::deserialize(reader)?,
+            source_stamp: Etag::deserialize(reader)?,
+            dest_stamp: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[derive(Debug)]
+    struct Manifest(Vec<(&'static str, PathBuf)>);
+
+    impl<'c> Asset<'c> for Manifest {
+        type Etag = ();
+        type Output = Vec<(&'static str, PathBuf)>;
+        type Generator = Self;
+        fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+            Delta::Same.track(self)
+        }
+    }
+
+    impl asset::Generator for Manifest {
+        type Output = Vec<(&'static str, PathBuf)>;
+        fn generate(self) -> Self::Output {
+            self.0
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mast-test-{name}-{}", process::id()));
+        drop(fs::remove_dir_all(&dir));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn content_hash_and_delete() {
+        let dir = temp_dir("rsync-like");
+        let (src, dest) = (dir.join("src"), dir.join("dest"));
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a"), "a").unwrap();
+        fs::write(src.join("b"), "b").unwrap();
+        fs::create_dir_all(dest.join("nested")).unwrap();
+        fs::write(dest.join("nested/a"), "a").unwrap();
+
+        let mut etag = Default::default();
+        let mut run = |files: Vec<(&'static str, PathBuf)>| {
+            let asset = rsync_like(Manifest(files), &dest)
+                .compare(Compare::ContentHash)
+                .delete_removed(true);
+            let res = asset.update(Context::default(), &mut etag);
+            res.map(asset::Generator::generate).map(Result::unwrap)
+        };
+
+        let report = run(vec![("nested/a", src.join("a")), ("b", src.join("b"))]).value;
+        assert_eq!(report.skipped, ["nested/a"]);
+        assert_eq!(report.transferred, ["b"]);
+
+        // Nothing changed on disk and the manifest is the same.
+        assert!(run(vec![("nested/a", src.join("a"))]).is_same());
+
+        fs::write(dest.join("b"), "tampered").unwrap();
+        let res = run(vec![("nested/a", src.join("a"))]);
+        assert!(res.is_modified());
+        assert_eq!(res.value.deleted, ["b"]);
+        assert!(!dest.join("b").exists());
+
+        let asset = rsync_like(Manifest(vec![("../escape", src.join("a"))]), &dest);
+        let mut etag = Default::default();
+        let res = asset.update(Context::default(), &mut etag);
+        let err = asset::Generator::generate(res.value).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn delete_later() {
+        let dir = temp_dir("rsync-like-delete-later");
+        let (src, dest) = (dir.join("src"), dir.join("dest"));
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a"), "a").unwrap();
+        fs::write(src.join("b"), "b").unwrap();
+
+        let mut etag = Default::default();
+        let mut run = |files: Vec<(&'static str, PathBuf)>, delete_removed| {
+            // Report the manifest as modified whenever its length changes.
+            let version = u32::try_from(files.len()).unwrap();
+            let manifest = Manifest(files).version(version);
+            let asset = rsync_like(manifest, &dest).delete_removed(delete_removed);
+            let res = asset.update(Context::default(), &mut etag);
+            res.map(asset::Generator::generate).map(Result::unwrap)
+        };
+
+        run(vec![("a", src.join("a")), ("b", src.join("b"))], false);
+        let res = run(vec![("a", src.join("a"))], false);
+        assert!(res.value.deleted.is_empty());
+        assert!(dest.join("b").exists());
+        assert!(run(vec![("a", src.join("a"))], false).is_same());
+
+        // `b` was removed while deletion was disabled, but is still deleted once it is enabled.
+        let res = run(vec![("a", src.join("a"))], true);
+        assert!(res.is_modified());
+        assert_eq!(res.value.deleted, ["b"]);
+        assert!(!dest.join("b").exists());
+        assert!(run(vec![("a", src.join("a"))], true).is_same());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn write_options() {
+        let dir = temp_dir("rsync-like-mode");
+        let (src, dest) = (dir.join("src"), dir.join("dest"));
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a"), "a").unwrap();
+
+        let mut etag = Default::default();
+        let mut run = |options| {
+            let asset = rsync_like(Manifest(vec![("a", src.join("a"))]), &dest);
+            let res = asset
+                .write_options(options)
+                .update(Context::default(), &mut etag);
+            res.map(asset::Generator::generate).map(Result::unwrap)
+        };
+        let mode = || fs::metadata(dest.join("a")).unwrap().permissions().mode() & 0o777;
+
+        assert_eq!(
+            run(WriteOptions::new().mode(0o640)).value.transferred,
+            ["a"]
+        );
+        assert_eq!(mode(), 0o640);
+        assert!(run(WriteOptions::new().mode(0o640)).is_same());
+
+        // Only the permissions changed, so the file is not copied again.
+        let res = run(WriteOptions::new().mode(0o640).read_only(true));
+        assert!(res.is_modified());
+        assert_eq!(res.value.skipped, ["a"]);
+        assert_eq!(mode(), 0o440);
+
+        // Without a mode, the copy is made afresh to drop the old permissions.
+        let res = run(WriteOptions::new());
+        assert_eq!(res.value.transferred, ["a"]);
+        let source_mode = fs::metadata(src.join("a")).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(), source_mode);
+        assert!(run(WriteOptions::new()).is_same());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use super::rsync_like;
+    use super::Compare;
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::fs::WriteOptions;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Tracked;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::io;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt as _;
+    use std::path::PathBuf;
+    use std::process;
+    use std::vec;
+    use std::vec::Vec;
+}
+
+use super::Report;
+use crate::asset;
+use crate::asset::Context;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::fs::hash_file;
+use crate::fs::make_writable;
+use crate::fs::Stamp;
+use crate::fs::WriteOptions;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::format;
+use std::fs;
+use std::io;
+use std::path;
+use std::path::Path;
+use std::path::PathBuf;
+use std::string::String;
+use std::vec::Vec;
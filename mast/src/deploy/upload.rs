@@ -0,0 +1,437 @@
+/// Upload a set of files to a [`Bucket`].
+///
+/// The `files` asset should output an iterator of `(key, contents)` pairs.
+/// If a key appears more than once, only its last contents are uploaded.
+/// Each object is uploaded only if its contents changed since the last successful upload
+/// (or, with [`Upload::verify_remote`], if its remote etag no longer matches the stored one).
+///
+/// The output of this asset is a [`Report`] of the objects that were uploaded, skipped and deleted.
+/// If an operation on the bucket fails,
+/// the objects that were uploaded before the failure are remembered,
+/// and the rest are retried on the next build.
+pub fn upload<B: Bucket, A>(bucket: B, files: A) -> Upload<B, A> {
+    Upload {
+        bucket,
+        files,
+        delete_removed: false,
+        verify_remote: false,
+    }
+}
+
+/// Asset for [`upload`].
+pub struct Upload<B, A> {
+    bucket: B,
+    files: A,
+    delete_removed: bool,
+    verify_remote: bool,
+}
+
+impl<B, A> Upload<B, A> {
+    /// Set whether objects that were previously uploaded
+    /// but are no longer output by the `files` asset
+    /// are deleted from the bucket.
+    ///
+    /// By default, nothing is deleted,
+    /// but removed objects are remembered,
+    /// so that they are deleted once this is enabled.
+    /// Removed objects that are not deleted do not appear in the [`Report`].
+    #[must_use]
+    pub fn delete_removed(mut self, delete_removed: bool) -> Self {
+        self.delete_removed = delete_removed;
+        self
+    }
+
+    /// Set whether the remote etag of each unchanged object is checked using [`Bucket::head`],
+    /// so objects that were modified or deleted by a third party are uploaded again.
+    ///
+    /// By default, the bucket is assumed to be modified only by this asset.
+    /// Enabling this makes the asset always report itself as modified.
+    #[must_use]
+    pub fn verify_remote(mut self, verify_remote: bool) -> Self {
+        self.verify_remote = verify_remote;
+        self
+    }
+}
+
+impl<B: Debug, A: Debug> Debug for Upload<B, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upload")
+            .field("bucket", &self.bucket)
+            .field("files", &self.files)
+            .field("delete_removed", &self.delete_removed)
+            .field("verify_remote", &self.verify_remote)
+            .finish()
+    }
+}
+
+impl<'c, B, A, K, V> Asset<'c> for Upload<B, A>
+where
+    B: Bucket,
+    A: Asset<'c>,
+    A::Output: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: AsRef<[u8]>,
+{
+    type Etag = (A::Etag, State);
+    type Output = Result<Report, B::Error>;
+    type Generator = Generator<'c, B, A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (files_etag, state) = etag;
+        let files = self.files.update(cx, files_etag);
+        let delta = files
+            .delta
+            .or(Delta::cmp(&state.complete, &true))
+            .or_else(|| {
+                let to_delete = self.delete_removed && !state.removed.is_empty();
+                Delta::cmp(&to_delete, &false)
+            })
+            .or(if self.verify_remote {
+                Delta::Modified
+            } else {
+                Delta::Same
+            });
+        delta.track(Generator {
+            bucket: self.bucket,
+            files: files.value,
+            state,
+            delta,
+            delete_removed: self.delete_removed,
+            verify_remote: self.verify_remote,
+        })
+    }
+}
+
+/// Generator for [`Upload`].
+pub struct Generator<'c, B, G> {
+    bucket: B,
+    files: G,
+    state: &'c mut State,
+    delta: Delta,
+    delete_removed: bool,
+    verify_remote: bool,
+}
+
+impl<B: Debug, G: Debug> Debug for Generator<'_, B, G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Generator")
+            .field("bucket", &self.bucket)
+            .field("files", &self.files)
+            .field("state", &self.state)
+            .field("delta", &self.delta)
+            .field("delete_removed", &self.delete_removed)
+            .field("verify_remote", &self.verify_remote)
+            .finish()
+    }
+}
+
+impl<B, G, K, V> asset::Generator for Generator<'_, B, G>
+where
+    B: Bucket,
+    G: asset::Generator,
+    G::Output: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: AsRef<[u8]>,
+{
+    type Output = Result<Report, B::Error>;
+
+    fn generate(self) -> Self::Output {
+        let state = self.state;
+        let mut report = Report::default();
+
+        if self.delta == Delta::Same {
+            report.skipped = state.objects.keys().cloned().collect();
+            return Ok(report);
+        }
+
+        state.complete = false;
+
+        let files: BTreeMap<String, V> = self
+            .files
+            .generate()
+            .into_iter()
+            .map(|(key, contents)| (key.into(), contents))
+            .collect();
+
+        for (key, contents) in &files {
+            let contents = contents.as_ref();
+            let digest = Sha256::digest(contents);
+
+            if let Some(object) = state.removed.remove(key) {
+                state.objects.insert(key.clone(), object);
+            }
+            let up_to_date = match state.objects.get(key) {
+                Some(object) if object.digest == digest => {
+                    !self.verify_remote
+                        || self.bucket.head(key)?.as_ref() == Some(&object.remote_etag)
+                }
+                _ => false,
+            };
+
+            if up_to_date {
+                report.skipped.push(key.clone());
+            } else {
+                let remote_etag = self.bucket.put(key, contents)?;
+                let object = Object {
+                    digest,
+                    remote_etag,
+                };
+                state.objects.insert(key.clone(), object);
+                report.transferred.push(key.clone());
+            }
+        }
+
+        let removed: Vec<String> = state
+            .objects
+            .keys()
+            .filter(|key| !files.contains_key(*key))
+            .cloned()
+            .collect();
+        for key in removed {
+            if let Some(object) = state.objects.remove(&key) {
+                state.removed.insert(key, object);
+            }
+        }
+        if self.delete_removed {
+            while let Some((key, object)) = state.removed.pop_first() {
+                if let Err(e) = self.bucket.delete(&key) {
+                    state.removed.insert(key, object);
+                    return Err(e);
+                }
+                report.deleted.push(key);
+            }
+        }
+
+        state.complete = true;
+        Ok(report)
+    }
+}
+
+/// The persistent state of an [`Upload`] asset.
+#[derive(Debug, Default)]
+pub struct State {
+    objects: BTreeMap<String, Object>,
+    /// Objects that were uploaded but are no longer output by the `files` asset,
+    /// and have not yet been deleted.
+    removed: BTreeMap<String, Object>,
+    complete: bool,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.objects.serialize(writer);
+        self.removed.serialize(writer);
+        self.complete.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            objects: Etag::deserialize(reader)?,
+            removed: Etag::deserialize(reader)?,
+            complete: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Object {
+    digest: Digest,
+    remote_etag: String,
+}
+
+impl Etag for Object {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.digest.serialize(writer);
+        self.remote_etag.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            digest: Etag::deserialize(reader)?,
+            remote_etag: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[derive(Debug, Default)]
+    struct FlakyBucket {
+        objects: RefCell<BTreeMap<String, Vec<u8>>>,
+        puts_before_failure: Cell<Option<usize>>,
+    }
+
+    impl Bucket for FlakyBucket {
+        type Error = &'static str;
+        fn put(&self, key: &str, body: &[u8]) -> Result<String, Self::Error> {
+            if let Some(remaining) = self.puts_before_failure.get() {
+                if remaining == 0 {
+                    return Err("injected failure");
+                }
+                self.puts_before_failure.set(Some(remaining - 1));
+            }
+            self.objects
+                .borrow_mut()
+                .insert(key.to_owned(), body.to_owned());
+            Ok(Sha256::digest(body).to_string())
+        }
+        fn head(&self, key: &str) -> Result<Option<String>, Self::Error> {
+            let objects = self.objects.borrow();
+            Ok(objects
+                .get(key)
+                .map(|body| Sha256::digest(body).to_string()))
+        }
+        fn delete(&self, key: &str) -> Result<(), Self::Error> {
+            self.objects.borrow_mut().remove(key);
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Files(&'static [(&'static str, &'static str)]);
+
+    impl<'c> Asset<'c> for Files {
+        type Etag = ();
+        type Output = Vec<(&'static str, &'static str)>;
+        type Generator = Self;
+        fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+            Delta::Modified.track(self)
+        }
+    }
+
+    impl asset::Generator for Files {
+        type Output = Vec<(&'static str, &'static str)>;
+        fn generate(self) -> Self::Output {
+            self.0.to_vec()
+        }
+    }
+
+    fn run(
+        bucket: &FlakyBucket,
+        etag: &mut <Upload<&FlakyBucket, Files> as Asset<'_>>::Etag,
+        files: Files,
+        configure: impl FnOnce(Upload<&FlakyBucket, Files>) -> Upload<&FlakyBucket, Files>,
+    ) -> Tracked<Result<Report, &'static str>> {
+        let asset = configure(upload(bucket, files));
+        asset
+            .update(Context::default(), etag)
+            .map(asset::Generator::generate)
+    }
+
+    #[test]
+    fn resumes_after_failure() {
+        const FILES: &[(&str, &str)] = &[("a", "1"), ("b", "2"), ("c", "3")];
+        let bucket = FlakyBucket::default();
+        let mut etag = Default::default();
+
+        bucket.puts_before_failure.set(Some(1));
+        let res = run(&bucket, &mut etag, Files(FILES), |a| a);
+        assert_eq!(res.value, Err("injected failure"));
+
+        bucket.puts_before_failure.set(None);
+        let report = run(&bucket, &mut etag, Files(FILES), |a| a).value.unwrap();
+        assert_eq!(report.transferred, ["b", "c"]);
+        assert_eq!(report.skipped, ["a"]);
+    }
+
+    #[test]
+    fn verify_and_delete() {
+        let bucket = FlakyBucket::default();
+        let mut etag = Default::default();
+
+        run(&bucket, &mut etag, Files(&[("a", "1"), ("b", "2")]), |a| a)
+            .value
+            .unwrap();
+        bucket
+            .objects
+            .borrow_mut()
+            .insert("a".to_owned(), b"changed".to_vec());
+
+        let res = run(&bucket, &mut etag, Files(&[("a", "1")]), |a| {
+            a.verify_remote(true).delete_removed(true)
+        });
+        assert!(res.is_modified());
+        let report = res.value.unwrap();
+        assert_eq!(report.transferred, ["a"]);
+        assert_eq!(report.deleted, ["b"]);
+        assert_eq!(bucket.objects.borrow()["a"], b"1");
+        assert!(!bucket.objects.borrow().contains_key("b"));
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let bucket = FlakyBucket::default();
+        let mut etag = Default::default();
+
+        // A second upload of `a` would fail.
+        bucket.puts_before_failure.set(Some(1));
+        let report = run(&bucket, &mut etag, Files(&[("a", "1"), ("a", "2")]), |a| a)
+            .value
+            .unwrap();
+        assert_eq!(report.transferred, ["a"]);
+        assert_eq!(bucket.objects.borrow()["a"], b"2");
+    }
+
+    #[test]
+    fn removed_without_deleting() {
+        let bucket = FlakyBucket::default();
+        let mut etag = Default::default();
+
+        run(&bucket, &mut etag, Files(&[("a", "1"), ("b", "2")]), |a| a)
+            .value
+            .unwrap();
+
+        let report = run(&bucket, &mut etag, Files(&[("a", "1")]), |a| a)
+            .value
+            .unwrap();
+        assert_eq!(report.skipped, ["a"]);
+        assert!(report.deleted.is_empty());
+        assert!(bucket.objects.borrow().contains_key("b"));
+
+        // `b` was removed while deletion was disabled, but is still deleted once it is enabled.
+        let report = run(&bucket, &mut etag, Files(&[("a", "1")]), |a| {
+            a.delete_removed(true)
+        })
+        .value
+        .unwrap();
+        assert_eq!(report.skipped, ["a"]);
+        assert_eq!(report.deleted, ["b"]);
+        assert!(!bucket.objects.borrow().contains_key("b"));
+    }
+
+    use super::upload;
+    use super::Upload;
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::deploy::Bucket;
+    use crate::deploy::Report;
+    use crate::hash::Sha256;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Tracked;
+    use core::cell::Cell;
+    use core::cell::RefCell;
+    use std::borrow::ToOwned;
+    use std::collections::BTreeMap;
+    use std::string::String;
+    use std::string::ToString;
+    use std::vec::Vec;
+}
+
+use super::Bucket;
+use super::Report;
+use crate::asset;
+use crate::asset::Context;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::collections::BTreeMap;
+use std::string::String;
+use std::vec::Vec;
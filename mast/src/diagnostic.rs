@@ -0,0 +1,374 @@
+//! Warnings and errors reported by assets, such as broken links or template errors.
+//!
+//! Assets [emit] [`Diagnostic`]s to the [`Diagnostics`] in the [`Context`],
+//! which aggregates them so that the driver of the build can print them once it finishes,
+//! either [human-readably](Diagnostic#impl-Display-for-Diagnostic)
+//! or as [JSON](Diagnostic::to_json) for editors and CI.
+//!
+//! # Examples
+//!
+//! ```
+//! use mast::asset;
+//! use mast::diagnostic;
+//! use mast::diagnostic::Diagnostic;
+//! use mast::diagnostic::Location;
+//!
+//! let values = (diagnostic::Diagnostics::new(),);
+//! let cx = asset::Context::from_tuple(&values);
+//!
+//! let location = Location::new("content/post.md").line(3).column(7);
+//! diagnostic::emit(cx, Diagnostic::warning("broken link to `/pots`").at(location));
+//!
+//! let diagnostics = cx.get::<diagnostic::Diagnostics>().take();
+//! assert_eq!(diagnostics[0].to_string(), "content/post.md:3:7: warning: broken link to `/pots`");
+//! assert_eq!(
+//!     diagnostics[0].to_json(),
+//!     r#"{"severity":"warning","message":"broken link to `/pots`","file":"content/post.md","line":3,"column":7}"#,
+//! );
+//! ```
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Additional information, such as the location of a related definition.
+    Note,
+    /// Something is probably wrong, but the build can still succeed.
+    Warning,
+    /// Something is wrong, and the build should fail.
+    Error,
+}
+
+impl Severity {
+    /// The lowercase name of the severity, as used in messages.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Note => "note",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A position in a source file that a [`Diagnostic`] refers to.
+///
+/// Lines and columns are one-based.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Location {
+    /// The file.
+    pub file: PathBuf,
+    /// The line in the file, if known.
+    pub line: Option<u32>,
+    /// The column in the line, if known.
+    pub column: Option<u32>,
+}
+
+impl Location {
+    /// Refer to a whole file.
+    #[must_use]
+    pub fn new<P: Into<PathBuf>>(file: P) -> Self {
+        Self {
+            file: file.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    /// Set the line.
+    #[must_use]
+    pub fn line(self, line: u32) -> Self {
+        Self {
+            line: Some(line),
+            ..self
+        }
+    }
+
+    /// Set the column.
+    #[must_use]
+    pub fn column(self, column: u32) -> Self {
+        Self {
+            column: Some(column),
+            ..self
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+            if let Some(column) = self.column {
+                write!(f, ":{column}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A warning or error reported by an asset.
+///
+/// The [`Display`] implementation formats it like a compiler message,
+/// `file:line:column: severity: message`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    /// How serious the diagnostic is.
+    pub severity: Severity,
+    /// What is wrong.
+    pub message: String,
+    /// Where it is wrong, if it relates to a source file.
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    /// Construct a diagnostic without a location.
+    #[must_use]
+    pub fn new<S: Into<String>>(severity: Severity, message: S) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    /// Construct an [error](Severity::Error) without a location.
+    #[must_use]
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    /// Construct a [warning](Severity::Warning) without a location.
+    #[must_use]
+    pub fn warning<S: Into<String>>(message: S) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    /// Construct a [note](Severity::Note) without a location.
+    #[must_use]
+    pub fn note<S: Into<String>>(message: S) -> Self {
+        Self::new(Severity::Note, message)
+    }
+
+    /// Set the location the diagnostic refers to.
+    #[must_use]
+    pub fn at(self, location: Location) -> Self {
+        Self {
+            location: Some(location),
+            ..self
+        }
+    }
+
+    /// Format the diagnostic as a single-line JSON object.
+    ///
+    /// The object has the fields `severity`, `message`, `file`, `line` and `column`,
+    /// the last three of which are `null` when unknown.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"severity\":");
+        write_json_str(&mut json, self.severity.as_str());
+        json.push_str(",\"message\":");
+        write_json_str(&mut json, &self.message);
+        json.push_str(",\"file\":");
+        match &self.location {
+            Some(location) => write_json_str(&mut json, &location.file.to_string_lossy()),
+            None => json.push_str("null"),
+        }
+        let location = self.location.as_ref();
+        for (key, value) in [
+            ("line", location.and_then(|l| l.line)),
+            ("column", location.and_then(|l| l.column)),
+        ] {
+            match value {
+                Some(value) => write!(json, ",\"{key}\":{value}").unwrap(),
+                None => write!(json, ",\"{key}\":null").unwrap(),
+            }
+        }
+        json.push('}');
+        json
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{location}: ")?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+pub(crate) fn write_json_str(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => write!(json, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// How the driver of a build responds to failures,
+/// which can be placed in the [`Context`].
+///
+/// The default policy fails fast on the first error and lets warnings pass.
+///
+/// # Examples
+///
+/// ```
+/// use mast::diagnostic::FailurePolicy;
+/// use mast::diagnostic::Severity;
+///
+/// let policy = FailurePolicy::new().keep_going(true).warnings_as_errors(true);
+/// assert!(policy.fails(Severity::Warning));
+/// assert!(!policy.fails(Severity::Note));
+/// assert!(!FailurePolicy::new().fails(Severity::Warning));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FailurePolicy {
+    /// Whether to continue building what does not depend on a failed asset,
+    /// collecting every failure instead of stopping at the first.
+    pub keep_going: bool,
+    /// Whether [warnings](Severity::Warning) fail the build like errors do.
+    pub warnings_as_errors: bool,
+    /// Whether panics in user code, such as pipeline plugins,
+    /// are caught and reported as errors of the asset they occurred in
+    /// instead of unwinding through the build.
+    pub catch_panics: bool,
+}
+
+impl FailurePolicy {
+    /// Construct the default policy.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`Self::keep_going`].
+    #[must_use]
+    pub fn keep_going(self, keep_going: bool) -> Self {
+        Self { keep_going, ..self }
+    }
+
+    /// Set [`Self::warnings_as_errors`].
+    #[must_use]
+    pub fn warnings_as_errors(self, warnings_as_errors: bool) -> Self {
+        Self {
+            warnings_as_errors,
+            ..self
+        }
+    }
+
+    /// Set [`Self::catch_panics`].
+    #[must_use]
+    pub fn catch_panics(self, catch_panics: bool) -> Self {
+        Self {
+            catch_panics,
+            ..self
+        }
+    }
+
+    /// Whether a diagnostic of the given severity fails the build under this policy.
+    #[must_use]
+    pub fn fails(&self, severity: Severity) -> bool {
+        match severity {
+            Severity::Error => true,
+            Severity::Warning => self.warnings_as_errors,
+            Severity::Note => false,
+        }
+    }
+}
+
+/// The collector of [`Diagnostic`]s, which can be placed in the [`Context`].
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    diagnostics: Mutex<Vec<Diagnostic>>,
+}
+
+impl Diagnostics {
+    /// Construct an empty collector.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a diagnostic to the collector.
+    pub fn push(&self, diagnostic: Diagnostic) {
+        lock(&self.diagnostics).push(diagnostic);
+    }
+
+    /// Remove and return all the diagnostics collected so far, in the order they were emitted.
+    #[must_use]
+    pub fn take(&self) -> Vec<Diagnostic> {
+        mem::take(&mut *lock(&self.diagnostics))
+    }
+}
+
+/// Report a diagnostic to the [`Diagnostics`] in the context.
+///
+/// If there is no `Diagnostics` in the context,
+/// the diagnostic is printed to standard error instead.
+pub fn emit(cx: Context<'_>, diagnostic: Diagnostic) {
+    match cx.try_get::<Diagnostics>() {
+        Some(diagnostics) => diagnostics.push(diagnostic),
+        None => std::eprintln!("{diagnostic}"),
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn json() {
+        let diagnostic = Diagnostic::error("bad \"front\\matter\"\n\u{1}");
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: bad \"front\\matter\"\n\u{1}"
+        );
+        assert_eq!(
+            diagnostic.to_json(),
+            r#"{"severity":"error","message":"bad \"front\\matter\"\n\u0001","file":null,"line":null,"column":null}"#
+        );
+
+        let diagnostic = Diagnostic::note("defined here").at(Location::new("a.toml").line(2));
+        assert_eq!(diagnostic.to_string(), "a.toml:2: note: defined here");
+        assert_eq!(
+            diagnostic.to_json(),
+            r#"{"severity":"note","message":"defined here","file":"a.toml","line":2,"column":null}"#
+        );
+    }
+
+    use super::Diagnostic;
+    use super::Location;
+    use std::string::ToString as _;
+}
+
+use crate::asset::Context;
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Write as _;
+use core::mem;
+use std::path::PathBuf;
+use std::string::String;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::vec::Vec;
@@ -0,0 +1,277 @@
+//! Directory trees embedded in the binary.
+//!
+//! [`generate`] is called from a build script to walk a directory
+//! and write Rust source for a [`Dir`] containing every file in it,
+//! read with [`include_bytes!`] and hashed at build time.
+//! The program then includes that source,
+//! and each embedded [`File`] is an asset whose etag is its content hash,
+//! so pipeline code that reads from the filesystem with [`fs::bytes`](crate::fs::bytes)
+//! can run against the embedded files instead,
+//! and unchanged files are still skipped when the binary is rebuilt.
+//!
+//! # Examples
+//!
+//! In `build.rs`:
+//!
+//! ```no_run
+//! let out = std::path::Path::new(&std::env::var_os("OUT_DIR").unwrap()).join("static.rs");
+//! mast::embed::generate("static", out).unwrap();
+//! ```
+//!
+//! In the program:
+//!
+//! ```ignore
+//! static STATIC: mast::embed::Dir = include!(concat!(env!("OUT_DIR"), "/static.rs"));
+//!
+//! for file in STATIC.files() {
+//!     let output = file.map(|contents| write(file.path(), contents));
+//!     // …
+//! }
+//! ```
+
+/// A directory tree embedded in the binary, as generated by [`generate`].
+#[derive(Debug, Clone, Copy)]
+pub struct Dir {
+    files: &'static [File],
+}
+
+impl Dir {
+    /// Construct a directory from its files, sorted by path.
+    ///
+    /// This is usually called by the code written by [`generate`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::embed::Dir;
+    /// use mast::embed::File;
+    /// use mast::hash::Digest;
+    ///
+    /// static DIR: Dir = Dir::new(&[
+    ///     File::new("css/style.css", b"body {}", Digest([0; 32])),
+    ///     File::new("index.html", b"<h1>Hi</h1>", Digest([1; 32])),
+    /// ]);
+    ///
+    /// assert_eq!(DIR.files().len(), 2);
+    /// assert_eq!(DIR.get("index.html").unwrap().contents(), b"<h1>Hi</h1>");
+    /// assert!(DIR.get("missing").is_none());
+    /// ```
+    #[must_use]
+    pub const fn new(files: &'static [File]) -> Self {
+        Self { files }
+    }
+
+    /// The files in the tree, sorted by path.
+    #[must_use]
+    pub const fn files(&self) -> &'static [File] {
+        self.files
+    }
+
+    /// Look up a file by its path relative to the root of the tree.
+    #[must_use]
+    pub fn get(&self, path: &str) -> Option<&'static File> {
+        let files = self.files;
+        files
+            .binary_search_by(|file| file.path.cmp(path))
+            .ok()
+            .map(|i| &files[i])
+    }
+}
+
+/// A file embedded in the binary.
+///
+/// As an asset, its etag is the hash of its contents computed at build time,
+/// and its output is its contents.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::embed::File;
+/// use mast::hash::Digest;
+/// use mast::Asset as _;
+///
+/// let file = File::new("index.html", b"<h1>Hi</h1>", Digest([1; 32]));
+///
+/// let mut etag = Digest::default();
+/// let res = file.update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate(), b"<h1>Hi</h1>");
+/// assert!(file.update(asset::Context::default(), &mut etag).is_same());
+///
+/// let changed = File::new("index.html", b"<h1>Hello</h1>", Digest([2; 32]));
+/// assert!(changed.update(asset::Context::default(), &mut etag).is_modified());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct File {
+    path: &'static str,
+    contents: &'static [u8],
+    digest: Digest,
+}
+
+impl File {
+    /// Construct a file from its path, its contents
+    /// and the [`Sha256`](crate::hash::Sha256) digest of its contents.
+    ///
+    /// This is usually called by the code written by [`generate`].
+    #[must_use]
+    pub const fn new(path: &'static str, contents: &'static [u8], digest: Digest) -> Self {
+        Self {
+            path,
+            contents,
+            digest,
+        }
+    }
+
+    /// The path of the file relative to the root of the tree,
+    /// with components separated by `/`.
+    #[must_use]
+    pub const fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// The contents of the file.
+    #[must_use]
+    pub const fn contents(&self) -> &'static [u8] {
+        self.contents
+    }
+
+    /// The digest of the file’s contents.
+    #[must_use]
+    pub const fn digest(&self) -> Digest {
+        self.digest
+    }
+}
+
+impl<'c> Asset<'c> for File {
+    type Etag = Digest;
+    type Output = &'static [u8];
+    type Generator = Generator;
+
+    fn update(self, _: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let delta = Delta::cmp(etag, &self.digest);
+        *etag = self.digest;
+        delta.track(Generator {
+            contents: self.contents,
+        })
+    }
+}
+
+/// Generator for [`File`].
+#[derive(Debug)]
+pub struct Generator {
+    contents: &'static [u8],
+}
+
+impl asset::Generator for Generator {
+    type Output = &'static [u8];
+
+    fn generate(self) -> Self::Output {
+        self.contents
+    }
+}
+
+/// Write Rust source for a [`Dir`] expression embedding every file under `root` to `out`.
+///
+/// This is meant to be called from a build script:
+/// it prints `cargo:rerun-if-changed` directives for `root` and every directory within it,
+/// so that the build script runs again when files are added or removed.
+/// Files are embedded with [`include_bytes!`] by absolute path,
+/// and symbolic links are followed.
+///
+/// # Errors
+///
+/// Fails if the tree could not be read, contains a path that is not valid UTF-8,
+/// or the output could not be written.
+pub fn generate<P, Q>(root: P, out: Q) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let root = fs::canonicalize(root)?;
+    let mut files = Vec::new();
+    walk(&root, &mut String::new(), &mut files)?;
+    files.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut source = String::from("::mast::embed::Dir::new(&[\n");
+    for (path, absolute) in files {
+        let digest = hash_file(&absolute)?;
+        let absolute = absolute
+            .to_str()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "path is not UTF-8"))?;
+        let _ = writeln!(
+            source,
+            "    ::mast::embed::File::new({path:?}, include_bytes!({absolute:?}), ::mast::hash::Digest({:?})),",
+            digest.0,
+        );
+    }
+    source.push_str("])\n");
+    write_if_changed(out, source)?;
+    Ok(())
+}
+
+fn walk(dir: &Path, prefix: &mut String, files: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    std::println!("cargo:rerun-if-changed={}", dir.display());
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name
+            .to_str()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "file name is not UTF-8"))?;
+        let len = prefix.len();
+        prefix.push_str(name);
+        let path = entry.path();
+        if fs::metadata(&path)?.is_dir() {
+            prefix.push('/');
+            walk(&path, prefix, files)?;
+        } else {
+            files.push((prefix.clone(), path));
+        }
+        prefix.truncate(len);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn generate() {
+        let dir = TempDirs::default().create().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::write(root.join("index.html"), "<h1>Hi</h1>").unwrap();
+        fs::write(root.join("css/style.css"), "body {}").unwrap();
+
+        let out = dir.path().join("out.rs");
+        super::generate(&root, &out).unwrap();
+        let source = fs::read_to_string(&out).unwrap();
+
+        let css = source.find("\"css/style.css\"").unwrap();
+        let html = source.find("\"index.html\"").unwrap();
+        assert!(css < html);
+        let digest = Sha256::digest(b"body {}");
+        assert!(source.contains(&format!("Digest({:?})", digest.0)));
+    }
+
+    use crate::fs::TempDirs;
+    use crate::hash::Sha256;
+    use std::format;
+    use std::fs;
+}
+
+use crate::asset;
+use crate::asset::Context;
+use crate::fs::hash_file;
+use crate::fs::write_if_changed;
+use crate::hash::Digest;
+use crate::Asset;
+use crate::Delta;
+use crate::Tracked;
+use core::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::string::String;
+use std::vec::Vec;
@@ -10,23 +10,23 @@
 /// Implementations of this trait are assumed to uphold the following invariants:
 ///
 /// - The serialized forms of etags must be byte-for-byte equal
-///     only if the data the etags represent is equal.
+///   only if the data the etags represent is equal.
 /// - If the etags implement [`PartialEq`], two etags must compare equal
-///     if and only if their serialized forms are byte-for-byte equal.
+///   if and only if their serialized forms are byte-for-byte equal.
 /// - Serializing and then deserializing an etag
-///     must successfully result in the same etag.
-///     - Note that the converse is not always true:
-///         deserializing and then serializing a byte sequence as the etag
-///         does not necessarily produce the same byte sequence.
+///   must successfully result in the same etag.
+///   - Note that the converse is not always true:
+///     deserializing and then serializing a byte sequence as the etag
+///     does not necessarily produce the same byte sequence.
 /// - Serialization must produce an architecture-independent format.
 /// - Deserialization must not replace the [`Reader`] with a different one.
 /// - The exact bytes produced by serialization are not considered to be part of the public API,
-///     but it *is* a breaking change if old data previously returned by `serialize`
-///     now deserializes to a different thing or fails to deserialize.
-///     It is *not* a breaking change to allow data that previously failed to deserialize
-///     to successfully deserialize,
-///     or to disallow data that previously successfully deserialized
-///     but could not have been returned from `serialize`.
+///   but it *is* a breaking change if old data previously returned by `serialize`
+///   now deserializes to a different thing or fails to deserialize.
+///   It is *not* a breaking change to allow data that previously failed to deserialize
+///   to successfully deserialize,
+///   or to disallow data that previously successfully deserialized
+///   but could not have been returned from `serialize`.
 pub trait Etag: 'static + Sized + Debug + Default {
     /// Serialize the etag into its architecture-independent binary format.
     fn serialize<W: ?Sized + Writer>(&self, writer: &mut W);
@@ -140,6 +140,175 @@ impl Display for TrailingBytes {
 #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
 impl std::error::Error for TrailingBytes {}
 
+/// Compute an etag of the values of the given environment variables in the current process.
+///
+/// Only the named variables contribute,
+/// so the etag changes exactly when one of them is set, unset or changed.
+/// The order of the keys and any duplicates are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use mast::etag;
+///
+/// let before = etag::of_env(["MAST_DOCTEST_CC", "MAST_DOCTEST_CFLAGS"]);
+/// std::env::set_var("MAST_DOCTEST_UNRELATED", "1");
+/// assert_eq!(etag::of_env(["MAST_DOCTEST_CFLAGS", "MAST_DOCTEST_CC"]), before);
+/// std::env::set_var("MAST_DOCTEST_CC", "clang");
+/// assert_ne!(etag::of_env(["MAST_DOCTEST_CC", "MAST_DOCTEST_CFLAGS"]), before);
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub fn of_env<I>(keys: I) -> crate::hash::Digest
+where
+    I: IntoIterator,
+    I::Item: AsRef<std::ffi::OsStr>,
+{
+    let vars: alloc::collections::BTreeMap<std::ffi::OsString, Option<std::ffi::OsString>> = keys
+        .into_iter()
+        .map(|key| (key.as_ref().to_os_string(), std::env::var_os(key)))
+        .collect();
+    let mut hasher = crate::hash::Sha256::new();
+    vars.serialize(&mut hasher);
+    hasher.finish()
+}
+
+/// An etag paired with a salt, for invalidating persisted state wholesale.
+///
+/// Drivers that save etags between runs can store a `Salted` etag instead of a bare one
+/// and call [`unsalt`](Self::unsalt) after loading it:
+/// if the salt differs from the one the etag was saved with,
+/// the etag is replaced by its default and so every asset is considered modified.
+/// Salting with something that identifies the build logic,
+/// such as [`exe_modified`](crate::time::exe_modified),
+/// forces a full rebuild whenever that logic changes.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use mast::etag::Salted;
+/// use mast::hash::Sha256;
+/// use mast::Etag as _;
+///
+/// let v1 = Sha256::digest(b"v1");
+/// let bytes = Salted::new(v1, 37_u32).to_vec();
+///
+/// let loaded = Salted::<u32>::from_bytes(&bytes).unwrap();
+/// assert_eq!(loaded.clone().unsalt(&v1), 37);
+/// assert_eq!(loaded.unsalt(&Sha256::digest(b"v2")), 0);
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Salted<E> {
+    /// The salt the etag was saved with.
+    pub salt: crate::hash::Digest,
+    /// The etag itself.
+    pub etag: E,
+}
+
+impl<E> Salted<E> {
+    /// Pair an etag with a salt.
+    #[must_use]
+    pub const fn new(salt: crate::hash::Digest, etag: E) -> Self {
+        Self { salt, etag }
+    }
+
+    /// Obtain the etag if it was saved with `salt`, or the default etag otherwise.
+    #[must_use]
+    pub fn unsalt(self, salt: &crate::hash::Digest) -> E
+    where
+        E: Default,
+    {
+        if self.salt == *salt {
+            self.etag
+        } else {
+            E::default()
+        }
+    }
+}
+
+impl<E: Etag> Etag for Salted<E> {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.salt.serialize(writer);
+        self.etag.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            salt: Etag::deserialize(reader)?,
+            etag: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+/// An etag in the form of an HTTP `ETag` header value,
+/// for serving the outputs of assets with conditional requests.
+///
+/// The header value is the SHA-256 digest of the etag’s serialized form,
+/// in hexadecimal and quoted as a strong entity tag;
+/// it is obtained with the [`Display`] implementation.
+///
+/// # Examples
+///
+/// ```
+/// use mast::etag::HttpEtag;
+/// use mast::hash::Sha256;
+///
+/// let etag = HttpEtag::of(&Sha256::digest(b"<h1>Hi</h1>"));
+/// let header = etag.to_string();
+/// assert!(header.starts_with('"') && header.ends_with('"'));
+///
+/// // A client revalidating its cached copy gets a `304 Not Modified`.
+/// assert!(etag.matches(&header));
+/// assert!(etag.matches(&format!("W/\"0123\", {header}")));
+/// assert!(etag.matches("*"));
+/// assert!(!HttpEtag::of(&Sha256::digest(b"<h1>Hello</h1>")).matches(&header));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpEtag(crate::hash::Digest);
+
+impl HttpEtag {
+    /// Compute the HTTP etag of an etag.
+    #[must_use]
+    pub fn of<E: Etag>(etag: &E) -> Self {
+        let mut hasher = crate::hash::Sha256::new();
+        etag.serialize(&mut hasher);
+        Self(hasher.finish())
+    }
+
+    /// Whether an `If-None-Match` header value matches this etag,
+    /// in which case the server should respond with `304 Not Modified`.
+    ///
+    /// The header is a comma-separated list of entity tags or `*`.
+    /// As the HTTP specification requires for `If-None-Match`,
+    /// entity tags are compared weakly, ignoring any `W/` prefix.
+    #[must_use]
+    pub fn matches(&self, if_none_match: &str) -> bool {
+        if if_none_match.trim() == "*" {
+            return true;
+        }
+        if_none_match.split(',').any(|tag| {
+            let tag = tag.trim();
+            let tag = tag.strip_prefix("W/").unwrap_or(tag);
+            let Some(tag) = tag.strip_prefix('"').and_then(|tag| tag.strip_suffix('"')) else {
+                return false;
+            };
+            let hex = self.0 .0.iter().flat_map(|byte| [byte >> 4, byte & 0xF]);
+            tag.len() == 64
+                && tag
+                    .bytes()
+                    .zip(hex)
+                    .all(|(c, nibble)| c == b"0123456789abcdef"[usize::from(nibble)])
+        })
+    }
+}
+
+impl Display for HttpEtag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self.0)
+    }
+}
+
 macro_rules! impl_for_tuple {
     ($name:ident: $($t:ident)*) => {
         impl<$($t: Etag,)*> Etag for ($($t,)*) {
@@ -156,6 +325,255 @@ macro_rules! impl_for_tuple {
 }
 crate::for_tuples!(impl_for_tuple);
 
+impl Etag for bool {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_bytes(&[u8::from(*self)]);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        match reader.peek().read_u8()? {
+            0 => reader.consume(1),
+            1 => {
+                reader.consume(1);
+                return Ok(true);
+            }
+            _ => return Err(DeserializeError::Invalid),
+        }
+        Ok(false)
+    }
+}
+
+impl<T: Etag> Etag for Option<T> {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        match self {
+            None => writer.write_discriminant(0),
+            Some(value) => {
+                writer.write_discriminant(1);
+                value.serialize(writer);
+            }
+        }
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(match reader.read_discriminant(1)? {
+            0 => None,
+            _ => Some(T::deserialize(reader)?),
+        })
+    }
+}
+
+impl Etag for u8 {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_bytes(&[*self]);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        reader.read_u8()
+    }
+}
+
+impl Etag for i8 {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_bytes(&self.to_le_bytes());
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        reader.read_array().map(i8::from_le_bytes)
+    }
+}
+
+macro_rules! impl_for_int {
+    ($($t:ident $write:ident $read:ident,)*) => { $(
+        impl Etag for $t {
+            fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+                writer.$write(*self);
+            }
+            fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+                reader.$read()
+            }
+        }
+    )* }
+}
+impl_for_int! {
+    u16 write_u16_var read_u16_var,
+    u32 write_u32_var read_u32_var,
+    u64 write_u64_var read_u64_var,
+    u128 write_u128_var read_u128_var,
+    usize write_usize_var read_usize_var,
+    i16 write_i16_var read_i16_var,
+    i32 write_i32_var read_i32_var,
+    i64 write_i64_var read_i64_var,
+    i128 write_i128_var read_i128_var,
+    isize write_isize_var read_isize_var,
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+impl Etag for alloc::string::String {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_usize_var(self.len());
+        writer.write_bytes(self.as_bytes());
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        let mut peek = reader.peek();
+        let len = peek.read_usize_var()?;
+        let bytes = peek.read_bytes(len)?;
+        let s = core::str::from_utf8(bytes).map_err(|_| DeserializeError::Invalid)?;
+        reader.consume(reader.remaining().len() - peek.remaining().len());
+        Ok(s.into())
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+impl<T: Etag> Etag for alloc::vec::Vec<T> {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_usize_var(self.len());
+        for item in self {
+            item.serialize(writer);
+        }
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        let len = reader.read_usize_var()?;
+        // Don’t trust `len` for preallocation, since it comes from untrusted input.
+        let mut vec = alloc::vec::Vec::with_capacity(len.min(reader.remaining().len()));
+        for _ in 0..len {
+            vec.push(T::deserialize(reader)?);
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+impl<K: Etag + Ord, V: Etag> Etag for alloc::collections::BTreeMap<K, V> {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_usize_var(self.len());
+        for (key, value) in self {
+            key.serialize(writer);
+            value.serialize(writer);
+        }
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        let len = reader.read_usize_var()?;
+        let mut map = alloc::collections::BTreeMap::new();
+        for _ in 0..len {
+            let key = K::deserialize(reader)?;
+            let value = V::deserialize(reader)?;
+            // Duplicate keys would not round-trip, so are not canonical.
+            if map.insert(key, value).is_some() {
+                return Err(DeserializeError::Invalid);
+            }
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(feature = "bytes")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "bytes")))]
+impl Etag for bytes::Bytes {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_usize_var(self.len());
+        writer.write_bytes(self);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        let len = reader.read_usize_var()?;
+        Ok(Self::copy_from_slice(reader.read_bytes(len)?))
+    }
+}
+
+/// OS strings are serialized as UTF-8 where possible,
+/// falling back to the platform’s native encoding otherwise;
+/// such strings will fail to deserialize on other platforms.
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+impl Etag for std::ffi::OsString {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        serialize_os_str(self, writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        deserialize_os_string(reader)
+    }
+}
+
+/// Paths are serialized in the same way as [`OsString`](std::ffi::OsString)s.
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+impl Etag for std::path::PathBuf {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        serialize_os_str(self.as_os_str(), writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        deserialize_os_string(reader).map(Self::from)
+    }
+}
+
+#[cfg(feature = "std")]
+fn serialize_os_str<W: ?Sized + Writer>(os_str: &std::ffi::OsStr, writer: &mut W) {
+    if let Some(s) = os_str.to_str() {
+        writer.write_bytes(&[0]);
+        writer.write_usize_var(s.len());
+        writer.write_bytes(s.as_bytes());
+        return;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt as _;
+        let bytes = os_str.as_bytes();
+        writer.write_bytes(&[1]);
+        writer.write_usize_var(bytes.len());
+        writer.write_bytes(bytes);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt as _;
+        let wide: alloc::vec::Vec<u16> = os_str.encode_wide().collect();
+        writer.write_bytes(&[2]);
+        writer.write_usize_var(wide.len());
+        for unit in wide {
+            writer.write_u16(unit);
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        // Other platforms encode OS strings as arbitrary bytes, as Unix does.
+        let bytes = os_str.as_encoded_bytes();
+        writer.write_bytes(&[1]);
+        writer.write_usize_var(bytes.len());
+        writer.write_bytes(bytes);
+    }
+}
+
+#[cfg(feature = "std")]
+fn deserialize_os_string(reader: &mut Reader<'_>) -> Result<std::ffi::OsString, DeserializeError> {
+    match reader.read_u8()? {
+        0 => Ok(alloc::string::String::deserialize(reader)?.into()),
+        #[cfg(unix)]
+        1 => {
+            use std::os::unix::ffi::OsStrExt as _;
+            let len = reader.read_usize_var()?;
+            let bytes = reader.read_bytes(len)?;
+            Ok(std::ffi::OsStr::from_bytes(bytes).into())
+        }
+        // There is no safe way to turn arbitrary bytes into an OS string on other platforms,
+        // so only those that are valid UTF-8 can be read back.
+        #[cfg(not(any(unix, windows)))]
+        1 => {
+            let len = reader.read_usize_var()?;
+            let bytes = reader.read_bytes(len)?;
+            let s = core::str::from_utf8(bytes).map_err(|_| DeserializeError::Invalid)?;
+            Ok(s.into())
+        }
+        #[cfg(windows)]
+        2 => {
+            use std::os::windows::ffi::OsStringExt as _;
+            let len = reader.read_usize_var()?;
+            let mut wide = alloc::vec::Vec::with_capacity(len.min(reader.remaining().len()));
+            for _ in 0..len {
+                wide.push(reader.read_u16()?);
+            }
+            Ok(std::ffi::OsString::from_wide(&wide))
+        }
+        _ => Err(DeserializeError::Invalid),
+    }
+}
+
 /// A sink of bytes to serialize into.
 ///
 /// # Varint encoding
@@ -232,6 +650,59 @@ pub trait Writer {
     fn write_isize_var(&mut self, value: isize) {
         self.write_i64_var(value as i64);
     }
+
+    /// Write the discriminant of an enum variant.
+    ///
+    /// The discriminant is encoded as a variable-width `u32`,
+    /// so the first 128 variants take up a single byte.
+    /// Etags of enums should be serialized
+    /// as the discriminant of the variant, counting up from zero in declaration order,
+    /// followed by the fields of that variant in order;
+    /// they can then be deserialized with [`Reader::read_discriminant`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::etag::ArrayWriter;
+    /// use mast::etag::DeserializeError;
+    /// use mast::etag::Reader;
+    /// use mast::etag::Writer;
+    /// use mast::Etag;
+    ///
+    /// #[derive(Debug, Default, PartialEq)]
+    /// enum Version {
+    ///     #[default]
+    ///     Unknown,
+    ///     Known(u64),
+    /// }
+    ///
+    /// impl Etag for Version {
+    ///     fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+    ///         match self {
+    ///             Self::Unknown => writer.write_discriminant(0),
+    ///             Self::Known(n) => {
+    ///                 writer.write_discriminant(1);
+    ///                 n.serialize(writer);
+    ///             }
+    ///         }
+    ///     }
+    ///     fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+    ///         Ok(match reader.read_discriminant(1)? {
+    ///             0 => Self::Unknown,
+    ///             _ => Self::Known(u64::deserialize(reader)?),
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let mut writer = ArrayWriter::<2>::new();
+    /// Version::Known(5).serialize(&mut writer);
+    /// assert_eq!(writer.as_bytes(), Some(&[0b1000_0001, 0b1000_0101][..]));
+    /// assert_eq!(Version::from_bytes(&[0b1000_0000]).unwrap(), Version::Unknown);
+    /// assert!(Version::from_bytes(&[0b1000_0010]).is_err());
+    /// ```
+    fn write_discriminant(&mut self, discriminant: u32) {
+        self.write_u32_var(discriminant);
+    }
 }
 
 macro_rules! prim_writer_methods {
@@ -268,6 +739,100 @@ impl Writer for alloc::vec::Vec<u8> {
     }
 }
 
+#[cfg(feature = "bytes")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "bytes")))]
+impl Writer for bytes::BytesMut {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// A [`Writer`] into a fixed-size buffer on the stack,
+/// for serializing etags of bounded size without an allocator.
+///
+/// Writing more than `N` bytes does not panic;
+/// the excess is discarded and the writer is marked as overflowed,
+/// after which [`Self::as_bytes`] returns `None`.
+///
+/// # Examples
+///
+/// ```
+/// use mast::etag::ArrayWriter;
+/// use mast::Etag;
+///
+/// let mut writer = ArrayWriter::<4>::new();
+/// (true, 300_u32).serialize(&mut writer);
+/// assert_eq!(writer.as_bytes(), Some(&[1, 0b0100_0001, 0b0010_1100][..]));
+///
+/// u64::MAX.serialize(&mut writer);
+/// assert!(writer.overflowed());
+/// assert_eq!(writer.as_bytes(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArrayWriter<const N: usize> {
+    buf: [u8; N],
+    /// The total number of bytes written, which may exceed `N`.
+    len: usize,
+}
+
+impl<const N: usize> ArrayWriter<N> {
+    /// Construct a new, empty `ArrayWriter`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Get the bytes written so far,
+    /// or `None` if more than `N` bytes have been written.
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        self.buf.get(..self.len)
+    }
+
+    /// Get the total number of bytes written so far,
+    /// including those that did not fit in the buffer.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether nothing has been written yet.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether more than `N` bytes have been written.
+    #[must_use]
+    pub const fn overflowed(&self) -> bool {
+        N < self.len
+    }
+
+    /// Discard everything written so far, including any overflow.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for ArrayWriter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Writer for ArrayWriter<N> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if let Some(dest) = self.buf.get_mut(self.len..) {
+            let n = dest.len().min(bytes.len());
+            dest[..n].copy_from_slice(&bytes[..n]);
+        }
+        self.len = self.len.saturating_add(bytes.len());
+    }
+}
+
 /// A cursor around an in-memory buffer to deserialize from.
 ///
 /// # Errors
@@ -279,7 +844,7 @@ pub struct Reader<'buf> {
     buf: &'buf [u8],
 }
 
-#[allow(clippy::missing_errors_doc)]
+#[allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
 impl<'buf> Reader<'buf> {
     /// Construct a new `Reader` from the given byte slice.
     #[must_use]
@@ -350,6 +915,18 @@ impl<'buf> Reader<'buf> {
     pub fn read_isize_var(&mut self) -> Result<isize, DeserializeError> {
         isize::try_from(self.read_i64_var()?).map_err(|_| DeserializeError::WordSizeTooSmall)
     }
+
+    /// Read an enum discriminant written by [`Writer::write_discriminant`],
+    /// failing if it is greater than `max`.
+    pub fn read_discriminant(&mut self, max: u32) -> Result<u32, DeserializeError> {
+        let mut peek = self.peek();
+        let discriminant = peek.read_u32_var()?;
+        if max < discriminant {
+            return Err(DeserializeError::Invalid);
+        }
+        *self = peek;
+        Ok(discriminant)
+    }
 }
 
 macro_rules! prim_reader_methods {
@@ -368,19 +945,360 @@ macro_rules! prim_reader_methods {
 }
 use prim_reader_methods;
 
+/// Utilities for checking that implementations of [`Etag`] uphold its invariants.
+///
+/// [`assert_etag_roundtrip`](testing::assert_etag_roundtrip) checks a fixed set of etags;
+/// with the `proptest` feature enabled,
+/// the strategies in this module can be used to check arbitrarily many.
+///
+/// # Examples
+///
+/// ```
+/// use mast::etag::testing::assert_etag_roundtrip;
+///
+/// assert_etag_roundtrip([0_u32, 1, 127, 128, u32::MAX]);
+/// assert_etag_roundtrip([None, Some(String::new()), Some("hello".to_owned())]);
+/// ```
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+pub mod testing {
+    /// Assert that the given etags uphold the invariants of [`Etag`].
+    ///
+    /// For each etag, this checks that:
+    ///
+    /// - Serializing and then deserializing it produces an equal etag.
+    /// - Deserialization reads exactly the serialized bytes, leaving any that follow untouched.
+    /// - Deserializing any strict prefix of the serialized bytes fails.
+    ///
+    /// Additionally, every pair of etags must compare equal
+    /// if and only if their serialized forms are byte-for-byte equal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the above does not hold.
+    #[track_caller]
+    pub fn assert_etag_roundtrip<T, I>(values: I)
+    where
+        T: Etag + PartialEq,
+        I: IntoIterator<Item = T>,
+    {
+        let values: Vec<(T, Vec<u8>)> = values
+            .into_iter()
+            .map(|value| {
+                let bytes = value.to_vec();
+                check_one(&value, &bytes);
+                (value, bytes)
+            })
+            .collect();
+
+        for (a, a_bytes) in &values {
+            for (b, b_bytes) in &values {
+                assert_eq!(
+                    a == b,
+                    a_bytes == b_bytes,
+                    "etags {a:?} and {b:?} have serialized forms {a_bytes:?} and {b_bytes:?}",
+                );
+            }
+        }
+    }
+
+    /// Assert that deserializing arbitrary bytes as `T` is well-behaved.
+    ///
+    /// Deserialization is allowed to fail,
+    /// but if it succeeds the resulting etag must pass [`assert_etag_roundtrip`].
+    /// This is suitable for use with randomly generated or fuzzed input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if deserialization panics or the deserialized etag does not round-trip.
+    #[track_caller]
+    pub fn assert_etag_bytes<T: Etag + PartialEq>(bytes: &[u8]) {
+        if let Ok(value) = T::from_bytes(bytes) {
+            assert_etag_roundtrip([value]);
+        }
+    }
+
+    #[track_caller]
+    fn check_one<T: Etag + PartialEq>(value: &T, bytes: &[u8]) {
+        let mut reader = Reader::new(bytes);
+        let deserialized = T::deserialize(&mut reader).unwrap_or_else(|e| {
+            let position = bytes.len() - reader.remaining().len();
+            let rest = reader.remaining();
+            panic!("etag {value:?} failed to deserialize from {bytes:?} at byte {position}, before {rest:?}: {e}")
+        });
+        assert!(
+            reader.remaining().is_empty(),
+            "deserializing {value:?} from {bytes:?} left trailing bytes {:?}",
+            reader.remaining(),
+        );
+        assert_eq!(
+            *value, deserialized,
+            "etag did not round-trip through {bytes:?}"
+        );
+
+        let mut extended = bytes.to_vec();
+        extended.extend_from_slice(TRAILER);
+        let mut reader = Reader::new(&extended);
+        let deserialized = T::deserialize(&mut reader).unwrap_or_else(|e| {
+            let position = extended.len() - reader.remaining().len();
+            panic!("etag {value:?} failed to deserialize when followed by other data, at byte {position}: {e}")
+        });
+        assert_eq!(
+            reader.remaining(),
+            TRAILER,
+            "deserializing {value:?} did not consume exactly its serialized form {bytes:?}",
+        );
+        assert_eq!(
+            *value, deserialized,
+            "etag did not round-trip when followed by other data"
+        );
+
+        for len in 0..bytes.len() {
+            let prefix = &bytes[..len];
+            if let Ok(deserialized) = T::from_bytes(prefix) {
+                panic!("prefix {prefix:?} of the serialized form of {value:?} deserialized to {deserialized:?}");
+            }
+        }
+    }
+
+    /// Bytes appended to serialized etags to check that deserialization stops at the right place.
+    const TRAILER: &[u8] = &[0x00, 0xFF, 0x80, 0x01];
+
+    /// A strategy generating the serialized forms of etags generated by `values`.
+    ///
+    /// Every generated byte sequence is one that could have been returned from [`Etag::serialize`],
+    /// making this useful for testing code that consumes serialized etags,
+    /// such as custom [`Etag::deserialize`] implementations for wrapper types.
+    #[cfg(feature = "proptest")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "proptest")))]
+    pub fn serialized<T, S>(values: S) -> impl Strategy<Value = Vec<u8>>
+    where
+        T: Etag,
+        S: Strategy<Value = T>,
+    {
+        values.prop_map(|value| value.to_vec())
+    }
+
+    /// A strategy generating the serialized forms of arbitrary etags of type `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::etag::testing;
+    /// use proptest::proptest;
+    ///
+    /// proptest!(|(bytes in testing::arbitrary_serialized::<(u64, String)>())| {
+    ///     testing::assert_etag_bytes::<(u64, String)>(&bytes);
+    /// });
+    /// ```
+    #[cfg(feature = "proptest")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "proptest")))]
+    pub fn arbitrary_serialized<T: Etag + Arbitrary>() -> impl Strategy<Value = Vec<u8>> {
+        serialized(any::<T>())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn builtin() {
+            assert_etag_roundtrip([false, true]);
+            assert_etag_roundtrip([0_u16, 1, 127, 128, 16_383, 16_384, u16::MAX]);
+            assert_etag_roundtrip([0_i64, -1, 1, i64::MIN, i64::MAX]);
+            assert_etag_roundtrip([u128::MAX, 0, 1 << 56, 1 << 103]);
+            assert_etag_roundtrip([None, Some(0_u8), Some(1)]);
+            assert_etag_roundtrip([Vec::new(), vec![String::new()], vec!["a".to_owned()]]);
+            assert_etag_roundtrip([((), 0_u8, String::from("x")), ((), 1, String::new())]);
+        }
+
+        #[test]
+        #[should_panic = "did not consume exactly"]
+        fn greedy() {
+            #[derive(Debug, Default, PartialEq)]
+            struct Greedy(Vec<u8>);
+            impl Etag for Greedy {
+                fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+                    writer.write_bytes(&self.0);
+                }
+                fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+                    let bytes = reader.remaining().to_vec();
+                    reader.consume(bytes.len());
+                    Ok(Self(bytes))
+                }
+            }
+            assert_etag_roundtrip([Greedy(vec![1, 2])]);
+        }
+
+        #[test]
+        #[should_panic = "have serialized forms"]
+        fn not_canonical() {
+            #[derive(Debug, Default)]
+            struct AlwaysEqual(u8);
+            impl PartialEq for AlwaysEqual {
+                fn eq(&self, _: &Self) -> bool {
+                    true
+                }
+            }
+            impl Etag for AlwaysEqual {
+                fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+                    self.0.serialize(writer);
+                }
+                fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+                    u8::deserialize(reader).map(Self)
+                }
+            }
+            assert_etag_roundtrip([AlwaysEqual(0), AlwaysEqual(1)]);
+        }
+
+        #[test]
+        fn bytes() {
+            for bytes in [&[][..], &[0], &[1, 0x80], &[0xFF; 20]] {
+                assert_etag_bytes::<u64>(bytes);
+                assert_etag_bytes::<Option<u32>>(bytes);
+                assert_etag_bytes::<Vec<i16>>(bytes);
+                assert_etag_bytes::<String>(bytes);
+            }
+        }
+
+        #[cfg(feature = "proptest")]
+        proptest::proptest! {
+            #[test]
+            fn arbitrary(bytes in super::arbitrary_serialized::<(i32, Vec<u128>, Option<String>)>()) {
+                assert_etag_bytes::<(i32, Vec<u128>, Option<String>)>(&bytes);
+            }
+        }
+
+        use super::super::DeserializeError;
+        use super::super::Etag;
+        use super::super::Reader;
+        use super::super::Writer;
+        use super::assert_etag_bytes;
+        use super::assert_etag_roundtrip;
+        use alloc::borrow::ToOwned;
+        use alloc::string::String;
+        use alloc::vec;
+        use alloc::vec::Vec;
+    }
+
+    use super::Etag;
+    use super::Reader;
+    use alloc::vec::Vec;
+    #[cfg(feature = "proptest")]
+    use proptest::arbitrary::any;
+    #[cfg(feature = "proptest")]
+    use proptest::arbitrary::Arbitrary;
+    #[cfg(feature = "proptest")]
+    use proptest::strategy::Strategy;
+}
+
+/// Check the etag deserialization code against arbitrary input,
+/// panicking if any bug is found.
+///
+/// This is the entry point of the fuzz targets in `fuzz/`;
+/// it is not part of the public API.
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub fn fuzz_check(bytes: &[u8]) {
+    macro_rules! check_ints {
+        ($($u:ident $i:ident,)*) => { $(
+            fuzz::check_varint::<$u>(bytes, varint::decode_unsigned, varint::encode_unsigned);
+            fuzz::check_varint::<$i>(bytes, varint::decode_signed, varint::encode_signed);
+            testing::assert_etag_bytes::<$u>(bytes);
+            testing::assert_etag_bytes::<$i>(bytes);
+        )* };
+    }
+    check_ints! {
+        u8 i8,
+        u16 i16,
+        u32 i32,
+        u64 i64,
+        u128 i128,
+    }
+    testing::assert_etag_bytes::<usize>(bytes);
+    testing::assert_etag_bytes::<isize>(bytes);
+}
+
+#[cfg(feature = "alloc")]
+mod fuzz {
+    pub(crate) fn check_varint<T: Copy + Debug + PartialEq>(
+        bytes: &[u8],
+        decode: fn(&mut Reader<'_>) -> Result<T, DeserializeError>,
+        encode: fn(&mut Vec<u8>, T),
+    ) {
+        let mut reader = Reader::new(bytes);
+        let Ok(value) = decode(&mut reader) else {
+            assert_eq!(
+                reader.remaining(),
+                bytes,
+                "failed decode advanced the reader"
+            );
+            return;
+        };
+        let consumed = bytes.len() - reader.remaining().len();
+
+        let mut encoded = Vec::new();
+        encode(&mut encoded, value);
+        assert!(
+            encoded.len() <= consumed,
+            "{value:?} was decoded from {:?} but encodes to the longer {encoded:?}",
+            &bytes[..consumed],
+        );
+
+        let mut reader = Reader::new(&encoded);
+        let decoded = decode(&mut reader).expect("encoded value failed to decode");
+        assert_eq!(
+            value, decoded,
+            "value did not round-trip through {encoded:?}"
+        );
+        assert_eq!(
+            reader.remaining(),
+            &[],
+            "decoding did not consume {encoded:?}"
+        );
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn corpus() {
+            let inputs: [&[u8]; 8] = [
+                b"",
+                b"\x00",
+                b"\x00\x00",
+                b"\x80",
+                b"\x40\x80",
+                b"\x01\xFF\xFF\xFF\xFF\xFF\xFF\xFF",
+                b"\x00\x02\x80\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+                &[0; 20],
+            ];
+            for input in inputs {
+                for len in 0..=input.len() {
+                    fuzz_check(&input[..len]);
+                }
+            }
+        }
+
+        use super::super::fuzz_check;
+    }
+
+    use super::DeserializeError;
+    use super::Reader;
+    use alloc::vec::Vec;
+    use core::fmt::Debug;
+}
+
 mod varint {
     pub(crate) fn encode_unsigned<W: ?Sized + Writer, T: Unsigned>(writer: &mut W, value: T) {
-        for total_bytes in 1..=mem::size_of::<T>() {
+        for total_bytes in 1..=size_of::<T>() {
             let leading_zeros = total_bytes - 1;
             if value < (T::ONE << (total_bytes * 7)) {
                 let mut be = value.to_be_bytes();
-                let slice = &mut be.as_mut()[mem::size_of::<T>() - total_bytes..];
+                let slice = &mut be.as_mut()[size_of::<T>() - total_bytes..];
                 slice[leading_zeros / 8] |= 0b1000_0000 >> (leading_zeros % 8);
                 writer.write_bytes(slice);
                 return;
             }
         }
-        if mem::size_of::<u64>() < mem::size_of::<T>() {
+        if size_of::<u64>() < size_of::<T>() {
             writer.write_bytes(&[0, 0]);
         } else {
             writer.write_bytes(&[0]);
@@ -398,7 +1316,7 @@ mod varint {
         let first_byte = reader.peek().read_u8()?;
         let first_byte_leading = first_byte.leading_zeros() as usize;
         let (leading_zeros, initial) =
-            if mem::size_of::<u64>() < mem::size_of::<T>() && first_byte_leading == 8 {
+            if size_of::<u64>() < size_of::<T>() && first_byte_leading == 8 {
                 let [_, second_byte] = reader.peek().read_array()?;
                 (8 + second_byte.leading_zeros() as usize, 2)
             } else {
@@ -407,14 +1325,18 @@ mod varint {
         let total_bytes = leading_zeros + 1;
 
         let mut bytes = T::Bytes::default();
-        let res = if let Some(first_byte_index) = mem::size_of::<T>().checked_sub(total_bytes) {
+        let res = if let Some(first_byte_index) = size_of::<T>().checked_sub(total_bytes) {
             reader.read_exact(&mut bytes.as_mut()[first_byte_index..])?;
             bytes.as_mut()[first_byte_index + leading_zeros / 8] &=
                 0b0111_1111 >> (leading_zeros % 8);
             T::from_be_bytes(bytes)
         } else if leading_zeros % 8 == 0 {
-            reader.consume(initial);
-            reader.read_exact(bytes.as_mut())?;
+            let end = initial + size_of::<T>();
+            let le = reader.remaining().get(initial..end);
+            bytes
+                .as_mut()
+                .copy_from_slice(le.ok_or(DeserializeError::Invalid)?);
+            reader.consume(end);
             T::from_le_bytes(bytes)
         } else {
             return Err(DeserializeError::Invalid);
@@ -467,6 +1389,8 @@ mod varint {
             check_fail::<u8>(&[]);
             check_fail::<u128>(&[]);
             check_fail::<u16>(&[0b0100_0000]);
+            check_fail::<u16>(&[0, 0xFF]);
+            check_fail::<u128>(&[0, 0, 0xFF]);
         }
 
         #[track_caller]
@@ -499,7 +1423,7 @@ mod varint {
     use super::DeserializeError;
     use super::Reader;
     use super::Writer;
-    use core::mem;
+    use core::mem::size_of;
 }
 
 /// “zigzag” encoding of signed integers
@@ -554,7 +1478,6 @@ mod num {
         + Shl<usize, Output = Self>
         + Shr<u32, Output = Self>
     {
-        const ZERO: Self;
         const ONE: Self;
         const BITS: u32;
         type Bytes: Default + AsRef<[u8]> + AsMut<[u8]>;
@@ -562,9 +1485,6 @@ mod num {
         fn to_be_bytes(self) -> Self::Bytes;
         fn from_le_bytes(bytes: Self::Bytes) -> Self;
         fn from_be_bytes(bytes: Self::Bytes) -> Self;
-        fn wrapping_add(self, other: Self) -> Self;
-        fn wrapping_sub(self, other: Self) -> Self;
-        fn leading_zeros(self) -> u32;
     }
 
     pub(crate) trait Unsigned: Int {
@@ -580,10 +1500,9 @@ mod num {
     macro_rules! impl_int {
         ($t:ident) => {
             impl Int for $t {
-                const ZERO: Self = 0;
                 const ONE: Self = 1;
                 const BITS: u32 = Self::BITS;
-                type Bytes = [u8; mem::size_of::<Self>()];
+                type Bytes = [u8; size_of::<Self>()];
                 fn to_le_bytes(self) -> Self::Bytes {
                     self.to_le_bytes()
                 }
@@ -596,15 +1515,6 @@ mod num {
                 fn from_be_bytes(bytes: Self::Bytes) -> Self {
                     Self::from_be_bytes(bytes)
                 }
-                fn wrapping_add(self, other: Self) -> Self {
-                    self.wrapping_add(other)
-                }
-                fn wrapping_sub(self, other: Self) -> Self {
-                    self.wrapping_sub(other)
-                }
-                fn leading_zeros(self) -> u32 {
-                    self.leading_zeros()
-                }
             }
         };
     }
@@ -612,12 +1522,14 @@ mod num {
         ($($i:ident $u:ident,)*) => { $(
             impl Signed for $i {
                 type Unsigned = $u;
+                #[allow(clippy::cast_sign_loss)]
                 fn cast_unsigned(self) -> Self::Unsigned {
                     self as $u
                 }
             }
             impl Unsigned for $u {
                 type Signed = $i;
+                #[allow(clippy::cast_possible_wrap)]
                 fn cast_signed(self) -> Self::Signed {
                     self as $i
                 }
@@ -635,7 +1547,7 @@ mod num {
     }
 
     use core::fmt::Debug;
-    use core::mem;
+    use core::mem::size_of;
     use core::ops::BitAnd;
     use core::ops::BitXor;
     use core::ops::Neg;
@@ -0,0 +1,153 @@
+/// Read the contents of a file as bytes.
+///
+/// By default the etag of this asset is the file’s [`Stamp`](super::Stamp)
+/// (see [`Strategy`](super::Strategy) for alternatives),
+/// and the file is only read when the output is generated.
+/// If the file’s metadata cannot be read,
+/// the asset is considered modified and its output is the error.
+///
+/// By default the output is a `Vec<u8>`;
+/// use [`Bytes::buffer`] to read into a different buffer type.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::fs;
+/// use mast::Asset as _;
+///
+/// let path = std::env::temp_dir().join(format!("mast-doctest-bytes-{}", std::process::id()));
+/// std::fs::write(&path, "Hello world!")?;
+///
+/// let mut etag = Default::default();
+/// let res = fs::bytes(&path).update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate()?, b"Hello world!");
+///
+/// assert!(fs::bytes(&path).update(asset::Context::default(), &mut etag).is_same());
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn bytes<P: Into<PathBuf>>(path: P) -> Bytes {
+    Bytes {
+        path: path.into(),
+        strategy: Mtime,
+        buffer: PhantomData,
+    }
+}
+
+/// Asset for [`bytes`](fn@bytes).
+pub struct Bytes<B = Vec<u8>, S = Mtime> {
+    path: PathBuf,
+    strategy: S,
+    buffer: PhantomData<fn() -> B>,
+}
+
+impl<B, S> Bytes<B, S> {
+    /// Change the type of the output buffer.
+    ///
+    /// The file is always read into a `Vec<u8>` first,
+    /// so this is most useful with types that can take ownership of a `Vec<u8>` without copying,
+    /// such as `Box<[u8]>`, `Arc<[u8]>` and (with the `bytes` feature) `bytes::Bytes`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mast::fs;
+    /// let asset = fs::bytes("index.html").buffer::<Box<[u8]>>();
+    /// ```
+    #[must_use]
+    pub fn buffer<B2: From<Vec<u8>>>(self) -> Bytes<B2, S> {
+        Bytes {
+            path: self.path,
+            strategy: self.strategy,
+            buffer: PhantomData,
+        }
+    }
+
+    /// Track the file with the given [`Strategy`].
+    #[must_use]
+    pub fn etag_strategy<S2: Strategy>(self, strategy: S2) -> Bytes<B, S2> {
+        Bytes {
+            path: self.path,
+            strategy,
+            buffer: PhantomData,
+        }
+    }
+}
+
+strategy_methods!(Bytes, B);
+
+impl<B, S: Clone> Clone for Bytes<B, S> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            strategy: self.strategy.clone(),
+            buffer: PhantomData,
+        }
+    }
+}
+
+impl<B, S: Debug> Debug for Bytes<B, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bytes")
+            .field("path", &self.path)
+            .field("strategy", &self.strategy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, B: From<Vec<u8>>, S: Strategy> Asset<'c> for Bytes<B, S> {
+    type Etag = S::Etag;
+    type Output = io::Result<B>;
+    type Generator = Generator<B>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let path = roots::source_path(cx, self.path);
+        strategy::update(&self.strategy, path, etag).map(|path| Generator {
+            path,
+            buffer: PhantomData,
+        })
+    }
+}
+
+/// Generator for [`Bytes`].
+pub struct Generator<B> {
+    path: io::Result<PathBuf>,
+    buffer: PhantomData<fn() -> B>,
+}
+
+impl<B> Debug for Generator<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Generator")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: From<Vec<u8>>> asset::Generator for Generator<B> {
+    type Output = io::Result<B>;
+
+    fn generate(self) -> Self::Output {
+        Ok(fs::read(self.path?)?.into())
+    }
+}
+
+use super::roots;
+use super::strategy;
+use super::strategy::strategy_methods;
+use super::Mtime;
+use super::Strategy;
+use crate::asset;
+use crate::asset::Context;
+use crate::Asset;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::marker::PhantomData;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::vec::Vec;
@@ -0,0 +1,180 @@
+/// List the entries of a directory.
+///
+/// The output is the paths of the directory’s entries, sorted,
+/// not including `.` and `..` and not recursing into subdirectories.
+/// The etag is a digest of the name and [`Stamp`] of every entry,
+/// so the asset is modified whenever an entry is added, removed or changed.
+/// If the directory cannot be listed,
+/// the asset is considered modified and its output is the error.
+///
+/// With a [`PathNormalization`] in the context,
+/// the entries are sorted and hashed by their normalized names,
+/// though the output still has their paths as they are on disk,
+/// and it is an error for two entries to have the same normalized name.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::fs;
+/// use mast::Asset as _;
+///
+/// let dir = std::env::temp_dir().join(format!("mast-doctest-dir-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(dir.join("b"), "b")?;
+/// std::fs::write(dir.join("a"), "a")?;
+///
+/// let mut etag = Default::default();
+/// let res = fs::dir(&dir).update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate()?, [dir.join("a"), dir.join("b")]);
+/// assert!(fs::dir(&dir).update(asset::Context::default(), &mut etag).is_same());
+///
+/// std::fs::write(dir.join("c"), "c")?;
+/// assert!(fs::dir(&dir).update(asset::Context::default(), &mut etag).is_modified());
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn dir<P: Into<PathBuf>>(path: P) -> Dir {
+    Dir { path: path.into() }
+}
+
+/// Asset for [`dir`].
+#[derive(Debug, Clone)]
+pub struct Dir {
+    path: PathBuf,
+}
+
+impl<'c> Asset<'c> for Dir {
+    type Etag = Digest;
+    type Output = io::Result<Vec<PathBuf>>;
+    type Generator = Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let normalization = cx.try_get::<PathNormalization>().copied();
+        let path = roots::source_path(cx, self.path);
+        match list(&path, normalization.unwrap_or_default()) {
+            Ok((entries, digest)) => {
+                let delta = Delta::cmp(etag, &digest);
+                *etag = digest;
+                delta.track(Generator {
+                    entries: Ok(entries),
+                })
+            }
+            Err(e) => {
+                *etag = Digest::default();
+                Delta::Modified.track(Generator { entries: Err(e) })
+            }
+        }
+    }
+}
+
+fn list(path: &Path, normalization: PathNormalization) -> io::Result<(Vec<PathBuf>, Digest)> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let name = PathBuf::from(entry.file_name());
+        let key = normalization.normalize_path(&name).into_owned();
+        entries.push((key, Stamp::from_metadata(&entry.metadata()?), name));
+    }
+    entries.sort_unstable_by(|(a, ..), (b, ..)| a.cmp(b));
+    if let Some([(_, _, a), (_, _, b)]) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        let msg = format!(
+            "{} and {} are the same path after normalization",
+            path.join(a).display(),
+            path.join(b).display(),
+        );
+        return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.write_usize_var(entries.len());
+    for (key, stamp, _) in &entries {
+        key.serialize(&mut hasher);
+        stamp.serialize(&mut hasher);
+    }
+    let paths = entries.into_iter().map(|(_, _, name)| path.join(name));
+    Ok((paths.collect(), hasher.finish()))
+}
+
+/// Generator for [`Dir`].
+#[derive(Debug)]
+pub struct Generator {
+    entries: io::Result<Vec<PathBuf>>,
+}
+
+impl asset::Generator for Generator {
+    type Output = io::Result<Vec<PathBuf>>;
+
+    fn generate(self) -> Self::Output {
+        self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn normalized() {
+        let dir = env::temp_dir().join(format!("mast-test-dir-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("B"), "b").unwrap();
+        fs::write(dir.join("a"), "a").unwrap();
+
+        let values = (PathNormalization::new().case_insensitive(),);
+        let cx = Context::from_tuple(&values);
+        let mut etag = Digest::default();
+        let res = super::dir(&dir).update(cx, &mut etag);
+        assert!(res.is_modified());
+        assert_eq!(
+            res.value.generate().unwrap(),
+            [dir.join("a"), dir.join("B")]
+        );
+
+        // Changing only the case of a name does not modify the asset.
+        fs::rename(dir.join("B"), dir.join("b")).unwrap();
+        let res = super::dir(&dir).update(cx, &mut etag);
+        assert!(res.is_same());
+        assert_eq!(
+            res.value.generate().unwrap(),
+            [dir.join("a"), dir.join("b")]
+        );
+
+        fs::write(dir.join("A"), "A").unwrap();
+        let res = super::dir(&dir).update(cx, &mut etag);
+        let err = res.value.generate().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::fs::PathNormalization;
+    use crate::hash::Digest;
+    use crate::Asset as _;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::io;
+    use std::process;
+}
+
+use super::roots;
+use super::PathNormalization;
+use super::Stamp;
+use crate::asset;
+use crate::asset::Context;
+use crate::etag::Writer as _;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag as _;
+use crate::Tracked;
+use std::format;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::vec::Vec;
@@ -0,0 +1,190 @@
+/// The identity of a file on disk, which stays the same when the file is renamed or moved
+/// within the same filesystem.
+///
+/// On Unix, this is the device and inode number of the file.
+/// Other platforms, including Windows,
+/// do not expose file identities through the standard library,
+/// so no file has one there:
+/// [`file_ids`] is always empty and [`renames`] never finds a renamed file.
+///
+/// Comparing the identities of files between builds with [`renames`]
+/// lets a pipeline tell a renamed file apart from one that was deleted and another created,
+/// and carry over the state of the renamed file with [`Keys::rename`](crate::asset::Keys::rename).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId {
+    dev: u64,
+    ino: u64,
+}
+
+impl FileId {
+    /// Retrieve the identity of the file at the given path, following symbolic links.
+    ///
+    /// Returns `None` on platforms without file identities.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file’s metadata could not be retrieved.
+    pub fn of<P: AsRef<Path>>(path: P) -> io::Result<Option<Self>> {
+        Ok(Self::from_metadata(&fs::metadata(path)?))
+    }
+
+    /// Obtain the identity of a file from its metadata.
+    ///
+    /// Returns `None` on platforms without file identities.
+    #[must_use]
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    pub fn from_metadata(metadata: &fs::Metadata) -> Option<Self> {
+        #[cfg(unix)]
+        return Some(Self {
+            dev: std::os::unix::fs::MetadataExt::dev(metadata),
+            ino: std::os::unix::fs::MetadataExt::ino(metadata),
+        });
+        #[cfg(not(unix))]
+        return None;
+    }
+}
+
+impl Etag for FileId {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.dev.serialize(writer);
+        self.ino.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            dev: Etag::deserialize(reader)?,
+            ino: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+/// Retrieve the [`FileId`] of every file in `paths`, following symbolic links.
+///
+/// Files without an identity, as on platforms without file identities, are left out,
+/// and verbatim paths are [simplified](super::simplify_verbatim) on Windows.
+/// The result is an etag, so it can be saved to compare against in the next build.
+///
+/// # Errors
+///
+/// Fails if the metadata of any of the files could not be retrieved.
+pub fn file_ids<I>(paths: I) -> io::Result<BTreeMap<PathBuf, FileId>>
+where
+    I: IntoIterator,
+    I::Item: Into<PathBuf>,
+{
+    let mut ids = BTreeMap::new();
+    for path in paths {
+        let path = path.into();
+        if let Some(id) = FileId::of(&path)? {
+            ids.insert(comparable(&path).into_owned(), id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Find the files that were renamed between two sets of [`file_ids`].
+///
+/// A file is considered renamed from a path in `previous` that is not in `current`
+/// to a path in `current` that was not in `previous`, if both have the same identity.
+/// The result maps each file’s old path to its new one.
+///
+/// A file that has several of the removed or of the added paths,
+/// such as one with hard links, is left out,
+/// since it cannot be told which path became which.
+///
+/// # Examples
+///
+/// ```
+/// use mast::fs;
+///
+/// let root = std::env::temp_dir().join(format!("mast-doctest-renames-{}", std::process::id()));
+/// std::fs::create_dir_all(&root)?;
+/// std::fs::write(root.join("draft.md"), "Hello")?;
+/// std::fs::write(root.join("other.md"), "Other")?;
+/// let previous = fs::file_ids([root.join("draft.md"), root.join("other.md")])?;
+///
+/// std::fs::rename(root.join("draft.md"), root.join("hello.md"))?;
+/// let current = fs::file_ids([root.join("hello.md"), root.join("other.md")])?;
+///
+/// let renames = fs::renames(&previous, &current);
+/// if cfg!(unix) {
+///     assert_eq!(renames.len(), 1);
+///     assert_eq!(renames[&root.join("draft.md")], root.join("hello.md"));
+/// }
+/// # std::fs::remove_dir_all(&root)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[must_use]
+pub fn renames(
+    previous: &BTreeMap<PathBuf, FileId>,
+    current: &BTreeMap<PathBuf, FileId>,
+) -> BTreeMap<PathBuf, PathBuf> {
+    let removed = only_path(
+        previous
+            .iter()
+            .filter(|(path, _)| !current.contains_key(*path)),
+    );
+    let added = only_path(
+        current
+            .iter()
+            .filter(|(path, _)| !previous.contains_key(*path)),
+    );
+    removed
+        .into_iter()
+        .filter_map(|(id, from)| Some((from?.clone(), (*added.get(&id)?)?.clone())))
+        .collect()
+}
+
+/// Map each identity to its path, or to `None` if several of the paths have it.
+fn only_path<'a, I>(paths: I) -> HashMap<FileId, Option<&'a PathBuf>>
+where
+    I: Iterator<Item = (&'a PathBuf, &'a FileId)>,
+{
+    let mut ids = HashMap::new();
+    for (path, &id) in paths {
+        ids.entry(id)
+            .and_modify(|only| *only = None)
+            .or_insert(Some(path));
+    }
+    ids
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    #[test]
+    fn hard_links() {
+        let root = env::temp_dir().join(format!("mast-test-renames-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a"), "a").unwrap();
+        fs::write(root.join("b"), "b").unwrap();
+        let previous = super::file_ids([root.join("a"), root.join("b")]).unwrap();
+
+        // `a` gains two new names; `b` is renamed.
+        fs::hard_link(root.join("a"), root.join("a2")).unwrap();
+        fs::rename(root.join("a"), root.join("a1")).unwrap();
+        fs::rename(root.join("b"), root.join("b1")).unwrap();
+        let current = super::file_ids([root.join("a1"), root.join("a2"), root.join("b1")]).unwrap();
+
+        let renames = super::renames(&previous, &current);
+        assert_eq!(renames.len(), 1);
+        assert_eq!(renames[&root.join("b")], root.join("b1"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::process;
+}
+
+use super::comparable;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::Etag;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
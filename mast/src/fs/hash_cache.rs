@@ -0,0 +1,170 @@
+/// Track files by the SHA-256 digest of their contents,
+/// only rehashing files whose size, modification time or inode has changed.
+///
+/// This combines the speed of [`Mtime`](super::Mtime) with the robustness of
+/// [`ContentHash`](super::ContentHash):
+/// a file that is touched or rewritten with the same contents is not considered modified,
+/// but a file is only read again when its metadata suggests that it may have changed.
+/// The digests are remembered in memory,
+/// and can be persisted between runs with [`load`](Self::load) and [`save`](Self::save).
+///
+/// This type is cheap to clone, and clones share the same digests,
+/// so one cache can be passed to many assets with their `etag_strategy` method.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::fs;
+/// use mast::store::Store;
+/// use mast::Asset as _;
+///
+/// let root = std::env::temp_dir().join(format!("mast-doctest-hash-cache-{}", std::process::id()));
+/// std::fs::create_dir_all(&root)?;
+/// std::fs::write(root.join("page.md"), "Hello")?;
+/// let store = Store::open(root.join("store"))?;
+///
+/// let cache = fs::HashCache::load(&store, "hashes")?;
+/// let mut etag = Default::default();
+/// let page = fs::text(root.join("page.md")).etag_strategy(cache.clone());
+/// assert_eq!(page.update(asset::Context::default(), &mut etag).value.generate()?, "Hello");
+/// cache.save(&store, "hashes")?;
+///
+/// // In the next run, the file is not read again unless its metadata has changed.
+/// let cache = fs::HashCache::load(&store, "hashes")?;
+/// assert_eq!(cache.len(), 1);
+/// let page = fs::text(root.join("page.md")).etag_strategy(cache.clone());
+/// assert!(page.update(asset::Context::default(), &mut etag).is_same());
+/// # std::fs::remove_dir_all(&root)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HashCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+type Entries = BTreeMap<PathBuf, (Key, Digest)>;
+
+/// The metadata of a file that its digest is valid for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Key {
+    stamp: Stamp,
+    /// The inode number, on platforms that have them.
+    inode: Option<u64>,
+}
+
+impl Key {
+    fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        #[cfg(unix)]
+        let inode = Some(std::os::unix::fs::MetadataExt::ino(&metadata));
+        #[cfg(not(unix))]
+        let inode = None;
+        Ok(Self {
+            stamp: Stamp::from_metadata(&metadata),
+            inode,
+        })
+    }
+}
+
+impl Etag for Key {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.stamp.serialize(writer);
+        self.inode.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            stamp: Etag::deserialize(reader)?,
+            inode: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+impl HashCache {
+    /// Create an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the digests saved in `store` under `id` by [`save`](Self::save).
+    ///
+    /// If there are none, the cache starts empty.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the digests could not be read.
+    pub fn load(store: &Store, id: &str) -> io::Result<Self> {
+        let entries: Entries = store.load(id)?;
+        Ok(Self {
+            entries: Arc::new(Mutex::new(entries)),
+        })
+    }
+
+    /// Save the digests in `store` under `id`, to be loaded in the next run.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the digests could not be written.
+    pub fn save(&self, store: &Store, id: &str) -> io::Result<()> {
+        store.save(id, &*lock(&self.entries))
+    }
+
+    /// Get the number of files whose digests are cached.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        lock(&self.entries).len()
+    }
+
+    /// Whether no digests are cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        lock(&self.entries).is_empty()
+    }
+
+    /// Forget the digests of every file that no longer exists.
+    pub fn retain_existing(&self) {
+        lock(&self.entries).retain(|path, _| path.exists());
+    }
+}
+
+impl Strategy for HashCache {
+    type Etag = Digest;
+    fn etag(&self, path: &Path) -> io::Result<Self::Etag> {
+        let key = Key::of(path)?;
+        let name = comparable(path);
+        if let Some(&(cached, digest)) = lock(&self.entries).get(&*name) {
+            if cached == key {
+                return Ok(digest);
+            }
+        }
+        let digest = hash_file(path)?;
+        lock(&self.entries).insert(name.into_owned(), (key, digest));
+        Ok(digest)
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+use super::comparable;
+use super::hash_file;
+use super::Stamp;
+use super::Strategy;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::hash::Digest;
+use crate::store::Store;
+use crate::Etag;
+use alloc::sync::Arc;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
@@ -0,0 +1,145 @@
+/// Compute the SHA-256 digest of the contents of many files, in parallel.
+///
+/// The files are divided between up to `threads` threads,
+/// or the [available parallelism](thread::available_parallelism) if `threads` is `None`,
+/// each of which takes the next file to hash as soon as it finishes the previous one.
+/// The output maps each path to the digest of its file,
+/// with verbatim paths [simplified](super::simplify_verbatim) on Windows,
+/// and can be used directly as an etag, or as a component of one.
+///
+/// With thousands of files, hashing them one by one would dominate the time taken by an update;
+/// prefer this to [`ContentHash`](super::ContentHash) on many individual assets in that case.
+///
+/// # Errors
+///
+/// Fails if any of the files could not be read,
+/// with an error that names the file.
+///
+/// # Examples
+///
+/// ```
+/// use mast::fs;
+/// use mast::hash::Sha256;
+///
+/// let root = std::env::temp_dir().join(format!("mast-doctest-hash-files-{}", std::process::id()));
+/// std::fs::create_dir_all(&root)?;
+/// std::fs::write(root.join("a.txt"), "a")?;
+/// std::fs::write(root.join("b.txt"), "b")?;
+///
+/// let digests = fs::hash_files([root.join("a.txt"), root.join("b.txt")], None)?;
+/// assert_eq!(digests[&root.join("a.txt")], Sha256::digest(b"a"));
+/// assert_eq!(digests[&root.join("b.txt")], Sha256::digest(b"b"));
+///
+/// assert!(fs::hash_files([root.join("missing.txt")], None).is_err());
+/// # std::fs::remove_dir_all(&root)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn hash_files<I>(
+    paths: I,
+    threads: Option<NonZeroUsize>,
+) -> io::Result<BTreeMap<PathBuf, Digest>>
+where
+    I: IntoIterator,
+    I::Item: Into<PathBuf>,
+{
+    let paths: Vec<PathBuf> = paths.into_iter().map(Into::into).collect();
+    let threads = threads
+        .map_or_else(
+            || thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            NonZeroUsize::get,
+        )
+        .min(paths.len());
+    if threads <= 1 {
+        return paths.iter().map(|path| hash(path)).collect();
+    }
+
+    let queue = Mutex::new(paths.into_iter());
+    let results: Vec<_> = thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| -> io::Result<Vec<_>> {
+                    let mut digests = Vec::new();
+                    loop {
+                        let Some(path) = lock(&queue).next() else {
+                            break Ok(digests);
+                        };
+                        digests.push(hash(&path)?);
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+    let mut digests = BTreeMap::new();
+    for result in results {
+        digests.extend(result?);
+    }
+    Ok(digests)
+}
+
+fn hash(path: &Path) -> io::Result<(PathBuf, Digest)> {
+    let key = comparable(path).into_owned();
+    match hash_file(path) {
+        Ok(digest) => Ok((key, digest)),
+        Err(e) => Err(io::Error::new(e.kind(), format!("{}: {e}", key.display()))),
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parallel() {
+        let root =
+            std::env::temp_dir().join(format!("mast-test-hash-files-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let paths: Vec<_> = (0..32).map(|i| root.join(format!("{i}"))).collect();
+        for (i, path) in paths.iter().enumerate() {
+            std::fs::write(path, format!("{i}")).unwrap();
+        }
+
+        let sequential = hash_files(&paths, NonZeroUsize::new(1)).unwrap();
+        let parallel = hash_files(&paths, NonZeroUsize::new(4)).unwrap();
+        assert_eq!(sequential.len(), 32);
+        assert_eq!(sequential, parallel);
+        assert_eq!(sequential[&paths[3]], Sha256::digest(b"3"));
+
+        let mut paths = paths;
+        paths.push(root.join("missing"));
+        let e = hash_files(&paths, NonZeroUsize::new(4)).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+        assert!(e.to_string().contains("missing"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    use super::hash_files;
+    use crate::hash::Sha256;
+    use core::num::NonZeroUsize;
+    use std::format;
+    use std::io;
+    use std::string::ToString as _;
+    use std::vec::Vec;
+}
+
+use super::comparable;
+use super::hash_file;
+use crate::hash::Digest;
+use core::num::NonZeroUsize;
+use std::collections::BTreeMap;
+use std::format;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::thread;
+use std::vec::Vec;
@@ -0,0 +1,375 @@
+/// A journal of the changes made to a directory tree,
+/// recorded from the operating system’s change notifications
+/// (inotify on Linux, `FSEvents` on macOS and `ReadDirectoryChangesW` on Windows).
+///
+/// Placing a journal in the [`Context`](crate::asset::Context)
+/// lets a [`scan`](super::scan) of the same root
+/// skip walking the tree on every build after the first:
+/// it instead re-stats only the paths that the journal reports as changed
+/// since the previous scan.
+/// If the operating system reports that events were lost,
+/// the next scan falls back to a full walk.
+///
+/// The journal must be created before any of the changes it should catch are made,
+/// so it is most useful in long-running processes such as `watch` commands.
+/// Such processes can also use [`wait`](Self::wait) to sleep until something changes,
+/// rather than polling.
+///
+/// The `journal` feature needs Rust 1.77 or later, above the rest of the crate,
+/// because `notify` 8 does.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mast::asset;
+/// use mast::fs;
+/// use mast::Asset as _;
+///
+/// let values = (fs::Journal::new("content")?,);
+/// let cx = asset::Context::from_tuple(&values);
+///
+/// let mut etag = Default::default();
+/// // Only the first scan with this journal walks the whole tree.
+/// let snapshot = fs::scan("content").update(cx, &mut etag);
+/// # Ok::<_, fs::JournalError>(())
+/// ```
+pub struct Journal {
+    root: PathBuf,
+    shared: Arc<Shared>,
+    _watcher: Mutex<RecommendedWatcher>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    /// Notified whenever `State::pending` is set.
+    condvar: Condvar,
+    ignored: Mutex<Vec<Ignore>>,
+}
+
+type Ignore = Box<dyn Fn(&Path) -> bool + Send>;
+
+#[derive(Debug, Default)]
+struct State {
+    /// Paths changed since the last scan, relative to the root.
+    changed: BTreeSet<PathBuf>,
+    /// Whether changes may have been missed since the last scan.
+    overflowed: bool,
+    /// The snapshot produced by the last scan.
+    last: Option<Snapshot>,
+    /// Whether there have been changes that are not ignored since the last wait.
+    pending: bool,
+    /// When the most recent change that is not ignored was recorded.
+    last_change: Option<Instant>,
+}
+
+/// An error starting a [`Journal`].
+pub type JournalError = notify::Error;
+
+impl Journal {
+    /// Start recording changes to the directory tree at `root`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `root` cannot be watched.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Result<Self, JournalError> {
+        let root = root.into();
+        let canonical = std::fs::canonicalize(&root)?;
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            condvar: Condvar::new(),
+            ignored: Mutex::new(Vec::new()),
+        });
+
+        let handler_shared = shared.clone();
+        let handler_root = root.clone();
+        let handler =
+            move |res: notify::Result<Event>| handler_shared.record(res, &canonical, &handler_root);
+
+        let mut watcher = notify::recommended_watcher(handler)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+        Ok(Self {
+            root,
+            shared,
+            _watcher: Mutex::new(watcher),
+        })
+    }
+
+    /// Get the root of the tree this journal records changes to.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Ignore changes to paths matching `ignore` when [`wait`](Self::wait)ing.
+    ///
+    /// The predicate is given paths relative to the root.
+    /// This is useful for files that change frequently but do not affect the build,
+    /// such as the swap and backup files of text editors.
+    /// Changes to ignored paths are still seen by [`scan`](super::scan)s,
+    /// so that their snapshots are always accurate.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mast::fs;
+    ///
+    /// let journal = fs::Journal::new("content")?.ignore(|path| {
+    ///     let name = path.file_name().unwrap_or_default().to_string_lossy();
+    ///     name.ends_with('~') || name.ends_with(".swp") || name.starts_with(".#")
+    /// });
+    /// # Ok::<_, fs::JournalError>(())
+    /// ```
+    #[must_use]
+    pub fn ignore<F: Fn(&Path) -> bool + Send + 'static>(self, ignore: F) -> Self {
+        lock(&self.shared.ignored).push(Box::new(ignore));
+        self
+    }
+
+    /// Block until there is a change to the tree that is not [`ignore`](Self::ignore)d,
+    /// and then until no further changes have arrived for `debounce`.
+    ///
+    /// The second step coalesces a burst of changes,
+    /// such as a version control checkout or an editor saving several files at once,
+    /// so that a `watch` loop rebuilds once for the whole burst instead of once per change.
+    /// Changes made since the previous call are counted,
+    /// so none are missed while the caller is busy rebuilding.
+    pub fn wait(&self, debounce: Duration) {
+        let mut state = lock(&self.shared.state);
+        while !state.pending {
+            state = self
+                .shared
+                .condvar
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        while let Some(remaining) = state
+            .last_change
+            .and_then(|last| debounce.checked_sub(last.elapsed()))
+            .filter(|remaining| !remaining.is_zero())
+        {
+            state = self
+                .shared
+                .condvar
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        state.pending = false;
+    }
+
+    /// Update the snapshot of the last scan of `root` with the changes since then,
+    /// or walk the tree in full if that is not possible.
+    pub(crate) fn scan(&self, root: &Path, threads: usize) -> io::Result<Snapshot> {
+        if root != self.root {
+            let entries = super::scan::walk(root, threads)?;
+            return Ok(Snapshot::new(entries));
+        }
+
+        // Take the changes before walking,
+        // so that changes made during the walk will be picked up next time.
+        let mut state = lock(&self.shared.state);
+        let changed = mem::take(&mut state.changed);
+        let overflowed = mem::take(&mut state.overflowed);
+        let last = state.last.take();
+        drop(state);
+
+        let snapshot = match last {
+            Some(mut snapshot) if !overflowed => {
+                let entries = Arc::make_mut(&mut snapshot.entries);
+                for relative in changed {
+                    let path = root.join(relative);
+                    let outdated: Vec<PathBuf> = entries
+                        .range::<Path, _>((Bound::Included(&*path), Bound::Unbounded))
+                        .map(|(entry, _)| entry)
+                        .take_while(|entry| entry.starts_with(&path))
+                        .cloned()
+                        .collect();
+                    for entry in outdated {
+                        entries.remove(&entry);
+                    }
+                    match std::fs::symlink_metadata(&path) {
+                        Ok(metadata) if metadata.is_dir() => {
+                            entries.extend(super::scan::walk(&path, threads)?);
+                        }
+                        Ok(metadata) => drop(entries.insert(path, Stamp::from_metadata(&metadata))),
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                snapshot
+            }
+            _ => Snapshot::new(super::scan::walk(root, threads)?),
+        };
+
+        let mut state = lock(&self.shared.state);
+        state.last = Some(snapshot.clone());
+        Ok(snapshot)
+    }
+}
+
+impl Shared {
+    /// Record an event from the watcher of the tree at `root`, whose canonical path is `canonical`.
+    fn record(&self, res: notify::Result<Event>, canonical: &Path, root: &Path) {
+        let mut state = lock(&self.state);
+        let mut relevant = false;
+        match res {
+            Ok(event) if !event.need_rescan() => {
+                if let EventKind::Access(_) = event.kind {
+                    return;
+                }
+                let ignored = lock(&self.ignored);
+                for path in event.paths {
+                    let relative = path
+                        .strip_prefix(canonical)
+                        .or_else(|_| path.strip_prefix(root));
+                    let Ok(relative) = relative else {
+                        state.overflowed = true;
+                        relevant = true;
+                        continue;
+                    };
+                    relevant |= !ignored.iter().any(|ignore| ignore(relative));
+                    state.changed.insert(relative.to_path_buf());
+                }
+            }
+            _ => {
+                state.overflowed = true;
+                relevant = true;
+            }
+        }
+        if relevant {
+            state.pending = true;
+            state.last_change = Some(Instant::now());
+            self.condvar.notify_all();
+        }
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Debug for Journal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Journal")
+            .field("root", &self.root)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn incremental() {
+        let root = std::env::temp_dir().join(format!("mast-test-journal-{}", process::id()));
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/b/c"), "c").unwrap();
+        std::fs::write(root.join("d"), "d").unwrap();
+
+        let journal = Journal::new(&root).unwrap();
+        assert_eq!(journal.scan(&root, 2).unwrap().len(), 2);
+
+        std::fs::remove_dir_all(root.join("a")).unwrap();
+        std::fs::create_dir_all(root.join("e/f")).unwrap();
+        std::fs::write(root.join("e/f/g"), "g").unwrap();
+        std::fs::write(root.join("d"), "dd").unwrap();
+
+        let snapshot = catch_up(&journal);
+        assert_eq!(snapshot.get(root.join("d")).unwrap().len(), 2);
+        assert!(snapshot.get(root.join("a/b/c")).is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn wait() {
+        let root = std::env::temp_dir().join(format!("mast-test-journal-wait-{}", process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let journal = Journal::new(&root)
+            .unwrap()
+            .ignore(|path| path.ends_with("ignored"));
+        let shared = &*journal.shared;
+        // Events are fed in directly rather than by changing files,
+        // so that the test does not depend on when the operating system delivers them.
+        let change = |name| {
+            let event = Event::new(EventKind::Create(CreateKind::File)).add_path(root.join(name));
+            shared.record(Ok(event), &root, &root);
+        };
+
+        change("ignored");
+        assert!(!lock(&shared.state).pending);
+
+        change("a");
+        change("b");
+        let last_change = lock(&shared.state).last_change.unwrap();
+        let debounce = Duration::from_millis(200);
+        journal.wait(debounce);
+        assert!(Instant::now() >= last_change + debounce);
+        assert!(!lock(&shared.state).pending);
+        assert_eq!(lock(&shared.state).changed.len(), 3);
+
+        // A change recorded while waiting wakes the waiter.
+        thread::scope(|s| {
+            let waiter = s.spawn(|| journal.wait(Duration::ZERO));
+            change("c");
+            waiter.join().unwrap();
+        });
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Scan with the journal until its snapshot matches a full walk of the tree,
+    /// since the operating system delivers events with no particular latency.
+    fn catch_up(journal: &Journal) -> Snapshot {
+        let root = journal.root();
+        let full = walk(root, 2).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let snapshot = journal.scan(root, 2).unwrap();
+            if *snapshot.entries == full {
+                return snapshot;
+            }
+            assert!(Instant::now() < deadline, "journal did not catch up");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    use super::super::scan::walk;
+    use super::lock;
+    use super::Journal;
+    use super::Snapshot;
+    use notify::event::CreateKind;
+    use notify::Event;
+    use notify::EventKind;
+    use std::format;
+    use std::process;
+    use std::thread;
+    use std::time::Duration;
+    use std::time::Instant;
+}
+
+use super::Snapshot;
+use super::Stamp;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::mem;
+use core::ops::Bound;
+use core::time::Duration;
+use notify::Event;
+use notify::EventKind;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher as _;
+use std::boxed::Box;
+use std::collections::BTreeSet;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::time::Instant;
+use std::vec::Vec;
@@ -0,0 +1,169 @@
+//! Assets that read from the filesystem,
+//! and helpers for assets that write to it.
+
+mod roots;
+#[cfg(feature = "git")]
+pub(crate) use roots::source_path;
+pub use roots::Roots;
+
+mod strategy;
+pub use strategy::ContentHash;
+pub use strategy::Custom;
+pub use strategy::Mtime;
+pub use strategy::Strategy;
+
+mod hash_cache;
+pub use hash_cache::HashCache;
+
+mod verbatim;
+pub(crate) use verbatim::comparable;
+pub use verbatim::simplify_verbatim;
+
+mod normalize;
+pub use normalize::PathNormalization;
+#[cfg(feature = "unicode")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "unicode")))]
+pub use normalize::UnicodeForm;
+
+mod file_id;
+pub use file_id::file_ids;
+pub use file_id::renames;
+pub use file_id::FileId;
+
+mod bytes;
+pub use bytes::bytes;
+pub use bytes::Bytes;
+
+mod text;
+pub use text::text;
+pub use text::Text;
+
+mod path;
+pub use path::path;
+pub use path::Path;
+
+mod dir;
+pub use dir::dir;
+pub use dir::Dir;
+
+mod scan;
+pub use scan::scan;
+pub use scan::Scan;
+pub use scan::Snapshot;
+
+mod hash_files;
+pub use hash_files::hash_files;
+
+#[cfg(feature = "journal")]
+mod journal;
+#[cfg(feature = "journal")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "journal")))]
+pub use journal::Journal;
+#[cfg(feature = "journal")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "journal")))]
+pub use journal::JournalError;
+
+mod temp;
+pub use temp::TempDir;
+pub use temp::TempDirs;
+
+mod stream;
+pub use stream::stream;
+pub use stream::Chunks;
+pub use stream::Stream;
+
+mod write;
+pub use write::copy_if_changed;
+pub(crate) use write::make_writable;
+pub use write::write_if_changed;
+pub use write::WriteOptions;
+
+/// The size and modification time of a file,
+/// used as a cheap etag for its contents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stamp {
+    len: u64,
+    /// The modification time, if supported by the platform.
+    modified: Option<Time>,
+}
+
+impl Stamp {
+    /// Retrieve the stamp of the file at the given path, following symbolic links.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file’s metadata could not be retrieved.
+    pub fn of<P: AsRef<std::path::Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::from_metadata(&fs::metadata(path)?))
+    }
+
+    /// Obtain the stamp of a file from its metadata.
+    #[must_use]
+    pub fn from_metadata(metadata: &fs::Metadata) -> Self {
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok().map(Time::from_system),
+        }
+    }
+
+    /// The size of the file in bytes.
+    #[must_use]
+    pub const fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the file is empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The modification time of the file, if supported by the platform.
+    #[must_use]
+    pub const fn modified(&self) -> Option<Time> {
+        self.modified
+    }
+}
+
+impl Etag for Stamp {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.len.serialize(writer);
+        self.modified.is_some().serialize(writer);
+        if let Some(modified) = self.modified {
+            modified.serialize(writer);
+        }
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        let len = Etag::deserialize(reader)?;
+        let modified = if bool::deserialize(reader)? {
+            Some(Etag::deserialize(reader)?)
+        } else {
+            None
+        };
+        Ok(Self { len, modified })
+    }
+}
+
+/// Compute the SHA-256 digest of a file’s contents without loading it all into memory.
+pub(crate) fn hash_file(path: &std::path::Path) -> io::Result<Digest> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0; 8 * 1024];
+    loop {
+        match file.read(&mut buf)? {
+            0 => break Ok(hasher.finish()),
+            n => hasher.update(&buf[..n]),
+        }
+    }
+}
+
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::time::Time;
+use crate::Etag;
+use std::fs;
+use std::io;
+use std::io::Read as _;
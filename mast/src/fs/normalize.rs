@@ -0,0 +1,136 @@
+/// How paths are normalized before being used as keys or etag components,
+/// placed in the [`Context`](crate::asset::Context).
+///
+/// Filesystems disagree about which paths are the same:
+/// macOS treats paths case-insensitively and stores them decomposed (NFD),
+/// while Linux compares them byte for byte and usually stores them composed (NFC).
+/// Normalizing paths consistently lets state saved on one system be reused on another
+/// without spurious rebuilds or duplicate outputs.
+///
+/// With a `PathNormalization` in the context,
+/// the [`Snapshot`](super::Snapshot)s of [`scan`](super::scan) are keyed by normalized paths,
+/// and the entries listed by [`dir`](super::dir) are sorted and hashed by normalized name.
+/// Pass it to [`Keys::with_normalization`](crate::asset::Keys::with_normalization)
+/// to normalize the labels of keyed assets too.
+/// Paths that are not valid UTF-8 are never normalized.
+///
+/// The assets that read a single file, such as [`text`](super::text),
+/// do not use it: their etags do not include the path,
+/// and they must open the file by the path they were given,
+/// which a case-sensitive filesystem may not find once normalized.
+///
+/// By default, no normalization is done.
+///
+/// # Examples
+///
+/// ```
+/// use mast::fs::PathNormalization;
+/// use std::path::Path;
+///
+/// let normalization = PathNormalization::new().case_insensitive();
+/// assert_eq!(normalization.normalize_path(Path::new("Posts/Hello.md")), Path::new("posts/hello.md"));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathNormalization {
+    case_insensitive: bool,
+    #[cfg(feature = "unicode")]
+    form: Option<UnicodeForm>,
+}
+
+impl PathNormalization {
+    /// Construct a `PathNormalization` that leaves paths unchanged.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            case_insensitive: false,
+            #[cfg(feature = "unicode")]
+            form: None,
+        }
+    }
+
+    /// Fold paths to lower case, so that paths differing only in case are the same.
+    ///
+    /// This uses the lowercase mapping of Unicode,
+    /// which agrees with the case folding of case-insensitive filesystems for almost all paths.
+    #[must_use]
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// Normalize paths to the given Unicode normalization form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::fs::PathNormalization;
+    /// use mast::fs::UnicodeForm;
+    ///
+    /// let normalization = PathNormalization::new().unicode(UnicodeForm::Nfc);
+    /// assert_eq!(normalization.normalize_str("cafe\u{301}.md"), "caf\u{e9}.md");
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "unicode")))]
+    #[must_use]
+    pub fn unicode(mut self, form: UnicodeForm) -> Self {
+        self.form = Some(form);
+        self
+    }
+
+    /// Whether this leaves every path unchanged.
+    #[must_use]
+    pub const fn is_identity(&self) -> bool {
+        #[cfg(feature = "unicode")]
+        if self.form.is_some() {
+            return false;
+        }
+        !self.case_insensitive
+    }
+
+    /// Normalize a string, such as a label or a path that is known to be UTF-8.
+    #[must_use]
+    pub fn normalize_str<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let mut s = Cow::Borrowed(s);
+        if self.case_insensitive && s.chars().any(char::is_uppercase) {
+            s = Cow::Owned(s.to_lowercase());
+        }
+        #[cfg(feature = "unicode")]
+        match self.form {
+            Some(UnicodeForm::Nfc) if !unicode_normalization::is_nfc(&s) => {
+                s = Cow::Owned(unicode_normalization::UnicodeNormalization::nfc(&*s).collect());
+            }
+            Some(UnicodeForm::Nfd) if !unicode_normalization::is_nfd(&s) => {
+                s = Cow::Owned(unicode_normalization::UnicodeNormalization::nfd(&*s).collect());
+            }
+            _ => {}
+        }
+        s
+    }
+
+    /// Normalize a path.
+    ///
+    /// Paths that are not valid UTF-8 are returned unchanged.
+    #[must_use]
+    pub fn normalize_path<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        match path.to_str().map(|s| self.normalize_str(s)) {
+            Some(Cow::Owned(s)) => Cow::Owned(PathBuf::from(s)),
+            _ => Cow::Borrowed(path),
+        }
+    }
+}
+
+/// A Unicode normalization form, for [`PathNormalization::unicode`].
+#[cfg(feature = "unicode")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "unicode")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnicodeForm {
+    /// Canonical composition, as used by most Linux and Windows software.
+    Nfc,
+    /// Canonical decomposition, as used by the filesystems of macOS.
+    Nfd,
+}
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::path::PathBuf;
@@ -0,0 +1,94 @@
+/// Track a file without reading it, outputting its path.
+///
+/// This is useful for files that are consumed by something other than Mast,
+/// such as inputs to an external command:
+/// the asset is modified whenever the file’s [`Stamp`](super::Stamp) changes
+/// (or its etag under another [`Strategy`]),
+/// but generating it does no I/O.
+/// If the file’s metadata cannot be read,
+/// the asset is considered modified and its output is the error.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::fs;
+/// use mast::Asset as _;
+///
+/// let path = std::env::temp_dir().join(format!("mast-doctest-path-{}", std::process::id()));
+/// std::fs::write(&path, "Hello world!")?;
+///
+/// let mut etag = Default::default();
+/// let res = fs::path(&path).update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate()?, path);
+///
+/// assert!(fs::path(&path).update(asset::Context::default(), &mut etag).is_same());
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn path<P: Into<PathBuf>>(path: P) -> Path {
+    Path {
+        path: path.into(),
+        strategy: Mtime,
+    }
+}
+
+/// Asset for [`path()`].
+#[derive(Debug, Clone)]
+pub struct Path<S = Mtime> {
+    path: PathBuf,
+    strategy: S,
+}
+
+impl<S> Path<S> {
+    /// Track the file with the given [`Strategy`].
+    #[must_use]
+    pub fn etag_strategy<S2: Strategy>(self, strategy: S2) -> Path<S2> {
+        Path {
+            path: self.path,
+            strategy,
+        }
+    }
+}
+
+strategy_methods!(Path);
+
+impl<'c, S: Strategy> Asset<'c> for Path<S> {
+    type Etag = S::Etag;
+    type Output = io::Result<PathBuf>;
+    type Generator = Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let path = roots::source_path(cx, self.path);
+        strategy::update(&self.strategy, path, etag).map(|path| Generator { path })
+    }
+}
+
+/// Generator for [`Path`].
+#[derive(Debug)]
+pub struct Generator {
+    path: io::Result<PathBuf>,
+}
+
+impl asset::Generator for Generator {
+    type Output = io::Result<PathBuf>;
+
+    fn generate(self) -> Self::Output {
+        self.path
+    }
+}
+
+use super::roots;
+use super::strategy;
+use super::strategy::strategy_methods;
+use super::Mtime;
+
+use super::Strategy;
+use crate::asset;
+use crate::asset::Context;
+use crate::Asset;
+use crate::Tracked;
+use std::io;
+use std::path::PathBuf;
@@ -0,0 +1,125 @@
+/// The directories that relative paths are resolved against,
+/// placed in the [`Context`] to make pipelines independent of the working directory.
+///
+/// The file assets in this module resolve relative paths against [`Self::source`];
+/// [`Self::output`] and [`Self::cache`] are provided for assets that write files.
+/// Without a `Roots` in the context,
+/// and for each root left empty (the default),
+/// relative paths are resolved against the working directory as usual.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::fs;
+/// use mast::Asset as _;
+///
+/// let site = std::env::temp_dir().join(format!("mast-doctest-roots-{}", std::process::id()));
+/// std::fs::create_dir_all(site.join("content"))?;
+/// std::fs::write(site.join("content/index.md"), "Home")?;
+///
+/// let roots = fs::Roots {
+///     source: site.join("content"),
+///     output: site.join("public"),
+///     ..fs::Roots::default()
+/// };
+/// assert_eq!(roots.output_path("index.html"), site.join("public/index.html"));
+///
+/// let values = (roots,);
+/// let cx = asset::Context::from_tuple(&values);
+/// let mut etag = Default::default();
+/// let index = fs::text("index.md").update(cx, &mut etag);
+/// assert_eq!(index.value.generate()?, "Home");
+/// # std::fs::remove_dir_all(&site)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Roots {
+    /// The directory that input files are read from.
+    pub source: PathBuf,
+    /// The directory that build outputs are written to.
+    pub output: PathBuf,
+    /// The directory for intermediate files and persisted etags.
+    pub cache: PathBuf,
+}
+
+impl Roots {
+    /// Resolve a path to an input file against the source root.
+    ///
+    /// Absolute paths are returned unchanged.
+    #[must_use]
+    pub fn source_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.source.join(path)
+    }
+
+    /// Resolve a path to an output file against the output root.
+    ///
+    /// Absolute paths are returned unchanged.
+    #[must_use]
+    pub fn output_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.output.join(path)
+    }
+
+    /// Resolve a path to a cache file against the cache root.
+    ///
+    /// Absolute paths are returned unchanged.
+    #[must_use]
+    pub fn cache_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.cache.join(path)
+    }
+}
+
+/// Resolve a path to an input file against the source root in the context, if there is one.
+pub(crate) fn source_path(cx: Context<'_>, path: PathBuf) -> PathBuf {
+    match cx.try_get::<Roots>() {
+        Some(roots) if path.is_relative() => roots.source_path(path),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn resolve() {
+        let absolute = env::temp_dir().join("elsewhere");
+        let roots = Roots {
+            source: PathBuf::from("content"),
+            output: PathBuf::from("public"),
+            ..Roots::default()
+        };
+        assert_eq!(roots.source_path("a.md"), Path::new("content/a.md"));
+        assert_eq!(roots.output_path("a.html"), Path::new("public/a.html"));
+        // An empty root leaves relative paths relative to the working directory.
+        assert_eq!(roots.cache_path("etags"), Path::new("etags"));
+        assert_eq!(roots.source_path(&absolute), absolute);
+        assert_eq!(roots.output_path(&absolute), absolute);
+    }
+
+    #[test]
+    fn context() {
+        let absolute = env::temp_dir().join("elsewhere");
+        let relative = PathBuf::from("a.md");
+        let cx = Context::default();
+        assert_eq!(source_path(cx, relative.clone()), relative);
+
+        let values = (Roots {
+            source: PathBuf::from("content"),
+            ..Roots::default()
+        },);
+        let cx = Context::from_tuple(&values);
+        assert_eq!(source_path(cx, relative), Path::new("content/a.md"));
+        assert_eq!(source_path(cx, absolute.clone()), absolute);
+    }
+
+    use super::source_path;
+    use super::Roots;
+    use crate::asset::Context;
+    use std::env;
+    use std::path::Path;
+    use std::path::PathBuf;
+}
+
+use crate::asset::Context;
+use std::path::Path;
+use std::path::PathBuf;
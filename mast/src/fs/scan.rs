@@ -0,0 +1,394 @@
+/// Stat every file in a directory tree, in parallel.
+///
+/// The output is a [`Snapshot`] of the [`Stamp`] of every file under `root`,
+/// keyed by path (`root` joined with the path relative to it).
+/// Symbolic links are recorded but not followed.
+/// The etag is a digest of the whole snapshot,
+/// so the asset is modified whenever any file is added, removed or changed.
+///
+/// In large trees, this is much faster than having many file assets stat their files individually:
+/// pass the snapshot to those assets with their `etag_strategy` method
+/// and they will look up their stamps in it instead.
+/// The snapshot is only as fresh as the scan,
+/// so the scan should be updated once at the start of every build.
+///
+/// With the `journal` feature,
+/// placing a [`Journal`](super::Journal) for `root` in the context
+/// allows scans after the first to re-stat only the files that changed.
+///
+/// With a [`PathNormalization`] in the context,
+/// the snapshot is keyed by normalized paths, and the paths looked up in it are normalized,
+/// so that the etag and lookups do not depend on how the filesystem spells them.
+/// Only the part of each path relative to `root` is normalized,
+/// and [`Snapshot::iter`] still yields the paths as they are on disk.
+/// If two files have the same normalized path, such as `a.md` and `A.md`
+/// when normalizing case, the scan fails.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::fs;
+/// use mast::Asset as _;
+///
+/// let root = std::env::temp_dir().join(format!("mast-doctest-scan-{}", std::process::id()));
+/// std::fs::create_dir_all(root.join("posts"))?;
+/// std::fs::write(root.join("index.md"), "Home")?;
+/// std::fs::write(root.join("posts/hello.md"), "Hello")?;
+///
+/// let mut scan_etag = Default::default();
+/// let snapshot = fs::scan(&root).update(asset::Context::default(), &mut scan_etag);
+/// assert!(snapshot.is_modified());
+/// let snapshot = snapshot.value.generate()?;
+/// assert_eq!(snapshot.len(), 2);
+///
+/// let mut etag = Default::default();
+/// let post = fs::text(root.join("posts/hello.md")).etag_strategy(snapshot.clone());
+/// let post = post.update(asset::Context::default(), &mut etag);
+/// assert_eq!(post.value.generate()?, "Hello");
+///
+/// assert!(fs::scan(&root).update(asset::Context::default(), &mut scan_etag).is_same());
+/// # std::fs::remove_dir_all(&root)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn scan<P: Into<PathBuf>>(root: P) -> Scan {
+    Scan {
+        root: root.into(),
+        threads: None,
+    }
+}
+
+/// Asset for [`scan`].
+#[derive(Debug, Clone)]
+pub struct Scan {
+    root: PathBuf,
+    threads: Option<NonZeroUsize>,
+}
+
+impl Scan {
+    /// Set the number of threads to scan with.
+    ///
+    /// By default, this is the [available parallelism](thread::available_parallelism).
+    #[must_use]
+    pub fn threads(self, threads: NonZeroUsize) -> Self {
+        Self {
+            threads: Some(threads),
+            ..self
+        }
+    }
+}
+
+impl<'c> Asset<'c> for Scan {
+    type Etag = Digest;
+    type Output = io::Result<Snapshot>;
+    type Generator = Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let threads = self.threads.map_or_else(
+            || thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            NonZeroUsize::get,
+        );
+        let root = roots::source_path(cx, self.root);
+        #[cfg(feature = "journal")]
+        let snapshot = match cx.try_get::<super::Journal>() {
+            Some(journal) => journal.scan(&root, threads),
+            None => walk(&root, threads).map(Snapshot::new),
+        };
+        #[cfg(not(feature = "journal"))]
+        let snapshot = walk(&root, threads).map(Snapshot::new);
+        let normalization = cx.try_get::<PathNormalization>().copied();
+        let snapshot = snapshot
+            .and_then(|snapshot| snapshot.normalized(&root, normalization.unwrap_or_default()));
+        match snapshot {
+            Ok(snapshot) => {
+                let mut hasher = Sha256::new();
+                snapshot.entries.serialize(&mut hasher);
+                let digest = hasher.finish();
+                let delta = Delta::cmp(etag, &digest);
+                *etag = digest;
+                delta.track(Generator {
+                    snapshot: Ok(snapshot),
+                })
+            }
+            Err(e) => {
+                *etag = Digest::default();
+                Delta::Modified.track(Generator { snapshot: Err(e) })
+            }
+        }
+    }
+}
+
+/// Walk the tree breadth-first, reading the directories of each level in parallel.
+pub(super) fn walk(root: &Path, threads: usize) -> io::Result<BTreeMap<PathBuf, Stamp>> {
+    fs::metadata(root)?;
+    let mut entries = BTreeMap::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while !dirs.is_empty() {
+        let chunk_size = (dirs.len() + threads - 1) / threads;
+        let results = if dirs.len() <= chunk_size {
+            vec![read_dirs(&dirs)]
+        } else {
+            thread::scope(|s| {
+                let handles: Vec<_> = dirs
+                    .chunks(chunk_size)
+                    .map(|chunk| s.spawn(|| read_dirs(chunk)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                    .collect()
+            })
+        };
+        dirs.clear();
+        for result in results {
+            let (files, subdirs) = result?;
+            entries.extend(files);
+            dirs.extend(subdirs);
+        }
+    }
+    Ok(entries)
+}
+
+type Entries = (Vec<(PathBuf, Stamp)>, Vec<PathBuf>);
+
+/// Read the given directories, returning the files and subdirectories found.
+///
+/// Entries that are removed while being read are skipped.
+fn read_dirs(dirs: &[PathBuf]) -> io::Result<Entries> {
+    let mut files = Vec::new();
+    let mut subdirs = Vec::new();
+    for dir in dirs {
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for entry in read_dir {
+            let entry = entry?;
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            if metadata.is_dir() {
+                subdirs.push(entry.path());
+            } else {
+                files.push((entry.path(), Stamp::from_metadata(&metadata)));
+            }
+        }
+    }
+    Ok((files, subdirs))
+}
+
+/// Generator for [`Scan`].
+#[derive(Debug)]
+pub struct Generator {
+    snapshot: io::Result<Snapshot>,
+}
+
+impl asset::Generator for Generator {
+    type Output = io::Result<Snapshot>;
+
+    fn generate(self) -> Self::Output {
+        self.snapshot
+    }
+}
+
+/// The output of [`scan`]: the [`Stamp`] of every file in a directory tree.
+///
+/// This type is cheap to clone.
+/// As a [`Strategy`], it looks up the stamps of files in the snapshot
+/// instead of retrieving them from the filesystem,
+/// treating files not in the snapshot as nonexistent.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    /// The stamps of the files, keyed by [normalized](Self::key) path.
+    pub(super) entries: Arc<BTreeMap<PathBuf, Stamp>>,
+    /// The paths on disk of the files whose keys differ from them.
+    real: Arc<BTreeMap<PathBuf, PathBuf>>,
+    root: PathBuf,
+    normalization: PathNormalization,
+}
+
+impl Snapshot {
+    pub(super) fn new(entries: BTreeMap<PathBuf, Stamp>) -> Self {
+        Self {
+            entries: Arc::new(entries),
+            real: Arc::default(),
+            root: PathBuf::new(),
+            normalization: PathNormalization::new(),
+        }
+    }
+
+    /// Key the snapshot of `root` by normalized paths.
+    ///
+    /// On Windows, verbatim paths are also [simplified](super::simplify_verbatim).
+    ///
+    /// Fails if two paths have the same key.
+    fn normalized(self, root: &Path, normalization: PathNormalization) -> io::Result<Self> {
+        if normalization.is_identity() && !cfg!(windows) {
+            return Ok(self);
+        }
+        let mut normalized = Self {
+            entries: Arc::default(),
+            real: Arc::default(),
+            root: comparable(root).into_owned(),
+            normalization,
+        };
+        let mut entries = BTreeMap::new();
+        let mut real = BTreeMap::new();
+        for (path, &stamp) in &*self.entries {
+            let key = normalized.key(path);
+            if entries.contains_key(&key) {
+                let msg = format!(
+                    "{} and {} are the same path after normalization",
+                    real.get(&key).unwrap_or(&key).display(),
+                    path.display(),
+                );
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+            entries.insert(key.clone(), stamp);
+            if key != *path {
+                real.insert(key, path.clone());
+            }
+        }
+        normalized.entries = Arc::new(entries);
+        normalized.real = Arc::new(real);
+        Ok(normalized)
+    }
+
+    /// The path under which the file at `path` is kept in the snapshot:
+    /// the root, followed by the normalized path relative to it.
+    fn key(&self, path: &Path) -> PathBuf {
+        let path = comparable(path);
+        match path.strip_prefix(&self.root) {
+            Ok(relative) => self.root.join(self.normalization.normalize_path(relative)),
+            Err(_) => path.into_owned(),
+        }
+    }
+
+    /// Get the stamp of the file at `path`, if it was present in the snapshot.
+    #[must_use]
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<Stamp> {
+        self.entries.get(&self.key(path.as_ref())).copied()
+    }
+
+    /// Iterate over the paths and stamps of every file in the snapshot.
+    ///
+    /// The paths are as they are on disk,
+    /// but are in order of their normalized paths.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, Stamp)> {
+        self.entries.iter().map(|(key, &stamp)| {
+            let path = self.real.get(key).unwrap_or(key);
+            (&**path, stamp)
+        })
+    }
+
+    /// Get the number of files in the snapshot.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the snapshot contains no files.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Strategy for Snapshot {
+    type Etag = Stamp;
+    fn etag(&self, path: &Path) -> io::Result<Self::Etag> {
+        self.get(path).ok_or_else(|| {
+            let msg = format!("{} is not in the snapshot", comparable(path).display());
+            io::Error::new(io::ErrorKind::NotFound, msg)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parallel() {
+        let root = std::env::temp_dir().join(format!("mast-test-scan-{}", std::process::id()));
+        for i in 0..8 {
+            let dir = root.join(format!("{i}/nested"));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("file"), format!("{i}")).unwrap();
+            std::fs::write(root.join(format!("{i}/file")), "").unwrap();
+        }
+
+        let sequential = walk(&root, 1).unwrap();
+        let parallel = walk(&root, 3).unwrap();
+        assert_eq!(sequential.len(), 16);
+        assert_eq!(sequential, parallel);
+        assert!(walk(&root.join("missing"), 3).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn normalized() {
+        let mut entries = BTreeMap::new();
+        entries.insert(PathBuf::from("Site/Posts/Hello.md"), Stamp::default());
+        entries.insert(PathBuf::from("Site/index.md"), Stamp::default());
+        let normalization = PathNormalization::new().case_insensitive();
+        let snapshot = Snapshot::new(entries.clone())
+            .normalized(Path::new("Site"), normalization)
+            .unwrap();
+        let paths: Vec<_> = snapshot.iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            [Path::new("Site/index.md"), Path::new("Site/Posts/Hello.md")]
+        );
+        assert!(snapshot.get("Site/POSTS/hello.md").is_some());
+        assert!(snapshot.get("Site/posts/other.md").is_none());
+        // The root is not normalized.
+        assert!(snapshot.get("site/posts/hello.md").is_none());
+
+        entries.insert(PathBuf::from("Site/posts/hello.md"), Stamp::default());
+        let err = Snapshot::new(entries)
+            .normalized(Path::new("Site"), normalization)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    use super::walk;
+    use super::PathNormalization;
+    use super::Snapshot;
+    use super::Stamp;
+    use std::collections::BTreeMap;
+    use std::format;
+    use std::io;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::vec::Vec;
+}
+
+use super::comparable;
+use super::roots;
+use super::PathNormalization;
+use super::Stamp;
+use super::Strategy;
+use crate::asset;
+use crate::asset::Context;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag as _;
+use crate::Tracked;
+use alloc::sync::Arc;
+use core::num::NonZeroUsize;
+use std::collections::BTreeMap;
+use std::format;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::vec;
+use std::vec::Vec;
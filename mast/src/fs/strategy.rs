@@ -0,0 +1,243 @@
+/// A way of computing the etag of a file,
+/// used by the assets in this module to decide whether the file has changed.
+///
+/// The strategy of an asset is chosen with its
+/// `etag_mtime`, `etag_content_hash`, `etag_custom` and `etag_strategy` methods
+/// (and `etag_last_commit` with the `git` feature);
+/// different assets in the same pipeline may use different strategies.
+pub trait Strategy {
+    /// The etag produced by this strategy.
+    type Etag: Etag + PartialEq;
+
+    /// Compute the etag of the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file could not be inspected.
+    fn etag(&self, path: &Path) -> io::Result<Self::Etag>;
+}
+
+/// Track files by their [`Stamp`], i.e. their size and modification time.
+///
+/// This is the default strategy.
+/// It is cheap, but it considers a file modified whenever its modification time changes
+/// even if its contents do not,
+/// as happens after `git checkout` or when a file is rewritten with the same contents.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mtime;
+
+impl Strategy for Mtime {
+    type Etag = Stamp;
+    fn etag(&self, path: &Path) -> io::Result<Self::Etag> {
+        Stamp::of(path)
+    }
+}
+
+/// Track files by the SHA-256 digest of their contents.
+///
+/// This is immune to spurious changes in modification time,
+/// but it must read the whole file on every update,
+/// so is too slow for very large files or trees.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContentHash;
+
+impl Strategy for ContentHash {
+    type Etag = Digest;
+    fn etag(&self, path: &Path) -> io::Result<Self::Etag> {
+        hash_file(path)
+    }
+}
+
+/// Track files with a user-provided function.
+///
+/// # Examples
+///
+/// Only consider a file modified when its size changes:
+///
+/// ```
+/// use mast::fs;
+///
+/// let asset = fs::bytes("log.txt").etag_custom(|path| Ok(std::fs::metadata(path)?.len()));
+/// ```
+pub struct Custom<F, E> {
+    f: F,
+    etag: PhantomData<fn() -> E>,
+}
+
+impl<F, E> Custom<F, E>
+where
+    F: Fn(&Path) -> io::Result<E>,
+    E: Etag + PartialEq,
+{
+    /// Construct a strategy from a function computing the etag of a file.
+    #[must_use]
+    pub const fn new(f: F) -> Self {
+        Self {
+            f,
+            etag: PhantomData,
+        }
+    }
+}
+
+impl<F, E> Strategy for Custom<F, E>
+where
+    F: Fn(&Path) -> io::Result<E>,
+    E: Etag + PartialEq,
+{
+    type Etag = E;
+    fn etag(&self, path: &Path) -> io::Result<Self::Etag> {
+        (self.f)(path)
+    }
+}
+
+impl<F, E> Debug for Custom<F, E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Custom").finish_non_exhaustive()
+    }
+}
+
+/// Update `etag` to the current etag of the file at `path`,
+/// resetting it and returning the error if the file could not be inspected.
+pub(crate) fn update<S: Strategy>(
+    strategy: &S,
+    path: PathBuf,
+    etag: &mut S::Etag,
+) -> Tracked<io::Result<PathBuf>> {
+    match strategy.etag(&path) {
+        Ok(new) => {
+            let delta = Delta::cmp(etag, &new);
+            *etag = new;
+            delta.track(Ok(path))
+        }
+        Err(e) => {
+            *etag = S::Etag::default();
+            Delta::Modified.track(Err(e))
+        }
+    }
+}
+
+/// Implement the methods for choosing a [`Strategy`] on a file asset.
+macro_rules! strategy_methods {
+    ($name:ident $(, $param:ident)*) => {
+        impl<$($param,)* S> $name<$($param,)* S> {
+            /// Track the file by its size and modification time; see [`Mtime`](super::Mtime).
+            #[must_use]
+            pub fn etag_mtime(self) -> $name<$($param,)* super::Mtime> {
+                self.etag_strategy(super::Mtime)
+            }
+
+            /// Track the file by a hash of its contents; see [`ContentHash`](super::ContentHash).
+            #[must_use]
+            pub fn etag_content_hash(self) -> $name<$($param,)* super::ContentHash> {
+                self.etag_strategy(super::ContentHash)
+            }
+
+            /// Track the file with a custom function; see [`Custom`](super::Custom).
+            #[must_use]
+            pub fn etag_custom<F, E>(self, f: F) -> $name<$($param,)* super::Custom<F, E>>
+            where
+                F: Fn(&std::path::Path) -> std::io::Result<E>,
+                E: crate::Etag + PartialEq,
+            {
+                self.etag_strategy(super::Custom::new(f))
+            }
+
+            /// Track the file by the last commit that changed it;
+            /// see [`LastCommit`](crate::git::LastCommit).
+            #[cfg(feature = "git")]
+            #[cfg_attr(doc_nightly, doc(cfg(feature = "git")))]
+            #[must_use]
+            pub fn etag_last_commit(self) -> $name<$($param,)* crate::git::LastCommit> {
+                self.etag_strategy(crate::git::LastCommit)
+            }
+        }
+    };
+}
+pub(crate) use strategy_methods;
+
+#[cfg(test)]
+mod tests {
+    fn touch(path: &Path, contents: &str, mtime: SystemTime) {
+        fs::write(path, contents).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    #[test]
+    fn strategies() {
+        let path = env::temp_dir().join(format!("mast-test-strategy-{}", process::id()));
+        let then = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let later = then + Duration::from_secs(60);
+        touch(&path, "same", then);
+
+        let mut mtime = Stamp::default();
+        let mut hash = Digest::default();
+        let mut len = 0;
+        let len_of = |path: &Path| Ok(fs::metadata(path)?.len());
+        assert!(update(&Mtime, path.clone(), &mut mtime).is_modified());
+        assert!(update(&ContentHash, path.clone(), &mut hash).is_modified());
+        assert!(update(&Custom::new(len_of), path.clone(), &mut len).is_modified());
+        assert_eq!(len, 4);
+
+        // Rewriting the same contents only changes the modification time.
+        touch(&path, "same", later);
+        assert!(update(&Mtime, path.clone(), &mut mtime).is_modified());
+        assert!(update(&ContentHash, path.clone(), &mut hash).is_same());
+        assert!(update(&Custom::new(len_of), path.clone(), &mut len).is_same());
+
+        // Changing the contents but not the size is only visible to some strategies.
+        touch(&path, "diff", later);
+        assert!(update(&ContentHash, path.clone(), &mut hash).is_modified());
+        assert!(update(&Custom::new(len_of), path.clone(), &mut len).is_same());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing() {
+        let path = env::temp_dir().join(format!("mast-test-strategy-missing-{}", process::id()));
+        let mut hash = Digest([1; 32]);
+        let res = update(&ContentHash, path.clone(), &mut hash);
+        assert!(res.is_modified());
+        assert!(res.value.is_err());
+        assert_eq!(hash, Digest::default());
+
+        // The file is still considered modified once it appears.
+        fs::write(&path, "").unwrap();
+        assert!(update(&ContentHash, path.clone(), &mut hash).is_modified());
+        fs::remove_file(&path).unwrap();
+    }
+
+    use super::update;
+    use super::ContentHash;
+    use super::Custom;
+    use super::Mtime;
+    use crate::fs::Stamp;
+    use crate::hash::Digest;
+    use core::time::Duration;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::fs::File;
+    use std::path::Path;
+    use std::process;
+    use std::time::SystemTime;
+}
+
+use super::hash_file;
+use super::Stamp;
+use crate::hash::Digest;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::marker::PhantomData;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
@@ -0,0 +1,194 @@
+/// Read the contents of a file incrementally, in chunks of at most `chunk_size` bytes.
+///
+/// Unlike [`fs::bytes`](super::bytes), this never loads the whole file into memory,
+/// making it suitable for hashing or transcoding very large inputs.
+/// By default the etag of this asset is the file’s [`Stamp`](super::Stamp)
+/// (see [`Strategy`] for alternatives),
+/// and the file is only opened when the output is generated.
+///
+/// The output is a [`Chunks`],
+/// which can be used both as an iterator of chunks and as a [`Read`]er.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::fs;
+/// use mast::Asset as _;
+///
+/// let path = std::env::temp_dir().join(format!("mast-doctest-stream-{}", std::process::id()));
+/// std::fs::write(&path, "Hello world!")?;
+///
+/// let mut etag = Default::default();
+/// let chunks = fs::stream(&path, 5).update(asset::Context::default(), &mut etag);
+/// let chunks = chunks.value.generate()?.collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(chunks, [&b"Hello"[..], b" worl", b"d!"]);
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[track_caller]
+pub fn stream<P: Into<PathBuf>>(path: P, chunk_size: usize) -> Stream {
+    assert!(chunk_size != 0, "chunk size must be nonzero");
+    Stream {
+        path: path.into(),
+        chunk_size,
+        strategy: Mtime,
+    }
+}
+
+/// Asset for [`stream`].
+#[derive(Debug, Clone)]
+pub struct Stream<S = Mtime> {
+    path: PathBuf,
+    chunk_size: usize,
+    strategy: S,
+}
+
+impl<S> Stream<S> {
+    /// Track the file with the given [`Strategy`].
+    #[must_use]
+    pub fn etag_strategy<S2: Strategy>(self, strategy: S2) -> Stream<S2> {
+        Stream {
+            path: self.path,
+            chunk_size: self.chunk_size,
+            strategy,
+        }
+    }
+}
+
+strategy_methods!(Stream);
+
+impl<'c, S: Strategy> Asset<'c> for Stream<S> {
+    type Etag = S::Etag;
+    type Output = io::Result<Chunks>;
+    type Generator = Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let chunk_size = self.chunk_size;
+        let path = roots::source_path(cx, self.path);
+        strategy::update(&self.strategy, path, etag).map(|path| Generator { path, chunk_size })
+    }
+}
+
+/// Generator for [`Stream`].
+#[derive(Debug)]
+pub struct Generator {
+    path: io::Result<PathBuf>,
+    chunk_size: usize,
+}
+
+impl asset::Generator for Generator {
+    type Output = io::Result<Chunks>;
+
+    fn generate(self) -> Self::Output {
+        Ok(Chunks {
+            file: fs::File::open(self.path?)?,
+            chunk_size: self.chunk_size,
+        })
+    }
+}
+
+/// The output of [`stream`]: an open file, read in chunks.
+///
+/// Each item of the iterator is a chunk of exactly the configured chunk size,
+/// except for the last one which may be shorter.
+/// Reading from this type via [`Read`] ignores the chunk size.
+#[derive(Debug)]
+pub struct Chunks {
+    file: fs::File,
+    chunk_size: usize,
+}
+
+impl Iterator for Chunks {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = Vec::with_capacity(self.chunk_size);
+        let limit = self.chunk_size as u64;
+        match (&mut self.file).take(limit).read_to_end(&mut chunk) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(chunk)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl Read for Chunks {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn chunks(path: &Path, chunk_size: usize) -> io::Result<Chunks> {
+        let mut etag = Stamp::default();
+        let stream = stream(path, chunk_size).update(Context::default(), &mut etag);
+        stream.value.generate()
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        let path = env::temp_dir().join(format!("mast-test-stream-{}", process::id()));
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(chunks(&path, 4).unwrap().count(), 0);
+
+        fs::write(&path, "abcdefgh").unwrap();
+        let all: Vec<_> = chunks(&path, 4).unwrap().map(Result::unwrap).collect();
+        assert_eq!(all, [b"abcd", b"efgh"]);
+
+        // Reading and iterating can be mixed.
+        let mut mixed = chunks(&path, 3).unwrap();
+        assert_eq!(mixed.next().unwrap().unwrap(), b"abc");
+        let mut rest = String::new();
+        mixed.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "defgh");
+        assert!(mixed.next().is_none());
+
+        fs::remove_file(&path).unwrap();
+        assert!(chunks(&path, 4).is_err());
+    }
+
+    #[test]
+    #[should_panic = "chunk size must be nonzero"]
+    fn zero_chunk_size() {
+        let _ = stream("file", 0);
+    }
+
+    use super::stream;
+    use super::Chunks;
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::fs::Stamp;
+    use crate::Asset as _;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::io;
+    use std::io::Read as _;
+    use std::path::Path;
+    use std::process;
+    use std::string::String;
+    use std::vec::Vec;
+}
+
+use super::roots;
+use super::strategy;
+use super::strategy::strategy_methods;
+use super::Mtime;
+use super::Strategy;
+use crate::asset;
+use crate::asset::Context;
+use crate::Asset;
+use crate::Tracked;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::PathBuf;
+use std::vec::Vec;
@@ -0,0 +1,175 @@
+/// Where [`Context::temp_dir`] allocates temporary directories,
+/// placed in the [`Context`] by the driver of a build.
+///
+/// Without a `TempDirs` in the context,
+/// temporary directories are allocated in the [system temporary directory](env::temp_dir).
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::fs;
+///
+/// let root = std::env::temp_dir().join(format!("mast-doctest-temp-{}", std::process::id()));
+/// let values = (fs::TempDirs::new(&root),);
+/// let cx = asset::Context::from_tuple(&values);
+///
+/// let dir = cx.temp_dir()?;
+/// assert!(dir.path().starts_with(&root));
+/// std::fs::write(dir.path().join("scratch"), "")?;
+///
+/// let path = dir.path().to_owned();
+/// drop(dir);
+/// assert!(!path.exists());
+/// # std::fs::remove_dir_all(&root)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TempDirs {
+    root: PathBuf,
+}
+
+impl TempDirs {
+    /// Allocate temporary directories inside `root`, which is created as needed.
+    #[must_use]
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The directory temporary directories are allocated inside.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Create a new, empty temporary directory.
+    ///
+    /// Names that are already taken,
+    /// such as by directories kept by an earlier process with the same ID,
+    /// are skipped, so the directory is always freshly created.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be created.
+    pub fn create(&self) -> io::Result<TempDir> {
+        fs::create_dir_all(&self.root)?;
+        loop {
+            let n = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+            let path = self.root.join(format!("mast-{}-{n}", process::id()));
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(TempDir { path, keep: false }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// The number of the next temporary directory to try to create in this process.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+impl Default for TempDirs {
+    fn default() -> Self {
+        Self::new(env::temp_dir())
+    }
+}
+
+/// A temporary directory, returned by [`Context::temp_dir`].
+///
+/// The directory and its contents are removed when this is dropped,
+/// except while panicking, so that the state a failed generator left behind can be inspected.
+/// Use [`Self::keep`] to preserve it in other cases, such as when the generator returns an error.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl TempDir {
+    /// The path of the directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Preserve the directory instead of removing it, returning its path.
+    #[must_use = "the directory is left behind; report its path"]
+    pub fn keep(mut self) -> PathBuf {
+        self.keep = true;
+        mem::take(&mut self.path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if !self.keep && !thread::panicking() {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+impl Context<'_> {
+    /// Allocate a new temporary directory for use by an asset,
+    /// inside the root given by the [`TempDirs`] in the context.
+    ///
+    /// Each call returns a different directory,
+    /// so every asset that calls this gets its own for every build.
+    /// The directory is removed when the returned [`TempDir`] is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory could not be created.
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    pub fn temp_dir(self) -> io::Result<TempDir> {
+        match self.try_get::<TempDirs>() {
+            Some(dirs) => dirs.create(),
+            None => TempDirs::default().create(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn skip_existing() {
+        let root = env::temp_dir().join(format!("mast-test-temp-{}", process::id()));
+        let dirs = TempDirs::new(&root);
+
+        // Leave behind directories with the next few names, as a crashed process might.
+        let next = COUNTER.load(atomic::Ordering::Relaxed);
+        for n in next..next + 3 {
+            let stale = root.join(format!("mast-{}-{n}", process::id()));
+            fs::create_dir_all(&stale).unwrap();
+            fs::write(stale.join("stale"), "").unwrap();
+        }
+
+        let dir = dirs.create().unwrap();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+        let other = dirs.create().unwrap();
+        assert_ne!(dir.path(), other.path());
+
+        drop((dir, other));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    use super::TempDirs;
+    use super::COUNTER;
+    use core::sync::atomic;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::process;
+}
+
+use crate::asset::Context;
+use core::mem;
+use core::sync::atomic;
+use core::sync::atomic::AtomicU64;
+use std::env;
+use std::format;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
@@ -0,0 +1,91 @@
+/// Read the contents of a file as a UTF-8 string.
+///
+/// This is like [`fs::bytes`](super::bytes),
+/// except that generation fails with [`io::ErrorKind::InvalidData`]
+/// if the file is not valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::fs;
+/// use mast::Asset as _;
+///
+/// let path = std::env::temp_dir().join(format!("mast-doctest-text-{}", std::process::id()));
+/// std::fs::write(&path, "Hello world!")?;
+///
+/// let mut etag = Default::default();
+/// let res = fs::text(&path).update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate()?, "Hello world!");
+///
+/// assert!(fs::text(&path).update(asset::Context::default(), &mut etag).is_same());
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn text<P: Into<PathBuf>>(path: P) -> Text {
+    Text {
+        path: path.into(),
+        strategy: Mtime,
+    }
+}
+
+/// Asset for [`text`].
+#[derive(Debug, Clone)]
+pub struct Text<S = Mtime> {
+    path: PathBuf,
+    strategy: S,
+}
+
+impl<S> Text<S> {
+    /// Track the file with the given [`Strategy`].
+    #[must_use]
+    pub fn etag_strategy<S2: Strategy>(self, strategy: S2) -> Text<S2> {
+        Text {
+            path: self.path,
+            strategy,
+        }
+    }
+}
+
+strategy_methods!(Text);
+
+impl<'c, S: Strategy> Asset<'c> for Text<S> {
+    type Etag = S::Etag;
+    type Output = io::Result<String>;
+    type Generator = Generator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let path = roots::source_path(cx, self.path);
+        strategy::update(&self.strategy, path, etag).map(|path| Generator { path })
+    }
+}
+
+/// Generator for [`Text`].
+#[derive(Debug)]
+pub struct Generator {
+    path: io::Result<PathBuf>,
+}
+
+impl asset::Generator for Generator {
+    type Output = io::Result<String>;
+
+    fn generate(self) -> Self::Output {
+        fs::read_to_string(self.path?)
+    }
+}
+
+use super::roots;
+use super::strategy;
+use super::strategy::strategy_methods;
+use super::Mtime;
+use super::Strategy;
+use crate::asset;
+use crate::asset::Context;
+use crate::Asset;
+use crate::Tracked;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::string::String;
@@ -0,0 +1,151 @@
+/// Remove the `\\?\` prefix from a verbatim Windows path, where it is not needed.
+///
+/// Windows has two spellings of most absolute paths:
+/// the usual one, like `C:\site\index.md` or `\\server\share\index.md`,
+/// and the verbatim one, like `\\?\C:\site\index.md` or `\\?\UNC\server\share\index.md`,
+/// which is what [`std::fs::canonicalize`] returns.
+/// Both refer to the same file,
+/// but they are different paths as far as keys, etags and error messages are concerned.
+/// This converts verbatim paths to the usual spelling,
+/// so that the same file is always known by the same path.
+/// Verbatim paths without a usual spelling are returned unchanged:
+/// volume GUID paths, drive-relative paths like `\\?\C:`,
+/// and paths with components that the usual spelling would alter,
+/// such as `..`, names ending in `.` or a space, or reserved device names like `NUL`.
+/// All other paths are returned unchanged too.
+///
+/// The conversion is purely textual, so it behaves the same on every platform;
+/// the assets in this module only apply it on Windows.
+/// Use the result for display and comparison rather than to access the file,
+/// since only the verbatim spelling can reach paths longer than `MAX_PATH`.
+///
+/// # Examples
+///
+/// ```
+/// use mast::fs;
+/// use std::path::Path;
+///
+/// let simplified = fs::simplify_verbatim(Path::new(r"\\?\C:\site\index.md"));
+/// assert_eq!(simplified, Path::new(r"C:\site\index.md"));
+/// let simplified = fs::simplify_verbatim(Path::new(r"\\?\UNC\server\share\index.md"));
+/// assert_eq!(simplified, Path::new(r"\\server\share\index.md"));
+/// ```
+#[must_use]
+pub fn simplify_verbatim(path: &Path) -> Cow<'_, Path> {
+    let Some(s) = path.to_str() else {
+        return Cow::Borrowed(path);
+    };
+    let Some(rest) = s.strip_prefix(r"\\?\") else {
+        return Cow::Borrowed(path);
+    };
+    if let Some(unc) = rest.strip_prefix(r"UNC\") {
+        if !is_plain(unc) {
+            return Cow::Borrowed(path);
+        }
+        return Cow::Owned(PathBuf::from(format!(r"\\{unc}")));
+    }
+    // Without a backslash after it, the drive letter would refer to
+    // the current directory of that drive rather than its root.
+    let bytes = rest.as_bytes();
+    let is_disk =
+        bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\';
+    if is_disk && is_plain(&rest[3..]) {
+        Cow::Borrowed(Path::new(rest))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+/// Whether the components of a path mean the same in the usual spelling as in the verbatim one.
+///
+/// The usual spelling resolves `.` and `..`, treats `/` as a separator,
+/// collapses repeated separators, strips trailing dots and spaces from names,
+/// and maps reserved names such as `CON` or `nul.txt` to devices.
+fn is_plain(path: &str) -> bool {
+    if path.contains('/') {
+        return false;
+    }
+    let components = path.strip_suffix('\\').unwrap_or(path);
+    components.is_empty()
+        || components
+            .split('\\')
+            .all(|c| !c.is_empty() && !c.ends_with(['.', ' ']) && !is_reserved(c))
+}
+
+/// Whether a file name refers to a device in the usual spelling, regardless of its extension.
+fn is_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+    let upper = stem.to_ascii_uppercase();
+    match upper.as_str() {
+        "CON" | "PRN" | "AUX" | "NUL" | "CONIN$" | "CONOUT$" => true,
+        _ => upper
+            .strip_prefix("COM")
+            .or_else(|| upper.strip_prefix("LPT"))
+            .is_some_and(|n| {
+                matches!(
+                    n,
+                    "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "¹" | "²" | "³"
+                )
+            }),
+    }
+}
+
+/// The path by which the assets in this module know a file,
+/// in keys, etags and error messages:
+/// [`simplify_verbatim`] on Windows, and the path unchanged elsewhere.
+pub(crate) fn comparable(path: &Path) -> Cow<'_, Path> {
+    if cfg!(windows) {
+        simplify_verbatim(path)
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn verbatim() {
+        let simplify = |s: &str| simplify_verbatim(Path::new(s)).into_owned();
+        assert_eq!(simplify(r"\\?\C:\a\b.md"), Path::new(r"C:\a\b.md"));
+        assert_eq!(simplify(r"\\?\c:\"), Path::new(r"c:\"));
+        assert_eq!(simplify(r"\\?\C:\a\"), Path::new(r"C:\a\"));
+        assert_eq!(
+            simplify(r"\\?\C:\console\a.con"),
+            Path::new(r"C:\console\a.con")
+        );
+        assert_eq!(
+            simplify(r"\\?\UNC\server\share"),
+            Path::new(r"\\server\share")
+        );
+        // Paths without a usual spelling are left alone.
+        for s in [
+            r"\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\a",
+            r"\\?\C:\a\..\b",
+            r"\\?\C:\a/b",
+            r"\\?\c:",
+            r"\\?\C:a",
+            r"\\?\C:\a\\b",
+            r"\\?\C:\a.\b",
+            r"\\?\C:\a \b",
+            r"\\?\C:\nul",
+            r"\\?\C:\a\Con.txt",
+            r"\\?\C:\COM1",
+            r"\\?\C:\lpt9 .md",
+            r"\\?\UNC\server\share\aux",
+            r"\\?\CD:\a",
+            r"\\.\COM1",
+            r"C:\a",
+            "/a/b",
+        ] {
+            assert_eq!(simplify(s), Path::new(s));
+        }
+    }
+
+    use super::simplify_verbatim;
+    use std::path::Path;
+}
+
+use std::borrow::Cow;
+use std::format;
+use std::path::Path;
+use std::path::PathBuf;
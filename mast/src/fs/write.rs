@@ -0,0 +1,330 @@
+/// Write `contents` to the file at `path`,
+/// unless the file already has exactly those contents.
+///
+/// Skipping identical writes preserves the file’s modification time,
+/// so that consumers of the build’s outputs that look at modification times,
+/// such as `rsync` or browser caches, do not see spurious changes.
+/// Parent directories are created as needed.
+///
+/// Returns whether the file was written.
+///
+/// Use [`WriteOptions`] to control the permissions of the file.
+///
+/// # Errors
+///
+/// Fails if the existing file could not be read or the new contents could not be written.
+///
+/// # Examples
+///
+/// ```
+/// use mast::fs;
+/// # let dir = mast::fs::TempDirs::default().create()?;
+///
+/// let path = dir.path().join("out/index.html");
+/// assert!(fs::write_if_changed(&path, "<h1>Hi</h1>")?);
+/// let modified = std::fs::metadata(&path)?.modified()?;
+/// assert!(!fs::write_if_changed(&path, "<h1>Hi</h1>")?);
+/// assert_eq!(std::fs::metadata(&path)?.modified()?, modified);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn write_if_changed<P, C>(path: P, contents: C) -> io::Result<bool>
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    WriteOptions::new().write_if_changed(path, contents)
+}
+
+/// Copy the file at `from` to `to`,
+/// unless `to` already has the same contents.
+///
+/// Like [`write_if_changed`], this preserves the modification time of unchanged files,
+/// and parent directories are created as needed.
+///
+/// Returns whether the file was copied.
+///
+/// Use [`WriteOptions`] to control the permissions of the copy.
+///
+/// # Errors
+///
+/// Fails if either file could not be read or the copy failed.
+pub fn copy_if_changed<P, Q>(from: P, to: Q) -> io::Result<bool>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    WriteOptions::new().copy_if_changed(from, to)
+}
+
+/// Options for how files are written, chiefly their permissions.
+///
+/// The methods of this type behave like [`write_if_changed`] and [`copy_if_changed`],
+/// but also bring the permissions of the file up to date with the options,
+/// even when its contents are already up to date.
+/// Read-only files, whether made so by these options or by earlier ones,
+/// are made writable again before they are next written.
+///
+/// Options that leave permissions alone cannot tell
+/// whether a file’s permissions were set by other options before,
+/// so when the options of a file change,
+/// call [`change_from`](Self::change_from) with the old ones before writing it.
+///
+/// This type is an etag,
+/// so assets that write files can include the options in their etags
+/// and rewrite their outputs when the options change.
+///
+/// # Examples
+///
+/// ```
+/// use mast::fs::WriteOptions;
+/// # let dir = mast::fs::TempDirs::default().create()?;
+///
+/// let path = dir.path().join("out/index.html");
+/// let options = WriteOptions::new().read_only(true);
+/// assert!(options.write_if_changed(&path, "<h1>Hi</h1>")?);
+/// assert!(std::fs::metadata(&path)?.permissions().readonly());
+///
+/// // The file is made writable to be rewritten, then read-only again.
+/// assert!(options.write_if_changed(&path, "<h1>Hello</h1>")?);
+/// assert!(std::fs::metadata(&path)?.permissions().readonly());
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    mode: Option<u32>,
+    read_only: bool,
+    preserve_permissions: bool,
+}
+
+impl WriteOptions {
+    /// Construct options that leave permissions as the operating system creates them.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            mode: None,
+            read_only: false,
+            preserve_permissions: false,
+        }
+    }
+
+    /// Set the Unix permission bits of written files, such as `0o644`.
+    ///
+    /// On other platforms, only whether the mode has any write bits is used,
+    /// to decide whether the file is read-only.
+    #[must_use]
+    pub const fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Set whether written files are made read-only,
+    /// on top of any [`mode`](Self::mode).
+    ///
+    /// By default, files are not made read-only.
+    #[must_use]
+    pub const fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Set whether copies keep the permissions of the file they are copied from,
+    /// before any [`mode`](Self::mode) is applied,
+    /// even when their contents are already up to date.
+    ///
+    /// Copying a file, like [`std::fs::copy`], always gives the copy the source’s permissions;
+    /// by default, a copy whose contents are unchanged is left with the permissions it has,
+    /// so a later change to the source’s permissions alone is not picked up.
+    /// This has no effect on files that are written rather than copied.
+    #[must_use]
+    pub const fn preserve_permissions(mut self, preserve_permissions: bool) -> Self {
+        self.preserve_permissions = preserve_permissions;
+        self
+    }
+
+    /// Write `contents` to the file at `path` like [`write_if_changed`],
+    /// then apply these options to its permissions.
+    ///
+    /// Returns whether the file’s contents or permissions were changed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the existing file could not be read,
+    /// the new contents could not be written,
+    /// or the permissions could not be set.
+    pub fn write_if_changed<P, C>(&self, path: P, contents: C) -> io::Result<bool>
+    where
+        P: AsRef<Path>,
+        C: AsRef<[u8]>,
+    {
+        let path = path.as_ref();
+        let contents = contents.as_ref();
+        let unchanged = same_len(path, contents.len() as u64)? && fs::read(path)? == contents;
+        if !unchanged {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            make_writable(path)?;
+            fs::write(path, contents)?;
+        }
+        let changed = self.apply(path, None)?;
+        Ok(!unchanged || changed)
+    }
+
+    /// Copy the file at `from` to `to` like [`copy_if_changed`],
+    /// then apply these options to the permissions of the copy.
+    ///
+    /// Returns whether the copy’s contents or permissions were changed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if either file could not be read,
+    /// the copy failed,
+    /// or the permissions could not be set.
+    pub fn copy_if_changed<P, Q>(&self, from: P, to: Q) -> io::Result<bool>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        let unchanged =
+            same_len(to, fs::metadata(from)?.len())? && hash_file(from)? == hash_file(to)?;
+        if !unchanged {
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            make_writable(to)?;
+            fs::copy(from, to)?;
+        }
+        let changed = self.apply(to, Some(from))?;
+        Ok(!unchanged || changed)
+    }
+
+    /// Prepare the file at `path`, last written with the `previous` options,
+    /// to be written with these options instead.
+    ///
+    /// If the previous options changed the file’s permissions
+    /// and these options do not set a [`mode`](Self::mode) that replaces them,
+    /// the file is removed,
+    /// so that it is next written afresh with the permissions of a new file
+    /// before these options are applied.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file could not be removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::fs::WriteOptions;
+    /// # let dir = mast::fs::TempDirs::default().create()?;
+    ///
+    /// let path = dir.path().join("index.html");
+    /// let read_only = WriteOptions::new().read_only(true);
+    /// read_only.write_if_changed(&path, "<h1>Hi</h1>")?;
+    ///
+    /// let options = WriteOptions::new();
+    /// options.change_from(&read_only, &path)?;
+    /// assert!(options.write_if_changed(&path, "<h1>Hi</h1>")?);
+    /// assert!(!std::fs::metadata(&path)?.permissions().readonly());
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn change_from<P: AsRef<Path>>(&self, previous: &Self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if previous == self || !previous.sets_permissions() || self.mode.is_some() {
+            return Ok(());
+        }
+        // Read-only files cannot be removed on Windows.
+        make_writable(path)?;
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether these options change the permissions of files at all.
+    fn sets_permissions(&self) -> bool {
+        *self != Self::new()
+    }
+
+    /// Bring the permissions of the file at `path`, copied from `source` if any,
+    /// up to date with these options, returning whether they were changed.
+    pub(crate) fn apply(&self, path: &Path, source: Option<&Path>) -> io::Result<bool> {
+        if !self.sets_permissions() {
+            return Ok(false);
+        }
+        let current = fs::metadata(path)?.permissions();
+        let mut permissions = match source {
+            Some(source) if self.preserve_permissions => fs::metadata(source)?.permissions(),
+            _ => current.clone(),
+        };
+        if let Some(mode) = self.mode {
+            #[cfg(unix)]
+            std::os::unix::fs::PermissionsExt::set_mode(&mut permissions, mode);
+            #[cfg(not(unix))]
+            permissions.set_readonly(mode & 0o222 == 0);
+        }
+        if self.read_only {
+            permissions.set_readonly(true);
+        }
+        if permissions == current {
+            return Ok(false);
+        }
+        fs::set_permissions(path, permissions)?;
+        Ok(true)
+    }
+}
+
+impl Etag for WriteOptions {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.mode.serialize(writer);
+        self.read_only.serialize(writer);
+        self.preserve_permissions.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            mode: Etag::deserialize(reader)?,
+            read_only: Etag::deserialize(reader)?,
+            preserve_permissions: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+/// Make the file at `path`, if there is one, writable,
+/// so that it can be replaced even if it was made read-only.
+pub(crate) fn make_writable(path: &Path) -> io::Result<()> {
+    let mut permissions = match fs::metadata(path) {
+        Ok(metadata) => metadata.permissions(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !permissions.readonly() {
+        return Ok(());
+    }
+    #[cfg(unix)]
+    {
+        let mode = std::os::unix::fs::PermissionsExt::mode(&permissions);
+        std::os::unix::fs::PermissionsExt::set_mode(&mut permissions, mode | 0o200);
+    }
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions)
+}
+
+/// Whether `path` is a file of length `len`.
+fn same_len(path: &Path, len: u64) -> io::Result<bool> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.is_file() && metadata.len() == len),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+use super::hash_file;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::Etag;
+use std::fs;
+use std::io;
+use std::path::Path;
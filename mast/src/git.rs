@@ -0,0 +1,356 @@
+//! Assets that read from a Git repository.
+//!
+//! These run the `git` command-line program,
+//! which must be installed and on the `PATH`.
+//! Their etags are derived from Git object ids rather than file modification times,
+//! so they are accurate even in fresh checkouts, where every modification time is new.
+//! Repository paths are resolved against the source root of the [`Roots`](crate::fs::Roots) in the context.
+//!
+//! The [`LastCommit`] strategy brings the same benefit to the file assets of [`fs`].
+//!
+//! # Examples
+//!
+//! ```
+//! use mast::asset;
+//! use mast::asset::Generator as _;
+//! use mast::git;
+//! use mast::Asset as _;
+//! # let dir = mast::fs::TempDirs::default().create()?;
+//! # let repo = dir.path();
+//! # let run = |args: &[&str]| {
+//! #     let status = std::process::Command::new("git")
+//! #         .args(["-c", "user.name=mast", "-c", "user.email=mast@example.com", "-C"])
+//! #         .arg(repo)
+//! #         .args(args)
+//! #         .status()?;
+//! #     assert!(status.success());
+//! #     Ok::<_, std::io::Error>(())
+//! # };
+//! # run(&["init", "-q"])?;
+//! # std::fs::write(repo.join("README"), "Hello")?;
+//! # run(&["add", "README"])?;
+//! # run(&["commit", "-q", "-m", "Initial commit"])?;
+//! let cx = asset::Context::default();
+//!
+//! // `repo` is the path to a repository with one commit.
+//! let mut etag = Default::default();
+//! let head = git::head(repo).update(cx, &mut etag).value.generate()?;
+//! assert_eq!(head.len(), 40);
+//!
+//! let mut etag = Default::default();
+//! let readme = git::file_at(repo, "HEAD", "README").update(cx, &mut etag);
+//! assert!(readme.is_modified());
+//! assert_eq!(readme.value.generate()?, b"Hello");
+//! assert!(git::file_at(repo, "HEAD", "README").update(cx, &mut etag).is_same());
+//!
+//! let mut etag = Default::default();
+//! assert!(!git::dirty(repo).update(cx, &mut etag).value.generate()?);
+//! std::fs::write(repo.join("README"), "Goodbye")?;
+//! let dirty = git::dirty(repo).update(cx, &mut etag);
+//! assert!(dirty.is_modified());
+//! assert!(dirty.value.generate()?);
+//! # Ok::<_, std::io::Error>(())
+//! ```
+
+/// Get the id of the commit checked out in a repository.
+///
+/// The etag is the commit id,
+/// so the asset is modified whenever a different commit is checked out.
+/// If the commit cannot be determined,
+/// the asset is considered modified and its output is the error.
+pub fn head<P: Into<PathBuf>>(repo: P) -> Head {
+    Head { repo: repo.into() }
+}
+
+/// Asset for [`head`].
+#[derive(Debug, Clone)]
+pub struct Head {
+    repo: PathBuf,
+}
+
+impl<'c> Asset<'c> for Head {
+    type Etag = String;
+    type Output = io::Result<String>;
+    type Generator = Generator<String>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let repo = fs::source_path(cx, self.repo);
+        track(etag, rev_parse(&repo, "HEAD"))
+    }
+}
+
+/// Get whether a repository has uncommitted changes,
+/// including untracked files that are not ignored.
+///
+/// The etag is the output,
+/// so the asset is modified when the repository becomes clean or dirty.
+/// If the status cannot be determined,
+/// the asset is considered modified and its output is the error.
+pub fn dirty<P: Into<PathBuf>>(repo: P) -> Dirty {
+    Dirty { repo: repo.into() }
+}
+
+/// Asset for [`dirty`].
+#[derive(Debug, Clone)]
+pub struct Dirty {
+    repo: PathBuf,
+}
+
+impl<'c> Asset<'c> for Dirty {
+    type Etag = Option<bool>;
+    type Output = io::Result<bool>;
+    type Generator = Generator<bool>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let repo = fs::source_path(cx, self.repo);
+        let dirty = git(&repo, &["status", "--porcelain", "-z"]).map(|status| !status.is_empty());
+        match dirty {
+            Ok(dirty) => {
+                let delta = Delta::cmp(etag, &Some(dirty));
+                *etag = Some(dirty);
+                delta.track(Generator { output: Ok(dirty) })
+            }
+            Err(e) => {
+                *etag = None;
+                Delta::Modified.track(Generator { output: Err(e) })
+            }
+        }
+    }
+}
+
+/// Read the contents of a file as of a revision of a repository.
+///
+/// `rev` is any revision Git understands, such as a commit id, branch name or tag,
+/// and `path` is relative to the root of the repository.
+/// The etag is the id of the file’s blob,
+/// so the asset is modified only when the file’s contents differ,
+/// and the contents are only read when the output is generated.
+/// If the file cannot be found,
+/// the asset is considered modified and its output is the error.
+pub fn file_at<P, R, Q>(repo: P, rev: R, path: Q) -> FileAt
+where
+    P: Into<PathBuf>,
+    R: Into<String>,
+    Q: Into<String>,
+{
+    FileAt {
+        repo: repo.into(),
+        rev: rev.into(),
+        path: path.into(),
+    }
+}
+
+/// Asset for [`file_at`].
+#[derive(Debug, Clone)]
+pub struct FileAt {
+    repo: PathBuf,
+    rev: String,
+    path: String,
+}
+
+impl<'c> Asset<'c> for FileAt {
+    type Etag = String;
+    type Output = io::Result<Vec<u8>>;
+    type Generator = BlobGenerator;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let repo = fs::source_path(cx, self.repo);
+        let tracked = track(
+            etag,
+            rev_parse(&repo, &format!("{}:{}", self.rev, self.path)),
+        );
+        tracked.map(|generator| BlobGenerator {
+            repo,
+            blob: generator.output,
+        })
+    }
+}
+
+/// Track files by the last commit that changed them,
+/// falling back to their [`Stamp`] while they have uncommitted changes
+/// or are not committed at all, such as untracked or ignored files.
+///
+/// This is a [`Strategy`] for the file assets in [`fs`],
+/// chosen with their `etag_last_commit` methods.
+/// Unlike [`Mtime`](fs::Mtime), it is not fooled by a fresh clone or checkout
+/// giving every file a new modification time,
+/// and unlike [`ContentHash`](fs::ContentHash), it does not read the files;
+/// however, it runs `git` twice for each file on every update.
+/// It fails for files outside a repository.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::fs;
+/// use mast::Asset as _;
+/// # let dir = mast::fs::TempDirs::default().create()?;
+/// # let repo = dir.path();
+/// # let run = |args: &[&str]| {
+/// #     let status = std::process::Command::new("git")
+/// #         .args(["-c", "user.name=mast", "-c", "user.email=mast@example.com", "-C"])
+/// #         .arg(repo)
+/// #         .args(args)
+/// #         .status()?;
+/// #     assert!(status.success());
+/// #     Ok::<_, std::io::Error>(())
+/// # };
+/// # run(&["init", "-q"])?;
+/// # std::fs::write(repo.join("index.md"), "# Hi")?;
+/// # run(&["add", "index.md"])?;
+/// # run(&["commit", "-q", "-m", "Initial commit"])?;
+/// let cx = asset::Context::default();
+/// let page = || fs::text(repo.join("index.md")).etag_last_commit();
+///
+/// // `index.md` is committed in `repo`.
+/// let mut etag = Default::default();
+/// assert!(page().update(cx, &mut etag).is_modified());
+///
+/// // Rewriting the file, as a fresh checkout would, does not modify the asset…
+/// std::fs::remove_file(repo.join("index.md"))?;
+/// std::fs::write(repo.join("index.md"), "# Hi")?;
+/// assert!(page().update(cx, &mut etag).is_same());
+///
+/// // …but uncommitted changes do.
+/// std::fs::write(repo.join("index.md"), "# Hello")?;
+/// assert!(page().update(cx, &mut etag).is_modified());
+///
+/// // Files that were never committed, such as ignored ones, are tracked by their stamp.
+/// std::fs::write(repo.join(".gitignore"), "draft.md")?;
+/// std::fs::write(repo.join("draft.md"), "# Draft")?;
+/// let draft = || fs::text(repo.join("draft.md")).etag_last_commit();
+/// let mut etag = Default::default();
+/// assert!(draft().update(cx, &mut etag).is_modified());
+/// assert!(draft().update(cx, &mut etag).is_same());
+/// std::fs::write(repo.join("draft.md"), "# Second draft")?;
+/// assert!(draft().update(cx, &mut etag).is_modified());
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LastCommit;
+
+impl Strategy for LastCommit {
+    type Etag = (String, Option<Stamp>);
+    fn etag(&self, path: &Path) -> io::Result<Self::Etag> {
+        let dir = match path.parent() {
+            Some(parent) if parent != Path::new("") => parent,
+            _ => Path::new("."),
+        };
+        let name = path.file_name().unwrap_or(path.as_os_str());
+
+        let commit = git(dir, &with_path(&["log", "-1", "--format=%H", "--"], name))?;
+        let commit =
+            String::from_utf8(commit).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let commit = commit.trim_end().to_owned();
+        // `git status` says nothing about ignored files,
+        // so a file with no commit is always tracked by its stamp.
+        let local = if !commit.is_empty()
+            && git(dir, &with_path(&["status", "--porcelain", "--"], name))?.is_empty()
+        {
+            None
+        } else {
+            Some(Stamp::of(path)?)
+        };
+        Ok((commit, local))
+    }
+}
+
+/// Generator for [`Head`] and [`Dirty`].
+#[derive(Debug)]
+pub struct Generator<T> {
+    output: io::Result<T>,
+}
+
+impl<T> asset::Generator for Generator<T> {
+    type Output = io::Result<T>;
+
+    fn generate(self) -> Self::Output {
+        self.output
+    }
+}
+
+/// Generator for [`FileAt`].
+#[derive(Debug)]
+pub struct BlobGenerator {
+    repo: PathBuf,
+    blob: io::Result<String>,
+}
+
+impl asset::Generator for BlobGenerator {
+    type Output = io::Result<Vec<u8>>;
+
+    fn generate(self) -> Self::Output {
+        git(&self.repo, &["cat-file", "blob", &self.blob?])
+    }
+}
+
+/// Track an object id as the etag, resetting the etag on failure.
+fn track(etag: &mut String, id: io::Result<String>) -> Tracked<Generator<String>> {
+    match id {
+        Ok(id) => {
+            let delta = Delta::cmp(etag, &id);
+            etag.clone_from(&id);
+            delta.track(Generator { output: Ok(id) })
+        }
+        Err(e) => {
+            etag.clear();
+            Delta::Modified.track(Generator { output: Err(e) })
+        }
+    }
+}
+
+/// Resolve a revision to the id of the object it names.
+fn rev_parse(repo: &Path, rev: &str) -> io::Result<String> {
+    let id = git(repo, &["rev-parse", "--verify", "--end-of-options", rev])?;
+    let id = String::from_utf8(id).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(id.trim_end().to_owned())
+}
+
+/// Append a path to a list of arguments.
+fn with_path<'a>(args: &'a [&'a str], path: &'a OsStr) -> Vec<&'a OsStr> {
+    args.iter().map(OsStr::new).chain([path]).collect()
+}
+
+/// Run `git` in a repository, returning its standard output.
+///
+/// Pathspecs in `args` are taken literally.
+fn git<S: AsRef<OsStr>>(repo: &Path, args: &[S]) -> io::Result<Vec<u8>> {
+    let output = process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .arg("--literal-pathspecs")
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        let args: Vec<_> = args
+            .iter()
+            .map(|arg| arg.as_ref().to_string_lossy())
+            .collect();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let msg = format!(
+            "`git {}` failed: {}\n{stderr}",
+            args.join(" "),
+            output.status
+        );
+        return Err(io::Error::new(io::ErrorKind::Other, msg));
+    }
+    Ok(output.stdout)
+}
+
+use crate::asset;
+use crate::asset::Context;
+use crate::fs;
+use crate::fs::Stamp;
+use crate::fs::Strategy;
+use crate::Asset;
+use crate::Delta;
+use crate::Tracked;
+use std::borrow::ToOwned;
+use std::ffi::OsStr;
+use std::format;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::string::String;
+use std::vec::Vec;
@@ -0,0 +1,337 @@
+//! Stable content hashing,
+//! for assets whose etags are derived from the data itself.
+
+/// An incremental [SHA-256] hasher.
+///
+/// This type implements [`Writer`],
+/// so any [`Etag`] can be hashed by serializing it into a `Sha256`.
+///
+/// [SHA-256]: https://en.wikipedia.org/wiki/SHA-2
+///
+/// # Examples
+///
+/// ```
+/// # use mast::hash::Sha256;
+/// let mut hasher = Sha256::new();
+/// hasher.update(b"Hello ");
+/// hasher.update(b"world!");
+/// assert_eq!(hasher.finish(), Sha256::digest(b"Hello world!"));
+/// ```
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+impl Sha256 {
+    /// Construct a new hasher that has not yet consumed any data.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: INITIAL_STATE,
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    /// Hash a single byte slice in one go.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mast::hash::Sha256;
+    /// assert_eq!(
+    ///     Sha256::digest(b"abc").to_string(),
+    ///     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn digest(bytes: &[u8]) -> Digest {
+        let mut hasher = Self::new();
+        hasher.update(bytes);
+        hasher.finish()
+    }
+
+    /// Feed more data into the hasher.
+    #[allow(clippy::missing_panics_doc)] // All the `unwrap`s are on correctly-sized chunks.
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.total_len = self.total_len.wrapping_add(bytes.len() as u64);
+
+        if self.block_len != 0 {
+            let taken = bytes.len().min(64 - self.block_len);
+            self.block[self.block_len..][..taken].copy_from_slice(&bytes[..taken]);
+            self.block_len += taken;
+            bytes = &bytes[taken..];
+            if self.block_len < 64 {
+                return;
+            }
+            compress(&mut self.state, &self.block);
+            self.block_len = 0;
+        }
+
+        let mut blocks = bytes.chunks_exact(64);
+        for block in &mut blocks {
+            compress(&mut self.state, block.try_into().unwrap());
+        }
+        let rest = blocks.remainder();
+        self.block[..rest.len()].copy_from_slice(rest);
+        self.block_len = rest.len();
+    }
+
+    /// Finish hashing and obtain the resulting [`Digest`].
+    #[must_use]
+    pub fn finish(mut self) -> Digest {
+        let bit_len = self.total_len.wrapping_mul(8);
+
+        self.block[self.block_len] = 0x80;
+        self.block[self.block_len + 1..].fill(0);
+        if 64 - 8 <= self.block_len {
+            compress(&mut self.state, &self.block);
+            self.block.fill(0);
+        }
+        self.block[64 - 8..].copy_from_slice(&bit_len.to_be_bytes());
+        compress(&mut self.state, &self.block);
+
+        let mut digest = [0; 32];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        Digest(digest)
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Sha256 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sha256")
+            .field("total_len", &self.total_len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Writer for Sha256 {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+    fn use_varint(&self) -> bool {
+        false
+    }
+}
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+#[allow(clippy::many_single_char_names)]
+fn compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut schedule = [0_u32; 64];
+    for (word, bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..64 {
+        let s0 = schedule[i - 15].rotate_right(7)
+            ^ schedule[i - 15].rotate_right(18)
+            ^ (schedule[i - 15] >> 3);
+        let s1 = schedule[i - 2].rotate_right(17)
+            ^ schedule[i - 2].rotate_right(19)
+            ^ (schedule[i - 2] >> 10);
+        schedule[i] = schedule[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(schedule[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (&k, &w) in ROUND_CONSTANTS.iter().zip(&schedule) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(k)
+            .wrapping_add(w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (word, new) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(new);
+    }
+}
+
+/// The 32-byte output of [`Sha256`].
+///
+/// This is an [`Etag`] in its own right,
+/// and its [`Display`] implementation formats it as lowercase hexadecimal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Digest(pub [u8; 32]);
+
+impl Digest {
+    /// Obtain the raw bytes of the digest.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Display for Digest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Digest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest({self})")
+    }
+}
+
+impl Etag for Digest {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_bytes(&self.0);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        reader.read_array().map(Self)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    #[test]
+    fn known_answers() {
+        assert_eq!(
+            Sha256::digest(b"").to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        );
+        assert_eq!(
+            Sha256::digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").to_string(),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        );
+        let mut hasher = Sha256::new();
+        for _ in 0..1000 {
+            hasher.update(&[b'a'; 1000]);
+        }
+        assert_eq!(
+            hasher.finish().to_string(),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+        );
+    }
+
+    #[test]
+    fn split_updates() {
+        let data = [0x5A_u8; 200];
+        let expected = Sha256::digest(&data);
+        for split in 0..data.len() {
+            let mut hasher = Sha256::new();
+            hasher.update(&data[..split]);
+            hasher.update(&data[split..]);
+            assert_eq!(hasher.finish(), expected, "split at {split}");
+        }
+    }
+
+    use super::Sha256;
+    use alloc::string::ToString;
+}
+
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::Etag;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
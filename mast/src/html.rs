@@ -0,0 +1,301 @@
+//! Rewriting HTML as it streams through, with [`lol_html`].
+//!
+//! The [`rewrite`] asset applies a set of [`Rules`] to the output of a page asset,
+//! for cross-cutting transformations such as adding anchor links to headings
+//! or pointing image tags at fingerprinted file names.
+//! Each page is rewritten only when it or the rules have changed.
+//!
+//! The `html` feature needs Rust 1.85 or later, above the rest of the crate,
+//! because `lol_html` 2 does.
+//!
+//! # Examples
+//!
+//! ```
+//! use mast::asset;
+//! use mast::asset::Generator as _;
+//! use mast::html;
+//! use mast::Asset as _;
+//! # use mast::{Asset, Delta, Tracked};
+//! # struct Page(&'static str);
+//! # impl<'c> Asset<'c> for Page {
+//! #     type Etag = ();
+//! #     type Output = &'static str;
+//! #     type Generator = Self;
+//! #     fn update(self, _: asset::Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+//! #         Delta::Modified.track(self)
+//! #     }
+//! # }
+//! # impl asset::Generator for Page {
+//! #     type Output = &'static str;
+//! #     fn generate(self) -> Self::Output { self.0 }
+//! # }
+//! # fn page(html: &'static str) -> Page { Page(html) }
+//!
+//! let rules = html::Rules::new().element("h2[id]", |el| {
+//!     let href = format!("#{}", el.get_attribute("id").unwrap());
+//!     el.prepend(&format!(r#"<a href="{href}">§</a> "#), html::ContentType::Html);
+//!     Ok(())
+//! });
+//!
+//! let mut etag = Default::default();
+//! // `page` is some asset outputting HTML.
+//! let asset = html::rewrite(page(r#"<h2 id="intro">Intro</h2>"#), rules);
+//! assert_eq!(
+//!     asset.update(asset::Context::default(), &mut etag).value.generate()?,
+//!     r##"<h2 id="intro"><a href="#intro">§</a> Intro</h2>"##,
+//! );
+//! # Ok::<_, html::RewritingError>(())
+//! ```
+
+/// A set of rules for rewriting HTML, used by [`rewrite`].
+///
+/// Cloning a set of rules is cheap, so the same rules can be given to every page.
+#[derive(Clone, Default)]
+pub struct Rules {
+    elements: Vec<(Selector, ElementHandler)>,
+    version: Digest,
+}
+
+type ElementHandler = Arc<dyn Fn(&mut Element<'_, '_>) -> HandlerResult + Send + Sync>;
+
+impl Rules {
+    /// Construct an empty set of rules, which leaves HTML unchanged.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule that calls `handler` on every element matching the CSS selector `selector`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the selector is invalid or not supported by [`lol_html`].
+    #[must_use]
+    pub fn element<F>(mut self, selector: &str, handler: F) -> Self
+    where
+        F: Fn(&mut Element<'_, '_>) -> HandlerResult + Send + Sync + 'static,
+    {
+        let selector = match selector.parse() {
+            Ok(selector) => selector,
+            Err(e) => panic!("invalid selector {selector:?}: {e}"),
+        };
+        self.elements.push((selector, Arc::new(handler)));
+        self
+    }
+
+    /// Set a fingerprint of the rules and of everything they depend on,
+    /// such as a fingerprint manifest that image tags are rewritten with.
+    ///
+    /// Handlers are opaque functions, so the rules are only considered changed,
+    /// causing every page to be rewritten,
+    /// when this version changes.
+    #[must_use]
+    pub fn version<E: Etag>(mut self, version: &E) -> Self {
+        let mut hasher = Sha256::new();
+        version.serialize(&mut hasher);
+        self.version = hasher.finish();
+        self
+    }
+
+    /// Apply the rules to a string of HTML.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a handler returns an error.
+    pub fn apply(&self, html: &str) -> Result<String, RewritingError> {
+        let element_content_handlers = self
+            .elements
+            .iter()
+            .map(|(selector, handler)| {
+                let handler = &**handler;
+                let handlers = ElementContentHandlers::default().element(handler);
+                (Cow::Borrowed(selector), handlers)
+            })
+            .collect();
+        lol_html::rewrite_str(
+            html,
+            RewriteStrSettings {
+                element_content_handlers,
+                ..RewriteStrSettings::new()
+            },
+        )
+    }
+}
+
+impl Debug for Rules {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rules")
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Rewrite the HTML output of `page` with a set of rules.
+///
+/// `page` should output HTML as anything that is [`AsRef<str>`].
+/// The page is rewritten only when it is modified
+/// or the [version](Rules::version) of the rules has changed.
+/// If rewriting fails, the page is rewritten again by the next build
+/// even if neither has changed.
+pub fn rewrite<A>(page: A, rules: Rules) -> Rewrite<A> {
+    Rewrite { page, rules }
+}
+
+/// Asset for [`rewrite`].
+#[derive(Debug)]
+pub struct Rewrite<A> {
+    page: A,
+    rules: Rules,
+}
+
+impl<'c, A> Asset<'c> for Rewrite<A>
+where
+    A: Asset<'c>,
+    A::Output: AsRef<str>,
+{
+    type Etag = (A::Etag, Option<Digest>);
+    type Output = Result<String, RewritingError>;
+    type Generator = Generator<'c, A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (page_etag, version) = etag;
+        let page = self.page.update(cx, page_etag);
+        let delta = page
+            .delta
+            .or(Delta::cmp(&*version, &Some(self.rules.version)));
+        // The page’s etag has already been updated,
+        // so forget the version until the rewrite succeeds to make a failure be retried.
+        if delta == Delta::Modified {
+            *version = None;
+        }
+        delta.track(Generator {
+            page: page.value,
+            rules: self.rules,
+            version,
+        })
+    }
+}
+
+/// Generator for [`Rewrite`].
+#[derive(Debug)]
+pub struct Generator<'c, G> {
+    page: G,
+    rules: Rules,
+    version: &'c mut Option<Digest>,
+}
+
+impl<G> asset::Generator for Generator<'_, G>
+where
+    G: asset::Generator,
+    G::Output: AsRef<str>,
+{
+    type Output = Result<String, RewritingError>;
+
+    fn generate(self) -> Self::Output {
+        let html = self.rules.apply(self.page.generate().as_ref())?;
+        *self.version = Some(self.rules.version);
+        Ok(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn incremental() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let rules = |version: u32| {
+            let runs = runs.clone();
+            Rules::new()
+                .element("p", move |el| {
+                    runs.fetch_add(1, SeqCst);
+                    el.set_attribute("class", "text")?;
+                    Ok(())
+                })
+                .version(&version)
+        };
+        let cx = Context::default();
+        let mut etag = Default::default();
+        let mut rewrite = |page: u32, rules| {
+            let page = asset::constant("<p>hi</p>").version(page);
+            let tracked = super::rewrite(page, rules).update(cx, &mut etag);
+            (tracked.delta, tracked.value.generate().unwrap())
+        };
+
+        let expected = String::from(r#"<p class="text">hi</p>"#);
+        assert_eq!(rewrite(1, rules(1)), (Delta::Modified, expected.clone()));
+        assert_eq!(rewrite(1, rules(1)).0, Delta::Same);
+        assert_eq!(rewrite(2, rules(1)).0, Delta::Modified);
+        assert_eq!(rewrite(2, rules(2)).0, Delta::Modified);
+        assert_eq!(rewrite(2, rules(2)).0, Delta::Same);
+        assert_eq!(runs.load(SeqCst), 5);
+    }
+
+    #[test]
+    fn retry_failure() {
+        let fail = Arc::new(AtomicBool::new(true));
+        let rules = {
+            let fail = fail.clone();
+            Rules::new().element("p", move |_| {
+                if fail.load(SeqCst) {
+                    return Err("failed".into());
+                }
+                Ok(())
+            })
+        };
+        let cx = Context::default();
+        let mut etag = Default::default();
+        let mut rewrite = || {
+            let page = asset::constant("<p>hi</p>");
+            let tracked = super::rewrite(page, rules.clone()).update(cx, &mut etag);
+            (tracked.delta, tracked.value.generate())
+        };
+
+        let (delta, html) = rewrite();
+        assert_eq!(delta, Delta::Modified);
+        assert!(html.is_err());
+
+        // Neither the page nor the rules changed, but the failed rewrite is retried.
+        fail.store(false, SeqCst);
+        let (delta, html) = rewrite();
+        assert_eq!(delta, Delta::Modified);
+        assert_eq!(html.unwrap(), "<p>hi</p>");
+
+        assert_eq!(rewrite().0, Delta::Same);
+    }
+
+    use super::Rules;
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::Asset as _;
+    use crate::Delta;
+    use std::string::String;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::SeqCst;
+    use std::sync::Arc;
+}
+
+pub use lol_html::errors::RewritingError;
+pub use lol_html::html_content::ContentType;
+pub use lol_html::html_content::Element;
+pub use lol_html::HandlerResult;
+
+use crate::asset;
+use crate::asset::Asset;
+use crate::asset::Context;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use lol_html::ElementContentHandlers;
+use lol_html::RewriteStrSettings;
+use lol_html::Selector;
+use std::borrow::Cow;
+use std::string::String;
+use std::sync::Arc;
+use std::vec::Vec;
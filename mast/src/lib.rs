@@ -14,6 +14,8 @@
     missing_debug_implementations,
     clippy::pedantic
 )]
+// We put `use` declarations at the bottom of modules, after any tests.
+#![allow(clippy::items_after_test_module)]
 #![no_std]
 
 #[cfg(feature = "std")]
@@ -25,9 +27,87 @@ extern crate alloc;
 pub mod asset;
 pub use asset::Asset;
 
+#[cfg(feature = "derive")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "derive")))]
+pub use mast_derive::Asset;
+
 pub mod etag;
 pub use etag::Etag;
 
+pub mod build;
+
+#[cfg(feature = "bench")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "bench")))]
+pub mod bench;
+
+pub mod hash;
+
+pub mod time;
+
+pub mod mime;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod observe;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod diagnostic;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod deploy;
+
+pub mod db;
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "alloc")))]
+pub mod vfs;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod check;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod fs;
+
+#[cfg(feature = "embed")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "embed")))]
+pub mod embed;
+
+#[cfg(feature = "git")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "git")))]
+pub mod git;
+
+#[cfg(feature = "html")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "html")))]
+pub mod html;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod net;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod pipeline;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod process;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod store;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+pub mod search;
+
+#[cfg(feature = "arc-swap")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "arc-swap")))]
+pub mod share_arcswap;
+
 mod tracked {
     /// A value as well whether it is the same or modified.
     #[derive(Debug, Clone, Copy)]
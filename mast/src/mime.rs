@@ -0,0 +1,218 @@
+//! Inferring the content types of outputs.
+//!
+//! Content types are guessed from the extension of a path with [`from_extension`]
+//! and from the first bytes of the contents with [`sniff`];
+//! the [`infer`] combinator annotates the output of an asset with the result of both.
+
+/// Annotate the byte output of an asset with its content type.
+///
+/// The content type is guessed from the extension of `path` if it has a known one,
+/// then from the contents with [`sniff`];
+/// pass an empty path to rely on the contents alone.
+/// The etag of the inner asset is passed through unchanged,
+/// and the content type is only computed when the output is generated.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::mime;
+/// use mast::Asset as _;
+///
+/// let page = asset::static_str("<!DOCTYPE html><h1>Hi</h1>");
+/// let typed = mime::infer(page, "").update(asset::Context::default(), &mut ());
+/// assert_eq!(typed.value.generate().content_type, "text/html; charset=utf-8");
+///
+/// let style = asset::static_str("body {}");
+/// let typed = mime::infer(style, "style.css").update(asset::Context::default(), &mut ());
+/// assert_eq!(typed.value.generate().content_type, "text/css; charset=utf-8");
+/// ```
+pub fn infer<A, P: AsRef<str>>(asset: A, path: P) -> Infer<A, P> {
+    Infer { asset, path }
+}
+
+/// Asset for [`infer`].
+#[derive(Debug, Clone)]
+pub struct Infer<A, P> {
+    asset: A,
+    path: P,
+}
+
+impl<'c, A, P> Asset<'c> for Infer<A, P>
+where
+    A: Asset<'c>,
+    A::Output: AsRef<[u8]>,
+    P: AsRef<str>,
+{
+    type Etag = A::Etag;
+    type Output = Typed<A::Output>;
+    type Generator = Generator<A::Generator, P>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let path = self.path;
+        self.asset
+            .update(cx, etag)
+            .map(|generator| Generator { generator, path })
+    }
+}
+
+/// Generator for [`Infer`].
+#[derive(Debug)]
+pub struct Generator<G, P> {
+    generator: G,
+    path: P,
+}
+
+impl<G, P> asset::Generator for Generator<G, P>
+where
+    G: asset::Generator,
+    G::Output: AsRef<[u8]>,
+    P: AsRef<str>,
+{
+    type Output = Typed<G::Output>;
+
+    fn generate(self) -> Self::Output {
+        let value = self.generator.generate();
+        let content_type = from_extension(self.path.as_ref())
+            .unwrap_or_else(|| sniff(value.as_ref()).unwrap_or(OCTET_STREAM));
+        Typed {
+            content_type,
+            value,
+        }
+    }
+}
+
+/// A value annotated with its content type, as output by [`infer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Typed<T> {
+    /// The media type of the value, suitable for a `Content-Type` header.
+    pub content_type: &'static str,
+    /// The value itself.
+    pub value: T,
+}
+
+/// The content type of arbitrary binary data,
+/// used when no better one can be inferred.
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Guess the content type of a file from the extension of its path.
+///
+/// Returns [`None`] if the path has no extension or the extension is not recognized.
+/// Textual types include a UTF-8 `charset` parameter.
+///
+/// # Examples
+///
+/// ```
+/// use mast::mime;
+///
+/// assert_eq!(mime::from_extension("img/logo.PNG"), Some("image/png"));
+/// assert_eq!(mime::from_extension("README"), None);
+/// ```
+#[must_use]
+pub fn from_extension(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let (_, extension) = name.rsplit_once('.')?;
+    Some(match extension {
+        e if e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm") => {
+            "text/html; charset=utf-8"
+        }
+        e if e.eq_ignore_ascii_case("css") => "text/css; charset=utf-8",
+        e if e.eq_ignore_ascii_case("js") || e.eq_ignore_ascii_case("mjs") => {
+            "text/javascript; charset=utf-8"
+        }
+        e if e.eq_ignore_ascii_case("json") => "application/json",
+        e if e.eq_ignore_ascii_case("xml") => "application/xml",
+        e if e.eq_ignore_ascii_case("txt") => "text/plain; charset=utf-8",
+        e if e.eq_ignore_ascii_case("md") => "text/markdown; charset=utf-8",
+        e if e.eq_ignore_ascii_case("svg") => "image/svg+xml",
+        e if e.eq_ignore_ascii_case("png") => "image/png",
+        e if e.eq_ignore_ascii_case("jpg") || e.eq_ignore_ascii_case("jpeg") => "image/jpeg",
+        e if e.eq_ignore_ascii_case("gif") => "image/gif",
+        e if e.eq_ignore_ascii_case("webp") => "image/webp",
+        e if e.eq_ignore_ascii_case("avif") => "image/avif",
+        e if e.eq_ignore_ascii_case("ico") => "image/x-icon",
+        e if e.eq_ignore_ascii_case("woff") => "font/woff",
+        e if e.eq_ignore_ascii_case("woff2") => "font/woff2",
+        e if e.eq_ignore_ascii_case("wasm") => "application/wasm",
+        e if e.eq_ignore_ascii_case("pdf") => "application/pdf",
+        e if e.eq_ignore_ascii_case("mp4") => "video/mp4",
+        e if e.eq_ignore_ascii_case("webm") => "video/webm",
+        _ => return None,
+    })
+}
+
+/// Guess the content type of data from its first bytes.
+///
+/// Binary formats are recognized by their signatures,
+/// and HTML, SVG and XML documents by their opening tags.
+/// Other data that is valid UTF-8 without control characters is considered plain text.
+/// Returns [`None`] if nothing matches.
+///
+/// # Examples
+///
+/// ```
+/// use mast::mime;
+///
+/// assert_eq!(mime::sniff(b"\x89PNG\r\n\x1a\n..."), Some("image/png"));
+/// assert_eq!(mime::sniff(b"  <svg xmlns=\"http://www.w3.org/2000/svg\">"), Some("image/svg+xml"));
+/// assert_eq!(mime::sniff(b"\x00\x01\x02"), None);
+/// ```
+#[must_use]
+pub fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: [(&[u8], &str); 9] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"\x00asm", "application/wasm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+        (b"\x1A\x45\xDF\xA3", "video/webm"),
+    ];
+    for (signature, content_type) in SIGNATURES {
+        if bytes.starts_with(signature) {
+            return Some(content_type);
+        }
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+        return Some(if bytes[8..].starts_with(b"avif") {
+            "image/avif"
+        } else {
+            "video/mp4"
+        });
+    }
+
+    let text = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let text = core::str::from_utf8(text).ok()?;
+    if text
+        .chars()
+        .any(|c| c.is_control() && !c.is_ascii_whitespace())
+    {
+        return None;
+    }
+    let start = text.trim_start();
+    let starts_with = |prefix: &str| {
+        start
+            .get(..prefix.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(prefix))
+    };
+    Some(if starts_with("<!doctype html") || starts_with("<html") {
+        "text/html; charset=utf-8"
+    } else if starts_with("<svg") {
+        "image/svg+xml"
+    } else if starts_with("<?xml") {
+        "application/xml"
+    } else {
+        "text/plain; charset=utf-8"
+    })
+}
+
+use crate::asset;
+use crate::asset::Context;
+use crate::Asset;
+use crate::Tracked;
@@ -0,0 +1,431 @@
+//! Assets that fetch resources over the network.
+//!
+//! HTTP is supported through the [`Client`] trait,
+//! which is a thin abstraction over whichever client library you already use.
+//! The [`fetch`] asset remembers the validators the server sent with the last response
+//! (its `ETag` and `Last-Modified` headers)
+//! and sends them back as `If-None-Match` and `If-Modified-Since`,
+//! so a server that answers `304 Not Modified` makes the asset report itself as the same.
+//!
+//! To avoid hammering remote APIs during watch builds,
+//! [`Fetch::max_age`] skips requests entirely while the last response is fresh enough,
+//! and a [`RateLimit`] in the [`Context`] spaces out the requests that are made.
+//!
+//! # Examples
+//!
+//! ```
+//! use mast::asset;
+//! use mast::asset::Generator as _;
+//! use mast::net;
+//! use mast::Asset as _;
+//! use std::cell::Cell;
+//! use std::convert::Infallible;
+//!
+//! #[derive(Default)]
+//! struct Server {
+//!     requests: Cell<usize>,
+//! }
+//!
+//! impl net::Client for Server {
+//!     type Error = Infallible;
+//!     fn get(&self, url: &str, conditions: &net::Conditions<'_>) -> Result<net::Response, Infallible> {
+//!         self.requests.set(self.requests.get() + 1);
+//!         if conditions.if_none_match == Some("\"v1\"") {
+//!             return Ok(net::Response::NotModified);
+//!         }
+//!         let body = format!("contents of {url}").into_bytes();
+//!         Ok(net::Response::Ok(net::Resource::new(body).etag("\"v1\"")))
+//!     }
+//! }
+//!
+//! let server = Server::default();
+//! let mut etag = Default::default();
+//! let cx = asset::Context::default();
+//!
+//! let res = net::fetch(&server, "https://example.com/api").update(cx, &mut etag);
+//! assert!(res.is_modified());
+//! assert_eq!(res.value.generate().unwrap(), b"contents of https://example.com/api");
+//!
+//! // The server answers `304 Not Modified`, and the previous body is reused.
+//! let res = net::fetch(&server, "https://example.com/api").update(cx, &mut etag);
+//! assert!(res.is_same());
+//! assert_eq!(res.value.generate().unwrap(), b"contents of https://example.com/api");
+//! assert_eq!(server.requests.get(), 2);
+//! ```
+
+/// An HTTP client.
+///
+/// Implement this for whichever client you use to make requests.
+pub trait Client {
+    /// The error type of requests made by this client.
+    type Error;
+
+    /// Make a `GET` request for `url`,
+    /// adding the `If-None-Match` and `If-Modified-Since` headers given in `conditions`.
+    ///
+    /// A `304 Not Modified` response should be returned as [`Response::NotModified`];
+    /// other unsuccessful responses should be returned as errors.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request could not be made or the server responded with an error.
+    fn get(&self, url: &str, conditions: &Conditions<'_>) -> Result<Response, Self::Error>;
+}
+
+impl<C: ?Sized + Client> Client for &C {
+    type Error = C::Error;
+    fn get(&self, url: &str, conditions: &Conditions<'_>) -> Result<Response, Self::Error> {
+        (**self).get(url, conditions)
+    }
+}
+
+/// The conditional request headers to send with a request,
+/// derived from the previous response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Conditions<'a> {
+    /// The value of the `If-None-Match` header, if it should be sent.
+    pub if_none_match: Option<&'a str>,
+    /// The value of the `If-Modified-Since` header, if it should be sent.
+    pub if_modified_since: Option<&'a str>,
+}
+
+/// The response to a request made by a [`Client`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// The server responded with `304 Not Modified`.
+    NotModified,
+    /// The server responded successfully with the resource.
+    Ok(Resource),
+}
+
+/// A resource returned by a server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Resource {
+    /// The body of the response.
+    pub body: Vec<u8>,
+    /// The value of the `ETag` header, if there was one.
+    pub etag: Option<String>,
+    /// The value of the `Last-Modified` header, if there was one.
+    pub last_modified: Option<String>,
+}
+
+impl Resource {
+    /// Construct a resource with the given body and no validators.
+    #[must_use]
+    pub fn new(body: Vec<u8>) -> Self {
+        Self {
+            body,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Set the value of the `ETag` header.
+    #[must_use]
+    pub fn etag<S: Into<String>>(mut self, etag: S) -> Self {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// Set the value of the `Last-Modified` header.
+    #[must_use]
+    pub fn last_modified<S: Into<String>>(mut self, last_modified: S) -> Self {
+        self.last_modified = Some(last_modified.into());
+        self
+    }
+}
+
+/// Fetch a resource with a [`Client`].
+///
+/// The request is made when the asset is updated,
+/// and the asset is modified when the body of the response changes.
+/// The last body is kept in the etag,
+/// so it can be output again when the server responds with `304 Not Modified`.
+/// If the request fails, the asset is modified, its output is the error,
+/// and the request is made again on the next build.
+/// If the URL changes, the state saved for the previous URL is discarded.
+pub fn fetch<C: Client, U: Into<String>>(client: C, url: U) -> Fetch<C> {
+    Fetch {
+        client,
+        url: url.into(),
+        max_age: None,
+    }
+}
+
+/// Asset for [`fetch`].
+#[derive(Debug)]
+pub struct Fetch<C> {
+    client: C,
+    url: String,
+    max_age: Option<Duration>,
+}
+
+impl<C> Fetch<C> {
+    /// Reuse the last response without making a request
+    /// if it was received less than `max_age` ago,
+    /// according to [`time::now`].
+    ///
+    /// By default, a request is made on every update.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::asset;
+    /// use mast::net;
+    /// use mast::time;
+    /// use mast::Asset as _;
+    /// use std::cell::Cell;
+    /// use std::time::Duration;
+    ///
+    /// struct Server(Cell<usize>);
+    ///
+    /// impl net::Client for Server {
+    ///     type Error = std::convert::Infallible;
+    ///     fn get(&self, _: &str, _: &net::Conditions<'_>) -> Result<net::Response, Self::Error> {
+    ///         self.0.set(self.0.get() + 1);
+    ///         Ok(net::Response::Ok(net::Resource::new(b"[]".to_vec())))
+    ///     }
+    /// }
+    ///
+    /// let server = Server(Cell::new(0));
+    /// let clock = (time::Clock::new(|| time::Time::from_unix_nanos(0)),);
+    /// let cx = asset::Context::from_tuple(&clock);
+    /// let mut etag = Default::default();
+    ///
+    /// let fetch = || net::fetch(&server, "https://example.com").max_age(Duration::from_secs(60));
+    /// fetch().update(cx, &mut etag);
+    /// assert!(fetch().update(cx, &mut etag).is_same());
+    /// assert_eq!(server.0.get(), 1);
+    /// ```
+    #[must_use]
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+}
+
+impl<'c, C: Client> Asset<'c> for Fetch<C> {
+    type Etag = State;
+    type Output = Result<Vec<u8>, C::Error>;
+    type Generator = Generator<C::Error>;
+
+    fn update(self, cx: Context<'c>, state: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let now = time::now(cx);
+
+        // Validators and the body of another URL say nothing about this one.
+        if state.url.as_deref() != Some(&*self.url) {
+            *state = State {
+                url: Some(self.url.clone()),
+                ..State::default()
+            };
+        }
+
+        if let (Some(body), Some(max_age), Some(now), Some(fetched)) =
+            (&state.body, self.max_age, now, state.fetched)
+        {
+            let age = now.unix_nanos() - fetched.unix_nanos();
+            if (0..=i128::try_from(max_age.as_nanos()).unwrap_or(i128::MAX)).contains(&age) {
+                return Delta::Same.track(Generator {
+                    body: Ok(body.clone()),
+                });
+            }
+        }
+
+        if let Some(limit) = cx.try_get::<RateLimit>() {
+            limit.wait();
+        }
+
+        let conditions = match &state.body {
+            Some(_) => Conditions {
+                if_none_match: state.etag.as_deref(),
+                if_modified_since: state.last_modified.as_deref(),
+            },
+            None => Conditions::default(),
+        };
+
+        match self.client.get(&self.url, &conditions) {
+            Err(e) => Delta::Modified.track(Generator { body: Err(e) }),
+            Ok(Response::NotModified) if state.body.is_some() => {
+                state.fetched = now;
+                let body = state.body.clone().unwrap_or_default();
+                Delta::Same.track(Generator { body: Ok(body) })
+            }
+            // Without a previous body there is nothing to reuse.
+            Ok(Response::NotModified) => Delta::Modified.track(Generator {
+                body: Ok(Vec::new()),
+            }),
+            Ok(Response::Ok(resource)) => {
+                let delta = Delta::cmp(&state.body.as_ref(), &Some(&resource.body));
+                *state = State {
+                    url: Some(self.url),
+                    etag: resource.etag,
+                    last_modified: resource.last_modified,
+                    body: Some(resource.body.clone()),
+                    fetched: now,
+                };
+                delta.track(Generator {
+                    body: Ok(resource.body),
+                })
+            }
+        }
+    }
+}
+
+/// Generator for [`Fetch`].
+#[derive(Debug)]
+pub struct Generator<E> {
+    body: Result<Vec<u8>, E>,
+}
+
+impl<E> asset::Generator for Generator<E> {
+    type Output = Result<Vec<u8>, E>;
+
+    fn generate(self) -> Self::Output {
+        self.body
+    }
+}
+
+/// The persistent state of a [`Fetch`] asset.
+#[derive(Debug, Default)]
+pub struct State {
+    url: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Option<Vec<u8>>,
+    fetched: Option<Time>,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.url.serialize(writer);
+        self.etag.serialize(writer);
+        self.last_modified.serialize(writer);
+        self.body.serialize(writer);
+        self.fetched.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            url: Etag::deserialize(reader)?,
+            etag: Etag::deserialize(reader)?,
+            last_modified: Etag::deserialize(reader)?,
+            body: Etag::deserialize(reader)?,
+            fetched: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+/// Spaces out the requests made by [`fetch`] assets, placed in the [`Context`].
+///
+/// Before each request, the asset waits until at least the interval
+/// has passed since the previous request made through the same `RateLimit`.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::net;
+/// use std::time::Duration;
+/// use std::time::Instant;
+///
+/// let limit = net::RateLimit::new(Duration::from_millis(20));
+/// let start = Instant::now();
+/// limit.wait();
+/// limit.wait();
+/// assert!(start.elapsed() >= Duration::from_millis(20));
+///
+/// let values = (limit,);
+/// let cx = asset::Context::from_tuple(&values);
+/// # let _ = cx;
+/// ```
+#[derive(Debug)]
+pub struct RateLimit {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimit {
+    /// Construct a rate limit that allows one request per `interval`.
+    #[must_use]
+    pub const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Block until another request is allowed, and reserve it.
+    pub fn wait(&self) {
+        let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let start = match *next {
+            Some(next) if now < next => {
+                thread::sleep(next - now);
+                next
+            }
+            _ => now,
+        };
+        *next = Some(start + self.interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn change_url() {
+        struct Server;
+        impl Client for Server {
+            type Error = Infallible;
+            fn get(&self, url: &str, conditions: &Conditions<'_>) -> Result<Response, Infallible> {
+                if conditions.if_none_match.is_some() {
+                    return Ok(Response::NotModified);
+                }
+                let resource = Resource::new(url.as_bytes().to_vec()).etag("\"v1\"");
+                Ok(Response::Ok(resource))
+            }
+        }
+
+        let cx = Context::default();
+        let mut etag = State::default();
+        let mut fetch = |url| {
+            let res = super::fetch(Server, url).update(cx, &mut etag);
+            (res.delta, res.value.generate().unwrap())
+        };
+        assert_eq!(fetch("/a"), (Delta::Modified, b"/a".to_vec()));
+        assert_eq!(fetch("/a"), (Delta::Same, b"/a".to_vec()));
+        // The validators of `/a` are not sent for `/b`.
+        assert_eq!(fetch("/b"), (Delta::Modified, b"/b".to_vec()));
+        assert_eq!(fetch("/b"), (Delta::Same, b"/b".to_vec()));
+    }
+
+    use super::Client;
+    use super::Conditions;
+    use super::Resource;
+    use super::Response;
+    use super::State;
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::Asset as _;
+    use crate::Delta;
+    use core::convert::Infallible;
+}
+
+use crate::asset;
+use crate::asset::Context;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::time;
+use crate::time::Time;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use std::string::String;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
@@ -0,0 +1,96 @@
+//! Observing the progress of a build.
+//!
+//! Assets wrapped with [`Asset::observe`](crate::Asset::observe)
+//! report [`Event`]s to the [`Observer`] in the [`Context`](crate::asset::Context),
+//! if there is one.
+//! Since observed assets may contain other observed assets,
+//! the start and end events of an asset bracket those of the assets inside it,
+//! allowing observers to reconstruct the tree of assets.
+
+/// Something that happened during a build.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// An asset started updating.
+    UpdateStart {
+        /// The name of the asset.
+        name: &'a str,
+    },
+    /// An asset finished updating.
+    UpdateEnd {
+        /// The name of the asset.
+        name: &'a str,
+        /// Whether the asset was found to be modified.
+        delta: Delta,
+        /// How long the update took.
+        elapsed: Duration,
+    },
+    /// An asset started generating its output.
+    GenerateStart {
+        /// The name of the asset.
+        name: &'a str,
+    },
+    /// An asset finished generating its output.
+    GenerateEnd {
+        /// The name of the asset.
+        name: &'a str,
+        /// How long the generation took.
+        elapsed: Duration,
+    },
+}
+
+/// A callback receiving [`Event`]s,
+/// which can be placed in the [`Context`](crate::asset::Context).
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::observe;
+/// use mast::Asset as _;
+/// use std::sync::Mutex;
+///
+/// static NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+///
+/// let cx = (observe::Observer::new(|event| {
+///     if let observe::Event::UpdateEnd { name, .. } = event {
+///         NAMES.lock().unwrap().push(String::from(*name));
+///     }
+/// }),);
+/// let cx = asset::Context::from_tuple(&cx);
+///
+/// let mut etag = Default::default();
+/// let args = asset::cli_args().observe("args");
+/// args.update(cx, &mut etag).value.generate();
+/// assert_eq!(*NAMES.lock().unwrap(), ["args"]);
+/// ```
+pub struct Observer {
+    f: Box<dyn Fn(&Event<'_>) + Send + Sync>,
+}
+
+impl Observer {
+    /// Construct an observer from a callback.
+    #[must_use]
+    pub fn new<F: Fn(&Event<'_>) + Send + Sync + 'static>(f: F) -> Self {
+        Self { f: Box::new(f) }
+    }
+
+    /// Report an event to this observer.
+    pub fn notify(&self, event: &Event<'_>) {
+        (self.f)(event);
+    }
+}
+
+impl Debug for Observer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observer").finish_non_exhaustive()
+    }
+}
+
+use crate::Delta;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::time::Duration;
+use std::boxed::Box;
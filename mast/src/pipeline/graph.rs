@@ -0,0 +1,247 @@
+/// The structure of a [`Pipeline`]: its nodes and the edges between them,
+/// as returned by [`Pipeline::graph`].
+///
+/// A graph is an [`Etag`], so the graph of the previous run can be persisted
+/// (for example in a [`Store`](crate::store::Store))
+/// and [diffed](Self::diff) against the current one
+/// to explain what a change to the pipeline’s description will rebuild.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Graph {
+    nodes: Vec<Node>,
+}
+
+/// A node in a [`Graph`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Node {
+    /// What kind of node this is.
+    pub kind: NodeKind,
+    /// The name of the node; for sinks, the path written to.
+    pub name: String,
+    /// What the node does: the path read by a source or the plugin run by a transform.
+    /// Empty for sinks.
+    pub label: String,
+    /// The name of the node this node takes its input from, if it has one.
+    pub input: Option<String>,
+}
+
+/// The kind of a [`Node`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NodeKind {
+    /// A file read from disk.
+    #[default]
+    Source,
+    /// The output of another node fed through a plugin.
+    Transform,
+    /// A file the output of another node is written to.
+    Sink,
+}
+
+impl Graph {
+    pub(super) fn new(pipeline: &Pipeline) -> Self {
+        let sources = pipeline.sources.iter().map(|source| Node {
+            kind: NodeKind::Source,
+            name: source.name.clone(),
+            label: source.path.display().to_string(),
+            input: None,
+        });
+        let transforms = pipeline.transforms.iter().map(|transform| Node {
+            kind: NodeKind::Transform,
+            name: transform.name.clone(),
+            label: transform.plugin.clone(),
+            input: Some(transform.input.clone()),
+        });
+        let sinks = pipeline.sinks.iter().map(|sink| Node {
+            kind: NodeKind::Sink,
+            name: sink.path.display().to_string(),
+            label: String::new(),
+            input: Some(sink.input.clone()),
+        });
+        Self {
+            nodes: sources.chain(transforms).chain(sinks).collect(),
+        }
+    }
+
+    /// The nodes of the graph, with every node after the node it takes its input from.
+    #[must_use]
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Compare this graph, from a previous run, with the graph of the current pipeline.
+    ///
+    /// A removed node and an added node of the same kind,
+    /// with the same label and the same (possibly renamed) input, are reported as a rename.
+    /// Renamed sources are re-keyed in the pipeline’s etag,
+    /// and the files of removed or renamed sinks are orphaned:
+    /// they are no longer written, but are left in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mast::pipeline::Graph;
+    /// use mast::pipeline::Pipeline;
+    /// use mast::Etag as _;
+    ///
+    /// let old = Pipeline::new()
+    ///     .source("post", "post.md")
+    ///     .transform("html", "markdown", "post")
+    ///     .sink("post.html", "html");
+    /// // The graph of the previous run is typically persisted between runs.
+    /// let old = Graph::from_bytes(&old.graph().to_vec()).unwrap();
+    ///
+    /// let new = Pipeline::new()
+    ///     .source("article", "post.md")
+    ///     .transform("html", "commonmark", "article")
+    ///     .sink("post.html", "html");
+    ///
+    /// let changes = old.diff(&new.graph());
+    /// assert_eq!(changes.len(), 2);
+    /// assert_eq!(changes[0].to_string(), "renamed source `post` to `article`");
+    /// assert_eq!(changes[1].to_string(), "changed transform `html`");
+    /// ```
+    #[must_use]
+    pub fn diff(&self, new: &Self) -> Vec<Change> {
+        let old_names = self.names();
+        let new_names = new.names();
+        let mut changes = Vec::new();
+        let mut renames = BTreeMap::<&str, &str>::new();
+        let mut added: Vec<&Node> = new
+            .nodes
+            .iter()
+            .filter(|node| !old_names.contains_key(&(node.kind, &*node.name)))
+            .collect();
+
+        for old in &self.nodes {
+            let input = old
+                .input
+                .as_deref()
+                .map(|input| renames.get(input).copied().unwrap_or(input));
+            if let Some(new) = new_names.get(&(old.kind, &*old.name)) {
+                if new.label != old.label || new.input.as_deref() != input {
+                    changes.push(Change::Changed(old.clone()));
+                }
+                continue;
+            }
+            let matching = added.iter().position(|new| {
+                new.kind == old.kind && new.label == old.label && new.input.as_deref() == input
+            });
+            match matching {
+                Some(i) => {
+                    let new = added.remove(i);
+                    renames.insert(&old.name, &new.name);
+                    changes.push(Change::Renamed {
+                        from: old.clone(),
+                        to: new.name.clone(),
+                    });
+                }
+                None => changes.push(Change::Removed(old.clone())),
+            }
+        }
+        changes.extend(added.into_iter().cloned().map(Change::Added));
+        changes
+    }
+
+    fn names(&self) -> BTreeMap<(NodeKind, &str), &Node> {
+        self.nodes
+            .iter()
+            .map(|node| ((node.kind, &*node.name), node))
+            .collect()
+    }
+}
+
+/// A difference between two [`Graph`]s, as returned by [`Graph::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Change {
+    /// A node was added.
+    Added(Node),
+    /// A node was removed.
+    Removed(Node),
+    /// A node was renamed without otherwise changing.
+    Renamed {
+        /// The node under its old name.
+        from: Node,
+        /// The new name of the node.
+        to: String,
+    },
+    /// A node kept its name, but its label or input changed.
+    Changed(Node),
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added(node) => write!(f, "added {} `{}`", node.kind, node.name),
+            Self::Removed(node) => write!(f, "removed {} `{}`", node.kind, node.name),
+            Self::Renamed { from, to } => {
+                write!(f, "renamed {} `{}` to `{to}`", from.kind, from.name)
+            }
+            Self::Changed(node) => write!(f, "changed {} `{}`", node.kind, node.name),
+        }
+    }
+}
+
+impl Display for NodeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Source => "source",
+            Self::Transform => "transform",
+            Self::Sink => "sink",
+        })
+    }
+}
+
+impl Etag for Graph {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.nodes.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            nodes: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+impl Etag for Node {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.kind.serialize(writer);
+        self.name.serialize(writer);
+        self.label.serialize(writer);
+        self.input.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            kind: Etag::deserialize(reader)?,
+            name: Etag::deserialize(reader)?,
+            label: Etag::deserialize(reader)?,
+            input: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+impl Etag for NodeKind {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_discriminant(*self as u32);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(match reader.read_discriminant(2)? {
+            0 => Self::Source,
+            1 => Self::Transform,
+            _ => Self::Sink,
+        })
+    }
+}
+
+use super::Pipeline;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::Etag;
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use std::collections::BTreeMap;
+use std::string::String;
+use std::string::ToString as _;
+use std::vec::Vec;
@@ -0,0 +1,750 @@
+//! Declarative pipelines, for projects that would rather not write Rust.
+//!
+//! A [`Pipeline`] is a graph of three kinds of node:
+//!
+//! - Sources, which are files read from disk.
+//! - Transforms, which feed the output of one earlier node through a named plugin.
+//! - Sinks, which write the output of a node to a file.
+//!
+//! Plugins are looked up by name in the [`Registry`] placed in the [`Context`].
+//! By default the first failing node stops the build;
+//! with a [`FailurePolicy`] that [keeps going](FailurePolicy::keep_going) in the context,
+//! every node not downstream of a failure is still built, and all the failures are reported.
+//! With the `toml` feature, pipelines can be loaded from a TOML file with [`from_toml`].
+//! The [`Graph`] of a pipeline can be diffed against that of a previous run,
+//! to explain what a change to the pipeline will rebuild.
+//!
+//! # Examples
+//!
+//! ```
+//! use mast::asset;
+//! use mast::asset::Generator as _;
+//! use mast::pipeline;
+//! use mast::Asset as _;
+//!
+//! let dir = std::env::temp_dir().join(format!("mast-doctest-pipeline-{}", std::process::id()));
+//! std::fs::create_dir_all(&dir)?;
+//! std::fs::write(dir.join("name.txt"), "world")?;
+//!
+//! let pipeline = pipeline::Pipeline::new()
+//!     .source("name", dir.join("name.txt"))
+//!     .transform("greeting", "greet", "name")
+//!     .sink(dir.join("greeting.txt"), "greeting");
+//!
+//! let mut registry = pipeline::Registry::new();
+//! registry.register("greet", |input| {
+//!     Ok([b"Hello ", input, b"!"].concat())
+//! });
+//! let cx = (registry,);
+//! let cx = asset::Context::from_tuple(&cx);
+//!
+//! let mut etag = Default::default();
+//! let res = pipeline.clone().update(cx, &mut etag);
+//! assert!(res.is_modified());
+//! res.value.generate().unwrap();
+//! assert_eq!(std::fs::read(dir.join("greeting.txt"))?, b"Hello world!");
+//!
+//! assert!(pipeline.update(cx, &mut etag).is_same());
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<_, std::io::Error>(())
+//! ```
+
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "toml")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "toml")))]
+pub use self::toml::from_toml;
+#[cfg(feature = "toml")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "toml")))]
+pub use self::toml::from_toml_str;
+
+mod graph;
+pub use self::graph::Change;
+pub use self::graph::Graph;
+pub use self::graph::Node;
+pub use self::graph::NodeKind;
+
+/// A declarative build graph.
+///
+/// Nodes may only refer to nodes defined before them,
+/// so a pipeline is always acyclic.
+/// References are checked when the pipeline is run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    sources: Vec<Source>,
+    transforms: Vec<Transform>,
+    sinks: Vec<Sink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Source {
+    name: String,
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Transform {
+    name: String,
+    plugin: String,
+    input: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Sink {
+    path: PathBuf,
+    input: String,
+    options: WriteOptions,
+}
+
+impl Pipeline {
+    /// Construct an empty pipeline.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source node called `name`, which reads the file at `path`.
+    #[must_use]
+    pub fn source<N: Into<String>, P: Into<PathBuf>>(mut self, name: N, path: P) -> Self {
+        self.sources.push(Source {
+            name: name.into(),
+            path: path.into(),
+        });
+        self
+    }
+
+    /// Add a transform node called `name`,
+    /// which runs the plugin called `plugin` on the output of the node called `input`.
+    #[must_use]
+    pub fn transform<N, P, I>(mut self, name: N, plugin: P, input: I) -> Self
+    where
+        N: Into<String>,
+        P: Into<String>,
+        I: Into<String>,
+    {
+        self.transforms.push(Transform {
+            name: name.into(),
+            plugin: plugin.into(),
+            input: input.into(),
+        });
+        self
+    }
+
+    /// Add a sink, which writes the output of the node called `input` to the file at `path`.
+    #[must_use]
+    pub fn sink<P: Into<PathBuf>, I: Into<String>>(self, path: P, input: I) -> Self {
+        self.sink_with(path, input, WriteOptions::new())
+    }
+
+    /// Add a sink like [`sink`](Self::sink),
+    /// writing the file with the given options, such as its permissions.
+    ///
+    /// Changing the options of a sink causes the pipeline to be rerun,
+    /// and the file to be written afresh if the old options changed its permissions
+    /// in a way the new ones do not replace (see [`WriteOptions::change_from`]).
+    #[must_use]
+    pub fn sink_with<P, I>(mut self, path: P, input: I, options: WriteOptions) -> Self
+    where
+        P: Into<PathBuf>,
+        I: Into<String>,
+    {
+        self.sinks.push(Sink {
+            path: path.into(),
+            input: input.into(),
+            options,
+        });
+        self
+    }
+
+    /// The paths of every file this pipeline writes to.
+    pub fn outputs(&self) -> impl Iterator<Item = &Path> {
+        self.sinks.iter().map(|sink| &*sink.path)
+    }
+
+    /// The structure of the pipeline, for comparison with that of a previous run.
+    #[must_use]
+    pub fn graph(&self) -> Graph {
+        Graph::new(self)
+    }
+
+    /// Render the pipeline as a graph in the Graphviz DOT language.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mast::pipeline::Pipeline;
+    /// let pipeline = Pipeline::new()
+    ///     .source("post", "post.md")
+    ///     .transform("html", "markdown", "post")
+    ///     .sink("post.html", "html");
+    /// assert_eq!(pipeline.to_dot(), "\
+    /// digraph pipeline {
+    ///     \"post\" [shape=box];
+    ///     \"html\" [label=\"html\\n(markdown)\"];
+    ///     \"post\" -> \"html\";
+    ///     \"post.html\" [shape=box, style=bold];
+    ///     \"html\" -> \"post.html\";
+    /// }
+    /// ");
+    /// ```
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph pipeline {\n");
+        for source in &self.sources {
+            let name = DotEscape(&source.name);
+            writeln!(dot, "    \"{name}\" [shape=box];").unwrap();
+        }
+        for transform in &self.transforms {
+            let name = DotEscape(&transform.name);
+            let plugin = DotEscape(&transform.plugin);
+            let input = DotEscape(&transform.input);
+            writeln!(dot, "    \"{name}\" [label=\"{name}\\n({plugin})\"];").unwrap();
+            writeln!(dot, "    \"{input}\" -> \"{name}\";").unwrap();
+        }
+        for sink in &self.sinks {
+            let path = sink.path.display().to_string();
+            let path = DotEscape(&path);
+            let input = DotEscape(&sink.input);
+            writeln!(dot, "    \"{path}\" [shape=box, style=bold];").unwrap();
+            writeln!(dot, "    \"{input}\" -> \"{path}\";").unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Check that every node name is unique and every reference is to an earlier node.
+    fn validate(&self) -> Result<(), Error> {
+        let mut names = BTreeSet::new();
+        for source in &self.sources {
+            if !names.insert(&*source.name) {
+                return Err(Error::DuplicateNode(source.name.clone()));
+            }
+        }
+        for transform in &self.transforms {
+            if !names.contains(&*transform.input) {
+                return Err(Error::UnknownNode(transform.input.clone()));
+            }
+            if !names.insert(&*transform.name) {
+                return Err(Error::DuplicateNode(transform.name.clone()));
+            }
+        }
+        for sink in &self.sinks {
+            if !names.contains(&*sink.input) {
+                return Err(Error::UnknownNode(sink.input.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The set of plugins available to [`Pipeline`] transforms.
+///
+/// Place a `Registry` in the [`Context`] to make its plugins available.
+#[derive(Default)]
+pub struct Registry {
+    plugins: BTreeMap<String, Registered>,
+}
+
+struct Registered {
+    version: u32,
+    plugin: Box<Plugin>,
+}
+
+type Plugin = dyn Fn(&[u8]) -> Result<Vec<u8>, BoxError> + Send + Sync;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+impl Registry {
+    /// Construct an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a plugin under the given name,
+    /// replacing any plugin previously registered under that name.
+    ///
+    /// Plugins should be pure functions of their input.
+    /// This is equivalent to [`register_version`](Self::register_version) with version 0.
+    pub fn register<N, F>(&mut self, name: N, plugin: F) -> &mut Self
+    where
+        N: Into<String>,
+        F: Fn(&[u8]) -> Result<Vec<u8>, BoxError> + Send + Sync + 'static,
+    {
+        self.register_version(name, 0, plugin)
+    }
+
+    /// Register a plugin under the given name with a version,
+    /// replacing any plugin previously registered under that name.
+    ///
+    /// The name and version of each plugin a pipeline uses are part of its etag,
+    /// so change the version whenever the plugin’s output changes
+    /// to rebuild the pipelines that use it.
+    pub fn register_version<N, F>(&mut self, name: N, version: u32, plugin: F) -> &mut Self
+    where
+        N: Into<String>,
+        F: Fn(&[u8]) -> Result<Vec<u8>, BoxError> + Send + Sync + 'static,
+    {
+        let plugin = Box::new(plugin);
+        self.plugins
+            .insert(name.into(), Registered { version, plugin });
+        self
+    }
+}
+
+impl Debug for Registry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.plugins.keys()).finish()
+    }
+}
+
+impl<'c> Asset<'c> for Pipeline {
+    type Etag = State;
+    type Output = Result<(), Error>;
+    type Generator = Generator<'c>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let sources = self
+            .sources
+            .iter()
+            .map(|source| (source.name.clone(), Stamp::of(&source.path).ok()))
+            .collect::<BTreeMap<_, _>>();
+        let sinks = self
+            .sinks
+            .iter()
+            .map(|sink| (sink.path.clone(), Stamp::of(&sink.path).ok()))
+            .collect::<BTreeMap<_, _>>();
+
+        let registry = cx.try_get::<Registry>();
+        let delta = Delta::cmp(&etag.complete, &true)
+            .or_else(|| Delta::cmp(&etag.pipeline, &Some(self.digest(registry))))
+            .or_else(|| Delta::cmp(&etag.sources, &sources))
+            .or_else(|| Delta::cmp(&etag.sinks, &sinks));
+
+        delta.track(Generator {
+            pipeline: self,
+            registry,
+            policy: cx.try_get::<FailurePolicy>().copied().unwrap_or_default(),
+            state: etag,
+        })
+    }
+}
+
+impl Pipeline {
+    fn digest(&self, registry: Option<&Registry>) -> Digest {
+        let mut hasher = Sha256::new();
+        for source in &self.sources {
+            source.name.serialize(&mut hasher);
+            source.path.serialize(&mut hasher);
+        }
+        hasher.write_bytes(&[0xFF]);
+        for transform in &self.transforms {
+            transform.name.serialize(&mut hasher);
+            transform.plugin.serialize(&mut hasher);
+            let plugin = registry.and_then(|registry| registry.plugins.get(&transform.plugin));
+            plugin.map(|plugin| plugin.version).serialize(&mut hasher);
+            transform.input.serialize(&mut hasher);
+        }
+        hasher.write_bytes(&[0xFF]);
+        for sink in &self.sinks {
+            sink.path.serialize(&mut hasher);
+            sink.input.serialize(&mut hasher);
+            sink.options.serialize(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// Generator for [`Pipeline`].
+#[derive(Debug)]
+pub struct Generator<'c> {
+    pipeline: Pipeline,
+    registry: Option<&'c Registry>,
+    policy: FailurePolicy,
+    state: &'c mut State,
+}
+
+impl asset::Generator for Generator<'_> {
+    type Output = Result<(), Error>;
+
+    fn generate(self) -> Self::Output {
+        let Self {
+            pipeline,
+            registry,
+            policy,
+            state,
+        } = self;
+        let pipeline = &pipeline;
+        state.complete = false;
+        pipeline.validate()?;
+
+        let mut errors = Vec::new();
+        let mut fail = |error| {
+            if policy.keep_going {
+                errors.push(error);
+                Ok(())
+            } else {
+                Err(error)
+            }
+        };
+
+        // Nodes downstream of a failed node are absent from `outputs` and skipped.
+        let mut sources = BTreeMap::new();
+        let mut outputs = BTreeMap::<&str, Vec<u8>>::new();
+        for source in &pipeline.sources {
+            let read = Stamp::of(&source.path).and_then(|stamp| {
+                let contents = fs::read(&source.path)?;
+                Ok((stamp, contents))
+            });
+            match read {
+                Ok((stamp, contents)) => {
+                    sources.insert(source.name.clone(), Some(stamp));
+                    outputs.insert(&source.name, contents);
+                }
+                Err(e) => fail(Error::Io(e))?,
+            }
+        }
+
+        for transform in &pipeline.transforms {
+            let Some(input) = outputs.get(&*transform.input) else {
+                continue;
+            };
+            let Some(Registered { plugin, .. }) =
+                registry.and_then(|registry| registry.plugins.get(&transform.plugin))
+            else {
+                fail(Error::UnknownPlugin(transform.plugin.clone()))?;
+                continue;
+            };
+            let output = if policy.catch_panics {
+                panic::catch_unwind(AssertUnwindSafe(|| plugin(input))).unwrap_or_else(|payload| {
+                    Err(Box::new(Panic::new(transform.name.clone(), &*payload)))
+                })
+            } else {
+                plugin(input)
+            };
+            match output {
+                Ok(output) => drop(outputs.insert(&transform.name, output)),
+                Err(source) => fail(Error::Plugin {
+                    node: transform.name.clone(),
+                    source,
+                })?,
+            }
+        }
+
+        let mut sinks = BTreeMap::new();
+        for sink in &pipeline.sinks {
+            let Some(contents) = outputs.get(&*sink.input) else {
+                continue;
+            };
+            let previous = state.options.get(&sink.path).copied().unwrap_or_default();
+            match write_sink(&sink.path, contents, &sink.options, &previous) {
+                Ok(stamp) => drop(sinks.insert(sink.path.clone(), Some(stamp))),
+                Err(e) => fail(Error::Io(e))?,
+            }
+        }
+
+        match errors.len() {
+            0 => {}
+            1 => return Err(errors.pop().unwrap()),
+            _ => return Err(Error::Many(errors)),
+        }
+        state.pipeline = Some(pipeline.digest(registry));
+        state.sources = sources;
+        state.sinks = sinks;
+        state.options = pipeline
+            .sinks
+            .iter()
+            .map(|sink| (sink.path.clone(), sink.options))
+            .collect();
+        state.complete = true;
+        Ok(())
+    }
+}
+
+/// The contents of a quoted string in the DOT language.
+///
+/// DOT strings only treat `\"` and `\\` specially;
+/// other characters, including non-ASCII ones, are written as they are.
+struct DotEscape<'a>(&'a str);
+
+impl Display for DotEscape<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '"' | '\\' => write!(f, "\\{c}")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write `contents` to the sink at `path`, last written with the `previous` options,
+/// if they differ from what is there, returning the resulting stamp.
+fn write_sink(
+    path: &Path,
+    contents: &[u8],
+    options: &WriteOptions,
+    previous: &WriteOptions,
+) -> io::Result<Stamp> {
+    options.change_from(previous, path)?;
+    options.write_if_changed(path, contents)?;
+    Stamp::of(path)
+}
+
+/// The persistent state of a [`Pipeline`].
+#[derive(Debug, Default)]
+pub struct State {
+    pipeline: Option<Digest>,
+    sources: BTreeMap<String, Option<Stamp>>,
+    sinks: BTreeMap<PathBuf, Option<Stamp>>,
+    options: BTreeMap<PathBuf, WriteOptions>,
+    complete: bool,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.pipeline.serialize(writer);
+        self.sources.serialize(writer);
+        self.sinks.serialize(writer);
+        self.options.serialize(writer);
+        self.complete.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            pipeline: Etag::deserialize(reader)?,
+            sources: Etag::deserialize(reader)?,
+            sinks: Etag::deserialize(reader)?,
+            options: Etag::deserialize(reader)?,
+            complete: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+/// An error loading or running a [`Pipeline`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O error occurred.
+    Io(io::Error),
+    /// The pipeline description could not be parsed.
+    #[cfg(feature = "toml")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "toml")))]
+    Toml(::toml::de::Error),
+    /// The pipeline description had an invalid structure.
+    Invalid(String),
+    /// Two nodes had the same name.
+    DuplicateNode(String),
+    /// A node referred to a node that does not exist or is defined after it.
+    UnknownNode(String),
+    /// A transform referred to a plugin not in the [`Registry`].
+    UnknownPlugin(String),
+    /// A plugin failed.
+    Plugin {
+        /// The name of the transform node whose plugin failed.
+        node: String,
+        /// The error returned by the plugin.
+        source: BoxError,
+    },
+    /// Several nodes failed while [keeping going](FailurePolicy::keep_going).
+    Many(Vec<Error>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(_) => f.write_str("I/O error"),
+            #[cfg(feature = "toml")]
+            Self::Toml(_) => f.write_str("failed to parse pipeline"),
+            Self::Invalid(msg) => write!(f, "invalid pipeline: {msg}"),
+            Self::DuplicateNode(name) => write!(f, "duplicate node `{name}`"),
+            Self::UnknownNode(name) => write!(f, "unknown node `{name}`"),
+            Self::UnknownPlugin(name) => write!(f, "unknown plugin `{name}`"),
+            Self::Plugin { node, .. } => write!(f, "plugin failed in node `{node}`"),
+            Self::Many(errors) => {
+                write!(f, "{} nodes failed", errors.len())?;
+                for error in errors {
+                    write!(f, "\n- {error}")?;
+                    let mut source = std::error::Error::source(error);
+                    while let Some(error) = source {
+                        write!(f, ": {error}")?;
+                        source = error.source();
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            #[cfg(feature = "toml")]
+            Self::Toml(e) => Some(e),
+            Self::Plugin { source, .. } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn keep_going() {
+        let dir = env::temp_dir().join(format!("mast-test-pipeline-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("in"), "in").unwrap();
+        let pipeline = Pipeline::new()
+            .source("in", dir.join("in"))
+            .source("missing", dir.join("missing"))
+            .transform("bad", "fail", "in")
+            .transform("good", "copy", "in")
+            .transform("after-missing", "copy", "missing")
+            .sink(dir.join("bad"), "bad")
+            .sink(dir.join("good"), "good");
+        let mut registry = Registry::new();
+        registry.register("fail", |_| Err("boom".into()));
+        registry.register("copy", |input| Ok(input.to_owned()));
+
+        let run = |policy: FailurePolicy| {
+            let values: [&dyn Value; 2] = [&registry, &policy];
+            let mut etag = State::default();
+            let generator = pipeline
+                .clone()
+                .update(Context::from_array(&values), &mut etag);
+            generator.value.generate()
+        };
+
+        let res = run(FailurePolicy::new());
+        assert!(matches!(res, Err(Error::Io(_))));
+        assert!(!dir.join("good").exists());
+
+        let res = run(FailurePolicy::new().keep_going(true));
+        let Err(Error::Many(errors)) = res else {
+            panic!("expected several errors, found {res:?}");
+        };
+        assert!(matches!(errors[..], [Error::Io(_), Error::Plugin { .. }]));
+        assert!(Error::Many(errors)
+            .to_string()
+            .contains("plugin failed in node `bad`: boom"));
+        assert_eq!(fs::read(dir.join("good")).unwrap(), b"in");
+
+        let pipeline = Pipeline::new()
+            .source("in", dir.join("in"))
+            .transform("buggy", "panic", "in");
+        let mut registry = Registry::new();
+        registry.register("panic", |_| panic!("plugin bug"));
+        let policy = FailurePolicy::new().catch_panics(true);
+        let values: [&dyn Value; 2] = [&registry, &policy];
+        let mut etag = State::default();
+        let res = pipeline
+            .update(Context::from_array(&values), &mut etag)
+            .value
+            .generate();
+        let Err(Error::Plugin { node, source }) = res else {
+            panic!("expected a plugin error, found {res:?}");
+        };
+        assert_eq!(node, "buggy");
+        assert_eq!(source.to_string(), "asset `buggy` panicked: plugin bug");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plugin_version() {
+        let dir = env::temp_dir().join(format!("mast-test-pipeline-version-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("in"), "in").unwrap();
+        let pipeline = Pipeline::new()
+            .source("in", dir.join("in"))
+            .transform("out", "copy", "in")
+            .sink(dir.join("out"), "out");
+
+        let mut etag = State::default();
+        let mut run = |version| {
+            let mut registry = Registry::new();
+            registry.register_version("copy", version, |input| Ok(input.to_owned()));
+            let values: [&dyn Value; 1] = [&registry];
+            let res = pipeline
+                .clone()
+                .update(Context::from_array(&values), &mut etag);
+            let modified = res.is_modified();
+            res.value.generate().unwrap();
+            modified
+        };
+        assert!(run(1));
+        assert!(!run(1));
+        assert!(run(2));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dot_escape() {
+        let pipeline = Pipeline::new()
+            .source("say \"hi\"", "in")
+            .transform("é", "a\\b", "say \"hi\"")
+            .sink("C:\\out", "é");
+        assert_eq!(
+            pipeline.to_dot(),
+            "digraph pipeline {\n    \
+            \"say \\\"hi\\\"\" [shape=box];\n    \
+            \"é\" [label=\"é\\n(a\\\\b)\"];\n    \
+            \"say \\\"hi\\\"\" -> \"é\";\n    \
+            \"C:\\\\out\" [shape=box, style=bold];\n    \
+            \"é\" -> \"C:\\\\out\";\n\
+            }\n"
+        );
+    }
+
+    use super::Error;
+    use super::Pipeline;
+    use super::Registry;
+    use super::State;
+    use crate::asset::context::Value;
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::diagnostic::FailurePolicy;
+    use crate::Asset as _;
+    use std::borrow::ToOwned as _;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::process;
+    use std::string::ToString as _;
+}
+
+use crate::asset;
+use crate::asset::Context;
+use crate::asset::Panic;
+use crate::diagnostic::FailurePolicy;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::fs::Stamp;
+use crate::fs::WriteOptions;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Write as _;
+use std::boxed::Box;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::path::PathBuf;
+use std::string::String;
+use std::string::ToString as _;
+use std::vec::Vec;
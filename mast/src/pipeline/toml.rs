@@ -0,0 +1,151 @@
+/// Load a [`Pipeline`] from the TOML file at `path`.
+///
+/// Relative paths in the file are resolved relative to the directory containing it.
+/// See [`from_toml_str`] for the format.
+///
+/// # Errors
+///
+/// Fails if the file could not be read or does not describe a valid pipeline.
+pub fn from_toml<P: AsRef<Path>>(path: P) -> Result<Pipeline, Error> {
+    let path = path.as_ref();
+    let s = fs::read_to_string(path).map_err(Error::Io)?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    parse(&s, base)
+}
+
+/// Parse a [`Pipeline`] from a TOML string.
+///
+/// The `sources` table maps node names to file paths,
+/// the `transforms` array lists transform nodes in order,
+/// and the `sinks` table maps output paths to the names of the nodes to write there.
+///
+/// # Errors
+///
+/// Fails if the string does not describe a valid pipeline.
+///
+/// # Examples
+///
+/// ```
+/// use mast::pipeline;
+///
+/// let pipeline = pipeline::from_toml_str(r#"
+///     [sources]
+///     post = "content/post.md"
+///
+///     [[transforms]]
+///     name = "html"
+///     plugin = "markdown"
+///     input = "post"
+///
+///     [sinks]
+///     "public/post.html" = "html"
+/// "#)?;
+///
+/// assert_eq!(
+///     pipeline,
+///     pipeline::Pipeline::new()
+///         .source("post", "content/post.md")
+///         .transform("html", "markdown", "post")
+///         .sink("public/post.html", "html"),
+/// );
+/// # Ok::<_, pipeline::Error>(())
+/// ```
+pub fn from_toml_str(s: &str) -> Result<Pipeline, Error> {
+    parse(s, Path::new(""))
+}
+
+fn parse(s: &str, base: &Path) -> Result<Pipeline, Error> {
+    let mut table = s.parse::<Table>().map_err(Error::Toml)?;
+    let mut pipeline = Pipeline::new();
+
+    if let Some(sources) = table.remove("sources") {
+        for (name, path) in into_table(sources, "sources")? {
+            let path = into_string(path, "source paths")?;
+            pipeline = pipeline.source(name, base.join(path));
+        }
+    }
+
+    if let Some(transforms) = table.remove("transforms") {
+        let Value::Array(transforms) = transforms else {
+            return Err(invalid("`transforms` must be an array of tables"));
+        };
+        for transform in transforms {
+            let mut transform = into_table(transform, "transforms")?;
+            let mut field = |key| match transform.remove(key) {
+                Some(value) => into_string(value, key),
+                None => Err(invalid(format!("transform is missing `{key}`"))),
+            };
+            let (name, plugin, input) = (field("name")?, field("plugin")?, field("input")?);
+            if let Some(key) = transform.keys().next() {
+                return Err(invalid(format!("unknown transform key `{key}`")));
+            }
+            pipeline = pipeline.transform(name, plugin, input);
+        }
+    }
+
+    if let Some(sinks) = table.remove("sinks") {
+        for (path, input) in into_table(sinks, "sinks")? {
+            let input = into_string(input, "sink inputs")?;
+            pipeline = pipeline.sink(base.join(path), input);
+        }
+    }
+
+    if let Some(key) = table.keys().next() {
+        return Err(invalid(format!("unknown key `{key}`")));
+    }
+
+    pipeline.validate()?;
+    Ok(pipeline)
+}
+
+fn into_table(value: Value, what: &str) -> Result<Table, Error> {
+    match value {
+        Value::Table(table) => Ok(table),
+        _ => Err(invalid(format!("`{what}` must be a table"))),
+    }
+}
+
+fn into_string(value: Value, what: &str) -> Result<String, Error> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(invalid(format!("{what} must be strings"))),
+    }
+}
+
+fn invalid<S: Into<String>>(msg: S) -> Error {
+    Error::Invalid(msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn errors() {
+        let err = from_toml_str("[[transforms]]\nname = 'a'\nplugin = 'p'\ninput = 'b'");
+        assert!(matches!(err, Err(Error::UnknownNode(node)) if node == "b"));
+
+        let err = from_toml_str(
+            "[sources]\na = 'a'\n[[transforms]]\nname = 'a'\nplugin = 'p'\ninput = 'a'",
+        );
+        assert!(matches!(err, Err(Error::DuplicateNode(node)) if node == "a"));
+
+        let err = from_toml_str("[[transforms]]\nname = 'a'\ninput = 'b'");
+        assert!(matches!(err, Err(Error::Invalid(_))));
+
+        let err = from_toml_str("[source]\na = 'a'");
+        assert!(matches!(err, Err(Error::Invalid(_))));
+
+        assert!(matches!(from_toml_str("["), Err(Error::Toml(_))));
+    }
+
+    use super::from_toml_str;
+    use super::Error;
+}
+
+use super::Error;
+use super::Pipeline;
+use std::format;
+use std::fs;
+use std::path::Path;
+use std::string::String;
+use toml::Table;
+use toml::Value;
@@ -0,0 +1,681 @@
+//! Assets that run external commands.
+
+/// Run an external command.
+///
+/// The command is run when its arguments, environment or working directory change,
+/// when the [`Stamp`] of any of its declared [inputs](Command::input) changes,
+/// when any [tracked environment variable](Command::track_env) changes,
+/// when the program found by searching `PATH` changes,
+/// or when its previous run did not complete successfully.
+/// The output is that of the process;
+/// a non-zero exit status is reported as an error.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(unix)] {
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::process;
+/// use mast::Asset as _;
+///
+/// let mut etag = Default::default();
+/// let echo = process::command("echo").arg("hello");
+/// let res = echo.update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate()?.stdout, b"hello\n");
+///
+/// let echo = process::command("echo").arg("hello");
+/// assert!(echo.update(asset::Context::default(), &mut etag).is_same());
+/// # }
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn command<P: Into<OsString>>(program: P) -> Command {
+    Command {
+        program: program.into(),
+        args: Vec::new(),
+        env: BTreeMap::new(),
+        current_dir: None,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        tracked_env: Vec::new(),
+        sandbox: false,
+        trace: false,
+    }
+}
+
+/// Asset for [`command`].
+#[derive(Debug, Clone)]
+pub struct Command {
+    program: OsString,
+    args: Vec<OsString>,
+    env: BTreeMap<OsString, OsString>,
+    current_dir: Option<PathBuf>,
+    inputs: Vec<PathBuf>,
+    outputs: Vec<PathBuf>,
+    tracked_env: Vec<OsString>,
+    sandbox: bool,
+    trace: bool,
+}
+
+impl Command {
+    /// Add an argument to pass to the program.
+    #[must_use]
+    pub fn arg<S: Into<OsString>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Add multiple arguments to pass to the program.
+    #[must_use]
+    pub fn args<I>(mut self, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set an environment variable for the program.
+    #[must_use]
+    pub fn env<K: Into<OsString>, V: Into<OsString>>(mut self, key: K, value: V) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Rerun the command whenever the environment variable `key` of this process changes.
+    ///
+    /// Unless the command is [sandboxed](Self::sandbox),
+    /// the program inherits the environment of this process,
+    /// but the etag only includes the variables that are tracked with this method,
+    /// so that changes to unrelated variables do not cause rebuilds.
+    #[must_use]
+    pub fn track_env<K: Into<OsString>>(mut self, key: K) -> Self {
+        self.tracked_env.push(key.into());
+        self
+    }
+
+    /// Set the working directory of the program.
+    ///
+    /// Relative input and output paths are relative to this directory.
+    #[must_use]
+    pub fn current_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Declare a file that the program reads.
+    ///
+    /// The command is rerun whenever the file changes.
+    #[must_use]
+    pub fn input<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.inputs.push(path.into());
+        self
+    }
+
+    /// Declare a file that the program writes.
+    #[must_use]
+    pub fn output<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.outputs.push(path.into());
+        self
+    }
+
+    /// Run the program in a restricted environment,
+    /// to catch dependencies that were not declared.
+    ///
+    /// In a sandbox, the program starts with only the environment variables set with [`Self::env`]
+    /// (so `PATH` must be set explicitly if the program relies on it),
+    /// and runs in a fresh [temporary directory](Context::temp_dir)
+    /// containing copies of only its declared inputs.
+    /// After it exits successfully, its declared outputs are copied back out;
+    /// if it fails, the directory is preserved for debugging and its path is included in the error.
+    /// Inputs and outputs must then be relative paths.
+    ///
+    /// This is not a security boundary:
+    /// the program can still access any absolute path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use mast::asset;
+    /// use mast::asset::Generator as _;
+    /// use mast::fs;
+    /// use mast::process;
+    /// use mast::Asset as _;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("mast-doctest-sandbox-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir)?;
+    /// let values = (fs::TempDirs::new(dir.join("tmp")),);
+    /// let cx = asset::Context::from_tuple(&values);
+    /// std::fs::write(dir.join("declared"), "declared")?;
+    /// std::fs::write(dir.join("undeclared"), "undeclared")?;
+    ///
+    /// let cat = |file| {
+    ///     process::command("/bin/cat")
+    ///         .arg(file)
+    ///         .current_dir(&dir)
+    ///         .input("declared")
+    ///         .sandbox()
+    /// };
+    /// let run = |asset: process::Command| {
+    ///     asset.update(cx, &mut Default::default()).value.generate()
+    /// };
+    /// assert_eq!(run(cat("declared"))?.stdout, b"declared");
+    /// assert!(run(cat("undeclared")).is_err());
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # }
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    #[must_use]
+    pub fn sandbox(mut self) -> Self {
+        self.sandbox = true;
+        self
+    }
+
+    /// Run the program under a file access tracer,
+    /// failing if it reads any file in its working directory
+    /// that was not declared as an [input](Self::input).
+    ///
+    /// Files outside the working directory,
+    /// such as system libraries, are not checked.
+    /// The error lists every undeclared file that was read;
+    /// since the command then counts as failed, it will be run again next time.
+    ///
+    /// This uses `strace`, so is only supported on Linux with `strace` installed;
+    /// elsewhere the command fails with [`io::ErrorKind::Unsupported`].
+    #[must_use]
+    pub fn trace_inputs(mut self) -> Self {
+        self.trace = true;
+        self
+    }
+
+    /// Find the program that will be run, by searching `PATH` if necessary.
+    fn resolve_program(&self) -> Option<PathBuf> {
+        let program = Path::new(&self.program);
+        if program.components().count() != 1 {
+            return Some(self.base().join(program));
+        }
+        let path = match self.env.get(OsStr::new("PATH")) {
+            Some(path) => path.clone(),
+            None if self.sandbox => return None,
+            None => env::var_os("PATH")?,
+        };
+        env::split_paths(&path).find_map(|dir| {
+            let candidate = dir.join(program);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+            let candidate = candidate.with_extension(env::consts::EXE_EXTENSION);
+            (cfg!(windows) && candidate.is_file()).then_some(candidate)
+        })
+    }
+
+    fn base(&self) -> &Path {
+        self.current_dir.as_deref().unwrap_or_else(|| Path::new(""))
+    }
+
+    fn digest(&self) -> io::Result<Digest> {
+        let mut hasher = Sha256::new();
+        self.program.serialize(&mut hasher);
+        self.args.serialize(&mut hasher);
+        self.env.serialize(&mut hasher);
+        self.current_dir.serialize(&mut hasher);
+        self.outputs.serialize(&mut hasher);
+        self.sandbox.serialize(&mut hasher);
+        self.trace.serialize(&mut hasher);
+        etag::of_env(&self.tracked_env).serialize(&mut hasher);
+        let program = self.resolve_program();
+        program.serialize(&mut hasher);
+        program.map(Stamp::of).transpose()?.serialize(&mut hasher);
+        hasher.write_usize_var(self.inputs.len());
+        for input in &self.inputs {
+            input.serialize(&mut hasher);
+            Stamp::of(self.base().join(input))?.serialize(&mut hasher);
+        }
+        Ok(hasher.finish())
+    }
+
+    fn run(&self, cx: Context<'_>) -> io::Result<Output> {
+        let base = self.base();
+        let sandbox = if self.sandbox {
+            Some(cx.temp_dir()?)
+        } else {
+            None
+        };
+        if let Some(sandbox) = &sandbox {
+            for input in &self.inputs {
+                let dest = sandbox_path(sandbox, input)?;
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(base.join(input), dest)?;
+            }
+        }
+        let tracer = match self.trace {
+            true if cfg!(target_os = "linux") => Some(cx.temp_dir()?),
+            true => {
+                let msg = "tracing inputs is only supported on Linux";
+                return Err(io::Error::new(io::ErrorKind::Unsupported, msg));
+            }
+            false => None,
+        };
+        let trace_path = tracer.as_ref().map(|tracer| tracer.path().join("trace"));
+
+        let cwd = match &sandbox {
+            Some(sandbox) => Some(sandbox.path()),
+            None => self.current_dir.as_deref(),
+        };
+        let output = self.process(cwd, trace_path.as_deref()).output()?;
+        if !output.status.success() {
+            let program = Path::new(&self.program).display();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let mut msg = format!("`{program}` failed: {}\n{stderr}", output.status);
+            if let Some(sandbox) = sandbox {
+                write!(msg, "\nsandbox preserved at {}", sandbox.keep().display()).unwrap();
+            }
+            return Err(io::Error::new(io::ErrorKind::Other, msg));
+        }
+
+        if let Some(sandbox) = &sandbox {
+            for path in &self.outputs {
+                copy_if_changed(sandbox_path(sandbox, path)?, base.join(path))?;
+            }
+        }
+        if let Some(trace_path) = &trace_path {
+            let cwd = normalize(&env::current_dir()?.join(cwd.unwrap_or(base)));
+            self.check_trace(&fs::read_to_string(trace_path)?, &cwd)?;
+        }
+        Ok(output)
+    }
+
+    /// Construct the process to spawn, wrapping the program in `strace` if tracing.
+    fn process(&self, cwd: Option<&Path>, trace_path: Option<&Path>) -> process::Command {
+        let mut command = match trace_path {
+            Some(trace_path) => {
+                let mut command = process::Command::new("strace");
+                command.args(["-f", "-qq", "-e", TRACED_CALLS, "-o"]);
+                command.arg(trace_path).arg("--").arg(&self.program);
+                command
+            }
+            None => process::Command::new(&self.program),
+        };
+        command.args(&self.args);
+        if self.sandbox {
+            command.env_clear();
+        }
+        command.envs(&self.env);
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+        command
+    }
+
+    /// Check the output of `strace` for files read in `cwd` that were not declared as inputs.
+    ///
+    /// `cwd` must be absolute.
+    fn check_trace(&self, trace: &str, cwd: &Path) -> io::Result<()> {
+        let declared: BTreeSet<PathBuf> = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(|path| normalize(&cwd.join(path)))
+            .collect();
+
+        let undeclared: BTreeSet<PathBuf> = traced_reads(trace, cwd)
+            .into_iter()
+            .map(|path| normalize(&path))
+            .filter(|path| path.starts_with(cwd) && path != cwd && !declared.contains(path))
+            .collect();
+        if undeclared.is_empty() {
+            return Ok(());
+        }
+
+        let program = Path::new(&self.program).display();
+        let mut msg = format!("`{program}` read undeclared inputs:");
+        for path in &undeclared {
+            let path = path.strip_prefix(cwd).unwrap_or(path);
+            write!(msg, "\n  {}", path.display()).unwrap();
+        }
+        Err(io::Error::new(io::ErrorKind::Other, msg))
+    }
+}
+
+/// The system calls traced by `strace`:
+/// those that read files, and those that change how relative paths are resolved.
+const TRACED_CALLS: &str = "trace=open,openat,openat2,execve,chdir,fchdir,clone,clone3,fork,vfork";
+
+/// Reconstruct the paths of the files successfully opened for reading
+/// from the output of `strace -f`.
+///
+/// `cwd` is the absolute working directory the program started in.
+/// The working directory of each process and the directories it has open
+/// are followed through `chdir`, `fchdir` and forks,
+/// so that the returned paths are absolute
+/// even when they were opened relative to one of them.
+fn traced_reads(trace: &str, cwd: &Path) -> Vec<PathBuf> {
+    let mut tracer = Tracer {
+        cwd,
+        unfinished: BTreeMap::new(),
+        processes: BTreeMap::new(),
+        reads: Vec::new(),
+    };
+    for line in trace.lines() {
+        tracer.line(line);
+    }
+    tracer.reads
+}
+
+struct Tracer<'a> {
+    cwd: &'a Path,
+    /// The start of each system call whose output was interrupted by another process, by PID.
+    unfinished: BTreeMap<u32, String>,
+    processes: BTreeMap<u32, Process>,
+    reads: Vec<PathBuf>,
+}
+
+/// What is known about how a traced process resolves relative paths.
+#[derive(Clone)]
+struct Process {
+    cwd: PathBuf,
+    /// The directories the process has open, by file descriptor.
+    dirs: BTreeMap<u64, PathBuf>,
+}
+
+impl Tracer<'_> {
+    fn line(&mut self, line: &str) {
+        let Some((pid, call)) = line.split_once(' ') else {
+            return;
+        };
+        let Ok(pid) = pid.parse::<u32>() else {
+            return;
+        };
+        let call = call.trim_start();
+        // When processes make system calls concurrently,
+        // `strace` splits them into an unfinished and a resumed line.
+        if let Some(start) = call.strip_suffix(" <unfinished ...>") {
+            self.unfinished.insert(pid, start.to_owned());
+        } else if let Some(resumed) = call.strip_prefix("<... ") {
+            let Some((_, rest)) = resumed.split_once(" resumed>") else {
+                return;
+            };
+            if let Some(mut call) = self.unfinished.remove(&pid) {
+                call.push_str(rest);
+                self.call(pid, &call);
+            }
+        } else {
+            self.call(pid, call);
+        }
+    }
+
+    fn call(&mut self, pid: u32, call: &str) -> Option<()> {
+        let (name, rest) = call.split_once('(')?;
+        let (args, result) = rest.rsplit_once(") = ")?;
+        let result = result.split(' ').next()?.parse::<u64>().ok()?;
+        let process = self.processes.entry(pid).or_insert_with(|| Process {
+            cwd: self.cwd.to_owned(),
+            dirs: BTreeMap::new(),
+        });
+        match name {
+            "open" | "openat" | "openat2" | "execve" => {
+                let base = match name {
+                    "openat" | "openat2" => match args.split_once(", ")?.0 {
+                        "AT_FDCWD" => &process.cwd,
+                        dirfd => process.dirs.get(&dirfd.parse().ok()?)?,
+                    },
+                    _ => &process.cwd,
+                };
+                let (_, rest) = args.split_once('"')?;
+                let (path, flags) = unescape(rest)?;
+                let path = base.join(path_from_bytes(path));
+                if flags.contains("O_DIRECTORY") {
+                    process.dirs.insert(result, path);
+                } else if !flags.contains("O_WRONLY") {
+                    self.reads.push(path);
+                }
+            }
+            "chdir" => {
+                let (path, _) = unescape(args.strip_prefix('"')?)?;
+                process.cwd = process.cwd.join(path_from_bytes(path));
+            }
+            "fchdir" => process.cwd = process.dirs.get(&args.parse().ok()?)?.clone(),
+            "clone" | "clone3" | "fork" | "vfork" => {
+                let child = process.clone();
+                self.processes
+                    .entry(u32::try_from(result).ok()?)
+                    .or_insert(child);
+            }
+            _ => {}
+        }
+        Some(())
+    }
+}
+
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    #[cfg(unix)]
+    let path = OsString::from_vec(bytes);
+    #[cfg(not(unix))]
+    let path = String::from_utf8_lossy(&bytes).into_owned();
+    PathBuf::from(path)
+}
+
+/// Parse the contents of a C string literal as printed by `strace`, after the opening quote.
+fn unescape(s: &str) -> Option<(Vec<u8>, &str)> {
+    let mut bytes = Vec::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((bytes, &s[i + 1..])),
+            '\\' => {
+                let (_, c) = chars.next()?;
+                let byte = match c {
+                    'n' => b'\n',
+                    't' => b'\t',
+                    'r' => b'\r',
+                    'v' => 0x0B,
+                    'f' => 0x0C,
+                    '0'..='7' => {
+                        let mut n = c.to_digit(8)?;
+                        for _ in 0..2 {
+                            let digit = chars.clone().next().and_then(|(_, c)| c.to_digit(8));
+                            let Some(digit) = digit else { break };
+                            chars.next();
+                            n = n * 8 + digit;
+                        }
+                        u8::try_from(n).ok()?
+                    }
+                    'x' => {
+                        let (i, _) = chars.next()?;
+                        chars.next()?;
+                        u8::from_str_radix(s.get(i..i + 2)?, 16).ok()?
+                    }
+                    c => u8::try_from(c).ok()?,
+                };
+                bytes.push(byte);
+            }
+            c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    None
+}
+
+/// Lexically remove `.` and `..` components from an absolute path.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => drop(normalized.pop()),
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+impl<'c> Asset<'c> for Command {
+    type Etag = State;
+    type Output = io::Result<Output>;
+    type Generator = Generator<'c>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let digest = self.digest();
+        let delta = match &digest {
+            Ok(digest) => Delta::cmp(&etag.digest, digest).or(Delta::cmp(&etag.complete, &true)),
+            Err(_) => Delta::Modified,
+        };
+        if delta == Delta::Modified {
+            etag.complete = false;
+        }
+        delta.track(Generator {
+            command: self,
+            cx,
+            digest,
+            state: etag,
+        })
+    }
+}
+
+/// Generator for [`Command`].
+#[derive(Debug)]
+pub struct Generator<'c> {
+    command: Command,
+    cx: Context<'c>,
+    digest: io::Result<Digest>,
+    state: &'c mut State,
+}
+
+impl asset::Generator for Generator<'_> {
+    type Output = io::Result<Output>;
+
+    fn generate(self) -> Self::Output {
+        let digest = self.digest?;
+        self.state.complete = false;
+        let output = self.command.run(self.cx)?;
+        self.state.digest = digest;
+        self.state.complete = true;
+        Ok(output)
+    }
+}
+
+/// The persistent state of a [`Command`] asset.
+#[derive(Debug, Default)]
+pub struct State {
+    digest: Digest,
+    complete: bool,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.digest.serialize(writer);
+        self.complete.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            digest: Etag::deserialize(reader)?,
+            complete: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+/// Resolve a declared input or output path within a sandbox directory.
+fn sandbox_path(sandbox: &TempDir, path: &Path) -> io::Result<PathBuf> {
+    let escapes = path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        let msg = format!("sandboxed path {} must be relative", path.display());
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+    }
+    Ok(sandbox.path().join(path))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn trace() {
+        let lines = [
+            r#"12 openat(AT_FDCWD, "src/main.c", O_RDONLY) = 3"#,
+            r#"12 openat(AT_FDCWD, "out", O_WRONLY|O_CREAT|O_TRUNC, 0666) = 4"#,
+            r#"12 openat(AT_FDCWD, "missing", O_RDONLY) = -1 ENOENT (No such file or directory)"#,
+            r"12 clone(child_stack=NULL, flags=SIGCHLD) = 13",
+            r#"13 execve("/bin/cat", ["cat", "a\"b"], 0x7ffd /* 3 vars */) = 0"#,
+            r#"13 open("caf\303\251 \"q\".txt", O_RDONLY|O_CLOEXEC) = 5"#,
+            "13 +++ exited with 0 +++",
+        ];
+        assert_eq!(
+            traced_reads(&lines.join("\n"), Path::new("/work")),
+            [
+                PathBuf::from("/work/src/main.c"),
+                PathBuf::from("/bin/cat"),
+                PathBuf::from("/work/café \"q\".txt"),
+            ]
+        );
+
+        assert_eq!(normalize(Path::new("/a/./b/../c")), Path::new("/a/c"));
+    }
+
+    #[test]
+    fn trace_relative() {
+        let lines = [
+            r#"12 openat(AT_FDCWD, "src", O_RDONLY|O_DIRECTORY) = 3"#,
+            r"12 clone3({flags=CLONE_VM, exit_signal=SIGCHLD}, 88) = 13",
+            r#"12 chdir("build") = 0"#,
+            r#"13 openat(3, "lib.c", O_RDONLY <unfinished ...>"#,
+            r#"12 openat(AT_FDCWD, "config", O_RDONLY) = 4"#,
+            r"13 <... openat resumed>) = 4",
+            r"13 fchdir(3) = 0",
+            r#"13 open("../README", O_RDONLY) = 5"#,
+            r#"12 openat(3, "main.c", O_RDONLY) = 5"#,
+        ];
+        assert_eq!(
+            traced_reads(&lines.join("\n"), Path::new("/work")),
+            [
+                PathBuf::from("/work/build/config"),
+                PathBuf::from("/work/src/lib.c"),
+                PathBuf::from("/work/src/../README"),
+                PathBuf::from("/work/src/main.c"),
+            ]
+        );
+    }
+
+    use super::normalize;
+    use super::traced_reads;
+    use std::path::Path;
+    use std::path::PathBuf;
+}
+
+use crate::asset;
+use crate::asset::Context;
+use crate::etag;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::fs::copy_if_changed;
+use crate::fs::Stamp;
+use crate::fs::TempDir;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Asset;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt::Write as _;
+use std::borrow::ToOwned as _;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::env;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::format;
+use std::fs;
+use std::io;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStringExt as _;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::process::Output;
+use std::string::String;
+use std::vec::Vec;
@@ -0,0 +1,433 @@
+//! Client-side search indexes.
+//!
+//! The [`index`] asset builds an inverted index of a site’s pages as JSON files
+//! that a script on the site can fetch and query without a server.
+//! The index is split into shards by document,
+//! and only the shards whose documents changed are written again.
+//!
+//! # Format
+//!
+//! The directory contains `search.json`,
+//! an object whose `shards` field lists the file names of the shards.
+//! Each shard is an object with two fields:
+//!
+//! - `documents`: an array of `{"url": …, "title": …}` objects.
+//! - `terms`: an object mapping each term to an array of `[document, count]` pairs,
+//!   where `document` is an index into `documents`
+//!   and `count` is the number of times the term occurs in the document’s title and body.
+//!
+//! Terms are the maximal runs of alphanumeric characters, lowercased.
+//! A query is answered by looking up its terms in every shard.
+//!
+//! # Examples
+//!
+//! ```
+//! use mast::asset;
+//! use mast::asset::Generator as _;
+//! use mast::search;
+//! use mast::search::Document;
+//! use mast::Asset as _;
+//! use std::fs;
+//! # use mast::{Asset, Delta, Tracked};
+//! # struct Pages(Vec<Document>);
+//! # impl<'c> Asset<'c> for Pages {
+//! #     type Etag = ();
+//! #     type Output = Vec<Document>;
+//! #     type Generator = Self;
+//! #     fn update(self, _: asset::Context<'c>, (): &'c mut ()) -> Tracked<Self> {
+//! #         Delta::Modified.track(self)
+//! #     }
+//! # }
+//! # impl asset::Generator for Pages {
+//! #     type Output = Vec<Document>;
+//! #     fn generate(self) -> Self::Output { self.0 }
+//! # }
+//! # fn pages(documents: Vec<Document>) -> Pages { Pages(documents) }
+//! # let dir = std::env::temp_dir().join(format!("mast-doctest-search-{}", std::process::id()));
+//!
+//! let mut etag = Default::default();
+//! // `pages` is some asset outputting the documents.
+//! let documents = pages(vec![
+//!     Document::new("/", "Home", "Welcome to my site."),
+//!     Document::new("/about", "About", "I like writing static site generators."),
+//! ]);
+//! let asset = search::index(documents, &dir).shards(1);
+//! let report = asset.update(asset::Context::default(), &mut etag).value.generate()?;
+//! assert_eq!(report.written, ["search-0.json"]);
+//!
+//! let shard = fs::read_to_string(dir.join("search-0.json"))?;
+//! assert!(shard.starts_with(r#"{"documents":[{"url":"/","title":"Home"},"#));
+//! assert!(shard.contains(r#""site":[[0,1],[1,1]]"#));
+//! # fs::remove_dir_all(&dir).unwrap();
+//! # Ok::<_, std::io::Error>(())
+//! ```
+
+/// A page to be indexed for search.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Document {
+    /// The URL the search result links to.
+    pub url: String,
+    /// The title shown in the search result.
+    pub title: String,
+    /// The plain text content of the page.
+    pub body: String,
+}
+
+impl Document {
+    /// Construct a document.
+    #[must_use]
+    pub fn new<U, T, B>(url: U, title: T, body: B) -> Self
+    where
+        U: Into<String>,
+        T: Into<String>,
+        B: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            title: title.into(),
+            body: body.into(),
+        }
+    }
+}
+
+/// Build a search index of documents into a directory.
+///
+/// The `documents` asset should output an iterator of [`Document`]s.
+/// Each document is assigned to a shard by its URL,
+/// so editing a document causes only its own shard to be written again.
+/// See the [module documentation](self) for the format of the index.
+///
+/// The output of this asset is a [`Report`] of the shards that were written.
+pub fn index<A, P: Into<PathBuf>>(documents: A, dir: P) -> Index<A> {
+    Index {
+        documents,
+        dir: dir.into(),
+        shards: 16,
+    }
+}
+
+/// Asset for [`index`].
+#[derive(Debug)]
+pub struct Index<A> {
+    documents: A,
+    dir: PathBuf,
+    shards: u32,
+}
+
+impl<A> Index<A> {
+    /// Set the number of shards the index is split into.
+    ///
+    /// More shards mean less is written when a document changes,
+    /// but more files for the client to fetch.
+    /// Defaults to 16.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    #[must_use]
+    pub fn shards(mut self, shards: u32) -> Self {
+        assert_ne!(shards, 0, "a search index must have at least one shard");
+        self.shards = shards;
+        self
+    }
+}
+
+impl<'c, A> Asset<'c> for Index<A>
+where
+    A: Asset<'c>,
+    A::Output: IntoIterator<Item = Document>,
+{
+    type Etag = (A::Etag, State);
+    type Output = io::Result<Report>;
+    type Generator = Generator<'c, A::Generator>;
+
+    fn update(self, cx: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        let (documents_etag, state) = etag;
+        let documents = self.documents.update(cx, documents_etag);
+        let delta = documents
+            .delta
+            .or(Delta::cmp(&state.shards.len(), &(self.shards as usize)))
+            .or_else(|| {
+                let present = (0..self.shards).all(|i| self.dir.join(shard_name(i)).is_file())
+                    && self.dir.join(MANIFEST).is_file();
+                Delta::cmp(&present, &true)
+            });
+        delta.track(Generator {
+            documents: documents.value,
+            dir: self.dir,
+            shards: self.shards,
+            state,
+        })
+    }
+}
+
+/// Generator for [`Index`].
+#[derive(Debug)]
+pub struct Generator<'c, G> {
+    documents: G,
+    dir: PathBuf,
+    shards: u32,
+    state: &'c mut State,
+}
+
+impl<G> asset::Generator for Generator<'_, G>
+where
+    G: asset::Generator,
+    G::Output: IntoIterator<Item = Document>,
+{
+    type Output = io::Result<Report>;
+
+    fn generate(self) -> Self::Output {
+        let state = self.state;
+        let mut report = Report::default();
+
+        let mut shards: Vec<Vec<Document>> = (0..self.shards).map(|_| Vec::new()).collect();
+        for document in self.documents.generate() {
+            let digest = Sha256::digest(document.url.as_bytes());
+            let bucket = u32::from_le_bytes(digest.0[..4].try_into().unwrap()) % self.shards;
+            shards[bucket as usize].push(document);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+
+        // Shards from a previous, larger index are no longer referenced.
+        for i in self.shards..u32::try_from(state.shards.len()).unwrap_or(u32::MAX) {
+            match fs::remove_file(self.dir.join(shard_name(i))) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        state.shards.resize(self.shards as usize, None);
+
+        for (i, mut documents) in (0..self.shards).zip(shards) {
+            documents.sort_by(|a, b| a.url.cmp(&b.url));
+            let mut hasher = Sha256::new();
+            for document in &documents {
+                document.url.serialize(&mut hasher);
+                document.title.serialize(&mut hasher);
+                document.body.serialize(&mut hasher);
+            }
+            let digest = hasher.finish();
+
+            let name = shard_name(i);
+            let path = self.dir.join(&name);
+            if state.shards[i as usize] == Some(digest) && path.is_file() {
+                report.skipped.push(name);
+                continue;
+            }
+            // Forget the shard first so that a failed write is retried next time.
+            state.shards[i as usize] = None;
+            let written = write_if_changed(&path, shard_json(&documents))?;
+            state.shards[i as usize] = Some(digest);
+            if written {
+                report.written.push(name);
+            } else {
+                report.skipped.push(name);
+            }
+        }
+
+        let mut manifest = String::from("{\"shards\":[");
+        for i in 0..self.shards {
+            if i != 0 {
+                manifest.push(',');
+            }
+            write_json_str(&mut manifest, &shard_name(i));
+        }
+        manifest.push_str("]}");
+        write_if_changed(self.dir.join(MANIFEST), manifest)?;
+
+        Ok(report)
+    }
+}
+
+const MANIFEST: &str = "search.json";
+
+fn shard_name(i: u32) -> String {
+    format!("search-{i}.json")
+}
+
+fn shard_json(documents: &[Document]) -> String {
+    let mut terms = BTreeMap::<String, Vec<(usize, u32)>>::new();
+    for (i, document) in documents.iter().enumerate() {
+        let mut counts = BTreeMap::<String, u32>::new();
+        for text in [&document.title, &document.body] {
+            for term in text.split(|c: char| !c.is_alphanumeric()) {
+                if !term.is_empty() {
+                    *counts.entry(term.to_lowercase()).or_default() += 1;
+                }
+            }
+        }
+        for (term, count) in counts {
+            terms.entry(term).or_default().push((i, count));
+        }
+    }
+
+    let mut json = String::from("{\"documents\":[");
+    for (i, document) in documents.iter().enumerate() {
+        if i != 0 {
+            json.push(',');
+        }
+        json.push_str("{\"url\":");
+        write_json_str(&mut json, &document.url);
+        json.push_str(",\"title\":");
+        write_json_str(&mut json, &document.title);
+        json.push('}');
+    }
+    json.push_str("],\"terms\":{");
+    for (i, (term, postings)) in terms.iter().enumerate() {
+        if i != 0 {
+            json.push(',');
+        }
+        write_json_str(&mut json, term);
+        json.push_str(":[");
+        for (j, (document, count)) in postings.iter().enumerate() {
+            if j != 0 {
+                json.push(',');
+            }
+            write!(json, "[{document},{count}]").unwrap();
+        }
+        json.push(']');
+    }
+    json.push_str("}}");
+    json
+}
+
+/// A summary of the work performed by [`index`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Report {
+    /// The shards that were written.
+    pub written: Vec<String>,
+    /// The shards whose documents had not changed,
+    /// or whose file already had the same contents.
+    pub skipped: Vec<String>,
+}
+
+/// The persistent state of an [`Index`] asset:
+/// a digest of the documents in each shard.
+#[derive(Debug, Default)]
+pub struct State {
+    shards: Vec<Option<Digest>>,
+}
+
+impl Etag for State {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        self.shards.serialize(writer);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        Ok(Self {
+            shards: Etag::deserialize(reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn incremental() {
+        struct Documents(Vec<Document>);
+        impl<'c> Asset<'c> for Documents {
+            type Etag = ();
+            type Output = Vec<Document>;
+            type Generator = Box<dyn FnOnce() -> Vec<Document>>;
+            fn update(self, _: Context<'c>, (): &'c mut ()) -> Tracked<Self::Generator> {
+                Delta::Modified.track(Box::new(move || self.0))
+            }
+        }
+
+        let dir = env::temp_dir().join(format!("mast-test-search-{}", process::id()));
+        let mut etag = Default::default();
+        let cx = Context::default();
+        let documents = |body| {
+            Documents(
+                (0..8)
+                    .map(|i| Document::new(format!("/{i}"), "Page", body))
+                    .chain([Document::new("/edited", "Edited", body)])
+                    .collect(),
+            )
+        };
+
+        let index = |body| super::index(documents(body), &dir).shards(4);
+        let report = index("a").update(cx, &mut etag).value.generate().unwrap();
+        assert_eq!(report.written.len(), 4);
+
+        let report = index("a").update(cx, &mut etag).value.generate().unwrap();
+        assert_eq!(report.skipped.len(), 4);
+
+        let report = index("b").update(cx, &mut etag).value.generate().unwrap();
+        assert_eq!(report.written.len(), 4);
+
+        let edited = |body| {
+            let mut documents = documents("b");
+            documents.0.last_mut().unwrap().body = String::from(body);
+            super::index(documents, &dir).shards(4)
+        };
+        let report = edited("c").update(cx, &mut etag).value.generate().unwrap();
+        assert_eq!(report.written.len(), 1);
+        let shard = fs::read_to_string(dir.join(&report.written[0])).unwrap();
+        assert!(shard.contains(r#""c":[["#));
+
+        let report = edited("c")
+            .shards(2)
+            .update(cx, &mut etag)
+            .value
+            .generate()
+            .unwrap();
+        assert_eq!(report.written.len(), 2);
+        assert!(!dir.join("search-3.json").exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("search.json")).unwrap(),
+            r#"{"shards":["search-0.json","search-1.json"]}"#
+        );
+
+        // Without the previous state, shards whose files are unchanged are not rewritten.
+        let mut etag = Default::default();
+        let report = edited("c")
+            .shards(2)
+            .update(cx, &mut etag)
+            .value
+            .generate()
+            .unwrap();
+        assert!(report.written.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use super::Document;
+    use crate::asset::Context;
+    use crate::asset::Generator as _;
+    use crate::Asset;
+    use crate::Delta;
+    use crate::Tracked;
+    use std::boxed::Box;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::process;
+    use std::string::String;
+    use std::vec::Vec;
+}
+
+use crate::asset;
+use crate::asset::Asset;
+use crate::asset::Context;
+use crate::diagnostic::write_json_str;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::fs::write_if_changed;
+use crate::hash::Digest;
+use crate::hash::Sha256;
+use crate::Delta;
+use crate::Etag;
+use crate::Tracked;
+use core::fmt::Write as _;
+use std::collections::BTreeMap;
+use std::format;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::string::String;
+use std::vec::Vec;
@@ -0,0 +1,264 @@
+//! Serving the latest output of a build that is kept up to date in the background.
+//!
+//! [`watch`] moves a [`Build`] to a background thread,
+//! which brings it up to date periodically or [on request](Watch::rebuild)
+//! and atomically publishes each newly generated output.
+//! Readers, such as the request handlers of a server embedding the generated content,
+//! [load](Live::load) the latest completed output through a [`Live`] handle
+//! without ever taking a lock or waiting for a build in progress.
+
+/// Build an asset on a background thread, publishing each new output to a [`Live`] handle.
+///
+/// The thread constructs a [`Context`] from `values`
+/// and a [`Build`] from `description`,
+/// then brings the build up to date immediately
+/// and again whenever `interval` passes or [`Watch::rebuild`] is called.
+/// Whenever the root asset is modified,
+/// its output is generated and replaces the previous one.
+///
+/// If the description or the generator panics,
+/// the panic is reported as usual and the thread carries on:
+/// readers continue to see the last output that was published,
+/// and the next build starts afresh, as if it were the first.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::share_arcswap;
+/// use mast::Asset as _;
+/// use std::sync::atomic::AtomicU32;
+/// use std::sync::atomic::Ordering::SeqCst;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let version = Arc::new(AtomicU32::new(1));
+/// let description = {
+///     let version = version.clone();
+///     move |_: asset::Context<'_>| {
+///         let v = version.load(SeqCst);
+///         asset::constant(format!("version {v}")).version(v)
+///     }
+/// };
+/// let watch = share_arcswap::watch((), description, Duration::from_secs(60));
+///
+/// let live = watch.live();
+/// while live.load().is_none() {
+///     std::thread::yield_now();
+/// }
+/// assert_eq!(*live.load().unwrap(), "version 1");
+///
+/// version.store(2, SeqCst);
+/// watch.rebuild();
+/// while *live.load().unwrap() != "version 2" {
+///     std::thread::yield_now();
+/// }
+/// watch.stop();
+/// ```
+pub fn watch<C, F, A, E, O>(values: C, description: F, interval: Duration) -> Watch<O>
+where
+    C: Tuple + Send + 'static,
+    F: FnMut(Context<'_>) -> A + Send + 'static,
+    A: for<'c> Asset<'c, Etag = E, Output = O>,
+    E: Etag + Send + 'static,
+    O: Send + Sync + 'static,
+{
+    let live = Live {
+        current: Arc::new(ArcSwapOption::empty()),
+    };
+    let signal = Arc::new(Signal {
+        state: Mutex::new(State::default()),
+        condvar: Condvar::new(),
+    });
+    let thread = thread::spawn({
+        let current = live.current.clone();
+        let signal = signal.clone();
+        move || {
+            let cx = Context::from_tuple(&values);
+            let mut build = Build::new(description);
+            loop {
+                match panic::catch_unwind(AssertUnwindSafe(|| build.build(cx))) {
+                    Ok(Some(output)) => current.store(Some(Arc::new(output))),
+                    Ok(None) => {}
+                    // The panic may have left the etag half updated.
+                    Err(_) => build.reset(),
+                }
+                let state = lock(&signal.state);
+                let (mut state, _) = signal
+                    .condvar
+                    .wait_timeout_while(state, interval, |state| !state.stop && !state.rebuild)
+                    .unwrap_or_else(PoisonError::into_inner);
+                if state.stop {
+                    return;
+                }
+                state.rebuild = false;
+            }
+        }
+    });
+    Watch {
+        live,
+        signal,
+        thread: Some(thread),
+    }
+}
+
+/// The background build started by [`watch`].
+///
+/// Dropping this stops the build, waiting for any build in progress to finish;
+/// [`Live`] handles keep the last output that was published.
+pub struct Watch<O> {
+    live: Live<O>,
+    signal: Arc<Signal>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<O> Watch<O> {
+    /// A handle to the latest output of the build.
+    #[must_use]
+    pub fn live(&self) -> Live<O> {
+        self.live.clone()
+    }
+
+    /// Bring the build up to date as soon as possible,
+    /// without waiting for the interval to pass.
+    pub fn rebuild(&self) {
+        lock(&self.signal.state).rebuild = true;
+        self.signal.condvar.notify_one();
+    }
+
+    /// Stop the build, waiting for any build in progress to finish.
+    pub fn stop(self) {}
+}
+
+impl<O> Debug for Watch<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watch")
+            .field("live", &self.live)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<O> Drop for Watch<O> {
+    fn drop(&mut self) {
+        lock(&self.signal.state).stop = true;
+        self.signal.condvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            // Panics in the build are caught by the thread.
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A handle to the latest output of a build started by [`watch`].
+///
+/// Cloning a handle is cheap, and the clones share the output.
+pub struct Live<O> {
+    current: Arc<ArcSwapOption<O>>,
+}
+
+impl<O> Live<O> {
+    /// The latest output of the build,
+    /// or [`None`] if the first build has not finished yet.
+    ///
+    /// This never blocks, even while a build is in progress.
+    #[must_use]
+    pub fn load(&self) -> Option<Arc<O>> {
+        self.current.load_full()
+    }
+}
+
+impl<O> Clone for Live<O> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<O> Debug for Live<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let ready = self.current.load().is_some();
+        f.debug_struct("Live").field("ready", &ready).finish()
+    }
+}
+
+struct Signal {
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    rebuild: bool,
+    stop: bool,
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn panic() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let description = {
+            let runs = runs.clone();
+            move |_: Context<'_>| {
+                let run = runs.fetch_add(1, SeqCst) + 1;
+                assert!(run != 1, "first build fails");
+                asset::constant(run).version(run)
+            }
+        };
+        let watch = watch((), description, Duration::from_secs(60));
+        let live = watch.live();
+
+        // The first build panics, so the thread waits for a rebuild.
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while runs.load(SeqCst) == 0 {
+            assert!(Instant::now() < deadline, "the first build never ran");
+            thread::yield_now();
+        }
+        watch.rebuild();
+        while live.load().is_none() {
+            assert!(
+                Instant::now() < deadline,
+                "the build did not recover from a panic"
+            );
+            thread::yield_now();
+        }
+        assert_eq!(*live.load().unwrap(), 2);
+        watch.stop();
+    }
+
+    use super::watch;
+    use crate::asset;
+    use crate::asset::Context;
+    use crate::Asset as _;
+    use core::sync::atomic::AtomicU32;
+    use core::sync::atomic::Ordering::SeqCst;
+    use core::time::Duration;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Instant;
+}
+
+use crate::asset::context::Tuple;
+use crate::asset::Context;
+use crate::build::Build;
+use crate::Asset;
+use crate::Etag;
+use arc_swap::ArcSwapOption;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::panic::AssertUnwindSafe;
+use core::time::Duration;
+use std::panic;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::thread;
+use std::thread::JoinHandle;
@@ -0,0 +1,900 @@
+//! Persisting etags between runs.
+//!
+//! A [`Store`] is a directory holding one file per etag,
+//! keyed by a string ID chosen by the driver
+//! (typically the name of the asset the etag belongs to).
+//! Since IDs tend to come and go as a pipeline evolves,
+//! [`Store::gc`] removes the etags of assets that no longer exist.
+//!
+//! To stop concurrent builds from corrupting each other’s etags and outputs,
+//! a process should hold the store’s [`Lock`] for the duration of each build.
+//!
+//! # Crash safety
+//!
+//! Each etag is saved by writing a new file and renaming it over the old one,
+//! so a crash never leaves an etag half-written.
+//! But an etag must also never outlive the outputs it describes:
+//! if a build crashes after an asset has started overwriting its outputs
+//! but before its new etag is saved,
+//! the old etag would claim that the half-written outputs are up to date.
+//! To prevent this, drivers call [`Store::begin`] with an asset’s ID
+//! before it writes any outputs, and [`Store::commit`] once the build is over.
+//! The IDs passed to `begin` are recorded in a journal,
+//! and if the store is opened while a journal exists,
+//! the etags it lists are discarded, so those assets report themselves as modified.
+//!
+//! # Invalidation hints
+//!
+//! Some dependencies are invisible to etags,
+//! such as a config parser noticing that a section other assets read has been renamed.
+//! An asset can [`invalidate`] another asset by its ID,
+//! which records a hint in the store;
+//! the etags of invalidated assets are discarded before the next build,
+//! so those assets report themselves as modified.
+
+/// A directory of etags, keyed by ID.
+///
+/// # Examples
+///
+/// ```
+/// use mast::store::Store;
+///
+/// let dir = std::env::temp_dir().join(format!("mast-doctest-store-{}", std::process::id()));
+/// let store = Store::open(&dir)?;
+///
+/// assert_eq!(store.load::<u32>("a")?, 0);
+/// store.save("a", &37_u32)?;
+/// store.save("b", &5_u32)?;
+/// assert_eq!(store.load::<u32>("a")?, 37);
+///
+/// // After a build in which only `a` was used, `b` can be removed.
+/// let report = store.gc(["a"])?;
+/// assert_eq!(report.removed, 1);
+/// assert_eq!(store.load::<u32>("b")?, 0);
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    /// Open the store in the directory `dir`, creating it if it does not exist.
+    ///
+    /// If a previous build crashed between [`begin`](Self::begin) and [`commit`](Self::commit),
+    /// the etags of the assets it had begun are discarded.
+    /// That recovery waits for the store’s [`Lock`] and holds it throughout,
+    /// so that it never mistakes a build running in another process for a crashed one;
+    /// hence this must not be called while the calling process holds the lock.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directory could not be created or recovery failed.
+    pub fn open<P: Into<PathBuf>>(dir: P) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let store = Self { dir };
+        // Most of the time there is nothing to recover, and so no need to wait for the lock.
+        let pending = [
+            store.journal_path(),
+            store.invalidations_path(),
+            store.applying_path(),
+        ];
+        if pending.iter().any(|path| path.exists()) {
+            let _lock = store.lock(&LockPolicy::new())?;
+            store.recover()?;
+        }
+        Ok(store)
+    }
+
+    fn recover(&self) -> io::Result<()> {
+        let journal = match fs::read_to_string(self.journal_path()) {
+            Ok(journal) => journal,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        // A line cut off by the crash is skipped:
+        // `begin` had not returned, so that asset cannot have written anything yet.
+        for name in journal.lines().filter(|name| is_etag_file(name)) {
+            match fs::remove_file(self.dir.join(name)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        self.commit()?;
+        self.apply_invalidations()
+    }
+
+    /// Get the directory this store keeps its etags in.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Load the etag with the given ID.
+    ///
+    /// If there is no such etag, or it cannot be deserialized as an `E`,
+    /// the default etag is returned, so the asset will simply be rebuilt.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the etag’s file exists but could not be read.
+    pub fn load<E: Etag>(&self, id: &str) -> io::Result<E> {
+        match fs::read(self.path(id)) {
+            Ok(bytes) => Ok(E::from_bytes(&bytes).unwrap_or_default()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(E::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Save the etag with the given ID, replacing any previous one.
+    ///
+    /// The etag is replaced atomically:
+    /// if the process crashes, either the old or the new etag will be loaded.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the etag could not be written.
+    pub fn save<E: Etag>(&self, id: &str, etag: &E) -> io::Result<()> {
+        let path = self.path(id);
+        // The name is unique so that concurrent saves of the same etag cannot interleave.
+        let n = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(format!(".{}-{n}.tmp", process::id()));
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&etag.to_vec())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Record that the asset with the given ID is about to write its outputs.
+    ///
+    /// Until the next [`commit`](Self::commit),
+    /// a crash will cause its etag to be discarded when the store is next opened.
+    /// See the [module documentation](self#crash-safety) for details.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the journal could not be written.
+    pub fn begin(&self, id: &str) -> io::Result<()> {
+        let mut journal = fs::File::options()
+            .create(true)
+            .append(true)
+            .open(self.journal_path())?;
+        writeln!(journal, "{}", file_name(id))?;
+        journal.sync_data()
+    }
+
+    /// Record that every asset passed to [`begin`](Self::begin) has saved its etag,
+    /// or that the build was abandoned with the etags left as they were.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the journal could not be removed.
+    pub fn commit(&self) -> io::Result<()> {
+        match fs::remove_file(self.journal_path()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record that the asset with the given ID should be considered modified in the next build,
+    /// even if its etag says otherwise.
+    ///
+    /// The hint is persisted immediately,
+    /// but only takes effect when [`apply_invalidations`](Self::apply_invalidations) is called,
+    /// which happens when the store is next opened,
+    /// so an asset that has already saved its etag in the current build
+    /// is not rebuilt again within it.
+    /// Assets usually call this through [`invalidate`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the hint could not be written.
+    pub fn invalidate(&self, id: &str) -> io::Result<()> {
+        let mut invalidations = fs::File::options()
+            .create(true)
+            .append(true)
+            .open(self.invalidations_path())?;
+        writeln!(invalidations, "{}", file_name(id))?;
+        invalidations.sync_data()
+    }
+
+    /// Discard the etags of every asset [invalidated](Self::invalidate) since this was last called.
+    ///
+    /// This is called by [`open`](Self::open);
+    /// drivers that keep a store open across builds, such as `watch` loops,
+    /// should also call it before each build.
+    /// It must be called while holding the store’s [`lock`](Self::lock),
+    /// so that no other process applies the hints at the same time.
+    ///
+    /// Hints recorded while this runs, by assets of a build in another process,
+    /// are kept for the next call rather than lost.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the hints could not be read or an etag could not be removed.
+    pub fn apply_invalidations(&self) -> io::Result<()> {
+        // Hints taken by a previous call that was interrupted.
+        self.apply_hints()?;
+        // `invalidate` appends to the hints file, so it is moved out of the way first:
+        // anything appended after this starts a new file instead of being removed unapplied.
+        match fs::rename(self.invalidations_path(), self.applying_path()) {
+            Ok(()) => self.apply_hints(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn apply_hints(&self) -> io::Result<()> {
+        let invalidations = match fs::read_to_string(self.applying_path()) {
+            Ok(invalidations) => invalidations,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        for name in invalidations.lines().filter(|name| is_etag_file(name)) {
+            match fs::remove_file(self.dir.join(name)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        match fs::remove_file(self.applying_path()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove the etag with the given ID, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the etag could not be removed.
+    pub fn remove(&self, id: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(id)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove every etag whose ID is not in `live_ids`.
+    ///
+    /// This is intended to be called after a successful build,
+    /// with the IDs of every asset in the current graph.
+    /// It is equivalent to [`gc_with`](Self::gc_with) with the default [`GcPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directory could not be read or an etag could not be removed.
+    pub fn gc<I>(&self, live_ids: I) -> io::Result<GcReport>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.gc_with(live_ids, &GcPolicy::default())
+    }
+
+    /// Remove etags whose ID is not in `live_ids`, subject to the given policy.
+    ///
+    /// Etags with live IDs are never removed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directory could not be read or an etag could not be removed.
+    pub fn gc_with<I>(&self, live_ids: I, policy: &GcPolicy) -> io::Result<GcReport>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let live: BTreeSet<OsString> = live_ids
+            .into_iter()
+            .map(|id| file_name(id.as_ref()).into())
+            .collect();
+
+        let now = SystemTime::now();
+        let mut total = 0;
+        let mut stale = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let is_etag = entry.file_name().to_str().is_some_and(is_etag_file);
+            if !metadata.is_file() || !is_etag {
+                continue;
+            }
+            total += metadata.len();
+            if live.contains(&entry.file_name()) {
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(now);
+            stale.push((modified, metadata.len(), entry.path()));
+        }
+        // Oldest first, so that those are the first to go when the store is too large.
+        stale.sort();
+
+        let mut report = GcReport::default();
+        for (modified, len, path) in stale {
+            let age = now.duration_since(modified).unwrap_or_default();
+            let too_large = matches!(policy.max_size, Some(max) if total > max);
+            if age < policy.keep_for && !too_large {
+                continue;
+            }
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            total -= len;
+            report.removed += 1;
+            report.freed += len;
+        }
+        Ok(report)
+    }
+
+    /// Acquire the lock of this store, according to the given policy.
+    ///
+    /// This is a [`Lock`] on the file `lock` in the store’s directory.
+    ///
+    /// # Errors
+    ///
+    /// See [`Lock::acquire`].
+    pub fn lock(&self, policy: &LockPolicy) -> io::Result<Lock> {
+        Lock::acquire(self.dir.join("lock"), policy)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(file_name(id))
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.dir.join("journal")
+    }
+
+    fn invalidations_path(&self) -> PathBuf {
+        self.dir.join("invalidations")
+    }
+
+    /// The hints being applied by [`apply_invalidations`](Self::apply_invalidations).
+    fn applying_path(&self) -> PathBuf {
+        self.dir.join("invalidations.applying")
+    }
+}
+
+/// Ask for the asset with the given ID to be considered modified in the next build,
+/// through the [`Store`] in the context.
+///
+/// See [`Store::invalidate`] for details.
+/// If there is no `Store` in the context, this does nothing.
+///
+/// # Errors
+///
+/// Fails if the hint could not be written.
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::store;
+/// use mast::store::LockPolicy;
+/// use mast::store::Store;
+///
+/// let dir = std::env::temp_dir().join(format!("mast-doctest-invalidate-{}", std::process::id()));
+/// let values = (Store::open(&dir)?,);
+/// let cx = asset::Context::from_tuple(&values);
+/// values.0.save("templates", &37_u32)?;
+///
+/// // The config parser notices that the templates section was renamed.
+/// store::invalidate(cx, "templates")?;
+/// assert_eq!(values.0.load::<u32>("templates")?, 37);
+///
+/// // Before the next build:
+/// let _lock = values.0.lock(&LockPolicy::new())?;
+/// values.0.apply_invalidations()?;
+/// assert_eq!(values.0.load::<u32>("templates")?, 0);
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn invalidate(cx: Context<'_>, id: &str) -> io::Result<()> {
+    match cx.try_get::<Store>() {
+        Some(store) => store.invalidate(id),
+        None => Ok(()),
+    }
+}
+
+/// A cross-process lock, held for as long as this value is alive.
+///
+/// The lock is a file that exists only while the lock is held,
+/// so it works on any filesystem and any platform.
+/// While the lock is held, a background thread rewrites the file every second,
+/// so its modification time shows that the holder is still alive.
+/// If a process dies while holding a lock,
+/// the file remains and must be removed manually or
+/// [stolen](LockPolicy::steal_after) by the next process.
+/// A holder whose lock was stolen leaves the file alone when it is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use mast::store::Lock;
+/// use mast::store::LockPolicy;
+/// use std::time::Duration;
+///
+/// let path = std::env::temp_dir().join(format!("mast-doctest-lock-{}", std::process::id()));
+/// let policy = LockPolicy::new().timeout(Duration::ZERO);
+///
+/// let lock = Lock::acquire(&path, &policy)?;
+/// assert!(Lock::acquire(&path, &policy).is_err());
+/// drop(lock);
+/// assert!(Lock::acquire(&path, &policy).is_ok());
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+    /// What this holder wrote to the lock file,
+    /// to tell whether the lock has since been stolen by another.
+    owner: String,
+    /// The thread keeping the lock file fresh, which stops once the sender is dropped.
+    refresher: Option<(mpsc::Sender<()>, thread::JoinHandle<()>)>,
+}
+
+impl Lock {
+    /// Acquire the lock whose file is at `path`,
+    /// waiting for any other holder to release it.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::TimedOut`]
+    /// if the lock could not be acquired within the [timeout](LockPolicy::timeout),
+    /// or if the lock file could not be created.
+    pub fn acquire<P: Into<PathBuf>>(path: P, policy: &LockPolicy) -> io::Result<Self> {
+        let path = path.into();
+        let start = Instant::now();
+        loop {
+            match fs::File::options().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    // The process ID also helps humans inspecting the file;
+                    // the counter distinguishes locks taken by the same process.
+                    let n = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+                    let owner = format!("{} {n}\n", process::id());
+                    if let Err(e) = file.write_all(owner.as_bytes()) {
+                        drop(file);
+                        let _ = fs::remove_file(&path);
+                        return Err(e);
+                    }
+                    let refresher = spawn_refresher(path.clone(), owner.clone());
+                    return Ok(Self {
+                        path,
+                        owner,
+                        refresher: Some(refresher),
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+
+            if let Some(steal_after) = policy.steal_after {
+                let age = fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .map(|modified| modified.elapsed().unwrap_or_default());
+                if matches!(age, Ok(age) if age >= steal_after) {
+                    // Check the age again once the file is out of the way,
+                    // in case it was replaced by a fresh lock in the meantime.
+                    if let Some(claimed) = claim(&path)? {
+                        let age = fs::metadata(&claimed)
+                            .and_then(|metadata| metadata.modified())
+                            .map(|modified| modified.elapsed().unwrap_or_default());
+                        if matches!(age, Ok(age) if age >= steal_after) {
+                            fs::remove_file(&claimed)?;
+                        } else {
+                            restore(&claimed, &path)?;
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            if matches!(policy.timeout, Some(timeout) if start.elapsed() >= timeout) {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "timed out waiting for the lock `{}`; \
+                        if no other build is running, remove it",
+                        path.display(),
+                    ),
+                ));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Get the path of the lock file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if let Some((stop, refresher)) = self.refresher.take() {
+            drop(stop);
+            let _ = refresher.join();
+        }
+        // The file is moved out of the way before it is checked,
+        // so that it cannot be stolen and replaced in between.
+        if let Ok(Some(claimed)) = claim(&self.path) {
+            if fs::read(&claimed).is_ok_and(|contents| contents == self.owner.as_bytes()) {
+                let _ = fs::remove_file(&claimed);
+            } else {
+                // The lock was stolen, and the file belongs to its new holder.
+                let _ = restore(&claimed, &self.path);
+            }
+        }
+    }
+}
+
+/// Start a thread that rewrites the lock file every [`REFRESH_INTERVAL`]
+/// for as long as it still belongs to `owner`,
+/// until the returned sender is dropped.
+fn spawn_refresher(path: PathBuf, owner: String) -> (mpsc::Sender<()>, thread::JoinHandle<()>) {
+    let (stop, stopped) = mpsc::channel();
+    let refresher = thread::spawn(move || {
+        while stopped.recv_timeout(REFRESH_INTERVAL) == Err(mpsc::RecvTimeoutError::Timeout) {
+            let _ = refresh(&path, &owner);
+        }
+    });
+    (stop, refresher)
+}
+
+/// Update the modification time of the lock file by rewriting it,
+/// if it still belongs to `owner`.
+fn refresh(path: &Path, owner: &str) -> io::Result<()> {
+    let mut file = fs::File::options().read(true).write(true).open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    if contents == owner {
+        file.rewind()?;
+        file.write_all(owner.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Move the lock file to a name unique to this call and return its new path,
+/// or `None` if there is no lock file.
+fn claim(path: &Path) -> io::Result<Option<PathBuf>> {
+    let n = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+    let mut claimed = path.to_path_buf().into_os_string();
+    claimed.push(format!(".{}-{n}.claimed", process::id()));
+    match fs::rename(path, &claimed) {
+        Ok(()) => Ok(Some(PathBuf::from(claimed))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Put back a lock file moved by [`claim`], unless another lock has been taken since.
+fn restore(claimed: &Path, path: &Path) -> io::Result<()> {
+    match fs::hard_link(claimed, path) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    fs::remove_file(claimed)
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A counter making the lock owners and temporary file names of this process unique.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// How [`Lock::acquire`] deals with a lock that is already held.
+///
+/// The default policy waits for as long as it takes and never steals the lock.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LockPolicy {
+    /// Give up waiting after this long.
+    pub timeout: Option<Duration>,
+    /// Consider a lock that has been held for this long to be abandoned,
+    /// for example by a process that crashed, and take it over.
+    ///
+    /// Holders refresh their lock every second,
+    /// so this need only be long enough that a holder that is alive
+    /// is never stalled for that long, such as ten seconds.
+    pub steal_after: Option<Duration>,
+}
+
+impl LockPolicy {
+    /// Construct the default policy.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`timeout`](Self::timeout).
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set [`steal_after`](Self::steal_after).
+    #[must_use]
+    pub fn steal_after(mut self, steal_after: Duration) -> Self {
+        self.steal_after = Some(steal_after);
+        self
+    }
+}
+
+/// The name of the file storing the etag with the given ID.
+///
+/// IDs are hashed so that they can contain any characters.
+fn file_name(id: &str) -> String {
+    Sha256::digest(id.as_bytes()).to_string()
+}
+
+/// Whether a file in the store’s directory holds an etag,
+/// as opposed to being the lock, the journal, the invalidation hints or a temporary file.
+fn is_etag_file(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// How [`Store::gc_with`] chooses which unused etags to remove.
+///
+/// The default policy removes every unused etag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GcPolicy {
+    /// Keep unused etags that were saved within this long,
+    /// so that briefly switching to another version of the pipeline
+    /// (say, on another branch) does not lose them.
+    pub keep_for: Duration,
+    /// Remove unused etags regardless of [`keep_for`](Self::keep_for),
+    /// oldest first, for as long as the store takes up more than this many bytes.
+    pub max_size: Option<u64>,
+}
+
+impl GcPolicy {
+    /// Construct the default policy.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`keep_for`](Self::keep_for).
+    #[must_use]
+    pub fn keep_for(mut self, keep_for: Duration) -> Self {
+        self.keep_for = keep_for;
+        self
+    }
+
+    /// Set [`max_size`](Self::max_size).
+    #[must_use]
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+}
+
+/// What [`Store::gc`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GcReport {
+    /// The number of etags removed.
+    pub removed: usize,
+    /// The total size of the removed etags, in bytes.
+    pub freed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    // `File::set_modified` is newer than the MSRV, but tests need not build on it.
+    #[allow(clippy::incompatible_msrv)]
+    fn gc_policy() {
+        let dir = env::temp_dir().join(format!("mast-test-store-gc-{}", process::id()));
+        let store = Store::open(&dir).unwrap();
+        store.save("old", &vec![0_u8; 100]).unwrap();
+        store.save("live", &vec![0_u8; 100]).unwrap();
+        store.save("new", &vec![0_u8; 100]).unwrap();
+        let hour = Duration::from_secs(60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(store.path("old"))
+            .unwrap()
+            .set_modified(SystemTime::now() - 2 * hour)
+            .unwrap();
+
+        let policy = GcPolicy::new().keep_for(hour);
+        let lock = store.lock(&LockPolicy::new()).unwrap();
+        let report = store.gc_with(["live"], &policy).unwrap();
+        assert_eq!(report.removed, 1);
+        assert!(!store.path("old").exists());
+        assert!(dir.join("lock").exists());
+        drop(lock);
+
+        let report = store.gc_with(["live"], &policy.max_size(150)).unwrap();
+        assert_eq!(report.removed, 1);
+        assert!(!store.path("new").exists());
+        assert_eq!(store.load::<Vec<u8>>("live").unwrap().len(), 100);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recovery() {
+        let dir = env::temp_dir().join(format!("mast-test-store-recovery-{}", process::id()));
+        let store = Store::open(&dir).unwrap();
+        store.save("a", &1_u32).unwrap();
+        store.save("b", &2_u32).unwrap();
+        store.save("c", &3_u32).unwrap();
+
+        // A build that crashes after `a` and `b` began writing their outputs
+        // and `a` saved its etag.
+        store.begin("a").unwrap();
+        store.save("a", &10_u32).unwrap();
+        store.begin("b").unwrap();
+        drop(store);
+
+        let store = Store::open(&dir).unwrap();
+        assert_eq!(store.load::<u32>("a").unwrap(), 0);
+        assert_eq!(store.load::<u32>("b").unwrap(), 0);
+        assert_eq!(store.load::<u32>("c").unwrap(), 3);
+
+        // A build that completes.
+        store.begin("c").unwrap();
+        store.save("c", &30_u32).unwrap();
+        store.commit().unwrap();
+        let lock = store.lock(&LockPolicy::new()).unwrap();
+        store.gc(["c"]).unwrap();
+
+        let store = Store::open(&dir).unwrap();
+        assert_eq!(store.load::<u32>("c").unwrap(), 30);
+        assert!(dir.join("lock").exists());
+        drop(lock);
+
+        // A build in progress elsewhere is waited for rather than recovered from.
+        let lock = store.lock(&LockPolicy::new()).unwrap();
+        store.begin("c").unwrap();
+        thread::scope(|s| {
+            let opener = s.spawn(|| Store::open(&dir).unwrap());
+            thread::sleep(Duration::from_millis(200));
+            assert!(!opener.is_finished());
+            store.save("c", &300_u32).unwrap();
+            store.commit().unwrap();
+            drop(lock);
+            assert_eq!(opener.join().unwrap().load::<u32>("c").unwrap(), 300);
+        });
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalidations() {
+        let dir = env::temp_dir().join(format!("mast-test-store-invalidate-{}", process::id()));
+        let store = Store::open(&dir).unwrap();
+        store.save("a", &1_u32).unwrap();
+        store.save("b", &2_u32).unwrap();
+        store.save("c", &3_u32).unwrap();
+
+        // Hints taken by a call that was interrupted are applied by the next one.
+        store.invalidate("a").unwrap();
+        fs::rename(store.invalidations_path(), store.applying_path()).unwrap();
+        store.invalidate("b").unwrap();
+        let lock = store.lock(&LockPolicy::new()).unwrap();
+        store.apply_invalidations().unwrap();
+        assert_eq!(store.load::<u32>("a").unwrap(), 0);
+        assert_eq!(store.load::<u32>("b").unwrap(), 0);
+        assert_eq!(store.load::<u32>("c").unwrap(), 3);
+        assert!(!store.applying_path().exists());
+        assert!(!store.invalidations_path().exists());
+
+        // A hint recorded after the hints were taken is kept for next time.
+        store.invalidate("c").unwrap();
+        fs::rename(store.invalidations_path(), store.applying_path()).unwrap();
+        store.invalidate("c").unwrap();
+        store.apply_hints().unwrap();
+        assert!(store.invalidations_path().exists());
+        drop(lock);
+
+        // And is applied, along with any interrupted ones, when the store is opened.
+        store.save("c", &3_u32).unwrap();
+        let store = Store::open(&dir).unwrap();
+        assert_eq!(store.load::<u32>("c").unwrap(), 0);
+        assert!(!store.invalidations_path().exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lock() {
+        let dir = env::temp_dir().join(format!("mast-test-store-lock-{}", process::id()));
+        let store = Store::open(&dir).unwrap();
+
+        let lock = store.lock(&LockPolicy::new()).unwrap();
+        thread::scope(|s| {
+            let waiter = s.spawn(|| store.lock(&LockPolicy::new()).unwrap());
+            thread::sleep(Duration::from_millis(200));
+            assert!(!waiter.is_finished());
+            drop(lock);
+            drop(waiter.join().unwrap());
+        });
+
+        // Simulate a process that crashed while holding the lock.
+        fs::write(dir.join("lock"), "crashed\n").unwrap();
+        let timeout = LockPolicy::new().timeout(Duration::from_millis(100));
+        let err = store.lock(&timeout).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        let stolen = store.lock(&timeout.clone().steal_after(Duration::from_millis(50)));
+        drop(stolen.unwrap());
+        assert!(!dir.join("lock").exists());
+
+        // A holder whose lock was stolen leaves the new holder's lock alone.
+        let slow = store.lock(&LockPolicy::new()).unwrap();
+        fs::remove_file(dir.join("lock")).unwrap();
+        fs::write(dir.join("lock"), "thief\n").unwrap();
+        drop(slow);
+        assert_eq!(fs::read(dir.join("lock")).unwrap(), b"thief\n");
+        fs::remove_file(dir.join("lock")).unwrap();
+
+        // A lock held for longer than `steal_after` is kept fresh, and so is not stolen.
+        let lock = store.lock(&LockPolicy::new()).unwrap();
+        let modified = || fs::metadata(dir.join("lock")).unwrap().modified().unwrap();
+        let acquired = modified();
+        let start = Instant::now();
+        while modified() == acquired {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "lock was never refreshed"
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+        let steal = timeout.steal_after(Duration::from_millis(900));
+        let err = store.lock(&steal).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        drop(lock);
+        assert!(!dir.join("lock").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use super::GcPolicy;
+    use super::LockPolicy;
+    use super::Store;
+    use core::time::Duration;
+    use std::env;
+    use std::format;
+    use std::fs;
+    use std::io;
+    use std::process;
+    use std::thread;
+    use std::time::Instant;
+    use std::time::SystemTime;
+    use std::vec;
+    use std::vec::Vec;
+}
+
+use crate::asset::Context;
+use crate::hash::Sha256;
+use crate::Etag;
+use core::sync::atomic;
+use core::sync::atomic::AtomicU64;
+use core::time::Duration;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::format;
+use std::fs;
+use std::io;
+use std::io::Read as _;
+use std::io::Seek as _;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::string::String;
+use std::string::ToString as _;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::vec::Vec;
@@ -0,0 +1,181 @@
+//! Points in time, and a clock that can be injected through the [`Context`].
+//!
+//! Nothing in this module requires the standard library to function,
+//! so it works on targets such as `wasm32-unknown-unknown`
+//! where [`SystemTime::now`](std::time::SystemTime::now) is unavailable:
+//! on those targets, supply a [`Clock`] in the [`Context`] instead.
+
+/// A point in time, stored as a signed number of nanoseconds relative to the Unix epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    unix_nanos: i128,
+}
+
+impl Time {
+    /// The earliest representable time,
+    /// which compares less than or equal to every other time.
+    ///
+    /// This is useful as a sentinel for “never”,
+    /// for example as the modification time of a file that does not exist.
+    pub const EARLIEST: Self = Self {
+        unix_nanos: i128::MIN,
+    };
+
+    /// Obtain [`Self::EARLIEST`].
+    #[must_use]
+    pub const fn earliest() -> Self {
+        Self::EARLIEST
+    }
+
+    /// Construct a time from a number of nanoseconds since the Unix epoch.
+    #[must_use]
+    pub const fn from_unix_nanos(unix_nanos: i128) -> Self {
+        Self { unix_nanos }
+    }
+
+    /// Get the number of nanoseconds since the Unix epoch.
+    #[must_use]
+    pub const fn unix_nanos(self) -> i128 {
+        self.unix_nanos
+    }
+
+    /// Convert a [`SystemTime`] to a `Time`,
+    /// saturating if it is out of range.
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    #[must_use]
+    pub fn from_system(time: SystemTime) -> Self {
+        let unix_nanos = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(after) => i128::try_from(after.as_nanos()).unwrap_or(i128::MAX),
+            Err(before) => {
+                i128::try_from(before.duration().as_nanos()).map_or(i128::MIN, |nanos| -nanos)
+            }
+        };
+        Self { unix_nanos }
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::EARLIEST
+    }
+}
+
+impl Etag for Time {
+    fn serialize<W: ?Sized + Writer>(&self, writer: &mut W) {
+        writer.write_i128_var(self.unix_nanos);
+    }
+    fn deserialize(reader: &mut Reader<'_>) -> Result<Self, DeserializeError> {
+        reader.read_i128_var().map(Self::from_unix_nanos)
+    }
+}
+
+/// A source of the current time.
+///
+/// A `Clock` can be placed in the [`Context`],
+/// where it overrides the system clock for everything that calls [`now`].
+///
+/// # Examples
+///
+/// ```
+/// use mast::asset;
+/// use mast::time;
+///
+/// let cx = (time::Clock::new(|| time::Time::from_unix_nanos(37)),);
+/// let cx = asset::Context::from_tuple(&cx);
+/// assert_eq!(time::now(cx), Some(time::Time::from_unix_nanos(37)));
+/// ```
+#[derive(Clone, Copy)]
+pub struct Clock {
+    now: fn() -> Time,
+}
+
+impl Clock {
+    /// Construct a clock from a function returning the current time.
+    #[must_use]
+    pub const fn new(now: fn() -> Time) -> Self {
+        Self { now }
+    }
+
+    /// The system clock, backed by [`SystemTime::now`].
+    ///
+    /// This clock panics on targets without a system clock,
+    /// such as `wasm32-unknown-unknown`.
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+    #[must_use]
+    pub const fn system() -> Self {
+        Self::new(|| Time::from_system(SystemTime::now()))
+    }
+
+    /// Get the current time according to this clock.
+    #[must_use]
+    pub fn now(&self) -> Time {
+        (self.now)()
+    }
+}
+
+impl Debug for Clock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clock").finish_non_exhaustive()
+    }
+}
+
+/// Get the current time.
+///
+/// This uses the [`Clock`] in the context if there is one,
+/// then the timestamp of the [`Deterministic`] in the context if there is one,
+/// and otherwise falls back to the system clock when it is available.
+/// Returns [`None`] if there is no clock to use,
+/// which happens without the `std` feature or on `wasm32-unknown-unknown`.
+#[must_use]
+pub fn now(cx: Context<'_>) -> Option<Time> {
+    if let Some(clock) = cx.try_get::<Clock>() {
+        return Some(clock.now());
+    }
+    if let Some(deterministic) = cx.try_get::<Deterministic>() {
+        return Some(deterministic.timestamp());
+    }
+    #[cfg(all(
+        feature = "std",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    return Some(Clock::system().now());
+    #[allow(unreachable_code)]
+    None
+}
+
+/// The modification time of the currently running executable,
+/// or [`None`] if it cannot be determined.
+///
+/// Mixing this into an etag causes it to be invalidated whenever the build program is recompiled,
+/// which is a conservative way of accounting for changes in the build logic itself.
+/// The result is computed once and cached for the lifetime of the process.
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+#[must_use]
+pub fn exe_modified() -> Option<Time> {
+    #[allow(clippy::option_option)] // The outer `Option` is whether it has been computed yet.
+    static EXE_MODIFIED: Mutex<Option<Option<Time>>> = Mutex::new(None);
+    let mut cached = EXE_MODIFIED.lock().unwrap_or_else(PoisonError::into_inner);
+    *cached.get_or_insert_with(|| {
+        let metadata = std::fs::metadata(std::env::current_exe().ok()?).ok()?;
+        Some(Time::from_system(metadata.modified().ok()?))
+    })
+}
+
+use crate::asset::Context;
+use crate::asset::Deterministic;
+use crate::etag::DeserializeError;
+use crate::etag::Reader;
+use crate::etag::Writer;
+use crate::Etag;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use std::sync::PoisonError;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
@@ -0,0 +1,168 @@
+//! A binary diff between two versions of a value,
+//! in the style of `rsync`:
+//! blocks of the old value are found in the new one with a rolling checksum,
+//! and everything else is stored literally.
+//!
+//! A patch is the length of the new value followed by a sequence of operations,
+//! each of which is either [`COPY`] followed by an offset and a length in the old value,
+//! or [`INSERT`] followed by a length and that many literal bytes.
+//! All integers are variable-width.
+
+const BLOCK: usize = 32;
+
+const COPY: u8 = 0;
+const INSERT: u8 = 1;
+
+/// Compute a patch that turns `old` into `new`.
+pub(crate) fn diff(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut patch = Vec::new();
+    patch.write_usize_var(new.len());
+
+    // Index the aligned blocks of the old value by their checksum.
+    let mut blocks = BTreeMap::new();
+    for offset in (0..old.len().saturating_sub(BLOCK - 1)).step_by(BLOCK) {
+        let checksum = Checksum::of(&old[offset..offset + BLOCK]);
+        blocks.entry(checksum.value()).or_insert(offset);
+    }
+
+    let mut literal = 0;
+    let mut i = 0;
+    let mut checksum = new.get(..BLOCK).map(Checksum::of);
+    while let Some(current) = checksum {
+        let found = blocks
+            .get(&current.value())
+            .copied()
+            .filter(|&offset| old[offset..offset + BLOCK] == new[i..i + BLOCK]);
+        let Some(offset) = found else {
+            checksum = new.get(i + BLOCK).map(|&byte| current.roll(new[i], byte));
+            i += 1;
+            continue;
+        };
+
+        // Grow the match in both directions as far as the bytes agree.
+        let (mut old_start, mut new_start) = (offset, i);
+        while old_start > 0 && new_start > literal && old[old_start - 1] == new[new_start - 1] {
+            old_start -= 1;
+            new_start -= 1;
+        }
+        let mut len = i + BLOCK - new_start;
+        while old.get(old_start + len).is_some()
+            && old.get(old_start + len) == new.get(new_start + len)
+        {
+            len += 1;
+        }
+
+        write_insert(&mut patch, &new[literal..new_start]);
+        patch.write_bytes(&[COPY]);
+        patch.write_usize_var(old_start);
+        patch.write_usize_var(len);
+
+        i = new_start + len;
+        literal = i;
+        checksum = new.get(i..i + BLOCK).map(Checksum::of);
+    }
+    write_insert(&mut patch, &new[literal..]);
+    patch
+}
+
+fn write_insert(patch: &mut Vec<u8>, bytes: &[u8]) {
+    if !bytes.is_empty() {
+        patch.write_bytes(&[INSERT]);
+        patch.write_usize_var(bytes.len());
+        patch.write_bytes(bytes);
+    }
+}
+
+/// Apply a patch produced by [`diff`] to `old`.
+///
+/// Returns [`None`] if the patch is malformed or was computed against a different value.
+pub(crate) fn patch(old: &[u8], patch: &[u8]) -> Option<Vec<u8>> {
+    let mut reader = Reader::new(patch);
+    let len = reader.read_usize_var().ok()?;
+    let mut new = Vec::with_capacity(len.min(old.len() + patch.len()));
+    while !reader.remaining().is_empty() {
+        match reader.read_u8().ok()? {
+            COPY => {
+                let offset = reader.read_usize_var().ok()?;
+                let len = reader.read_usize_var().ok()?;
+                new.extend_from_slice(old.get(offset..offset.checked_add(len)?)?);
+            }
+            INSERT => {
+                let len = reader.read_usize_var().ok()?;
+                new.extend_from_slice(reader.read_bytes(len).ok()?);
+            }
+            _ => return None,
+        }
+    }
+    (new.len() == len).then_some(new)
+}
+
+/// An Adler-style checksum of a block that can be rolled forward one byte at a time.
+#[derive(Clone, Copy)]
+struct Checksum {
+    a: u32,
+    b: u32,
+}
+
+impl Checksum {
+    fn of(block: &[u8]) -> Self {
+        let mut checksum = Self { a: 0, b: 0 };
+        for &byte in block {
+            checksum.a = checksum.a.wrapping_add(u32::from(byte));
+            checksum.b = checksum.b.wrapping_add(checksum.a);
+        }
+        checksum
+    }
+
+    /// The checksum of the block one byte further on, which drops `out` and gains `in_`.
+    fn roll(self, out: u8, in_: u8) -> Self {
+        let a = self
+            .a
+            .wrapping_sub(u32::from(out))
+            .wrapping_add(u32::from(in_));
+        #[allow(clippy::cast_possible_truncation)] // `BLOCK` is small.
+        let b = self
+            .b
+            .wrapping_sub((BLOCK as u32).wrapping_mul(u32::from(out)))
+            .wrapping_add(a);
+        Self { a, b }
+    }
+
+    fn value(self) -> u32 {
+        (self.b << 16) | (self.a & 0xFFFF)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn round_trip() {
+        let old: Vec<u8> = (0..4096_u32)
+            .flat_map(|i| (i * 7919).to_le_bytes())
+            .collect();
+        let mut new = old.clone();
+        new[1000..1010].copy_from_slice(b"0123456789");
+        new.splice(5000..5000, *b"inserted");
+        new.drain(9000..9100);
+        new.extend_from_slice(b"appended");
+
+        let patch = super::diff(&old, &new);
+        assert!(patch.len() < 100, "patch is {} bytes", patch.len());
+        assert_eq!(super::patch(&old, &patch), Some(new.clone()));
+
+        for (old, new) in [(&[][..], &new[..]), (&old[..], &[][..]), (b"abc", b"abd")] {
+            assert_eq!(
+                super::patch(old, &super::diff(old, new)).as_deref(),
+                Some(new)
+            );
+        }
+        assert_eq!(super::patch(b"other", &patch), None);
+    }
+
+    use alloc::vec::Vec;
+}
+
+use crate::etag::Reader;
+use crate::etag::Writer as _;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
@@ -0,0 +1,458 @@
+//! A [`Vfs`] backed by a key-value store.
+//!
+//! This is intended for running pipelines inside web-based playgrounds and editors,
+//! where files live in IndexedDB, `localStorage` or simply in memory.
+//! Implement [`Store`] for your bindings to the store,
+//! and wrap it in a [`Kv`] to obtain a [`Vfs`].
+//!
+//! Each file occupies two keys:
+//! `d:` followed by the path holds its contents,
+//! and `v:` followed by the path holds its version.
+//! Versions are kept when a file is removed,
+//! so that a recreated file never reuses an old version.
+//! For the same reason, a file whose version is missing or malformed
+//! cannot be used until it is fixed or removed from the store,
+//! and reports an [`Error::CorruptVersion`].
+//!
+//! To keep writes to a remote store small when large files change only slightly,
+//! wrap the store in a [`Diffed`].
+
+/// A key-value store.
+pub trait Store {
+    /// The error type of operations on this store.
+    type Error;
+
+    /// Retrieve the value associated with `key`, or [`None`] if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value could not be retrieved.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Associate `value` with `key`, replacing any previous value.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value could not be stored.
+    fn set(&self, key: &str, value: &[u8]) -> Result<(), Self::Error>;
+
+    /// Remove the value associated with `key`, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value could not be removed.
+    fn delete(&self, key: &str) -> Result<(), Self::Error>;
+}
+
+impl<S: ?Sized + Store> Store for &S {
+    type Error = S::Error;
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Self::Error> {
+        (**self).get(key)
+    }
+    fn set(&self, key: &str, value: &[u8]) -> Result<(), Self::Error> {
+        (**self).set(key, value)
+    }
+    fn delete(&self, key: &str) -> Result<(), Self::Error> {
+        (**self).delete(key)
+    }
+}
+
+/// A simple in-memory [`Store`].
+#[derive(Debug, Default)]
+pub struct Memory(RefCell<BTreeMap<String, Vec<u8>>>);
+
+impl Store for Memory {
+    type Error = Infallible;
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self.0.borrow().get(key).cloned())
+    }
+    fn set(&self, key: &str, value: &[u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().insert(key.into(), value.into());
+        Ok(())
+    }
+    fn delete(&self, key: &str) -> Result<(), Self::Error> {
+        self.0.borrow_mut().remove(key);
+        Ok(())
+    }
+}
+
+/// A [`Store`] that saves new values as binary patches against the previous ones.
+///
+/// This keeps the amount of data written to a remote store small
+/// when large values change only slightly between builds,
+/// at the cost of reading the previous value before each write
+/// and applying the patches on each read.
+///
+/// Each value is kept as a base value and a chain of patches.
+/// Once the chain reaches its [maximum length](Self::max_patches),
+/// or a patch would not be much smaller than the value itself,
+/// the new value is saved as a fresh base instead.
+/// A value whose chain cannot be read back is treated as missing.
+///
+/// Every key of the inner store is prefixed,
+/// with `m:` for the metadata of a value,
+/// `b:` for its base and `p:` for its patches,
+/// so a `Diffed` store should not share its keys with other users of the inner store.
+///
+/// # Examples
+///
+/// ```
+/// use mast::vfs::kv;
+/// use mast::vfs::kv::Store as _;
+///
+/// let store = kv::Diffed::new(kv::Memory::default());
+/// let mut artifact = vec![0; 64 * 1024];
+/// store.set("site.tar", &artifact).unwrap();
+///
+/// artifact[1000] = 1;
+/// store.set("site.tar", &artifact).unwrap();
+/// assert_eq!(store.get("site.tar"), Ok(Some(artifact)));
+/// ```
+#[derive(Debug, Default)]
+pub struct Diffed<S> {
+    store: S,
+    max_patches: u32,
+}
+
+impl<S: Store> Diffed<S> {
+    /// Wrap a store, keeping up to 16 patches per value.
+    #[must_use]
+    pub const fn new(store: S) -> Self {
+        Self {
+            store,
+            max_patches: 16,
+        }
+    }
+
+    /// Set the maximum number of patches kept before a value is saved in full again.
+    ///
+    /// Longer chains make writes smaller but reads slower.
+    #[must_use]
+    pub const fn max_patches(mut self, max_patches: u32) -> Self {
+        self.max_patches = max_patches;
+        self
+    }
+
+    /// Retrieve the underlying store.
+    #[must_use]
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<Meta>, S::Error> {
+        let meta = self.store.get(&self::key("m:", key))?;
+        Ok(meta.and_then(|bytes| Meta::from_bytes(&bytes)))
+    }
+
+    fn set_meta(&self, key: &str, meta: Meta) -> Result<(), S::Error> {
+        self.store.set(&self::key("m:", key), &meta.to_bytes())
+    }
+
+    /// Remove the base and patches of a generation of the value, after they have been replaced.
+    fn delete_generation(&self, key: &str, meta: Meta) -> Result<(), S::Error> {
+        self.store.delete(&meta.base_key(key))?;
+        for i in 0..meta.patches {
+            self.store.delete(&meta.patch_key(key, i))?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Store> Store for Diffed<S> {
+    type Error = S::Error;
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Self::Error> {
+        let Some(meta) = self.meta(key)? else {
+            return Ok(None);
+        };
+        let Some(mut value) = self.store.get(&meta.base_key(key))? else {
+            return Ok(None);
+        };
+        for i in 0..meta.patches {
+            let patch = self.store.get(&meta.patch_key(key, i))?;
+            match patch.and_then(|patch| diff::patch(&value, &patch)) {
+                Some(patched) => value = patched,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(value))
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> Result<(), Self::Error> {
+        // New parts are always written before the metadata that refers to them,
+        // so an interrupted write leaves the previous value intact.
+        let meta = self.meta(key)?;
+        if let Some(meta) = meta.filter(|meta| meta.patches < self.max_patches) {
+            if let Some(old) = self.get(key)? {
+                let patch = diff::diff(&old, value);
+                if patch.len() < value.len() / 2 {
+                    self.store.set(&meta.patch_key(key, meta.patches), &patch)?;
+                    return self.set_meta(
+                        key,
+                        Meta {
+                            patches: meta.patches + 1,
+                            ..meta
+                        },
+                    );
+                }
+            }
+        }
+
+        let new = Meta {
+            generation: meta.map_or(0, |meta| meta.generation.wrapping_add(1)),
+            patches: 0,
+        };
+        self.store.set(&new.base_key(key), value)?;
+        self.set_meta(key, new)?;
+        match meta {
+            Some(meta) => self.delete_generation(key, meta),
+            None => Ok(()),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Self::Error> {
+        let Some(meta) = self.meta(key)? else {
+            return Ok(());
+        };
+        self.store.delete(&self::key("m:", key))?;
+        self.delete_generation(key, meta)
+    }
+}
+
+/// Which keys of the inner store hold a value of a [`Diffed`] store.
+#[derive(Clone, Copy)]
+struct Meta {
+    /// Incremented each time the value is saved as a fresh base,
+    /// so that the new base and patches never overwrite the ones in use.
+    generation: u32,
+    patches: u32,
+}
+
+impl Meta {
+    fn base_key(self, key: &str) -> String {
+        format!("b:{}:{key}", self.generation)
+    }
+
+    fn patch_key(self, key: &str, i: u32) -> String {
+        format!("p:{}:{i}:{key}", self.generation)
+    }
+
+    fn to_bytes(self) -> [u8; 8] {
+        let mut bytes = [0; 8];
+        bytes[..4].copy_from_slice(&self.generation.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.patches.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; 8] = bytes.try_into().ok()?;
+        let (generation, patches) = bytes.split_at(4);
+        Some(Self {
+            generation: u32::from_le_bytes(generation.try_into().ok()?),
+            patches: u32::from_le_bytes(patches.try_into().ok()?),
+        })
+    }
+}
+
+/// A [`Vfs`] that stores files in a key-value [`Store`].
+///
+/// # Examples
+///
+/// ```
+/// use mast::vfs::kv;
+/// use mast::vfs::Vfs as _;
+///
+/// let vfs = kv::Kv::new(kv::Memory::default());
+/// assert_eq!(vfs.version("a.txt"), Ok(None));
+///
+/// vfs.write("a.txt", b"Hello").unwrap();
+/// let first = vfs.version("a.txt").unwrap();
+/// assert!(first.is_some());
+/// assert_eq!(vfs.read("a.txt"), Ok(Some(b"Hello".to_vec())));
+///
+/// vfs.remove("a.txt").unwrap();
+/// assert_eq!(vfs.read("a.txt"), Ok(None));
+///
+/// vfs.write("a.txt", b"Hello").unwrap();
+/// assert_ne!(vfs.version("a.txt").unwrap(), first);
+/// ```
+#[derive(Debug, Default)]
+pub struct Kv<S> {
+    store: S,
+}
+
+impl<S: Store> Kv<S> {
+    /// Construct a filesystem backed by the given store.
+    #[must_use]
+    pub const fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// Retrieve the underlying store.
+    #[must_use]
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    fn stored_version(&self, path: &str) -> Result<Version, Error<S::Error>> {
+        // Starting again from zero could reuse a version that a reader has already seen,
+        // so a missing or malformed version is an error unless the file was never written.
+        match self.store.get(&key("v:", path)).map_err(Error::Store)? {
+            Some(bytes) => {
+                Version::from_bytes(&bytes).ok_or_else(|| Error::CorruptVersion(path.into()))
+            }
+            None if self.read(path)?.is_some() => Err(Error::CorruptVersion(path.into())),
+            None => Ok(Version::default()),
+        }
+    }
+
+    fn set_version(&self, path: &str, version: Version) -> Result<(), Error<S::Error>> {
+        let key = key("v:", path);
+        self.store
+            .set(&key, &version.to_bytes())
+            .map_err(Error::Store)
+    }
+}
+
+impl<S: Store> Vfs for Kv<S> {
+    type Error = Error<S::Error>;
+
+    fn version(&self, path: &str) -> Result<Option<u64>, Self::Error> {
+        let version = self.stored_version(path)?;
+        Ok(Some(version.number).filter(|_| version.exists))
+    }
+
+    fn read(&self, path: &str) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.store.get(&key("d:", path)).map_err(Error::Store)
+    }
+
+    fn write(&self, path: &str, contents: &[u8]) -> Result<(), Self::Error> {
+        let version = self.stored_version(path)?;
+        let key = key("d:", path);
+        self.store.set(&key, contents).map_err(Error::Store)?;
+        self.set_version(path, version.next(true))
+    }
+
+    fn remove(&self, path: &str) -> Result<(), Self::Error> {
+        let version = self.stored_version(path)?;
+        if !version.exists {
+            return Ok(());
+        }
+        let key = key("d:", path);
+        self.store.delete(&key).map_err(Error::Store)?;
+        self.set_version(path, version.next(false))
+    }
+}
+
+/// The error type of a [`Kv`] filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error<E> {
+    /// An operation on the underlying store failed.
+    Store(E),
+    /// The version of the file at this path is missing or malformed,
+    /// although the file has contents.
+    CorruptVersion(String),
+}
+
+impl<E: Display> Display for Error<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Store(e) => Display::fmt(e, f),
+            Self::CorruptVersion(path) => write!(f, "the version of {path:?} is corrupt"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "std")))]
+impl<E: std::error::Error + 'static> std::error::Error for Error<E> {
+    fn source(&self) -> Option<&(dyn 'static + std::error::Error)> {
+        match self {
+            Self::Store(e) => e.source(),
+            Self::CorruptVersion(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Version {
+    number: u64,
+    exists: bool,
+}
+
+impl Version {
+    fn next(self, exists: bool) -> Self {
+        Self {
+            number: self.number.wrapping_add(1),
+            exists,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 9] {
+        let mut bytes = [0; 9];
+        bytes[..8].copy_from_slice(&self.number.to_le_bytes());
+        bytes[8] = u8::from(self.exists);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&exists, number) = bytes.split_last()?;
+        Some(Self {
+            number: u64::from_le_bytes(number.try_into().ok()?),
+            exists: match exists {
+                0 => false,
+                1 => true,
+                _ => return None,
+            },
+        })
+    }
+}
+
+fn key(prefix: &str, path: &str) -> String {
+    let mut key = String::with_capacity(prefix.len() + path.len());
+    key.push_str(prefix);
+    key.push_str(path);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn corrupt_version() {
+        let vfs = Kv::new(Memory::default());
+        vfs.write("a", b"a").unwrap();
+        vfs.store.set("v:a", b"nonsense").unwrap();
+        let corrupt = || Error::CorruptVersion("a".into());
+        assert_eq!(vfs.version("a"), Err(corrupt()));
+        assert_eq!(vfs.write("a", b"b"), Err(corrupt()));
+
+        // Contents without a version are just as unusable.
+        vfs.store.delete("v:a").unwrap();
+        assert_eq!(vfs.version("a"), Err(corrupt()));
+
+        // Removing the file from the store fixes it.
+        vfs.store.delete("d:a").unwrap();
+        assert_eq!(vfs.version("a"), Ok(None));
+        vfs.write("a", b"c").unwrap();
+        assert_eq!(vfs.read("a"), Ok(Some(b"c".to_vec())));
+    }
+
+    use super::Error;
+    use super::Kv;
+    use super::Memory;
+    use super::Store as _;
+    use crate::vfs::Vfs as _;
+}
+
+use super::diff;
+use super::Vfs;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
@@ -0,0 +1,189 @@
+//! A virtual filesystem abstraction,
+//! for running pipelines in environments without a real filesystem.
+//!
+//! The [`Vfs`] trait describes a flat namespace of files,
+//! each of which carries a version number that changes whenever the file does.
+//! The [`read`] asset uses that version number as its etag,
+//! so it works the same way as [`fs::bytes`](crate::fs::bytes)
+//! but without depending on `std`.
+//!
+//! With the `wasm` feature,
+//! the [`kv`] module implements [`Vfs`] on top of any key-value store,
+//! such as IndexedDB bindings in a browser or an in-memory map.
+
+/// A virtual filesystem.
+///
+/// Paths are opaque strings;
+/// implementations may give them structure, but are not required to.
+pub trait Vfs {
+    /// The error type of operations on this filesystem.
+    type Error;
+
+    /// Retrieve the version of the file at `path`,
+    /// or [`None`] if it does not exist.
+    ///
+    /// The version must change whenever the file’s contents do,
+    /// including when the file is removed and later recreated.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file’s version could not be retrieved.
+    fn version(&self, path: &str) -> Result<Option<u64>, Self::Error>;
+
+    /// Read the contents of the file at `path`,
+    /// or [`None`] if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file could not be read.
+    fn read(&self, path: &str) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Create or overwrite the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file could not be written.
+    fn write(&self, path: &str, contents: &[u8]) -> Result<(), Self::Error>;
+
+    /// Remove the file at `path`, if it exists.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file could not be removed.
+    fn remove(&self, path: &str) -> Result<(), Self::Error>;
+}
+
+impl<V: ?Sized + Vfs> Vfs for &V {
+    type Error = V::Error;
+    fn version(&self, path: &str) -> Result<Option<u64>, Self::Error> {
+        (**self).version(path)
+    }
+    fn read(&self, path: &str) -> Result<Option<Vec<u8>>, Self::Error> {
+        (**self).read(path)
+    }
+    fn write(&self, path: &str, contents: &[u8]) -> Result<(), Self::Error> {
+        (**self).write(path, contents)
+    }
+    fn remove(&self, path: &str) -> Result<(), Self::Error> {
+        (**self).remove(path)
+    }
+}
+
+/// Read the contents of a file in a [`Vfs`].
+///
+/// The etag of this asset is the file’s version,
+/// and the file is only read when the output is generated.
+/// The output is [`None`] if the file does not exist.
+/// If the version cannot be retrieved,
+/// the asset is considered modified and its output is the error.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "wasm")] {
+/// use mast::asset;
+/// use mast::asset::Generator as _;
+/// use mast::vfs;
+/// use mast::vfs::Vfs as _;
+/// use mast::Asset as _;
+///
+/// let vfs = vfs::kv::Kv::new(vfs::kv::Memory::default());
+/// vfs.write("index.md", b"# Hello").unwrap();
+///
+/// let mut etag = Default::default();
+/// let res = vfs::read(&vfs, "index.md").update(asset::Context::default(), &mut etag);
+/// assert!(res.is_modified());
+/// assert_eq!(res.value.generate(), Ok(Some(b"# Hello".to_vec())));
+///
+/// assert!(vfs::read(&vfs, "index.md").update(asset::Context::default(), &mut etag).is_same());
+/// # }
+/// ```
+pub fn read<V: Vfs, P: Into<String>>(vfs: V, path: P) -> Read<V> {
+    Read {
+        vfs,
+        path: path.into(),
+    }
+}
+
+/// Asset for [`read`].
+#[derive(Debug)]
+pub struct Read<V> {
+    vfs: V,
+    path: String,
+}
+
+impl<'c, V: Vfs> Asset<'c> for Read<V> {
+    type Etag = Option<u64>;
+    type Output = Result<Option<Vec<u8>>, V::Error>;
+    type Generator = Generator<V>;
+
+    fn update(self, _: Context<'c>, etag: &'c mut Self::Etag) -> Tracked<Self::Generator> {
+        match self.vfs.version(&self.path) {
+            Ok(version) => {
+                let delta = Delta::cmp(etag, &version);
+                *etag = version;
+                delta.track(Generator(Inner::Read {
+                    vfs: self.vfs,
+                    path: self.path,
+                }))
+            }
+            Err(e) => {
+                *etag = None;
+                Delta::Modified.track(Generator(Inner::Failed(e)))
+            }
+        }
+    }
+}
+
+/// Generator for [`Read`].
+pub struct Generator<V: Vfs>(Inner<V>);
+
+enum Inner<V: Vfs> {
+    Read { vfs: V, path: String },
+    Failed(V::Error),
+}
+
+impl<V: Vfs + Debug> Debug for Generator<V>
+where
+    V::Error: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Inner::Read { vfs, path } => f
+                .debug_struct("Generator")
+                .field("vfs", vfs)
+                .field("path", path)
+                .finish(),
+            Inner::Failed(e) => f.debug_tuple("Generator").field(e).finish(),
+        }
+    }
+}
+
+impl<V: Vfs> asset::Generator for Generator<V> {
+    type Output = Result<Option<Vec<u8>>, V::Error>;
+
+    fn generate(self) -> Self::Output {
+        match self.0 {
+            Inner::Read { vfs, path } => vfs.read(&path),
+            Inner::Failed(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[cfg_attr(doc_nightly, doc(cfg(feature = "wasm")))]
+pub mod kv;
+
+#[cfg(feature = "wasm")]
+mod diff;
+
+use crate::asset;
+use crate::asset::Context;
+use crate::Asset;
+use crate::Delta;
+use crate::Tracked;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;